@@ -0,0 +1,119 @@
+//! Expansion of host aliases, declared in the configuration file or in the hosts file
+//! of the system, so that filtering by an alias (e.g. `dev.local`) also matches
+//! the cookies of the domains it stands for.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::PathBuf,
+};
+
+use http::Uri;
+
+/// Aliases of the hosts, indexed by host.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct HostAliases {
+    aliases: HashMap<String, BTreeSet<String>>,
+}
+
+impl HostAliases {
+    /// Returns the path of the hosts file of the system.
+    pub fn system_hosts_path() -> PathBuf {
+        if cfg!(windows) {
+            PathBuf::from(r"C:\Windows\System32\drivers\etc\hosts")
+        } else {
+            PathBuf::from("/etc/hosts")
+        }
+    }
+
+    /// Add the aliases declared in the configuration file,
+    /// where each host is mapped to the domains it stands for.
+    pub fn add_config(&mut self, aliases: &HashMap<String, Vec<String>>) {
+        for (host, domains) in aliases {
+            self.aliases
+                .entry(host.to_ascii_lowercase())
+                .or_default()
+                .extend(domains.iter().map(|domain| domain.to_ascii_lowercase()));
+        }
+    }
+
+    /// Add the aliases of a hosts file, where the names listed
+    /// on the same line are aliases of each other.
+    pub fn add_hosts_file(&mut self, content: &str) {
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            // The first field is the address.
+            let names = line
+                .split_whitespace()
+                .skip(1)
+                .map(str::to_ascii_lowercase)
+                .collect::<Vec<_>>();
+
+            for name in &names {
+                self.aliases.entry(name.clone()).or_default().extend(
+                    names
+                        .iter()
+                        .filter(|alias| *alias != name && *alias != "localhost")
+                        .cloned(),
+                );
+            }
+        }
+    }
+
+    /// Returns the hosts followed by their aliases, without duplicates.
+    pub fn expand(&self, hosts: Vec<Uri>) -> Vec<Uri> {
+        let mut seen = hosts
+            .iter()
+            .filter_map(|uri| uri.host().map(str::to_ascii_lowercase))
+            .collect::<BTreeSet<_>>();
+
+        let aliases = hosts
+            .iter()
+            .filter_map(|uri| self.aliases.get(&uri.host()?.to_ascii_lowercase()))
+            .flatten()
+            .filter(|alias| seen.insert(alias.to_string()))
+            .filter_map(|alias| alias.parse::<Uri>().ok())
+            .collect::<Vec<_>>();
+
+        hosts.into_iter().chain(aliases).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand_aliases() {
+        let mut aliases = HostAliases::default();
+        aliases.add_config(&HashMap::from([(
+            "dev.local".to_string(),
+            vec!["api.example.com".to_string(), "example.com".to_string()],
+        )]));
+        aliases.add_hosts_file(
+            "127.0.0.1 localhost\n\
+            # 10.0.0.1 ignored.local\n\
+            10.0.0.2 staging.local app.staging.example.org # staging\n",
+        );
+
+        let hosts = |hosts: &[&'static str]| {
+            hosts
+                .iter()
+                .map(|host| Uri::from_static(host))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            aliases.expand(hosts(&["https://dev.local", "example.com"])),
+            hosts(&["https://dev.local", "example.com", "api.example.com"])
+        );
+        assert_eq!(
+            aliases.expand(hosts(&["staging.local"])),
+            hosts(&["staging.local", "app.staging.example.org"])
+        );
+        assert_eq!(aliases.expand(hosts(&["localhost"])), hosts(&["localhost"]));
+        assert_eq!(
+            aliases.expand(hosts(&["ignored.local"])),
+            hosts(&["ignored.local"])
+        );
+    }
+}
@@ -1,150 +1,891 @@
 use std::{
-    ffi::OsStr,
+    ffi::OsString,
+    fs,
     io::{self, BufWriter, Write},
-    path::PathBuf,
-    process::Command,
+    iter,
+    path::{Path, PathBuf},
+    process::{Child, Command, ExitStatus},
     sync::Arc,
+    thread,
+    time::{Duration, Instant},
 };
 
 use color_eyre::{
-    eyre::{ensure, Context},
-    Result,
+    eyre::{bail, ensure, eyre, Context, Report},
+    Result, Section,
 };
-use cookie::Cookie;
+use cookie::{time::OffsetDateTime, SameSite};
 use gateau::{
-    chrome,
-    firefox::{self, FirefoxManager},
-    Browser,
+    chrome::{self, ChromeVariant, CustomVariant},
+    confinement::Confinement,
+    firefox::{self, FirefoxChannel, FirefoxManager, FirefoxVariant},
+    inspect,
+    internet_explorer::{self, InternetExplorerManager},
+    lock::ProfileLock,
+    report::{BrowserCookie, CookieSet},
+    webkitgtk::{self, WebKitGtkManager},
+    Browser, CookiePathProvider,
 };
 use http::Uri;
+use zeroize::Zeroizing;
 
-use crate::url::BaseDomain;
+use crate::aliases::HostAliases;
+use crate::classify::{Category, Classifier};
+use crate::config::Config;
+use crate::container::{CopiedProfile, SshPath};
+use crate::interrupt::{self, Interruptible};
+use crate::policy::Policy;
+use crate::url::PublicSuffixList;
+use crate::wrapped::{self, CookieArgs, WrappedCmd, WrappedCmds};
 
+use self::changes::PreviousExport;
+#[cfg(unix)]
+use self::cookie_file::CookieFd;
+use self::cookie_file::CookieFile;
+use self::explain::HostMatch;
+use self::jar::CookieJar;
 use self::session::SessionBuilder;
+use self::tail::DatabaseStamp;
 use super::Args;
 
+mod auto;
+mod changes;
+mod confirm;
+mod cookie_file;
+mod expiring;
+mod explain;
+mod header;
+mod httpie_dir;
+mod jar;
+mod lint;
+mod merge;
 mod output;
+mod pass;
+mod proxy;
+mod render;
 mod session;
+mod shell_init;
+mod sort;
+mod tail;
+
+/// Name of the public suffix list in the data directory.
+const PUBLIC_SUFFIX_LIST_FILE: &str = "public_suffix_list.dat";
+
+/// Name of the cookie classification rules in the data directory.
+const CATEGORIES_FILE: &str = "categories.csv";
 
 pub struct App {
     args: Args,
+    config: Config,
+    policy: Policy,
 }
 
 impl App {
-    pub(crate) fn new(args: Args) -> Self {
-        Self { args }
+    pub(crate) fn new(args: Args, config: Config, policy: Policy) -> Self {
+        Self {
+            args,
+            config,
+            policy,
+        }
     }
 
     /// Get the cookies matching the provided hosts from the specified browser.
-    fn get_cookies(
-        root_dir: Option<PathBuf>,
-        bypass_lock: bool,
-        browser: Browser,
-        hosts: Vec<Uri>,
-    ) -> Result<Vec<Cookie<'static>>> {
-        let hosts = Arc::from(hosts);
+    fn get_cookies(options: &SourceOptions, hosts: Vec<Uri>) -> Result<Vec<BrowserCookie>> {
+        if !options.other_root_dirs.is_empty() || !options.other_browsers.is_empty() {
+            return App::get_merged_cookies(options, hosts);
+        }
 
-        match browser {
-            Browser::Firefox => {
-                let path_provider = if let Some(root_dir) = root_dir {
-                    firefox::PathProvider::from_root(root_dir)
-                } else {
-                    firefox::PathProvider::default_profile()
-                };
+        let source = CookieSource::open(options, hosts)?;
+        let cookies = source.get_cookie_set().map(|set| set.cookies);
 
-                let hosts = Arc::from(hosts);
-                let hosts = Arc::clone(&hosts);
-                let filter = Box::from(move |host: &str| {
-                    let hosts = Arc::clone(&hosts);
-                    filter_hosts(host, &hosts)
-                });
-
-                let manager = FirefoxManager::new(path_provider, Some(filter), bypass_lock)?;
-                manager
-                    .get_cookies()
-                    .wrap_err("Failed to get cookies from Firefox")
+        // Also printed on failure, to tell how many values could be decrypted before it.
+        if options.decryption_stats {
+            if let CookieSource::Chrome { manager, .. } = &source {
+                eprintln!("Decryption: {}", manager.decryption_stats());
             }
+        }
 
-            Browser::ChromeVariant(chrome_variant) => {
-                let path_provider = if let Some(root_dir) = root_dir {
-                    chrome::PathProvider::from_root(root_dir)
-                } else {
-                    chrome::PathProvider::default_profile(chrome_variant)
-                };
+        let cookies = cookies?;
 
-                let hosts = Arc::from(hosts);
-                let filter = Box::from(move |host: &str| filter_hosts(host, &hosts));
-                let chrome_manager = chrome::ChromeManager::new(
-                    chrome_variant,
-                    path_provider,
-                    Some(filter),
-                    bypass_lock,
-                )?;
-
-                chrome_manager
-                    .get_cookies()
-                    .wrap_err("Failed to get cookies from Chrome")
-            }
+        if options.browser == Browser::FirefoxVariant(FirefoxVariant::TorBrowser)
+            && cookies.is_empty()
+        {
+            eprintln!(
+                "Warning: no cookies found, Tor Browser only keeps cookies for the \
+                duration of the session unless its private browsing mode is disabled"
+            );
         }
+
+        Ok(cookies)
     }
 
-    /// Wraps the provided command while passing the cookies as a temporary file to the command.
-    fn wrap_command<C, A, Args, O>(
-        cmd: C,
-        cookies_opt: A,
-        forwarded_args: &[Args],
+    /// Get the cookies matching the provided hosts from each root path, or from each browser,
+    /// and merge them while reporting how many come from each one.
+    fn get_merged_cookies(options: &SourceOptions, hosts: Vec<Uri>) -> Result<Vec<BrowserCookie>> {
+        let (sources, kind) = if options.other_browsers.is_empty() {
+            let root_dirs = options
+                .root_dir
+                .iter()
+                .chain(&options.other_root_dirs)
+                .cloned();
+
+            let sources = root_dirs
+                .map(|root_dir| {
+                    let options = SourceOptions {
+                        root_dir: Some(root_dir.clone()),
+                        other_root_dirs: Vec::new(),
+                        ..options.clone()
+                    };
+                    let cookies = App::get_cookies(&options, hosts.clone())
+                        .wrap_err_with(|| format!("Failed to read {}", root_dir.display()))?;
+
+                    Ok((root_dir.display().to_string(), cookies))
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            (sources, "root path")
+        } else {
+            let browsers =
+                iter::once(options.browser).chain(options.other_browsers.iter().copied());
+
+            let sources = browsers
+                .map(|browser| {
+                    let options = SourceOptions {
+                        browser,
+                        other_browsers: Vec::new(),
+                        ..options.clone()
+                    };
+                    let cookies = App::get_cookies(&options, hosts.clone())
+                        .wrap_err_with(|| format!("Failed to read the cookies of {browser}"))?;
+
+                    Ok((browser.to_string(), cookies))
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            (sources, "browser")
+        };
+
+        let (cookies, provenances) = merge::merge(sources);
+        for provenance in provenances {
+            eprintln!(
+                "{}: {} cookie(s), {} already read from a previous {kind}",
+                provenance.source,
+                provenance.read,
+                provenance.duplicates()
+            );
+        }
+
+        Ok(cookies)
+    }
+
+    /// Wraps the provided command while passing the cookies as a temporary file to the command,
+    /// or through a file descriptor, with the arguments built from the path of the file.
+    ///
+    /// With `refresh`, the temporary file is rewritten with the cookies read again
+    /// at the interval, until the command exits.
+    /// With `replace_process`, gateau is replaced by the command on Unix,
+    /// and the cookies are always passed through a file descriptor, which outlives gateau.
+    ///
+    /// The commands which take a curl config file get the path of another file or descriptor,
+    /// which reads the cookie file.
+    fn wrap_command<O, F>(
+        cmd: &WrappedCmd,
         formatted_cookies: O,
+        through_fd: bool,
+        replace_process: bool,
+        refresh: Option<(Duration, &mut CookieRefresher<'_>)>,
+        args: F,
     ) -> Result<i32>
     where
-        C: AsRef<OsStr>,
-        A: AsRef<OsStr>,
-        Args: AsRef<OsStr>,
         O: AsRef<[u8]>,
+        F: FnOnce(&Path) -> Vec<OsString>,
     {
-        let mut tmp_cookie_file = tempfile::NamedTempFile::new()?;
-        tmp_cookie_file.write_all(formatted_cookies.as_ref())?;
-        let tmp_cookies_path = tmp_cookie_file.into_temp_path();
+        if through_fd || replace_process {
+            #[cfg(unix)]
+            {
+                let create_fd = |content: &[u8]| {
+                    if replace_process {
+                        CookieFd::create_for_exec(content)
+                    } else {
+                        CookieFd::create(content)
+                    }
+                    .wrap_err("Failed to pass the cookies through a file descriptor")
+                };
+                let cookie_fd = create_fd(formatted_cookies.as_ref())?;
+                drop(formatted_cookies);
+                let config_fd = (cmd.cookies == CookieArgs::CurlConfig)
+                    .then(|| create_fd(wrapped::curl_config(&cookie_fd.path()).as_bytes()))
+                    .transpose()?;
+
+                let mut command = Command::new(&cmd.binary);
+                command.args(args(&config_fd.as_ref().unwrap_or(&cookie_fd).path()));
+                cookie_fd.pass_to(&mut command);
+                if let Some(config_fd) = &config_fd {
+                    config_fd.pass_to(&mut command);
+                }
+
+                // The descriptor is only closed once the command has exited.
+                return App::wait_wrapped(cmd, &mut command, replace_process);
+            }
+        }
 
-        let mut child = Command::new(cmd.as_ref())
-            .arg(cookies_opt.as_ref())
-            .arg(tmp_cookies_path)
-            .args(forwarded_args)
-            .spawn()?;
+        let cookie_file = CookieFile::create(formatted_cookies.as_ref())
+            .wrap_err("Failed to create the temporary cookie file")?;
+        // Do not keep the cookies in memory while the command is running.
+        drop(formatted_cookies);
+        let config_file = (cmd.cookies == CookieArgs::CurlConfig)
+            .then(|| CookieFile::create(wrapped::curl_config(cookie_file.path()).as_bytes()))
+            .transpose()
+            .wrap_err("Failed to create the temporary curl config file")?;
 
-        let status = child.wait()?;
+        let mut command = Command::new(&cmd.binary);
+        command.args(args(config_file.as_ref().unwrap_or(&cookie_file).path()));
+        let status = match refresh {
+            None => App::wait_wrapped(cmd, &mut command, replace_process),
+            Some((interval, refresh)) => {
+                let _deferred = interrupt::Deferred::enter();
+                command
+                    .spawn()
+                    .wrap_err_with(|| format!("Failed to run {}", cmd.binary))
+                    .and_then(|mut child| {
+                        App::refresh_until_exit(&mut child, &cookie_file, interval, refresh)?;
+                        App::exit_code(cmd, child.wait()?)
+                    })
+            }
+        };
+        // The files are only removed once the command has exited.
+        drop(config_file);
+        drop(cookie_file);
+
+        status
+    }
+
+    /// Rewrite the cookie file every `interval` with the refreshed cookies, until the command exits.
+    ///
+    /// Errors while reading the cookies (e.g. when the browser is writing to the database)
+    /// are reported as warnings, and the file is kept as it is until the next interval.
+    fn refresh_until_exit(
+        child: &mut Child,
+        cookie_file: &CookieFile,
+        interval: Duration,
+        refresh: &mut CookieRefresher<'_>,
+    ) -> Result<()> {
+        const STEP: Duration = Duration::from_millis(100);
+
+        let mut next_refresh = Instant::now() + interval;
+        while child.try_wait()?.is_none() {
+            let remaining = next_refresh.saturating_duration_since(Instant::now());
+            if !remaining.is_zero() {
+                thread::sleep(remaining.min(STEP));
+                continue;
+            }
+
+            next_refresh = Instant::now() + interval;
+            let refreshed = refresh().and_then(|cookies| {
+                cookie_file
+                    .replace(&cookies)
+                    .wrap_err("Failed to rewrite the temporary cookie file")
+            });
+            if let Err(e) = refreshed {
+                eprintln!("Warning: {e}: {}", e.root_cause());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Run the wrapped command with the arguments until it exits, and returns its exit code.
+    fn run_wrapped(cmd: &WrappedCmd, args: Vec<OsString>, replace_process: bool) -> Result<i32> {
+        App::wait_wrapped(cmd, Command::new(&cmd.binary).args(args), replace_process)
+    }
+
+    /// Run the command until it exits, and returns its exit code.
+    ///
+    /// With `replace_process`, gateau is replaced by the command on Unix (`execvp`),
+    /// so it only returns if the command cannot be run.
+    /// Otherwise, the interruptions are left to the command, which receives them as well,
+    /// and the temporary files are only removed once it has exited.
+    fn wait_wrapped(cmd: &WrappedCmd, command: &mut Command, replace_process: bool) -> Result<i32> {
+        #[cfg(unix)]
+        if replace_process {
+            use std::os::unix::process::CommandExt;
+
+            let error = command.exec();
+            return Err(error).wrap_err_with(|| format!("Failed to run {}", cmd.binary));
+        }
+        // Without `execvp`, the command is waited for as by default.
+        #[cfg(not(unix))]
+        let _ = replace_process;
+        let _deferred = interrupt::Deferred::enter();
+
+        let mut child = command
+            .spawn()
+            .wrap_err_with(|| format!("Failed to run {}", cmd.binary))?;
+
+        App::exit_code(cmd, child.wait()?)
+    }
+
+    fn exit_code(cmd: &WrappedCmd, status: ExitStatus) -> Result<i32> {
         ensure!(
             status.code().is_some(),
             "{cmd} has been killed by a signal",
-            cmd = cmd.as_ref().to_string_lossy()
+            cmd = cmd.binary
         );
 
         Ok(status.code().unwrap())
     }
 
     pub fn run(self) -> Result<Option<i32>> {
-        let browser = self.args.browser.unwrap_or(Browser::Firefox);
+        self.policy.check_command(self.args.mode.name())?;
+
+        if let Some(data_dir) = &self.args.data_dir {
+            load_data_dir(data_dir)?;
+        }
+
+        let (first_browser, other_browser_names) = match self.args.browser.split_first() {
+            Some((first, others)) => (Some(first.as_str()), others),
+            None => (None, &[][..]),
+        };
+        let several_browsers = !other_browser_names.is_empty() || self.args.all_browsers;
+        ensure!(
+            !several_browsers || matches!(self.args.mode, crate::Mode::Wrap { .. }),
+            "Several --browser and --all-browsers can only be used to wrap a command"
+        );
+        ensure!(
+            !several_browsers
+                || (self.args.app.is_none()
+                    && self.args.root_path.is_empty()
+                    && self.args.container.is_none()
+                    && self.args.kube.is_none()
+                    && !self.args.session),
+            "Several --browser and --all-browsers cannot be used with --app, --root-path, \
+            --container, --kube or --session"
+        );
+        ensure!(
+            !self.args.all_browsers || first_browser.is_none(),
+            "--all-browsers cannot be used with --browser"
+        );
+        let mut other_browsers = other_browser_names
+            .iter()
+            .map(|name| name.parse::<Browser>().map_err(|e| eyre!(e)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let browser = match first_browser {
+            _ if self.args.app.is_some() => {
+                ensure!(
+                    first_browser.is_none()
+                        && self.args.root_path.is_empty()
+                        && self.args.container.is_none()
+                        && self.args.kube.is_none()
+                        && !self.args.session,
+                    "--app cannot be used with --browser, --root-path, --container, --kube or --session"
+                );
+                Browser::ChromeVariant(app_variant(self.args.app.as_deref().unwrap())?)
+            }
+            Some("auto") => {
+                ensure!(
+                    self.args.root_path.is_empty()
+                        && self.args.container.is_none()
+                        && self.args.kube.is_none(),
+                    "--browser auto cannot be used with --root-path, --container or --kube"
+                );
+                let browser = auto::detect_browser(self.args.mode.hosts(), |browser| {
+                    self.policy.allows_browser(browser)
+                })?;
+                eprintln!("Using the cookies of {browser}");
+                browser
+            }
+            None if self.args.all_browsers => {
+                let mut browsers =
+                    auto::installed_browsers(|browser| self.policy.allows_browser(browser));
+                ensure!(
+                    !browsers.is_empty(),
+                    "No cookies database found for the supported browsers"
+                );
+                other_browsers = browsers.split_off(1);
+                eprintln!(
+                    "Merging the cookies of {}",
+                    browsers
+                        .iter()
+                        .chain(&other_browsers)
+                        .map(Browser::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+                browsers[0]
+            }
+            browser => browser
+                .map(str::parse::<Browser>)
+                .transpose()
+                .map_err(|e| eyre!(e))?
+                .unwrap_or(Browser::FirefoxVariant(FirefoxVariant::Firefox)),
+        };
+        if let Some(&denied) = iter::once(&browser)
+            .chain(&other_browsers)
+            .find(|&&browser| !self.policy.allows_browser(browser))
+        {
+            bail!(
+                "Reading the cookies of {denied} is denied by the policy file {}",
+                Policy::path().display()
+            );
+        }
+        let reads_chrome = iter::once(browser)
+            .chain(other_browsers.iter().copied())
+            .any(|browser| matches!(browser, Browser::ChromeVariant(_)));
         let session = self.args.session;
+
+        ensure!(
+            self.args.channel.is_none()
+                || (browser == Browser::FirefoxVariant(FirefoxVariant::Firefox)
+                    && self.args.root_path.is_empty()
+                    && self.args.container.is_none()
+                    && self.args.kube.is_none()
+                    && !session),
+            "--channel can only be used with the default profiles of Firefox"
+        );
+        ensure!(
+            self.args.container.is_none() || self.args.kube.is_none(),
+            "--container cannot be used with --kube"
+        );
+        ensure!(
+            (self.args.container.is_none() && self.args.kube.is_none())
+                || (self.args.root_path.is_empty() && !session),
+            "--container and --kube cannot be used with --root-path or --session"
+        );
+        ensure!(
+            self.args.namespace.is_none() || self.args.kube.is_some(),
+            "--namespace can only be used with --kube"
+        );
+        let mut root_paths = self.args.root_path.into_iter();
+        let first_root_path = root_paths.next();
+        let other_root_paths = root_paths.collect::<Vec<_>>();
+        ensure!(
+            other_root_paths.is_empty()
+                || !(session || matches!(self.args.mode, crate::Mode::Tail { .. })),
+            "Several --root-path cannot be used with --session or to tail cookies"
+        );
+        let ssh_path = first_root_path
+            .as_deref()
+            .and_then(SshPath::from_root_path)
+            .transpose()
+            .map_err(|e| eyre!(e))?;
+        ensure!(
+            other_root_paths
+                .iter()
+                .all(|path| SshPath::from_root_path(path).is_none())
+                && (ssh_path.is_none() || other_root_paths.is_empty()),
+            "Several --root-path can only be used with local paths"
+        );
+        ensure!(
+            ssh_path.is_none() || !matches!(browser, Browser::InternetExplorerVariant(_)),
+            "The cookies of {browser} cannot be read from a remote machine"
+        );
+        // The copied profile is removed when it is dropped, at the end of the run.
+        let copied_profile = match (&self.args.container, &self.args.kube, &ssh_path) {
+            (Some(container), _, _) => Some(CopiedProfile::from_container(container)?),
+            (None, Some(pod), _) => Some(CopiedProfile::from_pod(
+                pod,
+                self.args.namespace.as_deref(),
+            )?),
+            (None, None, Some(ssh_path)) => {
+                let (required, optional) = remote_profile_files(browser);
+                Some(CopiedProfile::from_ssh(ssh_path, &required, &optional)?)
+            }
+            (None, None, None) => None,
+        };
+        let root_path = copied_profile
+            .as_ref()
+            .map(CopiedProfile::path)
+            .or(first_root_path);
+        ensure!(
+            !self.args.unencrypted || root_path.is_some(),
+            "--unencrypted can only be used with --root-path, --container or --kube"
+        );
+        ensure!(
+            self.args.as_of.is_none() || !session,
+            "--as-of cannot be used with --session"
+        );
+        ensure!(
+            !self.args.include_extension_cookies || reads_chrome,
+            "--include-extension-cookies is only supported for Chrome"
+        );
+        ensure!(
+            !self.args.decryption_stats || reads_chrome,
+            "--decryption-stats is only supported for Chrome"
+        );
+        ensure!(
+            self.args.unspecified_same_site.is_none() || reads_chrome,
+            "--unspecified-same-site is only supported for Chrome"
+        );
+        let raw_and_decrypted = match self.args.mode {
+            crate::Mode::Output {
+                raw_and_decrypted,
+                format,
+                ..
+            } => {
+                ensure!(
+                    !raw_and_decrypted || format == Some(crate::OutputFormat::Json),
+                    "--raw-and-decrypted can only be used with --format json"
+                );
+                raw_and_decrypted
+            }
+            _ => false,
+        };
+        ensure!(
+            !raw_and_decrypted || (matches!(browser, Browser::ChromeVariant(_)) && !session),
+            "--raw-and-decrypted is only supported for Chrome, without --session"
+        );
+        let source_options = SourceOptions {
+            root_dir: root_path,
+            other_root_dirs: other_root_paths,
+            browser,
+            other_browsers,
+            channel: self.args.channel,
+            bypass_lock: self.args.bypass_lock,
+            wait_for_close: self.args.wait_for_close,
+            unencrypted: self.args.unencrypted,
+            unspecified_same_site: self.args.unspecified_same_site.map(|same_site| same_site.0),
+            as_of: self.args.as_of.map(|as_of| as_of.0),
+            decryption_stats: self.args.decryption_stats,
+            include_extension_cookies: self.args.include_extension_cookies,
+            raw_values: raw_and_decrypted,
+        };
         let session_urls = self.args.session_urls;
+        let session_proxy = self.args.session_proxy;
+        ensure!(
+            !session_proxy || session,
+            "--session-proxy can only be used with --session"
+        );
+        let netscape_options = output::NetscapeOptions {
+            line_ending: if self.args.crlf {
+                output::LineEnding::CrLf
+            } else {
+                output::LineEnding::Lf
+            },
+            http_only_prefix: self.args.http_only_prefix,
+        };
+        let template = self.args.template.as_ref();
+        if let crate::Mode::Output { format, .. } | crate::Mode::Render { format, .. } =
+            &self.args.mode
+        {
+            ensure!(
+                *format != Some(crate::OutputFormat::Template) || template.is_some(),
+                "--format template requires --template"
+            );
+        }
+        let format_options = output::FormatOptions {
+            netscape: netscape_options,
+            #[cfg(feature = "human")]
+            human: output::HumanOptions {
+                color: !self.args.no_color
+                    && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()),
+                max_value_width: (self.args.max_value_width > 0)
+                    .then_some(self.args.max_value_width),
+            },
+            classifier: self.args.classify.then(Classifier::current),
+            raw_values: source_options.raw_values,
+            sql_table: self.args.sql_table.as_str(),
+            template,
+        };
+        let wrap_defaults = match &self.args.mode {
+            crate::Mode::Wrap { command, .. } => self.config.wrap.get(command),
+            _ => None,
+        };
+        let drop_categories = self
+            .config
+            .drop_categories
+            .iter()
+            .chain(wrap_defaults.map_or(&[][..], |defaults| &defaults.drop_categories))
+            .chain(&self.args.drop_categories)
+            .copied()
+            .collect::<Vec<_>>();
+        ensure!(
+            !self.args.reverse || self.args.sort.is_some(),
+            "--reverse can only be used with --sort"
+        );
+        let explain = self.args.explain;
+        ensure!(
+            explain.is_none() || !session,
+            "--explain cannot be used with --session"
+        );
+
+        let host_aliases = self.args.resolve_aliases.then(|| {
+            let mut aliases = HostAliases::default();
+            aliases.add_config(&self.config.host_aliases);
+            // The hosts file is missing on some systems.
+            if let Ok(content) = fs::read_to_string(HostAliases::system_hosts_path()) {
+                aliases.add_hosts_file(&content);
+            }
+            aliases
+        });
+        let resolve_hosts = |hosts: Vec<Uri>| match &host_aliases {
+            Some(aliases) => aliases.expand(hosts),
+            None => hosts,
+        };
+
+        // Read the cookies from the session or the browser, and filter them.
+        let read_filtered_cookies = |hosts: Vec<Uri>| -> Result<Vec<BrowserCookie>> {
+            let hosts = resolve_hosts(hosts);
+            let mut cookies = if session {
+                let mut builder = SessionBuilder::new(browser, session_urls.clone(), hosts);
+                if session_proxy {
+                    builder = builder.record_with_proxy();
+                }
+                let session = builder.build()?;
+                session.cookies().to_vec()
+            } else if let Some(format) = explain {
+                // All the cookies are read to explain why they are filtered out.
+                let mut cookies = App::get_cookies(&source_options, Vec::new())?;
+                self.policy.retain_allowed(&mut cookies);
+
+                return explain::filter(
+                    cookies,
+                    &hosts,
+                    &drop_categories,
+                    OffsetDateTime::now_utc(),
+                    format,
+                    &mut io::stderr().lock(),
+                )
+                .wrap_err("Could not write the explanations");
+            } else {
+                App::get_cookies(&source_options, hosts)?
+            };
+            self.policy.retain_allowed(&mut cookies);
+            drop_cookie_categories(&mut cookies, &drop_categories);
+
+            Ok(cookies)
+        };
+        let read_cookies = |hosts: Vec<Uri>| -> Result<Vec<BrowserCookie>> {
+            let mut cookies = read_filtered_cookies(hosts)?;
+            if let Some(key) = self.args.sort {
+                sort::sort(&mut cookies, key, self.args.reverse);
+            }
+
+            Ok(cookies)
+        };
 
         match self.args.mode {
-            crate::Mode::Output { format, hosts } => {
-                let cookies = if session {
-                    let session = SessionBuilder::new(browser, session_urls, hosts).build()?;
-                    session.cookies().to_vec()
-                } else {
-                    App::get_cookies(self.args.root_path, self.args.bypass_lock, browser, hosts)?
+            crate::Mode::Output {
+                format,
+                to_pass,
+                pass_command,
+                output: output_dir,
+                httpie_session,
+                to_firefox_db,
+                to_chromium_db,
+                chromium_key,
+                raw_and_decrypted: _,
+                changed_since_file,
+                merge: merge_file,
+                hosts,
+            } => {
+                let httpie_dir = format == Some(crate::OutputFormat::HttpieDir);
+                ensure!(
+                    httpie_dir || output_dir.is_none(),
+                    "--output can only be used with --format httpie-dir"
+                );
+                // The sessions are written in the sessions directory of HTTPie by default.
+                let output_dir = match output_dir {
+                    Some(dir) => Some(dir),
+                    None if httpie_dir => Some(httpie_dir::sessions_dir().ok_or_else(|| {
+                        eyre!("Could not find the configuration directory of HTTPie")
+                            .suggestion("Set the sessions directory with --output")
+                    })?),
+                    None => None,
                 };
+                ensure!(
+                    !httpie_dir || to_pass.is_none(),
+                    "--format httpie-dir cannot be used with --to-pass"
+                );
+                ensure!(
+                    to_firefox_db.is_none() || (format.is_none() && to_pass.is_none()),
+                    "--to-firefox-db cannot be used with --format or --to-pass"
+                );
+                ensure!(
+                    to_chromium_db.is_none()
+                        || (format.is_none() && to_pass.is_none() && to_firefox_db.is_none()),
+                    "--to-chromium-db cannot be used with --format, --to-pass or --to-firefox-db"
+                );
+                ensure!(
+                    chromium_key.is_none() || to_chromium_db.is_some(),
+                    "--chromium-key can only be used with --to-chromium-db"
+                );
+                let database = to_firefox_db.as_ref().or(to_chromium_db.as_ref());
+                if let Some(path) = database {
+                    ensure!(!path.exists(), "{} already exists", path.display());
+                }
+                ensure!(
+                    merge_file.is_none()
+                        || (matches!(format, None | Some(crate::OutputFormat::Netscape))
+                            && to_pass.is_none()
+                            && database.is_none()),
+                    "--merge can only be used with the netscape format, \
+                    without --to-pass, --to-firefox-db or --to-chromium-db"
+                );
+                if to_pass.is_none()
+                    && database.is_none()
+                    && output_dir.is_none()
+                    && merge_file.is_none()
+                {
+                    confirm::terminal_output(self.args.yes && !self.policy.require_confirmation)?;
+                }
+                let previous_export = changed_since_file
+                    .as_deref()
+                    .map(PreviousExport::load)
+                    .transpose()?;
 
-                let mut stream = BufWriter::new(std::io::stdout().lock());
+                let mut cookies = read_cookies(hosts.clone())?;
+                if let Some(previous_export) = &previous_export {
+                    previous_export.retain_changed(&mut cookies);
+                }
+                output::handle_partitioned(
+                    &mut cookies,
+                    Some(format.unwrap_or(crate::OutputFormat::Netscape)),
+                    self.args.on_unsupported,
+                );
+                if let Browser::ChromeVariant(_) = browser {
+                    output::warn_device_bound(&cookies);
+                }
+
+                // The cookies are written to stdout unless they are stored in the password store.
+                #[cfg(feature = "human")]
+                let format_options = output::FormatOptions {
+                    human: format_options.human.for_destination(
+                        to_pass.is_none() && io::IsTerminal::is_terminal(&io::stdout()),
+                    ),
+                    ..format_options
+                };
+
+                let write_cookies = |mut stream: &mut dyn Write| {
+                    output::write_cookies(
+                        format.unwrap_or(crate::OutputFormat::Netscape),
+                        &cookies,
+                        &format_options,
+                        &mut stream,
+                    )
+                };
+
+                if let Some(dir) = output_dir {
+                    let sessions =
+                        httpie_dir::write_sessions(&cookies, &hosts, &dir, &httpie_session)
+                            .wrap_err_with(|| {
+                                format!("Failed to write the sessions to {}", dir.display())
+                            })?;
+                    for (path, count) in sessions {
+                        eprintln!("{}: {count} cookie(s)", path.display());
+                    }
+
+                    return Ok(None);
+                }
+
+                if let Some(path) = merge_file {
+                    let jar = CookieJar::load(&path)?;
+                    let netscape_options = output::NetscapeOptions {
+                        http_only_prefix: netscape_options.http_only_prefix || jar.http_only_prefix,
+                        ..netscape_options
+                    };
+                    // The extracted cookies replace the ones of the file.
+                    let (cookies, _) = merge::merge(vec![
+                        ("extracted".to_string(), cookies),
+                        (path.display().to_string(), jar.cookies),
+                    ]);
+
+                    let mut content = Vec::new();
+                    output::netscape(&cookies, netscape_options, &mut content)?;
+                    jar::write_atomically(&path, &content)
+                        .wrap_err_with(|| format!("Failed to write {}", path.display()))?;
+                    eprintln!("{}: {} cookie(s)", path.display(), cookies.len());
 
-                let formatter = match format.unwrap_or(crate::OutputFormat::Netscape) {
-                    crate::OutputFormat::Netscape => output::netscape,
-                    #[cfg(feature = "human")]
-                    crate::OutputFormat::Human => output::human,
-                    crate::OutputFormat::HttpieSession => output::httpie_session,
+                    return Ok(None);
+                }
+
+                if let Some(path) = to_firefox_db {
+                    // A partially written database is removed if gateau is interrupted.
+                    interrupt::remove_on_interrupt(&path);
+                    let result = firefox::write_database(&path, &cookies);
+                    interrupt::forget(&path);
+
+                    return result
+                        .wrap_err_with(|| format!("Failed to create {}", path.display()))
+                        .map(|_| None);
+                }
+
+                if let Some(path) = to_chromium_db {
+                    interrupt::remove_on_interrupt(&path);
+                    let result = chrome::write_database(
+                        &path,
+                        &cookies,
+                        chromium_key.as_ref().map(|key| key.0.as_slice()),
+                    );
+                    interrupt::forget(&path);
+
+                    return result
+                        .wrap_err_with(|| format!("Failed to create {}", path.display()))
+                        .map(|_| None);
+                }
+
+                if let Some(entry) = to_pass {
+                    let mut cookies_buf = Zeroizing::new(Vec::new());
+                    write_cookies(&mut *cookies_buf)?;
+
+                    return pass::insert(&pass_command, &entry, &cookies_buf).map(|_| None);
+                }
+
+                let _graceful = interrupt::Graceful::enter();
+                let mut stream = Interruptible::new(BufWriter::new(std::io::stdout().lock()));
+
+                let result = write_cookies(&mut stream).and_then(|_| stream.flush());
+                finish_output(result, &mut stream, format)
+            }
+
+            crate::Mode::Render { env, format, hosts } => {
+                ensure!(
+                    format != Some(crate::OutputFormat::HttpieDir),
+                    "--format httpie-dir can only be used with the output command"
+                );
+                let environment = self.config.environments.get(&env).ok_or_else(|| {
+                    eyre!("Environment {env} not found").suggestion(
+                        "Declare it in the environments table of the configuration file",
+                    )
+                })?;
+                confirm::terminal_output(self.args.yes && !self.policy.require_confirmation)?;
+
+                let mut cookies = read_cookies(hosts)?;
+                output::handle_partitioned(
+                    &mut cookies,
+                    Some(format.unwrap_or(crate::OutputFormat::Netscape)),
+                    self.args.on_unsupported,
+                );
+                let cookies = render::render(cookies, environment);
+
+                let _graceful = interrupt::Graceful::enter();
+                let mut stream = Interruptible::new(BufWriter::new(std::io::stdout().lock()));
+                #[cfg(feature = "human")]
+                let format_options = output::FormatOptions {
+                    human: format_options
+                        .human
+                        .for_destination(io::IsTerminal::is_terminal(&io::stdout())),
+                    ..format_options
                 };
+                let result = output::write_cookies(
+                    format.unwrap_or(crate::OutputFormat::Netscape),
+                    &cookies,
+                    &format_options,
+                    &mut stream,
+                )
+                .and_then(|_| stream.flush());
 
-                formatter(&cookies, &mut stream)
+                finish_output(result, &mut stream, format)
+            }
+
+            crate::Mode::Header { url } => {
+                confirm::terminal_output(self.args.yes && !self.policy.require_confirmation)?;
+
+                let mut cookies = read_cookies(vec![url.clone()])?;
+                output::handle_partitioned(&mut cookies, None, self.args.on_unsupported);
+
+                let header = header::cookie_header(&cookies, &url, OffsetDateTime::now_utc());
+                writeln!(std::io::stdout().lock(), "{header}")
                     .map(|_| None)
                     .or_else(|e| match e {
                         e if e.kind() == io::ErrorKind::BrokenPipe => Ok(None),
@@ -153,73 +894,935 @@ impl App {
                     .wrap_err("Could not output cookies to the provided stream")
             }
 
+            crate::Mode::Lint { url } => {
+                let cookies = read_cookies(vec![url.clone()])?;
+                let warnings = lint::lint(&cookies, &url, OffsetDateTime::now_utc());
+
+                let mut stream = BufWriter::new(std::io::stdout().lock());
+                if warnings.is_empty() {
+                    writeln!(stream, "No issue found for {url}")
+                } else {
+                    warnings
+                        .iter()
+                        .try_for_each(|warning| writeln!(stream, "Warning: {warning}"))
+                }
+                .and_then(|_| stream.flush())
+                .map(|_| (!warnings.is_empty()).then_some(1))
+                .or_else(|e| match e {
+                    e if e.kind() == io::ErrorKind::BrokenPipe => Ok(None),
+                    _ => Err(e),
+                })
+                .wrap_err("Could not output the warnings to the provided stream")
+            }
+
             crate::Mode::Wrap {
+                #[cfg(unix)]
+                fd,
+                exec,
+                all_cookies,
+                refresh,
+                as_header,
                 command,
                 forwarded_args,
             } => {
-                let (cmd, option, formatter): (_, _, fn(_, _) -> _) = match command {
-                    crate::WrappedCmd::Curl => ("curl", "-b", output::netscape),
-                    crate::WrappedCmd::Wget => ("wget", "--load-cookies", output::netscape),
-                    crate::WrappedCmd::HttpieHttp | crate::WrappedCmd::HttpieHttps => {
-                        let cmd = match command {
-                            crate::WrappedCmd::HttpieHttp => "http",
-                            crate::WrappedCmd::HttpieHttps => "https",
-                            _ => unreachable!(),
-                        };
-
-                        (cmd, "--session", output::httpie_session)
+                #[cfg(not(unix))]
+                let fd = false;
+                let (command, forwarded_args) = match command.as_str() {
+                    wrapped::EXEC => {
+                        let mut args = forwarded_args.into_iter();
+                        let binary = args
+                            .next()
+                            .ok_or_else(|| eyre!("wrap exec requires the command to run"))?
+                            .into_string()
+                            .map_err(|binary| {
+                                eyre!("Invalid command name {}", binary.to_string_lossy())
+                            })?;
+                        (WrappedCmd::exec(binary), args.collect())
+                    }
+                    name => {
+                        let mut wrapped_cmds = WrappedCmds::builtin();
+                        wrapped_cmds.register(self.config.wrapped_commands);
+                        let command = wrapped_cmds.get(name).map_err(|e| eyre!(e))?.clone();
+                        (command, forwarded_args)
+                    }
+                };
+                let command = &command;
+
+                match command.format {
+                    crate::OutputFormat::Template if template.is_none() => bail!(
+                        "The template format requires --template to wrap {}",
+                        command.binary
+                    ),
+                    crate::OutputFormat::HttpieDir => bail!(
+                        "The httpie-dir format cannot be used to wrap {}",
+                        command.binary
+                    ),
+                    _ => {}
+                }
+                // The cookies are written to a file, which is never a terminal.
+                #[cfg(feature = "human")]
+                let format_options = output::FormatOptions {
+                    human: format_options.human.for_destination(false),
+                    ..format_options
+                };
+                let formatter = |cookies: &[BrowserCookie], writer: &mut Vec<u8>| {
+                    output::write_cookies(command.format, cookies, &format_options, writer)
+                };
+                // The arguments from the configuration come first, so they can be overridden.
+                let forwarded_args = wrap_defaults
+                    .into_iter()
+                    .flat_map(|defaults| defaults.args.iter().map(OsString::from))
+                    .chain(forwarded_args)
+                    .collect::<Vec<_>>();
+
+                // The copied profile cannot be removed once gateau has been replaced by the command,
+                // so it is removed as soon as the cookies have been read.
+                let mut copied_profile = copied_profile;
+                let mut read_cookies = |hosts: Vec<Uri>| {
+                    let cookies = read_cookies(hosts);
+                    if exec {
+                        drop(copied_profile.take());
                     }
+                    cookies
                 };
 
-                let cookies = if session {
-                    let session = SessionBuilder::new(browser, session_urls, Vec::new()).build()?;
-                    session.cookies().to_vec()
+                let refresh = refresh.map(|refresh| refresh.0);
+                ensure!(
+                    refresh.is_none_or(|refresh| !refresh.is_zero()),
+                    "The refresh interval must be longer than 0s"
+                );
+                ensure!(
+                    refresh.is_none() || !(fd || exec),
+                    "--refresh cannot be used with --fd or --exec"
+                );
+
+                if as_header {
+                    ensure!(
+                        command.cookies != CookieArgs::Placeholders,
+                        "--as-header cannot be used with exec, which replaces {} instead",
+                        wrapped::COOKIE_HEADER
+                    );
+                    ensure!(
+                        !command.header_args.is_empty(),
+                        "{} has no arguments passing the Cookie header",
+                        command.name
+                    );
+                    ensure!(
+                        refresh.is_none() && !fd,
+                        "--as-header cannot be used with --fd or --refresh"
+                    );
+                    let url = wrapped::request_url(&forwarded_args).ok_or_else(|| {
+                        eyre!(
+                            "Could not find the URL requested by {} in its arguments",
+                            command.binary
+                        )
+                    })?;
+                    let mut cookies = read_cookies(vec![url.clone()])?;
+                    output::handle_partitioned(&mut cookies, None, self.args.on_unsupported);
+                    if let Browser::ChromeVariant(_) = browser {
+                        output::warn_device_bound(&cookies);
+                    }
+
+                    let cookie_header =
+                        header::cookie_header(&cookies, &url, OffsetDateTime::now_utc());
+                    return App::run_wrapped(
+                        command,
+                        command.header_args(&cookie_header, &forwarded_args),
+                        exec,
+                    )
+                    .map(Some);
+                }
+
+                if command.cookies == CookieArgs::Pairs {
+                    ensure!(
+                        refresh.is_none(),
+                        "--refresh requires a cookie file, which {} does not read",
+                        command.binary
+                    );
+                    let url = wrapped::request_url(&forwarded_args).ok_or_else(|| {
+                        eyre!(
+                            "Could not find the URL requested by {} in its arguments",
+                            command.binary
+                        )
+                    })?;
+                    let mut cookies = read_cookies(vec![url.clone()])?;
+                    output::handle_partitioned(&mut cookies, None, self.args.on_unsupported);
+                    if let Browser::ChromeVariant(_) = browser {
+                        output::warn_device_bound(&cookies);
+                    }
+
+                    let sent = header::sent_cookies(&cookies, &url, OffsetDateTime::now_utc());
+                    return App::run_wrapped(
+                        command,
+                        command.pair_args(&sent, &forwarded_args),
+                        exec,
+                    )
+                    .map(Some);
+                }
+
+                let placeholders = command.cookies == CookieArgs::Placeholders;
+                let uses_cookie_file = !placeholders
+                    || wrapped::uses_placeholder(&forwarded_args, wrapped::COOKIE_FILE);
+                let header_url = if placeholders
+                    && wrapped::uses_placeholder(&forwarded_args, wrapped::COOKIE_HEADER)
+                {
+                    Some(wrapped::request_url(&forwarded_args).ok_or_else(|| {
+                        eyre!(
+                            "{} requires an HTTP(S) URL in the arguments",
+                            wrapped::COOKIE_HEADER
+                        )
+                    })?)
                 } else {
-                    App::get_cookies(
-                        self.args.root_path,
-                        self.args.bypass_lock,
-                        browser,
-                        Vec::new(),
-                    )?
+                    None
+                };
+                ensure!(
+                    refresh.is_none() || (uses_cookie_file && header_url.is_none()),
+                    "--refresh can only be used with {}, since the arguments cannot be changed \
+                    while the command is running",
+                    wrapped::COOKIE_FILE
+                );
+                ensure!(
+                    uses_cookie_file || header_url.is_some(),
+                    "The arguments of {} contain neither {} nor {}",
+                    command.binary,
+                    wrapped::COOKIE_FILE,
+                    wrapped::COOKIE_HEADER
+                );
+
+                // Only the cookies of the URL are needed for its header.
+                let hosts = match &header_url {
+                    Some(url) if !uses_cookie_file => vec![url.clone()],
+                    _ if all_cookies => Vec::new(),
+                    _ => {
+                        let urls = wrapped::request_urls(&forwarded_args);
+                        if urls.is_empty() {
+                            return Err(eyre!(
+                                "Could not find the URLs requested by {} in its arguments",
+                                command.binary
+                            )
+                            .suggestion("Pass all the cookies with --all-cookies"));
+                        }
+                        urls
+                    }
+                };
+                let mut cookies = read_cookies(hosts.clone())?;
+                // The cookie file keeps the partition keys if its format can, unlike the header.
+                let file_format = uses_cookie_file.then_some(command.format);
+                output::handle_partitioned(&mut cookies, file_format, self.args.on_unsupported);
+                if let Browser::ChromeVariant(_) = browser {
+                    output::warn_device_bound(&cookies);
+                }
+                let cookie_header = header_url
+                    .map(|url| header::cookie_header(&cookies, &url, OffsetDateTime::now_utc()));
+
+                if !uses_cookie_file {
+                    let args = wrapped::substitute_placeholders(
+                        &forwarded_args,
+                        None,
+                        cookie_header.as_deref(),
+                    );
+                    return App::run_wrapped(command, args, exec).map(Some);
+                }
+
+                let format_cookies = |cookies: &[BrowserCookie]| -> Result<Zeroizing<Vec<u8>>> {
+                    let capacity = (64 * cookies.len()).next_power_of_two();
+                    let mut cookies_buf: Zeroizing<Vec<u8>> =
+                        Zeroizing::new(Vec::with_capacity(capacity));
+                    formatter(cookies, &mut cookies_buf)?;
+                    Ok(cookies_buf)
+                };
+                let cookies_buf = format_cookies(&cookies)?;
+                drop(cookies);
+
+                let mut refresh_cookies = || {
+                    let mut cookies = read_cookies(hosts.clone())?;
+                    output::handle_partitioned(&mut cookies, file_format, self.args.on_unsupported);
+                    format_cookies(&cookies)
+                };
+                let refresh = refresh
+                    .map(|interval| (interval, &mut refresh_cookies as &mut CookieRefresher<'_>));
+
+                App::wrap_command(command, cookies_buf, fd, exec, refresh, |path| {
+                    if placeholders {
+                        wrapped::substitute_placeholders(
+                            &forwarded_args,
+                            Some(path),
+                            cookie_header.as_deref(),
+                        )
+                    } else {
+                        command.args(path, &forwarded_args)
+                    }
+                })
+                .map(Some)
+            }
+
+            crate::Mode::Expiring { within, hosts } => {
+                let cookies = read_cookies(hosts)?;
+
+                let mut stream = BufWriter::new(std::io::stdout().lock());
+
+                expiring::expiring(&cookies, OffsetDateTime::now_utc(), within.0, &mut stream)
+                    .and_then(|_| stream.flush())
+                    .map(|_| None)
+                    .or_else(|e| match e {
+                        e if e.kind() == io::ErrorKind::BrokenPipe => Ok(None),
+                        _ => Err(e),
+                    })
+                    .wrap_err("Could not output cookies to the provided stream")
+            }
+
+            crate::Mode::Tail { interval, hosts } => {
+                ensure!(!session, "Cookies cannot be tailed in a session");
+                ensure!(
+                    explain.is_none(),
+                    "--explain cannot be used to tail cookies"
+                );
+                confirm::terminal_output(self.args.yes && !self.policy.require_confirmation)?;
+
+                let hosts = resolve_hosts(hosts);
+                let mut source = CookieSource::open(&source_options, hosts.clone())?;
+                let database = source.database().map(Path::to_path_buf);
+                let mut stamp = database.as_deref().map(DatabaseStamp::of);
+
+                let poll = || {
+                    // The database is reopened when it changes, since a connection bypassing
+                    // the lock treats it as immutable and would not see the writes of the browser.
+                    if let Some(database) = &database {
+                        let current = DatabaseStamp::of(database);
+                        if stamp.as_ref() != Some(&current) {
+                            source = CookieSource::open(&source_options, hosts.clone())?;
+                            stamp = Some(current);
+                        }
+                    }
+
+                    let mut cookies = source.get_cookies()?;
+                    self.policy.retain_allowed(&mut cookies);
+                    drop_cookie_categories(&mut cookies, &drop_categories);
+                    Ok(cookies)
                 };
 
-                let capacity = (64 * cookies.len()).next_power_of_two();
-                let mut cookies_buf = Vec::with_capacity(capacity);
+                let _graceful = interrupt::Graceful::enter();
+                let mut stream = std::io::stdout().lock();
+                tail::tail(poll, Duration::from_secs(interval), &mut stream)
+                    .map(|_| interrupt::interrupted().then_some(interrupt::SIGNAL_EXIT_CODE))
+                    .or_else(|e| match e.downcast_ref::<io::Error>() {
+                        Some(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(None),
+                        _ => Err(e),
+                    })
+            }
+
+            crate::Mode::Browsers => {
+                let mut stream = BufWriter::new(std::io::stdout().lock());
+
+                chrome::scan_installations()
+                    .into_iter()
+                    .try_for_each(|installation| {
+                        writeln!(
+                            stream,
+                            "{}\t{}",
+                            installation.name,
+                            installation.root_dir.display()
+                        )
+                    })
+                    .and_then(|_| stream.flush())
+                    .map(|_| None)
+                    .or_else(|e| match e {
+                        e if e.kind() == io::ErrorKind::BrokenPipe => Ok(None),
+                        _ => Err(e),
+                    })
+                    .wrap_err("Could not output browsers to the provided stream")
+            }
+
+            crate::Mode::InspectDb { path } => {
+                let info = inspect::inspect_database(&path, self.args.bypass_lock)
+                    .wrap_err_with(|| format!("Failed to inspect {}", path.display()))
+                    .map_err(|e| with_confinement_hint(e, &path))?;
 
-                formatter(&cookies, &mut cookies_buf)?;
+                let mut stream = std::io::stdout().lock();
+                serde_json::to_writer_pretty(&mut stream, &info)?;
+                writeln!(stream)?;
 
-                App::wrap_command(cmd, option, &forwarded_args, cookies_buf).map(Some)
+                Ok(None)
+            }
+
+            crate::Mode::ShellInit {
+                prefix,
+                shell,
+                options,
+            } => {
+                ensure!(
+                    prefix
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-')),
+                    "The prefix can only contain letters, digits, '_' and '-'"
+                );
+                let options = options
+                    .into_iter()
+                    .map(|option| {
+                        option
+                            .into_string()
+                            .map_err(|option| eyre!("Invalid option {}", option.to_string_lossy()))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                let mut wrapped_cmds = WrappedCmds::builtin();
+                wrapped_cmds.register(self.config.wrapped_commands);
+                // The functions call gateau the way it has been called, so it is found the same way,
+                // unless it has been called by a relative path, which depends on the directory.
+                let program = match std::env::args().next() {
+                    Some(program) if !program.contains(std::path::MAIN_SEPARATOR) => program,
+                    _ => std::env::current_exe()
+                        .ok()
+                        .and_then(|path| path.into_os_string().into_string().ok())
+                        .unwrap_or_else(|| "gateau".to_string()),
+                };
+
+                let mut stream = BufWriter::new(std::io::stdout().lock());
+                shell_init::shell_init(
+                    shell,
+                    &program,
+                    &options,
+                    &prefix,
+                    &wrapped_cmds.commands(),
+                    &mut stream,
+                )
+                .and_then(|_| stream.flush())
+                .map(|_| None)
+                .wrap_err("Could not output the shell functions to the provided stream")
             }
         }
     }
 }
 
-fn filter_hosts(domain: &str, hosts: &[Uri]) -> bool {
-    let cookie_valid_domain = match domain.chars().next() {
-        Some('.') => domain.get(1..).unwrap(),
-        _ => domain,
+/// Location of the cookies of a browser, and how they are read.
+#[derive(Debug, Clone)]
+struct SourceOptions {
+    /// Root path of the browser, or `None` to use its default profile.
+    root_dir: Option<PathBuf>,
+    /// Root paths of other instances of the browser, whose cookies are merged
+    /// with a lower precedence.
+    other_root_dirs: Vec<PathBuf>,
+    browser: Browser,
+    /// Other browsers whose default profiles are read, and whose cookies are merged
+    /// with a lower precedence.
+    other_browsers: Vec<Browser>,
+    channel: Option<FirefoxChannel>,
+    bypass_lock: bool,
+    wait_for_close: bool,
+    unencrypted: bool,
+    /// SameSite attribute given to the cookies of Chrome set without one.
+    unspecified_same_site: Option<SameSite>,
+    /// Exclude the cookies created or updated after this time.
+    as_of: Option<OffsetDateTime>,
+    /// Print the decryption statistics after reading the cookies.
+    decryption_stats: bool,
+    /// Also read the `Extension Cookies` database of Chrome, if it exists.
+    include_extension_cookies: bool,
+    /// Keep the values of Chrome as stored in the database, to output them.
+    raw_values: bool,
+}
+
+/// Reads the cookies again and formats them for a wrapped command.
+type CookieRefresher<'a> = dyn FnMut() -> Result<Zeroizing<Vec<u8>>> + 'a;
+
+/// Opened cookies database of a browser, which can be queried several times.
+enum CookieSource {
+    Firefox {
+        browser: Browser,
+        manager: FirefoxManager<firefox::PathProvider>,
+        cookies_db: PathBuf,
+    },
+    Chrome {
+        manager: chrome::ChromeManager<chrome::PathProvider>,
+        cookies_db: PathBuf,
+        /// Manager of the `Extension Cookies` database, if it is read.
+        extension_manager: Option<Box<chrome::ChromeManager<chrome::PathProvider>>>,
+    },
+    WebKitGtk {
+        browser: Browser,
+        manager: WebKitGtkManager<webkitgtk::PathProvider>,
+        cookies_db: PathBuf,
+    },
+    InternetExplorer {
+        browser: Browser,
+        manager: InternetExplorerManager<internet_explorer::PathProvider>,
+    },
+}
+
+impl CookieSource {
+    /// Open the cookies database of the specified browser,
+    /// filtering the cookies by the provided hosts.
+    fn open(options: &SourceOptions, hosts: Vec<Uri>) -> Result<Self> {
+        let SourceOptions {
+            ref root_dir,
+            other_root_dirs: _,
+            browser,
+            other_browsers: _,
+            channel,
+            bypass_lock,
+            wait_for_close,
+            unencrypted,
+            unspecified_same_site,
+            as_of,
+            decryption_stats: _,
+            include_extension_cookies,
+            raw_values,
+        } = *options;
+        let hosts = Arc::from(hosts);
+
+        match browser {
+            Browser::FirefoxVariant(firefox_variant) => {
+                let path_provider = match (root_dir, channel) {
+                    (Some(root_dir), _) => firefox::PathProvider::from_root(root_dir),
+                    (None, Some(channel)) => {
+                        firefox::PathProvider::channel_profile(firefox_variant, channel)
+                            .ok_or_else(|| eyre!("No profile found for {channel}"))?
+                    }
+                    (None, None) => firefox::PathProvider::default_profile(firefox_variant),
+                };
+                check_profile_lock(&path_provider, browser, bypass_lock, wait_for_close)?;
+                let cookies_db = path_provider.cookies_database();
+
+                let hosts = Arc::clone(&hosts);
+                let filter = Box::from(move |host: &str| {
+                    let hosts = Arc::clone(&hosts);
+                    filter_hosts(host, &hosts)
+                });
+
+                let mut manager = FirefoxManager::new(path_provider, Some(filter), bypass_lock)
+                    .map_err(|e| with_confinement_hint(e.into(), &cookies_db))?;
+                if let Some(as_of) = as_of {
+                    manager = manager.as_of(as_of);
+                }
+
+                Ok(CookieSource::Firefox {
+                    browser,
+                    manager,
+                    cookies_db,
+                })
+            }
+
+            Browser::ChromeVariant(chrome_variant) => {
+                let path_provider = if let Some(root_dir) = root_dir {
+                    chrome::PathProvider::from_variant_root(chrome_variant, root_dir)
+                } else {
+                    chrome::PathProvider::default_profile(chrome_variant)
+                };
+                check_profile_lock(&path_provider, browser, bypass_lock, wait_for_close)?;
+                let cookies_db = path_provider.cookies_database();
+
+                let open_manager = |path_provider: chrome::PathProvider| -> Result<_> {
+                    let cookies_db = path_provider.cookies_database();
+                    let hosts = Arc::clone(&hosts);
+                    let filter = Box::from(move |host: &str| filter_hosts(host, &hosts));
+
+                    let mut manager = chrome::ChromeManager::new(
+                        chrome_variant,
+                        path_provider,
+                        Some(filter),
+                        bypass_lock,
+                    )
+                    .map_err(|e| with_confinement_hint(e.into(), &cookies_db))?;
+                    if unencrypted {
+                        manager = manager.without_decryption();
+                    }
+                    if raw_values {
+                        manager = manager.keep_raw_values();
+                    }
+                    if let Some(same_site) = unspecified_same_site {
+                        manager = manager.coerce_unspecified_same_site(same_site);
+                    }
+                    if let Some(as_of) = as_of {
+                        manager = manager.as_of(as_of);
+                    }
+
+                    Ok(manager)
+                };
+
+                let extension_manager = if !include_extension_cookies {
+                    None
+                } else if path_provider.has_extension_cookies() {
+                    Some(Box::new(open_manager(
+                        path_provider.clone().extension_cookies(),
+                    )?))
+                } else {
+                    eprintln!("Warning: no Extension Cookies database found for {browser}");
+                    None
+                };
+
+                Ok(CookieSource::Chrome {
+                    manager: open_manager(path_provider)?,
+                    cookies_db,
+                    extension_manager,
+                })
+            }
+
+            Browser::WebKitGtkVariant(webkitgtk_variant) => {
+                // libsoup does not record when the cookies are created.
+                ensure!(as_of.is_none(), "--as-of is not supported for {browser}");
+
+                let path_provider = if let Some(root_dir) = root_dir {
+                    webkitgtk::PathProvider::from_root(root_dir)
+                } else {
+                    webkitgtk::PathProvider::default_profile(webkitgtk_variant)
+                };
+                let cookies_db = path_provider.cookies_database();
+
+                let filter = Box::from(move |host: &str| filter_hosts(host, &hosts));
+                let manager = WebKitGtkManager::new(path_provider, Some(filter), bypass_lock)
+                    .map_err(|e| with_confinement_hint(e.into(), &cookies_db))?;
+
+                Ok(CookieSource::WebKitGtk {
+                    browser,
+                    manager,
+                    cookies_db,
+                })
+            }
+
+            Browser::InternetExplorerVariant(internet_explorer_variant) => {
+                let path_provider = if let Some(root_dir) = root_dir {
+                    internet_explorer::PathProvider::from_root(root_dir)
+                } else {
+                    internet_explorer::PathProvider::default_profile(internet_explorer_variant)
+                };
+
+                let filter = Box::from(move |host: &str| filter_hosts(host, &hosts));
+                let mut manager = InternetExplorerManager::new(path_provider, Some(filter))?;
+                if let Some(as_of) = as_of {
+                    manager = manager.as_of(as_of);
+                }
+
+                Ok(CookieSource::InternetExplorer { browser, manager })
+            }
+        }
+    }
+
+    /// Returns the path of the cookies database, if the cookies are read from one.
+    fn database(&self) -> Option<&Path> {
+        match self {
+            CookieSource::Firefox { cookies_db, .. }
+            | CookieSource::Chrome { cookies_db, .. }
+            | CookieSource::WebKitGtk { cookies_db, .. } => Some(cookies_db),
+            CookieSource::InternetExplorer { .. } => None,
+        }
+    }
+
+    /// Get the cookies from the database,
+    /// printing the warnings about the skipped and altered cookies.
+    fn get_cookies(&self) -> Result<Vec<BrowserCookie>> {
+        self.get_cookie_set().map(|set| set.cookies)
+    }
+
+    /// Get the cookies from the database with their last access time,
+    /// printing the warnings about the skipped and altered cookies.
+    fn get_cookie_set(&self) -> Result<CookieSet> {
+        let set = match self {
+            CookieSource::Firefox {
+                browser,
+                manager,
+                cookies_db,
+            } => manager
+                .get_cookies_with_report()
+                .wrap_err_with(|| format!("Failed to get cookies from {browser}"))
+                .map_err(|e| with_confinement_hint(e, cookies_db))?,
+
+            CookieSource::Chrome {
+                manager,
+                cookies_db,
+                extension_manager,
+            } => {
+                let mut set = manager
+                    .get_cookies_with_report()
+                    .wrap_err("Failed to get cookies from Chrome")
+                    .map_err(|e| with_confinement_hint(e, cookies_db))?;
+
+                if let Some(extension_manager) = extension_manager {
+                    let extension_set = extension_manager
+                        .get_cookies_with_report()
+                        .wrap_err("Failed to get the extension cookies from Chrome")?;
+                    set.cookies.extend(extension_set.cookies);
+                    set.warnings.extend(extension_set.warnings);
+                }
+
+                set
+            }
+
+            CookieSource::WebKitGtk {
+                browser,
+                manager,
+                cookies_db,
+            } => manager
+                .get_cookies_with_report()
+                .wrap_err_with(|| format!("Failed to get cookies from {browser}"))
+                .map_err(|e| with_confinement_hint(e, cookies_db))?,
+
+            CookieSource::InternetExplorer { browser, manager } => CookieSet {
+                cookies: manager
+                    .get_cookies()
+                    .wrap_err_with(|| format!("Failed to get cookies from {browser}"))?
+                    .into_iter()
+                    .map(BrowserCookie::from)
+                    .collect(),
+                ..CookieSet::default()
+            },
+        };
+
+        for warning in &set.warnings {
+            eprintln!("Warning: {warning}");
+        }
+
+        Ok(set)
+    }
+}
+
+/// Register the Electron or WebView2 application of the data directory
+/// as a Chromium-based browser named after the directory.
+///
+/// The keyring entry is guessed from the name of the application,
+/// other entries can be set by declaring the application in the configuration file.
+fn app_variant(app_dir: &Path) -> Result<ChromeVariant> {
+    let app_dir = fs::canonicalize(app_dir)
+        .wrap_err_with(|| format!("Failed to read application directory {}", app_dir.display()))?;
+    let name = app_dir
+        .file_name()
+        .ok_or_else(|| eyre!("Invalid application directory {}", app_dir.display()))?
+        .to_string_lossy()
+        .into_owned();
+
+    // Electron names the keychain entry after the application on macOS,
+    // and the secret service entry after its lowercased name on Linux.
+    let keyring_account = if cfg!(target_os = "macos") {
+        format!("{name} Key")
+    } else {
+        name.to_lowercase()
     };
 
-    if cookie_valid_domain.is_empty() {
-        return false;
+    Ok(chrome::register_variant(CustomVariant {
+        keyring_service: format!("{name} Safe Storage"),
+        keyring_account,
+        executable: name.clone(),
+        data_dir: app_dir.to_string_lossy().into_owned(),
+        name,
+        app: true,
+    }))
+}
+
+/// Check whether the profile is in use by a running browser,
+/// and either wait for the browser to exit or warn about the consequences.
+fn check_profile_lock<P: CookiePathProvider>(
+    path_provider: &P,
+    browser: Browser,
+    bypass_lock: bool,
+    wait_for_close: bool,
+) -> Result<()> {
+    /// Interval between two checks of the lock while waiting for the browser to exit.
+    const WAIT_INTERVAL: Duration = Duration::from_millis(500);
+    /// Time after which the lock is considered as stale while waiting for the browser to exit.
+    const WAIT_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+    // No browser can be using a profile on a read-only filesystem (e.g. a mounted forensic image),
+    // its lock is the one left when the image was taken.
+    if gateau::is_read_only_filesystem(path_provider.cookies_database()) {
+        return Ok(());
     }
 
-    hosts.is_empty()
-        || hosts.iter().any(|h| {
-            Some(cookie_valid_domain) == h.host()
-                || h.base_domain()
-                    .as_deref()
-                    .or_else(|| h.host())
-                    // either the base domain or the host should be Some
-                    .unwrap()
-                    .ends_with(cookie_valid_domain)
+    let Some(lock) = ProfileLock::detect(path_provider) else {
+        return Ok(());
+    };
+
+    let owner = lock
+        .owner()
+        .map(|owner| format!(" (PID {} on {})", owner.pid, owner.host))
+        .unwrap_or_default();
+
+    if wait_for_close {
+        eprintln!("Waiting for {browser}{owner} to exit...");
+        if !lock.wait(WAIT_INTERVAL, WAIT_TIMEOUT) {
+            bail!(
+                "The profile is still in use by {browser}{owner} after {} minutes, \
+                its lock ({}) may be stale",
+                WAIT_TIMEOUT.as_secs() / 60,
+                lock.path().display()
+            );
+        }
+    } else if bypass_lock {
+        eprintln!(
+            "Warning: the profile is in use by {browser}{owner}, \
+            bypassing the lock can cause read errors (use --wait-for-close to wait for it to exit)"
+        );
+    } else {
+        eprintln!(
+            "Warning: the profile is in use by {browser}{owner}, \
+            the database may be locked (use --wait-for-close to wait for it to exit)"
+        );
+    }
+
+    Ok(())
+}
+
+/// Handle the result of the output of the cookies.
+///
+/// A closed pipe is not an error, and if gateau has been interrupted, what has been written
+/// is flushed and marked as truncated when the format has comments.
+fn finish_output<W: Write>(
+    result: io::Result<()>,
+    stream: &mut Interruptible<W>,
+    format: Option<crate::OutputFormat>,
+) -> Result<Option<i32>> {
+    let result = match result {
+        Err(e) if interrupt::is_interruption(&e) => {
+            match format.unwrap_or(crate::OutputFormat::Netscape) {
+                crate::OutputFormat::Netscape => stream.write_truncation_comment(),
+                _ => stream.flush_written(),
+            }
+            .map(|_| Some(interrupt::SIGNAL_EXIT_CODE))
+        }
+        result => result.map(|_| None),
+    };
+
+    result
+        .or_else(|e| match e {
+            e if e.kind() == io::ErrorKind::BrokenPipe => Ok(None),
+            _ => Err(e),
+        })
+        .wrap_err("Could not output cookies to the provided stream")
+}
+
+/// Add a suggestion to the error when the cookies database cannot be read
+/// because of a security policy (snap confinement, AppArmor, SELinux).
+fn with_confinement_hint(report: Report, path: &Path) -> Report {
+    let denied = fs::File::open(path).is_err_and(|e| e.kind() == io::ErrorKind::PermissionDenied);
+
+    match Confinement::detect().filter(|_| denied) {
+        Some(confinement) => report.suggestion(confinement.hint(path)),
+        None => report,
+    }
+}
+
+/// Load the data files of the directory, in place of the built-in data.
+fn load_data_dir(data_dir: &Path) -> Result<()> {
+    let suffixes = data_dir.join(PUBLIC_SUFFIX_LIST_FILE);
+    let categories = data_dir.join(CATEGORIES_FILE);
+
+    ensure!(
+        suffixes.is_file() || categories.is_file(),
+        "No data file found in {}, expected {PUBLIC_SUFFIX_LIST_FILE} or {CATEGORIES_FILE}",
+        data_dir.display()
+    );
+
+    if suffixes.is_file() {
+        PublicSuffixList::load(&suffixes)?;
+    }
+    if categories.is_file() {
+        Classifier::load(&categories)?;
+    }
+
+    Ok(())
+}
+
+/// Remove the cookies which belong to one of the provided categories.
+fn drop_cookie_categories(cookies: &mut Vec<BrowserCookie>, categories: &[Category]) {
+    if categories.is_empty() {
+        return;
+    }
+
+    let classifier = Classifier::current();
+    cookies.retain(|cookie| {
+        classifier
+            .classify(cookie)
+            .is_none_or(|category| !categories.contains(&category))
+    });
+}
+
+/// Returns the files needed to read the cookies of the browser, relative to its root path,
+/// split between the required ones, given by their candidate locations, and the optional ones.
+///
+/// The locations are not checked, since they are on a remote machine.
+fn remote_profile_files(browser: Browser) -> (Vec<Vec<PathBuf>>, Vec<PathBuf>) {
+    let cookies_dbs = match browser {
+        Browser::FirefoxVariant(_) => {
+            vec![firefox::PathProvider::from_root("").cookies_database()]
+        }
+        Browser::ChromeVariant(_) => {
+            chrome::PathProvider::from_root("").cookies_database_candidates()
+        }
+        Browser::WebKitGtkVariant(_) => {
+            vec![webkitgtk::PathProvider::from_root("").cookies_database()]
+        }
+        Browser::InternetExplorerVariant(_) => {
+            unreachable!("{browser} keeps its cookies in a directory, which cannot be copied")
+        }
+    };
+
+    let mut optional = cookies_dbs
+        .iter()
+        .map(|cookies_db| {
+            let mut journal = cookies_db.clone().into_os_string();
+            journal.push("-wal");
+            PathBuf::from(journal)
         })
+        .collect::<Vec<_>>();
+
+    // The key of the cookies is stored in the `Local State` file on Windows,
+    // next to the profile holding the cookies database at its oldest location.
+    if let Browser::ChromeVariant(_) = browser {
+        let user_data_dir = cookies_dbs
+            .last()
+            .and_then(|cookies_db| cookies_db.parent())
+            .and_then(Path::parent)
+            .unwrap_or(Path::new(""));
+        optional.push(user_data_dir.join("Local State"));
+    }
+
+    (vec![cookies_dbs], optional)
+}
+
+fn filter_hosts(domain: &str, hosts: &[Uri]) -> bool {
+    HostMatch::new(domain, hosts).is_match()
 }
 
 #[cfg(test)]
 mod tests {
+    use cookie::Cookie;
+
     use super::*;
 
+    #[test]
+    #[cfg(unix)]
+    fn test_remote_profile_files() {
+        let (required, optional) =
+            remote_profile_files(Browser::ChromeVariant(chrome::ChromeVariant::Chromium));
+
+        assert_eq!(
+            required,
+            [[
+                PathBuf::from("Default/Network/Cookies"),
+                PathBuf::from("Default/Cookies")
+            ]]
+        );
+        assert_eq!(
+            optional,
+            [
+                PathBuf::from("Default/Network/Cookies-wal"),
+                PathBuf::from("Default/Cookies-wal"),
+                PathBuf::from("Local State")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_drop_cookie_categories() {
+        let mut cookies = vec![
+            Cookie::build(("_ga", "1")).domain(".example.com").into(),
+            Cookie::build(("_fbp", "1")).domain(".example.com").into(),
+            Cookie::build(("session", "1")).domain("example.com").into(),
+        ];
+
+        drop_cookie_categories(&mut cookies, &[Category::Analytics, Category::Advertising]);
+
+        assert_eq!(cookies.len(), 1);
+        assert_eq!(cookies[0].name(), "session");
+    }
+
     #[test]
     fn test_filter_hosts() {
         let hosts = vec![
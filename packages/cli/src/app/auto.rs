@@ -0,0 +1,157 @@
+//! Selection of the most recently used browser, with `--browser auto`.
+
+use std::{fs, path::PathBuf, sync::Arc, time::SystemTime};
+
+use color_eyre::{eyre::eyre, Result, Section};
+use gateau::{
+    chrome::{self, ChromeManager, ChromeManagerError, DecryptChromeCookieError},
+    firefox::{self, FirefoxManager},
+    internet_explorer::{self, InternetExplorerManager},
+    webkitgtk::{self, WebKitGtkManager},
+    Browser, CookiePathProvider,
+};
+use http::Uri;
+
+use super::filter_hosts;
+
+/// Select the browser whose cookies database has been modified the most recently.
+///
+/// Only the browsers accepted by the filter are considered,
+/// and if hosts are provided, the most recent browser which holds cookies for them is preferred.
+pub(super) fn detect_browser<F: Fn(Browser) -> bool>(hosts: &[Uri], filter: F) -> Result<Browser> {
+    let candidates = Browser::builtin()
+        .into_iter()
+        .filter(|&browser| filter(browser))
+        .filter_map(|browser| {
+            let modified = fs::metadata(default_cookies_database(browser)?)
+                .and_then(|metadata| metadata.modified())
+                .ok()?;
+            Some((browser, modified))
+        })
+        .collect::<Vec<_>>();
+
+    let hosts = Arc::<[Uri]>::from(hosts);
+    select(candidates, |browser| {
+        !hosts.is_empty() && holds_cookies(browser, &hosts)
+    })
+    .ok_or_else(|| {
+        eyre!("No cookies database found for the supported browsers")
+            .suggestion("Use --browser to select the browser")
+    })
+}
+
+/// Returns the browsers accepted by the filter whose default profile has a cookies database,
+/// in the order of the built-in browsers.
+pub(super) fn installed_browsers<F: Fn(Browser) -> bool>(filter: F) -> Vec<Browser> {
+    Browser::builtin()
+        .into_iter()
+        .filter(|&browser| filter(browser))
+        .filter(|&browser| default_cookies_database(browser).is_some_and(|path| path.exists()))
+        .collect()
+}
+
+/// Returns the most recently modified browser which satisfies the predicate,
+/// or the most recently modified one if none does.
+fn select<F: FnMut(Browser) -> bool>(
+    mut candidates: Vec<(Browser, SystemTime)>,
+    mut preferred: F,
+) -> Option<Browser> {
+    candidates.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+    candidates
+        .iter()
+        .map(|&(browser, _)| browser)
+        .find(|&browser| preferred(browser))
+        .or_else(|| candidates.first().map(|&(browser, _)| browser))
+}
+
+/// Returns the path to the cookies database of the default profile of the browser,
+/// or `None` if it cannot be located.
+fn default_cookies_database(browser: Browser) -> Option<PathBuf> {
+    let path = match browser {
+        Browser::FirefoxVariant(variant) => {
+            firefox::PathProvider::find_default_profile(variant)?.cookies_database()
+        }
+        Browser::ChromeVariant(variant) => {
+            chrome::PathProvider::default_profile(variant).cookies_database()
+        }
+        Browser::WebKitGtkVariant(variant) => {
+            webkitgtk::PathProvider::default_profile(variant).cookies_database()
+        }
+        Browser::InternetExplorerVariant(variant) => {
+            internet_explorer::PathProvider::default_profile(variant).cookies_database()
+        }
+    };
+
+    Some(path)
+}
+
+/// Returns whether the default profile of the browser holds cookies for the hosts.
+///
+/// The lock is bypassed and the values are not decrypted,
+/// so the running browsers are not disturbed and the keyring is not accessed.
+fn holds_cookies(browser: Browser, hosts: &Arc<[Uri]>) -> bool {
+    let hosts = Arc::clone(hosts);
+    let filter = Box::from(move |host: &str| filter_hosts(host, &hosts));
+
+    match browser {
+        Browser::FirefoxVariant(variant) => {
+            let Some(path_provider) = firefox::PathProvider::find_default_profile(variant) else {
+                return false;
+            };
+
+            FirefoxManager::new(path_provider, Some(filter), true)
+                .and_then(|manager| manager.get_cookies())
+                .is_ok_and(|cookies| !cookies.is_empty())
+        }
+        Browser::ChromeVariant(variant) => {
+            let cookies = ChromeManager::default_profile(variant, Some(filter), true)
+                .and_then(|manager| manager.without_decryption().get_cookies());
+
+            match cookies {
+                Ok(cookies) => !cookies.is_empty(),
+                // Only the cookies of the hosts are read, so an encrypted value is a match.
+                Err(ChromeManagerError::CookieValueDecrypt {
+                    source: DecryptChromeCookieError::DecryptionDisabled,
+                }) => true,
+                Err(_) => false,
+            }
+        }
+        Browser::WebKitGtkVariant(variant) => {
+            WebKitGtkManager::default_profile(variant, Some(filter), true)
+                .and_then(|manager| manager.get_cookies())
+                .is_ok_and(|cookies| !cookies.is_empty())
+        }
+        Browser::InternetExplorerVariant(variant) => {
+            InternetExplorerManager::default_profile(variant, Some(filter))
+                .and_then(|manager| manager.get_cookies())
+                .is_ok_and(|cookies| !cookies.is_empty())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use gateau::{chrome::ChromeVariant, firefox::FirefoxVariant};
+
+    use super::*;
+
+    #[test]
+    fn test_select() {
+        let firefox = Browser::FirefoxVariant(FirefoxVariant::Firefox);
+        let chrome = Browser::ChromeVariant(ChromeVariant::Chrome);
+        let candidates = vec![
+            (firefox, SystemTime::UNIX_EPOCH),
+            (chrome, SystemTime::UNIX_EPOCH + Duration::from_secs(60)),
+        ];
+
+        assert_eq!(select(candidates.clone(), |_| false), Some(chrome));
+        assert_eq!(
+            select(candidates, |browser| browser == firefox),
+            Some(firefox)
+        );
+        assert_eq!(select(Vec::new(), |_| true), None);
+    }
+}
@@ -0,0 +1,175 @@
+//! Comparison of the cookies against a previous JSON export, for incremental syncs.
+
+use std::{collections::HashMap, fs, path::Path};
+
+use color_eyre::{eyre::Context, Result};
+use cookie::{time::OffsetDateTime, Cookie, SameSite};
+use gateau::report::{self, BrowserCookie, CookieKey};
+use serde::Deserialize;
+
+/// Cookie of a previous export, in the JSON output format.
+#[derive(Debug, Deserialize)]
+struct ExportedCookie {
+    name: String,
+    value: String,
+    domain: Option<String>,
+    path: Option<String>,
+    expires: Option<i64>,
+    #[serde(default)]
+    secure: bool,
+    #[serde(default)]
+    http_only: bool,
+    same_site: Option<String>,
+    /// Missing from the exports written before it was added, whose cookies are then domain cookies.
+    #[serde(default)]
+    host_only: bool,
+}
+
+/// State of a cookie: its value, expiration, flags and SameSite attribute.
+type State = (String, Option<i64>, bool, bool, Option<String>);
+
+/// Cookies of a previous export, indexed by their domain (as it is stored), path and name.
+#[derive(Debug, Default)]
+pub(crate) struct PreviousExport {
+    cookies: HashMap<CookieKey, State>,
+}
+
+impl PreviousExport {
+    /// Read an export written with `--format json`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content =
+            fs::read(path).wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+        let cookies: Vec<ExportedCookie> =
+            serde_json::from_slice(&content).wrap_err_with(|| {
+                format!(
+                    "Failed to parse {}, which must be written with --format json",
+                    path.display()
+                )
+            })?;
+
+        Ok(Self {
+            cookies: cookies
+                .into_iter()
+                .map(|cookie| {
+                    let mut exported = Cookie::new(cookie.name, "");
+                    if let Some(domain) = cookie.domain {
+                        exported.set_domain(domain);
+                    }
+                    if let Some(path) = cookie.path {
+                        exported.set_path(path);
+                    }
+                    let key = report::cookie_key(&BrowserCookie {
+                        host_only: cookie.host_only,
+                        ..exported.into()
+                    });
+                    let state = (
+                        cookie.value,
+                        cookie.expires,
+                        cookie.secure,
+                        cookie.http_only,
+                        cookie.same_site,
+                    );
+
+                    (key, state)
+                })
+                .collect(),
+        })
+    }
+
+    /// Keep only the cookies which are not in the export, or whose value, expiration,
+    /// flags or SameSite attribute have changed since.
+    pub fn retain_changed(&self, cookies: &mut Vec<BrowserCookie>) {
+        cookies
+            .retain(|cookie| self.cookies.get(&report::cookie_key(cookie)) != Some(&state(cookie)));
+    }
+}
+
+/// Returns the state of the cookie, as it is exported in JSON.
+///
+/// The expiration is compared to the second, which is the precision of the export.
+fn state(cookie: &Cookie<'_>) -> State {
+    (
+        cookie.value().to_string(),
+        cookie
+            .expires_datetime()
+            .map(OffsetDateTime::unix_timestamp),
+        cookie.secure().unwrap_or_default(),
+        cookie.http_only().unwrap_or_default(),
+        cookie.same_site().map(|same_site| {
+            match same_site {
+                SameSite::Strict => "Strict",
+                SameSite::Lax => "Lax",
+                SameSite::None => "None",
+            }
+            .to_string()
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use cookie::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_retain_changed() {
+        let expires = OffsetDateTime::from_unix_timestamp(2000000000).unwrap();
+        let cookie = |name: &'static str, value: &'static str| -> BrowserCookie {
+            Cookie::build((name, value))
+                .domain("example.com")
+                .path("/")
+                .expires(expires + Duration::milliseconds(500))
+                .same_site(SameSite::Lax)
+                .into()
+        };
+        let mut cookies = vec![
+            cookie("same", "1"),
+            cookie("changed", "3"),
+            cookie("new", "4"),
+        ];
+
+        let mut previous = Vec::new();
+        gateau::output::json(
+            &[cookie("same", "1"), cookie("changed", "2")],
+            &mut previous,
+        )
+        .unwrap();
+        let path = tempfile::NamedTempFile::new().unwrap();
+        fs::write(path.path(), previous).unwrap();
+
+        PreviousExport::load(path.path())
+            .unwrap()
+            .retain_changed(&mut cookies);
+
+        let names = cookies
+            .iter()
+            .map(|cookie| cookie.name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["changed", "new"]);
+    }
+
+    #[test]
+    fn test_retain_changed_host_only() {
+        let cookie = |value: &'static str, host_only: bool| BrowserCookie {
+            host_only,
+            ..Cookie::build(("sid", value))
+                .domain("example.com")
+                .path("/")
+                .into()
+        };
+        let mut cookies = vec![cookie("1", false), cookie("2", true)];
+
+        let mut previous = Vec::new();
+        gateau::output::json(&[cookie("1", false), cookie("1", true)], &mut previous).unwrap();
+        let path = tempfile::NamedTempFile::new().unwrap();
+        fs::write(path.path(), previous).unwrap();
+
+        PreviousExport::load(path.path())
+            .unwrap()
+            .retain_changed(&mut cookies);
+
+        // Only the host-only cookie changed, the domain one is compared to its own export.
+        assert_eq!(cookies, [cookie("2", true)]);
+    }
+}
@@ -0,0 +1,65 @@
+//! Confirmation before printing the cookies to a terminal.
+
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use color_eyre::{
+    eyre::{bail, eyre},
+    Result, Section,
+};
+
+/// Ask for confirmation before printing the cookies to a terminal,
+/// where their values can be read over the shoulder or kept in the scrollback.
+///
+/// Nothing is asked if the standard output is not a terminal (e.g. a pipe or a file),
+/// or if the output has already been confirmed with `--yes`.
+pub(crate) fn terminal_output(yes: bool) -> Result<()> {
+    if yes || !io::stdout().is_terminal() {
+        return Ok(());
+    }
+
+    if !io::stdin().is_terminal() {
+        return Err(
+            eyre!("Refusing to print the cookies to a terminal without confirmation")
+                .suggestion("Use --yes to print them anyway, or redirect the output"),
+        );
+    }
+
+    if !confirm(&mut io::stdin().lock(), &mut io::stderr())? {
+        bail!("Aborted, the cookies have not been printed");
+    }
+
+    Ok(())
+}
+
+/// Ask whether the cookies should be printed, and read the answer (no by default).
+fn confirm<R: BufRead, W: Write>(reader: &mut R, writer: &mut W) -> io::Result<bool> {
+    write!(
+        writer,
+        "The cookie values will be printed to the terminal, continue? [y/N] "
+    )?;
+    writer.flush()?;
+
+    let mut answer = String::new();
+    reader.read_line(&mut answer)?;
+
+    Ok(matches!(
+        answer.trim().to_ascii_lowercase().as_str(),
+        "y" | "yes"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confirm() {
+        let answer = |input: &str| confirm(&mut input.as_bytes(), &mut io::sink()).unwrap();
+
+        assert!(answer("y\n"));
+        assert!(answer("Yes\n"));
+        assert!(!answer("\n"));
+        assert!(!answer("n\n"));
+        assert!(!answer(""));
+    }
+}
@@ -0,0 +1,294 @@
+//! Temporary cookie files passed to the wrapped commands.
+//!
+//! The files are created in a private directory (`$XDG_RUNTIME_DIR/gateau` when available),
+//! are only readable by the current user, and are kept until the wrapped command exits.
+//! Their content is then overwritten before they are removed,
+//! including when gateau is terminated by a signal.
+//!
+//! On Unix, the cookies can instead be passed through a file descriptor inherited by the command
+//! (`/dev/fd/N`), so they are never written to the file system,
+//! except when gateau is replaced by the command outside Linux.
+
+use std::{
+    cell::RefCell,
+    fs, io,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use tempfile::TempPath;
+
+use crate::interrupt;
+
+/// Temporary file holding the cookies, removed when dropped.
+#[derive(Debug)]
+pub(crate) struct CookieFile {
+    path: TempPath,
+    /// Previous files replaced by [`CookieFile::replace`], overwritten when dropped.
+    replaced: RefCell<Vec<fs::File>>,
+}
+
+impl CookieFile {
+    /// Create a temporary file with the provided content.
+    pub fn create(content: &[u8]) -> io::Result<Self> {
+        Ok(Self {
+            path: write_temporary(content)?.into_temp_path(),
+            replaced: RefCell::default(),
+        })
+    }
+
+    /// Replace the content of the file at once, so the command never reads it partially written.
+    ///
+    /// The new content is written to another temporary file which is renamed over this one.
+    /// The command may still be reading the previous file, so its content is only overwritten
+    /// when this one is dropped, once the command has exited.
+    pub fn replace(&self, content: &[u8]) -> io::Result<()> {
+        let previous = fs::OpenOptions::new().write(true).open(&self.path)?;
+
+        let file = write_temporary(content)?;
+        let temporary_path = file.path().to_owned();
+        let persisted = file.persist(&self.path);
+        interrupt::forget(&temporary_path);
+        persisted.map_err(|e| e.error)?;
+
+        self.replaced.borrow_mut().push(previous);
+        Ok(())
+    }
+
+    /// Path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for CookieFile {
+    fn drop(&mut self) {
+        // The files are removed anyway if they cannot be overwritten.
+        for mut previous in self.replaced.take() {
+            let _ = interrupt::shred_file(&mut previous);
+        }
+        let _ = interrupt::shred(&self.path);
+        interrupt::forget(&self.path);
+    }
+}
+
+/// Write the content to a new temporary file, only readable by the current user,
+/// which is overwritten and removed if gateau is interrupted.
+fn write_temporary(content: &[u8]) -> io::Result<tempfile::NamedTempFile> {
+    let mut builder = tempfile::Builder::new();
+    builder.prefix("gateau-");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        builder.permissions(fs::Permissions::from_mode(0o600));
+    }
+
+    let mut file = builder.tempfile_in(private_dir()?)?;
+    interrupt::shred_on_interrupt(file.path());
+
+    file.write_all(content)?;
+    file.flush()?;
+
+    Ok(file)
+}
+
+/// Cookies passed to the wrapped command through a file descriptor which it inherits.
+///
+/// On Linux, the descriptor is an anonymous memory file (`memfd`), which can be read several times.
+/// Elsewhere, it is the end of a pipe written by a thread, which can only be read once.
+#[cfg(unix)]
+#[derive(Debug)]
+pub(crate) struct CookieFd {
+    fd: std::os::fd::OwnedFd,
+}
+
+#[cfg(unix)]
+impl CookieFd {
+    /// Create a descriptor from which the content can be read.
+    #[allow(unsafe_code)]
+    pub fn create(content: &[u8]) -> io::Result<Self> {
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::fd::{FromRawFd, OwnedFd};
+
+            // SAFETY: the name is a valid C string, and the descriptor is owned if it is valid.
+            let fd = unsafe {
+                let fd = libc::memfd_create(c"gateau-cookies".as_ptr(), libc::MFD_CLOEXEC);
+                if fd == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                OwnedFd::from_raw_fd(fd)
+            };
+
+            let mut file = fs::File::from(fd);
+            file.write_all(content)?;
+
+            Ok(Self { fd: file.into() })
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            let (reader, mut writer) = io::pipe()?;
+            let content = zeroize::Zeroizing::new(content.to_vec());
+            // The writes fail once the command exits without reading everything.
+            std::thread::spawn(move || writer.write_all(&content));
+
+            Ok(Self { fd: reader.into() })
+        }
+    }
+
+    /// Create a descriptor which can still be read once gateau has been replaced by the command.
+    ///
+    /// Outside Linux, the thread writing to a pipe would not survive `exec`,
+    /// so it is a temporary file which is removed right away, once opened.
+    pub fn create_for_exec(content: &[u8]) -> io::Result<Self> {
+        #[cfg(target_os = "linux")]
+        {
+            Self::create(content)
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            let file = write_temporary(content)?;
+            let reader = fs::File::open(file.path())?;
+            let path = file.path().to_owned();
+            file.close()?;
+            interrupt::forget(&path);
+
+            Ok(Self { fd: reader.into() })
+        }
+    }
+
+    /// Path of the descriptor in the wrapped command.
+    pub fn path(&self) -> PathBuf {
+        use std::os::fd::AsRawFd;
+
+        PathBuf::from(format!("/dev/fd/{}", self.fd.as_raw_fd()))
+    }
+
+    /// Let the command inherit the descriptor, which is closed on `exec` otherwise.
+    #[allow(unsafe_code)]
+    pub fn pass_to(&self, command: &mut std::process::Command) {
+        use std::os::{fd::AsRawFd, unix::process::CommandExt};
+
+        let fd = self.fd.as_raw_fd();
+        // SAFETY: `fcntl` is async-signal-safe, and only changes the flags of the descriptor
+        // in the forked process.
+        unsafe {
+            command.pre_exec(move || {
+                if libc::fcntl(fd, libc::F_SETFD, 0) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+}
+
+/// Returns the directory where the cookie files are created,
+/// which is only accessible by the current user if possible.
+fn private_dir() -> io::Result<PathBuf> {
+    let Some(runtime_dir) = dirs_next::runtime_dir() else {
+        return Ok(std::env::temp_dir());
+    };
+
+    let dir = runtime_dir.join("gateau");
+
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(&dir)?;
+    // The directory may have been created with other permissions.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o700))?;
+    }
+
+    Ok(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cookie_file_lifetime() {
+        let file = CookieFile::create(b"cookies").unwrap();
+        let path = file.path().to_owned();
+
+        assert_eq!(fs::read(&path).unwrap(), b"cookies");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        assert!(interrupt::will_remove(&path));
+
+        drop(file);
+
+        assert!(!path.exists());
+        assert!(!interrupt::will_remove(&path));
+    }
+
+    #[test]
+    fn test_replace_cookie_file() {
+        let file = CookieFile::create(b"previous cookies").unwrap();
+        let path = file.path().to_owned();
+        let mut reader = fs::File::open(&path).unwrap();
+
+        file.replace(b"cookies").unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"cookies");
+        // The readers which still have the previous file open read it as it was.
+        let mut previous = Vec::new();
+        io::Read::read_to_end(&mut reader, &mut previous).unwrap();
+        assert_eq!(previous, b"previous cookies");
+        assert!(interrupt::will_remove(&path));
+
+        drop(file);
+
+        assert!(!path.exists());
+        assert!(!interrupt::will_remove(&path));
+        // The previous content is overwritten once the file is dropped.
+        let mut previous = Vec::new();
+        io::Seek::rewind(&mut reader).unwrap();
+        io::Read::read_to_end(&mut reader, &mut previous).unwrap();
+        assert_eq!(previous, [0; 16]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cookie_fd() {
+        let fd = CookieFd::create(b"cookies").unwrap();
+        let path = fd.path();
+
+        let mut command = std::process::Command::new("cat");
+        command.arg(&path);
+        fd.pass_to(&mut command);
+        let output = command.output().unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"cookies");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cookie_fd_for_exec() {
+        let fd = CookieFd::create_for_exec(b"cookies").unwrap();
+        let path = fd.path();
+
+        let mut command = std::process::Command::new("sh");
+        command.arg("-c").arg("exec cat \"$0\"").arg(&path);
+        fd.pass_to(&mut command);
+        let output = command.output().unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"cookies");
+    }
+}
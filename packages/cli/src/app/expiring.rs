@@ -0,0 +1,123 @@
+//! Report of the cookies which will expire soon.
+
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    time::Duration,
+};
+
+use cookie::{
+    time::{format_description, OffsetDateTime, PrimitiveDateTime, UtcOffset},
+    Cookie,
+};
+use gateau::report::BrowserCookie;
+
+use crate::duration::HumanDuration;
+
+/// Write the cookies which expire between `now` and `now + within`, grouped by domain
+/// and sorted by expiration date.
+/// Session cookies and cookies which have already expired are ignored.
+pub(crate) fn expiring<W: Write>(
+    cookies: &[BrowserCookie],
+    now: OffsetDateTime,
+    within: Duration,
+    writer: &mut W,
+) -> io::Result<()> {
+    let format =
+        format_description::parse("[year]-[month]-[day] [hour]:[minute]:[second] UTC").unwrap();
+    // A duration beyond the supported dates includes all the cookies which have not expired.
+    let deadline = cookie::time::Duration::try_from(within)
+        .ok()
+        .and_then(|within| now.checked_add(within))
+        .unwrap_or(PrimitiveDateTime::MAX.assume_utc());
+
+    let mut domains = BTreeMap::<&str, Vec<(&Cookie<'_>, OffsetDateTime)>>::new();
+    for cookie in cookies {
+        let Some(expires) = cookie.expires().and_then(|t| t.datetime()) else {
+            continue;
+        };
+
+        if (now..=deadline).contains(&expires) {
+            let domain = cookie.domain().unwrap_or_default();
+            domains
+                .entry(domain.strip_prefix('.').unwrap_or(domain))
+                .or_default()
+                .push((cookie, expires));
+        }
+    }
+
+    for (domain, mut cookies) in domains {
+        cookies.sort_by_key(|(_, expires)| *expires);
+
+        writeln!(writer, "{domain}")?;
+        for (cookie, expires) in cookies {
+            let remaining = HumanDuration(Duration::from_secs(
+                (expires - now).whole_seconds().unsigned_abs(),
+            ));
+
+            writeln!(
+                writer,
+                "  {name}\texpires {date} (in {remaining})",
+                name = cookie.name(),
+                date = expires
+                    .to_offset(UtcOffset::UTC)
+                    .format(&format)
+                    .map_err(io::Error::other)?,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cookie(name: &'static str, domain: &'static str, expires: i64) -> BrowserCookie {
+        Cookie::build((name, "value"))
+            .domain(domain)
+            .expires(OffsetDateTime::from_unix_timestamp(expires).unwrap())
+            .into()
+    }
+
+    #[test]
+    fn test_expiring_cookies() {
+        const DAY: i64 = 24 * 60 * 60;
+        let now = OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap();
+        let now_ts = now.unix_timestamp();
+
+        let cookies = [
+            cookie("late", ".example.com", now_ts + 6 * DAY),
+            cookie("soon", "example.com", now_ts + 2 * 60 * 60),
+            cookie("never", "example.com", now_ts + 30 * DAY),
+            cookie("expired", "example.com", now_ts - DAY),
+            cookie("other", "example.org", now_ts + DAY),
+            Cookie::build(("session", "value"))
+                .domain("example.com")
+                .into(),
+        ];
+
+        let mut output = Vec::new();
+        expiring(
+            &cookies,
+            now,
+            Duration::from_secs(7 * DAY as u64),
+            &mut output,
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "example.com\n\
+            \x20 soon\texpires 2023-11-15 00:13:20 UTC (in 2h)\n\
+            \x20 late\texpires 2023-11-20 22:13:20 UTC (in 6d)\n\
+            example.org\n\
+            \x20 other\texpires 2023-11-15 22:13:20 UTC (in 1d)\n"
+        );
+
+        let mut output = Vec::new();
+        expiring(&cookies, now, Duration::MAX, &mut output).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap().lines().count(), 6);
+    }
+}
@@ -0,0 +1,428 @@
+//! Explanation of the decisions taken by the filters for each cookie.
+
+use std::{
+    fmt::Display,
+    io::{self, Write},
+};
+
+use cookie::{
+    time::{format_description, OffsetDateTime},
+    SameSite,
+};
+use gateau::report::BrowserCookie;
+use http::{uri::Scheme, Uri};
+use serde::Serialize;
+
+use super::header::path_matches;
+use crate::classify::{Category, Classifier};
+use crate::url::{host_pattern, matches_host_pattern, normalize_host, BaseDomain};
+use crate::ExplainFormat;
+
+/// Result of the matching of a cookie domain against the hosts provided by the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum HostMatch<'a> {
+    /// No host was provided, so all cookies match.
+    AnyHost,
+    /// The domain is the host of the URL.
+    Host(&'a Uri),
+    /// The domain is a parent domain of the host of the URL.
+    ParentDomain(&'a Uri),
+    /// The domain matches the wildcard pattern of the host of the URL.
+    Pattern(&'a Uri),
+    /// The cookie has no domain.
+    NoDomain,
+    /// The domain does not match any host.
+    NoMatch,
+}
+
+impl HostMatch<'_> {
+    /// Match the domain of a cookie, with or without its leading dot, against the hosts.
+    ///
+    /// The domain and the hosts are compared regardless of their case and trailing dot.
+    pub fn new<'a>(domain: &str, hosts: &'a [Uri]) -> HostMatch<'a> {
+        let cookie_valid_domain = normalize_host(domain.strip_prefix('.').unwrap_or(domain));
+        let cookie_valid_domain = cookie_valid_domain.as_str();
+
+        if cookie_valid_domain.is_empty() {
+            return HostMatch::NoDomain;
+        }
+
+        if hosts.is_empty() {
+            return HostMatch::AnyHost;
+        }
+
+        if let Some(host) = hosts.iter().find(|h| {
+            host_pattern(h)
+                .is_some_and(|pattern| matches_host_pattern(pattern, cookie_valid_domain))
+        }) {
+            return HostMatch::Pattern(host);
+        }
+
+        if let Some(host) = hosts
+            .iter()
+            .find(|h| h.host().map(normalize_host).as_deref() == Some(cookie_valid_domain))
+        {
+            return HostMatch::Host(host);
+        }
+
+        // The patterns only match the domains they describe, not their parents.
+        hosts
+            .iter()
+            .filter(|h| host_pattern(h).is_none())
+            .find(|h| {
+                h.base_domain()
+                    .or_else(|| h.host().map(normalize_host))
+                    // either the base domain or the host should be Some
+                    .unwrap()
+                    .ends_with(cookie_valid_domain)
+            })
+            .map_or(HostMatch::NoMatch, HostMatch::ParentDomain)
+    }
+
+    /// Returns the URL whose host is matched, if any.
+    fn uri(&self) -> Option<&Uri> {
+        match self {
+            HostMatch::Host(uri) | HostMatch::ParentDomain(uri) | HostMatch::Pattern(uri) => {
+                Some(uri)
+            }
+            HostMatch::AnyHost | HostMatch::NoDomain | HostMatch::NoMatch => None,
+        }
+    }
+
+    pub fn is_match(&self) -> bool {
+        matches!(
+            self,
+            HostMatch::AnyHost
+                | HostMatch::Host(_)
+                | HostMatch::ParentDomain(_)
+                | HostMatch::Pattern(_)
+        )
+    }
+}
+
+impl Display for HostMatch<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HostMatch::AnyHost => write!(f, "no host filter"),
+            HostMatch::Host(uri) => write!(f, "domain is the host of {uri}"),
+            HostMatch::ParentDomain(uri) => write!(f, "domain is a parent of the host of {uri}"),
+            HostMatch::Pattern(uri) => write!(f, "domain matches the pattern {uri}"),
+            HostMatch::NoDomain => write!(f, "cookie has no domain"),
+            HostMatch::NoMatch => write!(f, "domain does not match any host"),
+        }
+    }
+}
+
+/// Decision taken for a cookie, as it is written.
+#[derive(Debug, Clone, Serialize)]
+struct Explanation<'a> {
+    name: &'a str,
+    domain: Option<&'a str>,
+    path: Option<&'a str>,
+    included: bool,
+    reason: String,
+    /// Expiration and attributes restricting the requests with which the cookie is sent,
+    /// as they are applied by the header modes.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    restrictions: Vec<String>,
+}
+
+/// Returns the expiration and the attributes of a cookie which restrict the requests
+/// with which a browser sends it, to the URL whose host is matched if any.
+fn restrictions(cookie: &BrowserCookie, uri: Option<&Uri>, now: OffsetDateTime) -> Vec<String> {
+    let mut restrictions = Vec::new();
+
+    if let Some(expires) = cookie.expires_datetime().filter(|&expires| expires <= now) {
+        let format =
+            format_description::parse("[year]-[month]-[day] [hour]:[minute]:[second] UTC").unwrap();
+        restrictions.push(format!(
+            "expired on {}, so not sent",
+            expires.format(&format).unwrap_or_default()
+        ));
+    }
+    if let Some(uri) = uri.filter(|uri| {
+        cookie.host_only && uri.host().map(normalize_host) != cookie.domain().map(normalize_host)
+    }) {
+        restrictions.push(format!("host-only, so not sent to {uri}"));
+    }
+    if let Some(uri) = uri.filter(|uri| {
+        let request_path = match uri.path() {
+            "" => "/",
+            path => path,
+        };
+        !path_matches(request_path, cookie.path().unwrap_or("/"))
+    }) {
+        restrictions.push(format!(
+            "path {} does not match {uri}, so not sent",
+            cookie.path().unwrap_or("/")
+        ));
+    }
+    if cookie.secure().unwrap_or_default() {
+        match uri {
+            Some(uri) if uri.scheme() == Some(&Scheme::HTTP) => {
+                restrictions.push(format!("Secure, so not sent to {uri}"))
+            }
+            _ => restrictions.push("Secure, so only sent over HTTPS".to_string()),
+        }
+    }
+    if cookie.http_only().unwrap_or_default() {
+        restrictions.push("HttpOnly, so hidden from the scripts of the pages".to_string());
+    }
+    match cookie.same_site() {
+        Some(SameSite::Strict) => {
+            restrictions.push("SameSite=Strict, so not sent with cross-site requests".to_string())
+        }
+        Some(SameSite::Lax) => restrictions.push(
+            "SameSite=Lax, so only sent with the top-level cross-site navigations".to_string(),
+        ),
+        Some(SameSite::None) | None => {}
+    }
+
+    restrictions
+}
+
+/// Filter the cookies by host and category, while writing the reason
+/// why each cookie is included or excluded, and what restricts the requests
+/// with which the included ones are sent at `now`.
+pub(crate) fn filter<W: Write>(
+    cookies: Vec<BrowserCookie>,
+    hosts: &[Uri],
+    drop_categories: &[Category],
+    now: OffsetDateTime,
+    format: ExplainFormat,
+    writer: &mut W,
+) -> io::Result<Vec<BrowserCookie>> {
+    let classifier = (!drop_categories.is_empty()).then(Classifier::current);
+    let mut kept = Vec::with_capacity(cookies.len());
+
+    for cookie in cookies {
+        let host_match = HostMatch::new(cookie.domain().unwrap_or_default(), hosts);
+        let category = classifier
+            .and_then(|classifier| classifier.classify(&cookie))
+            .filter(|category| drop_categories.contains(category));
+
+        let (included, reason) = match category {
+            _ if !host_match.is_match() => (false, host_match.to_string()),
+            Some(category) => (false, format!("category {category} is excluded")),
+            None => (true, host_match.to_string()),
+        };
+
+        let explanation = Explanation {
+            name: cookie.name(),
+            domain: cookie.domain(),
+            path: cookie.path(),
+            included,
+            reason,
+            restrictions: if included {
+                restrictions(&cookie, host_match.uri(), now)
+            } else {
+                Vec::new()
+            },
+        };
+
+        match format {
+            ExplainFormat::Text => writeln!(
+                writer,
+                "{decision} {name} ({domain}{path}): {reason}",
+                decision = if included { "included" } else { "excluded" },
+                name = explanation.name,
+                domain = explanation.domain.unwrap_or_default(),
+                path = explanation.path.unwrap_or_default(),
+                reason = [explanation.reason.as_str()]
+                    .into_iter()
+                    .chain(explanation.restrictions.iter().map(String::as_str))
+                    .collect::<Vec<_>>()
+                    .join("; "),
+            )?,
+            ExplainFormat::Json => {
+                serde_json::to_writer(&mut *writer, &explanation)?;
+                writeln!(writer)?;
+            }
+        }
+
+        if included {
+            kept.push(cookie);
+        }
+    }
+
+    Ok(kept)
+}
+
+#[cfg(test)]
+mod tests {
+    use cookie::{time::Duration, Cookie};
+
+    use super::*;
+
+    #[test]
+    fn test_host_match() {
+        let hosts = [Uri::from_static("https://www.example.com")];
+
+        assert_eq!(
+            HostMatch::new("www.example.com", &hosts),
+            HostMatch::Host(&hosts[0])
+        );
+        assert_eq!(
+            HostMatch::new(".example.com", &hosts),
+            HostMatch::ParentDomain(&hosts[0])
+        );
+        assert_eq!(HostMatch::new("example.org", &hosts), HostMatch::NoMatch);
+        assert_eq!(HostMatch::new(".", &hosts), HostMatch::NoDomain);
+        assert_eq!(HostMatch::new("example.org", &[]), HostMatch::AnyHost);
+    }
+
+    #[test]
+    fn test_host_match_case_and_trailing_dot() {
+        let hosts = [Uri::from_static("https://WWW.Example.COM./")];
+
+        assert_eq!(
+            HostMatch::new("www.example.com", &hosts),
+            HostMatch::Host(&hosts[0])
+        );
+        assert_eq!(
+            HostMatch::new(".Example.com.", &hosts),
+            HostMatch::ParentDomain(&hosts[0])
+        );
+        assert_eq!(
+            HostMatch::new("WWW.EXAMPLE.COM", &[Uri::from_static("*.example.com")]),
+            HostMatch::Pattern(&Uri::from_static("*.example.com"))
+        );
+    }
+
+    #[test]
+    fn test_host_match_pattern() {
+        let hosts = [
+            Uri::from_static("*.example.com"),
+            Uri::from_static("example.*"),
+        ];
+
+        assert_eq!(
+            HostMatch::new("www.example.com", &hosts),
+            HostMatch::Pattern(&hosts[0])
+        );
+        assert_eq!(
+            HostMatch::new(".example.org", &hosts),
+            HostMatch::Pattern(&hosts[1])
+        );
+        assert_eq!(
+            HostMatch::new("example.com", &hosts),
+            HostMatch::Pattern(&hosts[1])
+        );
+        assert_eq!(
+            HostMatch::new("www.example.org", &hosts),
+            HostMatch::NoMatch
+        );
+        assert_eq!(
+            HostMatch::new("example.com", &hosts[..1]),
+            HostMatch::NoMatch
+        );
+    }
+
+    #[test]
+    fn test_explain_filter() {
+        let cookies = vec![
+            Cookie::build(("sid", "1"))
+                .domain("example.com")
+                .path("/")
+                .into(),
+            Cookie::build(("_ga", "1"))
+                .domain(".example.com")
+                .path("/")
+                .into(),
+            Cookie::build(("lang", "en"))
+                .domain("example.org")
+                .path("/")
+                .into(),
+        ];
+        let hosts = [Uri::from_static("https://example.com")];
+
+        let mut output = Vec::new();
+        let kept = filter(
+            cookies,
+            &hosts,
+            &[Category::Analytics],
+            OffsetDateTime::now_utc(),
+            ExplainFormat::Text,
+            &mut output,
+        )
+        .unwrap();
+
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].name(), "sid");
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "included sid (example.com/): domain is the host of https://example.com/\n\
+            excluded _ga (example.com/): category analytics is excluded\n\
+            excluded lang (example.org/): domain does not match any host\n"
+        );
+    }
+
+    #[test]
+    fn test_explain_restrictions() {
+        let now = OffsetDateTime::from_unix_timestamp(2000000000).unwrap();
+        let cookies = vec![
+            Cookie::build(("sid", "1"))
+                .domain("example.com")
+                .path("/")
+                .secure(true)
+                .http_only(true)
+                .same_site(SameSite::Strict)
+                .expires(now - Duration::days(1))
+                .into(),
+            Cookie::build(("lang", "en"))
+                .domain("example.com")
+                .path("/")
+                .same_site(SameSite::None)
+                .into(),
+            Cookie::build(("cart", "1"))
+                .domain("example.com")
+                .path("/shop")
+                .into(),
+        ];
+        let hosts = [Uri::from_static("http://example.com")];
+        let mut output = Vec::new();
+        filter(
+            vec![BrowserCookie {
+                host_only: true,
+                ..Cookie::build(("id", "1"))
+                    .domain("example.com")
+                    .path("/")
+                    .into()
+            }],
+            &[Uri::from_static("https://www.example.com")],
+            &[],
+            now,
+            ExplainFormat::Text,
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "included id (example.com/): domain is a parent of the host of https://www.example.com/; \
+             host-only, so not sent to https://www.example.com/\n"
+        );
+
+        let mut output = Vec::new();
+        filter(cookies, &hosts, &[], now, ExplainFormat::Json, &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            concat!(
+                r#"{"name":"sid","domain":"example.com","path":"/","included":true,"#,
+                r#""reason":"domain is the host of http://example.com/","restrictions":["#,
+                r#""expired on 2033-05-17 03:33:20 UTC, so not sent","#,
+                r#""Secure, so not sent to http://example.com/","#,
+                r#""HttpOnly, so hidden from the scripts of the pages","#,
+                r#""SameSite=Strict, so not sent with cross-site requests"]}"#,
+                "\n",
+                r#"{"name":"lang","domain":"example.com","path":"/","included":true,"#,
+                r#""reason":"domain is the host of http://example.com/"}"#,
+                "\n",
+                r#"{"name":"cart","domain":"example.com","path":"/shop","included":true,"#,
+                r#""reason":"domain is the host of http://example.com/","restrictions":["#,
+                r#""path /shop does not match http://example.com/, so not sent"]}"#,
+                "\n"
+            )
+        );
+    }
+}
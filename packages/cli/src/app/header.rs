@@ -0,0 +1,138 @@
+//! `Cookie` request header, with the cookies a browser would send to a URL.
+
+use cookie::time::OffsetDateTime;
+use gateau::report::BrowserCookie;
+use http::{uri::Scheme, Uri};
+
+use crate::url::normalize_host;
+
+/// Returns the value of the `Cookie` header sent with a request to the URL,
+/// with the cookies matching its host, path and scheme which have not expired.
+///
+/// The host-only cookies are only sent to their host, not to its subdomains.
+///
+/// As in browsers, the cookies with the longest paths come first (RFC 6265, section 5.4).
+/// The URLs without scheme are considered secure.
+pub(crate) fn cookie_header(cookies: &[BrowserCookie], url: &Uri, now: OffsetDateTime) -> String {
+    sent_cookies(cookies, url, now)
+        .iter()
+        .map(|cookie| format!("{}={}", cookie.name(), cookie.value()))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Returns the cookies sent with a request to the URL, in the order of the `Cookie` header.
+pub(crate) fn sent_cookies<'a>(
+    cookies: &'a [BrowserCookie],
+    url: &Uri,
+    now: OffsetDateTime,
+) -> Vec<&'a BrowserCookie> {
+    let host = normalize_host(url.host().unwrap_or_default());
+    let path = match url.path() {
+        "" => "/",
+        path => path,
+    };
+    let secure = url.scheme() != Some(&Scheme::HTTP);
+
+    let mut cookies = cookies
+        .iter()
+        .filter(|cookie| {
+            cookie.domain().is_some_and(|domain| {
+                domain_matches(&host, domain)
+                    && (!cookie.host_only || normalize_host(domain) == host)
+            }) && path_matches(path, cookie.path().unwrap_or("/"))
+                && (secure || !cookie.secure().unwrap_or_default())
+                && cookie
+                    .expires_datetime()
+                    .is_none_or(|expires| expires > now)
+        })
+        .collect::<Vec<_>>();
+    cookies.sort_by_key(|cookie| std::cmp::Reverse(cookie.path().unwrap_or("/").len()));
+
+    cookies
+}
+
+/// Returns whether the cookie domain is the (normalized) host or one of its parent domains,
+/// whether the cookie is host-only or not.
+pub(crate) fn domain_matches(host: &str, domain: &str) -> bool {
+    let domain = normalize_host(domain.strip_prefix('.').unwrap_or(domain));
+
+    host == domain
+        || host
+            .strip_suffix(domain.as_str())
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+/// Returns whether the request path is in the cookie path (RFC 6265, section 5.1.4).
+pub(crate) fn path_matches(request_path: &str, cookie_path: &str) -> bool {
+    request_path
+        .strip_prefix(cookie_path)
+        .is_some_and(|rest| cookie_path.ends_with('/') || rest.is_empty() || rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use cookie::{time::Duration, Cookie};
+
+    use super::*;
+
+    #[test]
+    fn test_cookie_header() {
+        let now = OffsetDateTime::now_utc();
+        let cookies = [
+            Cookie::build(("sid", "1"))
+                .domain("example.com")
+                .path("/")
+                .secure(true)
+                .into(),
+            Cookie::build(("cart", "2"))
+                .domain("shop.example.com")
+                .path("/cart")
+                .into(),
+            Cookie::build(("lang", "en"))
+                .domain("www.example.com")
+                .path("/")
+                .into(),
+            BrowserCookie {
+                host_only: true,
+                ..Cookie::build(("theme", "dark"))
+                    .domain("www.example.com")
+                    .path("/")
+                    .into()
+            },
+            Cookie::build(("old", "3"))
+                .domain("example.com")
+                .path("/")
+                .expires(now - Duration::hours(1))
+                .into(),
+            Cookie::build(("_ga", "4")).domain("notexample.com").into(),
+        ];
+
+        let header = |url: &str| cookie_header(&cookies, &url.parse().unwrap(), now);
+
+        assert_eq!(
+            header("https://shop.example.com/cart/items"),
+            "cart=2; sid=1"
+        );
+        assert_eq!(header("http://shop.example.com/cartoon"), "");
+        assert_eq!(
+            header("https://www.example.com"),
+            "sid=1; lang=en; theme=dark"
+        );
+        assert_eq!(header("https://m.www.example.com"), "sid=1; lang=en");
+        assert_eq!(header("https://example.org/"), "");
+        assert_eq!(
+            header("https://WWW.Example.COM./"),
+            "sid=1; lang=en; theme=dark"
+        );
+    }
+
+    #[test]
+    fn test_path_matches() {
+        assert!(path_matches("/", "/"));
+        assert!(path_matches("/app/page", "/app"));
+        assert!(path_matches("/app/page", "/app/"));
+        assert!(!path_matches("/application", "/app"));
+        assert!(!path_matches("/", "/app"));
+    }
+}
@@ -0,0 +1,278 @@
+//! HTTPie sessions directory, with a session file for each host.
+//!
+//! HTTPie looks for the named sessions in `<sessions dir>/<host>/<name>.json`,
+//! where the characters of the host other than letters, digits, `-`, `_` and `.`
+//! (e.g. the `:` before the port) are replaced by `_`.
+//! The existing sessions are updated, keeping their headers, authentication and other cookies.
+
+use std::{
+    collections::BTreeSet,
+    env, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use gateau::report::BrowserCookie;
+use http::Uri;
+
+use super::{header::domain_matches, output};
+use crate::url::{host_pattern, normalize_host};
+
+/// Returns the sessions directory of HTTPie, in its configuration directory:
+/// `$HTTPIE_CONFIG_DIR`, `%APPDATA%\httpie` on Windows, `~/.httpie` if it exists,
+/// or `$XDG_CONFIG_HOME/httpie` (`~/.config/httpie` by default).
+pub(crate) fn sessions_dir() -> Option<PathBuf> {
+    let config_dir = if let Some(dir) = env::var_os("HTTPIE_CONFIG_DIR") {
+        PathBuf::from(dir)
+    } else if cfg!(windows) {
+        dirs_next::config_dir()?.join("httpie")
+    } else {
+        let home_dir = dirs_next::home_dir()?;
+        let legacy_dir = home_dir.join(".httpie");
+        if legacy_dir.is_dir() {
+            legacy_dir
+        } else {
+            env::var_os("XDG_CONFIG_HOME")
+                .map_or_else(|| home_dir.join(".config"), PathBuf::from)
+                .join("httpie")
+        }
+    };
+
+    Some(config_dir.join("sessions"))
+}
+
+/// Returns the name of the directory of the sessions of the host, as HTTPie names it.
+fn session_dir_name(host: &str) -> String {
+    host.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Write the session file, created only readable by the current user since it holds the cookies.
+fn write_session(path: &Path, session: &[u8]) -> io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    options.open(path)?.write_all(session)
+}
+
+/// Returns the session with the cookies replacing the ones with the same name, domain and path
+/// in the existing session, whose other fields are kept.
+///
+/// The cookies of sessions written before HTTPie 3.1, indexed by their name, are converted.
+fn update_session(
+    mut existing: serde_json::Value,
+    session: serde_json::Value,
+) -> Option<serde_json::Value> {
+    let key = |cookie: &serde_json::Value| {
+        (
+            cookie["name"].clone(),
+            cookie["domain"].clone(),
+            cookie["path"].clone(),
+        )
+    };
+
+    let existing_session = existing.as_object_mut()?;
+    let mut cookies = match existing_session.remove("cookies") {
+        Some(serde_json::Value::Array(cookies)) => cookies,
+        Some(serde_json::Value::Object(cookies)) => cookies
+            .into_iter()
+            .map(|(name, mut cookie)| {
+                cookie["name"] = name.into();
+                cookie
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    let new_cookies = session["cookies"].as_array()?;
+    let new_keys = new_cookies.iter().map(key).collect::<Vec<_>>();
+
+    cookies.retain(|cookie| !new_keys.contains(&key(cookie)));
+    cookies.extend(new_cookies.iter().cloned());
+    existing_session.insert("cookies".to_string(), cookies.into());
+
+    Some(existing)
+}
+
+/// Write a session named `session_name` for each host, with the cookies sent to it,
+/// and returns the paths of the session files with their number of cookies.
+///
+/// The sessions are written for the hosts which are not wildcard patterns,
+/// or for the domains of the cookies if there is no such host.
+/// The existing sessions are updated, and an error is returned if one of them cannot be parsed.
+pub(crate) fn write_sessions(
+    cookies: &[BrowserCookie],
+    hosts: &[Uri],
+    dir: &Path,
+    session_name: &str,
+) -> io::Result<Vec<(PathBuf, usize)>> {
+    let mut session_hosts = hosts
+        .iter()
+        .filter(|host| host_pattern(host).is_none())
+        .filter_map(|host| {
+            let name = host.host()?;
+            Some(match host.port() {
+                Some(port) => format!("{}:{port}", normalize_host(name)),
+                None => normalize_host(name),
+            })
+        })
+        .collect::<BTreeSet<_>>();
+    if session_hosts.is_empty() {
+        session_hosts = cookies
+            .iter()
+            .filter_map(|cookie| cookie.domain())
+            .map(normalize_host)
+            .collect();
+    }
+
+    session_hosts
+        .into_iter()
+        .map(|host| {
+            let name = host.split(':').next().unwrap_or_default();
+            let cookies = cookies
+                .iter()
+                .filter(|cookie| {
+                    cookie
+                        .domain()
+                        .is_some_and(|domain| domain_matches(name, domain))
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+
+            let session_dir = dir.join(session_dir_name(&host));
+            fs::create_dir_all(&session_dir)?;
+            let path = session_dir.join(format!("{session_name}.json"));
+
+            let mut session = Vec::new();
+            output::httpie_session(&cookies, &mut session)?;
+            let existing = match fs::read(&path) {
+                Ok(content) => Some(content),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+                Err(e) => return Err(e),
+            };
+            if let Some(existing) = existing {
+                // A session which cannot be updated is kept rather than replaced.
+                let updated = serde_json::from_slice(&existing)
+                    .ok()
+                    .and_then(|existing| {
+                        update_session(existing, serde_json::from_slice(&session).ok()?)
+                    })
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("{} is not a valid HTTPie session", path.display()),
+                        )
+                    })?;
+                session = serde_json::to_vec_pretty(&updated)?;
+            }
+            write_session(&path, &session)?;
+
+            Ok((path, cookies.len()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use cookie::Cookie;
+
+    use super::*;
+
+    #[test]
+    fn test_write_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let cookies = [
+            Cookie::build(("sid", "1"))
+                .domain("example.com")
+                .path("/")
+                .secure(false)
+                .into(),
+            Cookie::build(("lang", "en"))
+                .domain("www.example.com")
+                .path("/")
+                .secure(false)
+                .into(),
+        ];
+
+        let sessions = write_sessions(&cookies, &[], dir.path(), "gateau").unwrap();
+        assert_eq!(
+            sessions,
+            [
+                (dir.path().join("example.com/gateau.json"), 1),
+                (dir.path().join("www.example.com/gateau.json"), 2),
+            ]
+        );
+
+        let hosts = ["http://www.example.com:8080/".parse().unwrap()];
+        let sessions = write_sessions(&cookies, &hosts, dir.path(), "dev").unwrap();
+        assert_eq!(
+            sessions,
+            [(dir.path().join("www.example.com_8080/dev.json"), 2)]
+        );
+
+        let session: serde_json::Value =
+            serde_json::from_slice(&fs::read(&sessions[0].0).unwrap()).unwrap();
+        assert_eq!(session["cookies"].as_array().unwrap().len(), 2);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&sessions[0].0).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn test_update_session() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("example.com/gateau.json");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(
+            &path,
+            serde_json::json!({
+                "headers": [{"name": "Accept", "value": "application/json"}],
+                "cookies": {
+                    "sid": {"value": "old", "domain": "example.com", "path": "/"},
+                    "theme": {"value": "dark", "domain": "example.com", "path": "/"},
+                },
+                "auth": {"type": null, "username": null, "password": null},
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let cookies = [Cookie::build(("sid", "new"))
+            .domain("example.com")
+            .path("/")
+            .secure(false)
+            .into()];
+
+        write_sessions(&cookies, &[], dir.path(), "gateau").unwrap();
+
+        let session: serde_json::Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(session["headers"][0]["name"], "Accept");
+        let values = session["cookies"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|cookie| (cookie["name"].as_str(), cookie["value"].as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            values,
+            [(Some("theme"), Some("dark")), (Some("sid"), Some("new"))]
+        );
+
+        fs::write(&path, "{").unwrap();
+        assert!(write_sessions(&cookies, &[], dir.path(), "gateau").is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"{");
+    }
+}
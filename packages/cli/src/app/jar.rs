@@ -0,0 +1,154 @@
+//! Netscape cookie jars refreshed with `--merge` instead of being overwritten.
+
+use std::{
+    fs, io,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::{
+    eyre::{bail, Context},
+    Result,
+};
+use cookie::{time::OffsetDateTime, Cookie};
+use gateau::report::BrowserCookie;
+
+use crate::interrupt;
+
+/// Prefix of the domain of the HttpOnly cookies written by curl.
+const HTTP_ONLY_PREFIX: &str = "#HttpOnly_";
+
+/// Cookies of an existing Netscape cookies file.
+#[derive(Debug, Default)]
+pub(crate) struct CookieJar {
+    pub cookies: Vec<BrowserCookie>,
+    /// Whether the HttpOnly cookies are marked with the `#HttpOnly_` prefix,
+    /// which is then kept when the file is rewritten.
+    pub http_only_prefix: bool,
+}
+
+impl CookieJar {
+    /// Read a Netscape cookies file, or an empty jar if it does not exist yet.
+    pub fn load(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(content) => Self::parse(&content)
+                .wrap_err_with(|| format!("Failed to parse the cookies file {}", path.display())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).wrap_err_with(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    fn parse(content: &str) -> Result<Self> {
+        let mut jar = Self::default();
+
+        for (number, line) in content.lines().enumerate() {
+            let (line, http_only) = match line.strip_prefix(HTTP_ONLY_PREFIX) {
+                Some(line) => (line, true),
+                None if line.starts_with('#') || line.trim().is_empty() => continue,
+                None => (line, false),
+            };
+            jar.http_only_prefix |= http_only;
+
+            let fields = line.split('\t').collect::<Vec<_>>();
+            let [domain, include_subdomains, path, secure, expires, name, value @ ..] = &fields[..]
+            else {
+                bail!("Line {} has less than 6 fields", number + 1);
+            };
+            let expires = expires
+                .parse::<i64>()
+                .wrap_err_with(|| format!("Invalid expiration on line {}", number + 1))?;
+
+            let mut cookie = Cookie::build((name.to_string(), value.join("\t")))
+                .domain(domain.to_string())
+                .path(path.to_string())
+                .secure(secure.eq_ignore_ascii_case("TRUE"))
+                .http_only(http_only);
+            if expires != 0 {
+                cookie = cookie.expires(
+                    OffsetDateTime::from_unix_timestamp(expires)
+                        .wrap_err_with(|| format!("Invalid expiration on line {}", number + 1))?,
+                );
+            }
+            jar.cookies.push(BrowserCookie {
+                host_only: !include_subdomains.eq_ignore_ascii_case("TRUE"),
+                ..cookie.build().into()
+            });
+        }
+
+        Ok(jar)
+    }
+}
+
+/// Replace the file with the content, through a temporary file renamed over it,
+/// so the file is never partially written, keeping its permissions if it exists.
+pub(crate) fn write_atomically(path: &Path, content: &[u8]) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let mut file = tempfile::Builder::new()
+        .prefix(".gateau-")
+        .tempfile_in(dir)?;
+    interrupt::remove_on_interrupt(file.path());
+
+    let result = (|| {
+        if let Ok(metadata) = fs::metadata(path) {
+            file.as_file().set_permissions(metadata.permissions())?;
+        }
+        file.write_all(content)?;
+        file.as_file().sync_all()
+    })();
+    interrupt::forget(file.path());
+    result?;
+
+    file.persist(path).map(|_| ()).map_err(|e| e.error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let jar = CookieJar::parse(
+            "# Netscape HTTP Cookie File\n\
+             \n\
+             .example.com\tTRUE\t/\tTRUE\t2000000000\tsid\tabc\r\n\
+             #HttpOnly_example.com\tFALSE\t/app\tFALSE\t0\ttoken\t\n",
+        )
+        .unwrap();
+
+        assert!(jar.http_only_prefix);
+        assert_eq!(jar.cookies.len(), 2);
+        assert_eq!(jar.cookies[0].domain(), Some("example.com"));
+        assert!(!jar.cookies[0].host_only);
+        assert_eq!(jar.cookies[0].value(), "abc");
+        assert_eq!(jar.cookies[0].secure(), Some(true));
+        assert_eq!(
+            jar.cookies[0]
+                .expires_datetime()
+                .map(OffsetDateTime::unix_timestamp),
+            Some(2000000000)
+        );
+        assert_eq!(jar.cookies[1].path(), Some("/app"));
+        assert!(jar.cookies[1].host_only);
+        assert_eq!(jar.cookies[1].value(), "");
+        assert_eq!(jar.cookies[1].http_only(), Some(true));
+        assert_eq!(jar.cookies[1].expires(), None);
+
+        assert!(CookieJar::parse("example.com\tFALSE\t/\n").is_err());
+        assert!(CookieJar::parse("example.com\tFALSE\t/\tFALSE\tnever\tsid\t1\n").is_err());
+    }
+
+    #[test]
+    fn test_write_atomically() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cookies.txt");
+
+        write_atomically(&path, b"first").unwrap();
+        write_atomically(&path, b"second").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
@@ -0,0 +1,96 @@
+//! Checks of the cookies of a host against the limits of the browsers and the servers.
+
+use std::collections::BTreeMap;
+
+use cookie::time::OffsetDateTime;
+use gateau::report::BrowserCookie;
+use http::Uri;
+
+use super::header::cookie_header;
+
+/// Size of a cookie (name and value) which all the browsers can store (RFC 6265, section 6.1).
+const MAX_COOKIE_SIZE: usize = 4096;
+
+/// Number of cookies per domain which all the browsers can store (RFC 6265, section 6.1).
+const MAX_COOKIES_PER_DOMAIN: usize = 50;
+
+/// Size of a header accepted by the common servers and proxies (e.g. Apache, which is the lowest),
+/// above which the requests are rejected with `400 Bad Request` or `431`.
+const MAX_HEADER_SIZE: usize = 8190;
+
+/// Returns the warnings about the cookies sent with a request to the URL.
+pub(crate) fn lint(cookies: &[BrowserCookie], url: &Uri, now: OffsetDateTime) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    for cookie in cookies {
+        let size = cookie.name().len() + cookie.value().len();
+        if size > MAX_COOKIE_SIZE {
+            warnings.push(format!(
+                "cookie {name} ({domain}) is {size} bytes, \
+                more than the {MAX_COOKIE_SIZE} bytes all the browsers can store",
+                name = cookie.name(),
+                domain = cookie.domain().unwrap_or_default(),
+            ));
+        }
+    }
+
+    let mut domains = BTreeMap::<&str, usize>::new();
+    for cookie in cookies {
+        *domains
+            .entry(cookie.domain().unwrap_or_default())
+            .or_default() += 1;
+    }
+    for (domain, count) in domains {
+        if count > MAX_COOKIES_PER_DOMAIN {
+            warnings.push(format!(
+                "{domain} has {count} cookies, more than the {MAX_COOKIES_PER_DOMAIN} \
+                all the browsers can store, so some can be evicted"
+            ));
+        }
+    }
+
+    let header = cookie_header(cookies, url, now);
+    let size = "Cookie: ".len() + header.len();
+    if size > MAX_HEADER_SIZE {
+        warnings.push(format!(
+            "the Cookie header sent to {url} is {size} bytes, more than the {MAX_HEADER_SIZE} \
+            bytes accepted by some servers"
+        ));
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use cookie::Cookie;
+
+    use super::*;
+
+    #[test]
+    fn test_lint() {
+        let url = "https://example.com/".parse().unwrap();
+        let now = OffsetDateTime::now_utc();
+        let cookie = |name: String, value: String| -> BrowserCookie {
+            Cookie::build((name, value))
+                .domain("example.com")
+                .path("/")
+                .into()
+        };
+
+        assert!(lint(&[cookie("sid".into(), "1".into())], &url, now).is_empty());
+
+        let large = [cookie("sid".into(), "a".repeat(5000))];
+        let warnings = lint(&large, &url, now);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("cookie sid (example.com) is 5003 bytes"));
+
+        let many = (0..60)
+            .map(|i| cookie(format!("c{i}"), "a".repeat(200)))
+            .collect::<Vec<_>>();
+        let warnings = lint(&many, &url, now);
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("example.com has 60 cookies"));
+        assert!(warnings[1].starts_with("the Cookie header sent to https://example.com/"));
+    }
+}
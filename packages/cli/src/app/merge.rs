@@ -0,0 +1,117 @@
+//! Merging of the cookies read from several sources.
+
+use std::collections::HashSet;
+
+use gateau::report::{cookie_key, BrowserCookie};
+
+/// Number of cookies read from a source, and kept after merging.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Provenance {
+    /// Description of the source, e.g. its root path.
+    pub source: String,
+    pub read: usize,
+    pub kept: usize,
+}
+
+impl Provenance {
+    /// Returns the number of cookies already read from a previous source.
+    pub fn duplicates(&self) -> usize {
+        self.read - self.kept
+    }
+}
+
+/// Merge the cookies of the sources, given by order of priority:
+/// the cookies with the same domain (including whether they are host-only), path and name
+/// as a cookie of a previous source are dropped,
+/// and the source of each cookie kept is recorded on it.
+pub(crate) fn merge(
+    sources: Vec<(String, Vec<BrowserCookie>)>,
+) -> (Vec<BrowserCookie>, Vec<Provenance>) {
+    let mut seen = HashSet::new();
+    let mut merged = Vec::new();
+    let mut provenances = Vec::with_capacity(sources.len());
+
+    for (source, cookies) in sources {
+        let read = cookies.len();
+        let before = merged.len();
+
+        merged.extend(
+            cookies
+                .into_iter()
+                .filter(|cookie| seen.insert(cookie_key(cookie)))
+                .map(|cookie| BrowserCookie {
+                    source: Some(source.clone()),
+                    ..cookie
+                }),
+        );
+
+        provenances.push(Provenance {
+            source,
+            read,
+            kept: merged.len() - before,
+        });
+    }
+
+    (merged, provenances)
+}
+
+#[cfg(test)]
+mod tests {
+    use cookie::Cookie;
+
+    use super::*;
+
+    fn cookie(name: &'static str, value: &'static str) -> BrowserCookie {
+        Cookie::build((name, value))
+            .domain("example.com")
+            .path("/")
+            .into()
+    }
+
+    #[test]
+    fn test_merge() {
+        let (cookies, provenances) = merge(vec![
+            (
+                "work".into(),
+                vec![cookie("sid", "1"), cookie("lang", "en")],
+            ),
+            (
+                "personal".into(),
+                vec![cookie("sid", "2"), cookie("theme", "dark")],
+            ),
+        ]);
+
+        let cookies = cookies
+            .iter()
+            .map(|cookie| (cookie.name(), cookie.value(), cookie.source.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            cookies,
+            [
+                ("sid", "1", Some("work")),
+                ("lang", "en", Some("work")),
+                ("theme", "dark", Some("personal"))
+            ]
+        );
+        assert_eq!(provenances[0].duplicates(), 0);
+        assert_eq!(provenances[1].kept, 1);
+        assert_eq!(provenances[1].duplicates(), 1);
+    }
+
+    #[test]
+    fn test_merge_host_only() {
+        let host_only = BrowserCookie {
+            host_only: true,
+            ..cookie("sid", "2")
+        };
+
+        let (cookies, provenances) = merge(vec![
+            ("work".into(), vec![cookie("sid", "1")]),
+            ("personal".into(), vec![host_only]),
+        ]);
+
+        assert_eq!(cookies.len(), 2);
+        assert!(cookies[1].host_only);
+        assert_eq!(provenances[1].duplicates(), 0);
+    }
+}
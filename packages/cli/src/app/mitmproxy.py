@@ -0,0 +1,40 @@
+"""mitmproxy addon sending the cookies exported by gateau with the requests.
+
+Usage: mitmdump -s cookies.py
+
+The cookies replace the ones of the same name sent by the client,
+when their domain, path and Secure attribute match the request and they have not expired.
+The host-only cookies are only sent to their domain, not to its subdomains.
+"""
+
+import json
+import time
+
+from mitmproxy import http
+
+COOKIES = json.loads(GATEAU_COOKIES)
+
+
+def matches(cookie: dict, request: http.Request, now: float) -> bool:
+    host = request.pretty_host.lower().rstrip(".")
+    domain = (cookie["domain"] or "").lower().lstrip(".").rstrip(".")
+    cookie_path = cookie["path"] or "/"
+    path = request.path.split("?", 1)[0]
+
+    return (
+        (host == domain or not cookie["host_only"] and host.endswith("." + domain))
+        and (
+            path == cookie_path
+            or path.startswith(cookie_path)
+            and (cookie_path.endswith("/") or path[len(cookie_path)] == "/")
+        )
+        and (not cookie["secure"] or request.scheme == "https")
+        and (cookie["expires"] is None or cookie["expires"] > now)
+    )
+
+
+def request(flow: http.HTTPFlow) -> None:
+    now = time.time()
+    for cookie in COOKIES:
+        if matches(cookie, flow.request, now):
+            flow.request.cookies[cookie["name"]] = cookie["value"]
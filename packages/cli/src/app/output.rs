@@ -9,6 +9,47 @@
 //! The Netscape format is the one used by the `cookies.txt` file.
 //! It is recognized by `curl` and `wget`.
 //!
+//! ### lynx and w3m
+//!
+//! The cookie files of the text-mode browsers: lynx reads the Netscape format without its header,
+//! while w3m has its own tab-separated format.
+//!
+//! ### LWP
+//!
+//! The `Set-Cookie3` format is the one used by the cookie jars of Perl LWP (`HTTP::Cookies`)
+//! and Python (`http.cookiejar.LWPCookieJar`).
+//!
+//! ### Set-Cookie
+//!
+//! The cookies are written as the `Set-Cookie` response headers (RFC 6265) which would set them.
+//!
+//! ### CSV
+//!
+//! The cookies are written as CSV (RFC 4180), with their attributes and flags,
+//! to be processed in spreadsheets and scripts.
+//!
+//! ### HAR
+//!
+//! The cookies are written as an HTTP Archive (HAR 1.2) of the responses which set them,
+//! to be imported in intercepting proxies such as OWASP ZAP.
+//!
+//! ### SQL
+//!
+//! The cookies are written as `INSERT` statements, in SQL accepted by both SQLite and PostgreSQL.
+//!
+//! ### Template
+//!
+//! Each cookie is written on its own line following the template given with `--template`.
+//!
+//! ### requests
+//!
+//! The cookies are written as a JSON array of the arguments of `requests.cookies.create_cookie`,
+//! with the attributes unknown to `http.cookiejar` in the `rest` field.
+//!
+//! ### mitmproxy
+//!
+//! The cookies are written in a mitmproxy addon, which sends them with the intercepted requests.
+//!
 //! ### HTTPie session
 //!
 //! The HTTPie session format is the one used by the `httpie` tool.
@@ -25,16 +66,205 @@ use std::{
 };
 
 use cookie::Cookie;
+use gateau::{
+    chrome,
+    output::{JsonCookie, RawJsonCookie},
+    report::BrowserCookie,
+};
 
 use serde::Serialize;
 
+use crate::classify::Classifier;
+use crate::template::Template;
+use crate::{OnUnsupported, OutputFormat};
+
+/// Apply the policy to the partitioned cookies, unless the output format represents
+/// their partition key: exported as is, they are sent to every site embedding their domain
+/// instead of the top-level site they are partitioned by.
+///
+/// The format is `None` for the `Cookie` headers and the cookies passed as arguments,
+/// which never keep the partition key.
+pub(crate) fn handle_partitioned(
+    cookies: &mut Vec<BrowserCookie>,
+    format: Option<OutputFormat>,
+    policy: OnUnsupported,
+) {
+    if format.is_some_and(OutputFormat::keeps_partition_key) {
+        return;
+    }
+
+    let partitioned = cookies
+        .iter()
+        .filter(|cookie| cookie.partitioned().unwrap_or_default())
+        .count();
+
+    match policy {
+        _ if partitioned == 0 => {}
+        OnUnsupported::Drop => cookies.retain(|cookie| !cookie.partitioned().unwrap_or_default()),
+        OnUnsupported::Warn => eprintln!(
+            "Warning: {partitioned} partitioned cookie(s) exported without their partition key, \
+            which the output format cannot represent (use --on-unsupported drop to exclude them)"
+        ),
+        OnUnsupported::Force => {}
+    }
+}
+
+/// Warn about the cookies which Chrome binds to the device (DBSC),
+/// since the sessions relying on them stop working soon after being replayed elsewhere.
+pub(crate) fn warn_device_bound(cookies: &[BrowserCookie]) {
+    let bound = cookies
+        .iter()
+        .filter(|cookie| chrome::is_device_bound(cookie))
+        .map(|cookie| {
+            format!(
+                "{} ({})",
+                cookie.name(),
+                cookie.domain().unwrap_or_default()
+            )
+        })
+        .collect::<Vec<_>>();
+
+    if !bound.is_empty() {
+        eprintln!(
+            "Warning: {count} cookie(s) bound to this device by Chrome (DBSC): {names}, \
+            the sessions relying on them stop working shortly when used on another device",
+            count = bound.len(),
+            names = bound.join(", ")
+        );
+    }
+}
+
+/// Line terminator of the Netscape format.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum LineEnding {
+    /// `\n`, expected by most tools.
+    #[default]
+    Lf,
+    /// `\r\n`, required by some Windows tools.
+    CrLf,
+}
+
+impl LineEnding {
+    const fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+/// Options of the Netscape format.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct NetscapeOptions {
+    pub line_ending: LineEnding,
+    /// Prefix the domain of the HttpOnly cookies with `#HttpOnly_`, as curl does.
+    /// The tools which do not know this prefix (e.g. wget) ignore these cookies as comments.
+    pub http_only_prefix: bool,
+}
+
+/// Options of the output formats, shared by the commands which write them.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FormatOptions<'a> {
+    pub netscape: NetscapeOptions,
+    #[cfg(feature = "human")]
+    pub human: HumanOptions,
+    /// Classifier giving the category of the cookies in the human, JSON and CSV formats.
+    pub classifier: Option<&'a Classifier>,
+    /// Write the values stored in the database alongside the decrypted ones in the JSON format.
+    pub raw_values: bool,
+    /// Table into which the SQL format inserts the cookies.
+    pub sql_table: &'a str,
+    /// Template of the lines of the template format.
+    pub template: Option<&'a Template>,
+}
+
+/// Output cookies in the given format.
+///
+/// ## Panics
+///
+/// Panics for the httpie-dir format, which is written to a directory instead,
+/// and for the template format without template, which are rejected beforehand.
+pub(crate) fn write_cookies<W: Write>(
+    format: OutputFormat,
+    cookies: &[BrowserCookie],
+    options: &FormatOptions<'_>,
+    writer: &mut W,
+) -> io::Result<()> {
+    match format {
+        OutputFormat::Netscape => netscape(cookies, options.netscape, writer),
+        #[cfg(feature = "human")]
+        OutputFormat::Human => human(cookies, options.classifier, options.human, writer),
+        OutputFormat::HttpieSession => httpie_session(cookies, writer),
+        OutputFormat::Requests => requests(cookies, writer),
+        OutputFormat::Mitmproxy => mitmproxy(cookies, writer),
+        OutputFormat::Json => json(cookies, options.classifier, options.raw_values, writer),
+        #[cfg(feature = "msgpack")]
+        OutputFormat::Msgpack => gateau::output::msgpack(cookies, writer),
+        #[cfg(feature = "cbor")]
+        OutputFormat::Cbor => gateau::output::cbor(cookies, writer),
+        OutputFormat::Puppeteer => gateau::output::puppeteer(cookies, writer),
+        OutputFormat::EditThisCookie => gateau::output::edit_this_cookie(cookies, writer),
+        OutputFormat::Lwp => lwp(cookies, writer),
+        OutputFormat::SetCookie => set_cookie(cookies, writer),
+        OutputFormat::Wgetrc => wgetrc(cookies, writer),
+        OutputFormat::Csv => csv(cookies, options.classifier, writer),
+        OutputFormat::Har => har(cookies, cookie::time::OffsetDateTime::now_utc(), writer),
+        OutputFormat::Lynx => lynx(cookies, writer),
+        OutputFormat::W3m => w3m(cookies, writer),
+        OutputFormat::Sql => sql(cookies, options.sql_table, writer),
+        OutputFormat::Template => template(
+            cookies,
+            options.template.expect("checked by the caller"),
+            writer,
+        ),
+        OutputFormat::HttpieDir => unreachable!("written to the sessions directory"),
+    }
+}
+
 /// Output cookies in Netscape (cookies.txt) format, recognized by curl and wget.
 ///
+/// Every line, including the last one, is terminated by the line ending,
+/// and the expiration is a UNIX timestamp (`0` for the session cookies),
+/// so the output does not depend on the locale of the system.
+/// The domain cookies are written with a leading dot and are sent to the subdomains (`TRUE`),
+/// unlike the host-only ones (`FALSE`).
+///
+/// ## Panics
+///
+/// Panics if the domain or the path of a cookie is `None`.
+pub fn netscape<W: Write>(
+    cookies: &[BrowserCookie],
+    options: NetscapeOptions,
+    writer: &mut W,
+) -> io::Result<()> {
+    const NETSCAPE_HEADER: &str = "# Netscape HTTP Cookie File";
+
+    write!(
+        writer,
+        "{NETSCAPE_HEADER}{eol}",
+        eol = options.line_ending.as_str()
+    )?;
+
+    netscape_lines(cookies, options, writer)
+}
+
+/// Output cookies in the cookie file format of lynx (`cookie_file`),
+/// the Netscape format without its header.
+///
 /// ## Panics
 ///
-/// Panics if one the cookie's optional parameters is `None` or the expiration date is not a date.
-pub fn netscape<W: Write>(cookies: &[Cookie<'_>], writer: &mut W) -> io::Result<()> {
-    const NETSCAPE_HEADER: &[u8] = b"# Netscape HTTP Cookie File\n";
+/// Panics if the domain or the path of a cookie is `None`.
+pub(crate) fn lynx<W: Write>(cookies: &[BrowserCookie], writer: &mut W) -> io::Result<()> {
+    netscape_lines(cookies, NetscapeOptions::default(), writer)
+}
+
+/// Write a line of the Netscape format for each cookie.
+fn netscape_lines<W: Write>(
+    cookies: &[BrowserCookie],
+    options: NetscapeOptions,
+    writer: &mut W,
+) -> io::Result<()> {
+    const HTTP_ONLY_PREFIX: &str = "#HttpOnly_";
 
     const fn bool_to_uppercase(b: bool) -> &'static str {
         if b {
@@ -44,21 +274,24 @@ pub fn netscape<W: Write>(cookies: &[Cookie<'_>], writer: &mut W) -> io::Result<
         }
     }
 
-    writer.write_all(NETSCAPE_HEADER)?;
+    let eol = options.line_ending.as_str();
 
     for cookie in cookies {
-        writeln!(
+        write!(
             writer,
-            "{domain}\t{flag}\t{path}\t{secure}\t{expiration}\t{name}\t{value}",
-            domain = cookie.domain().unwrap(),
-            flag = bool_to_uppercase(cookie.domain().map(|d| d.starts_with('.')).unwrap()),
+            "{prefix}{domain}\t{flag}\t{path}\t{secure}\t{expiration}\t{name}\t{value}{eol}",
+            prefix = if options.http_only_prefix && cookie.http_only().unwrap_or_default() {
+                HTTP_ONLY_PREFIX
+            } else {
+                ""
+            },
+            domain = cookie.stored_domain(),
+            flag = bool_to_uppercase(!cookie.host_only),
             path = cookie.path().unwrap(),
-            secure = bool_to_uppercase(cookie.secure().unwrap()),
+            secure = bool_to_uppercase(cookie.secure().unwrap_or_default()),
             expiration = cookie
-                .expires()
-                .and_then(|t| t.datetime())
-                .unwrap()
-                .unix_timestamp(),
+                .expires_datetime()
+                .map_or(0, |t| t.unix_timestamp().max(0)),
             name = cookie.name(),
             value = cookie.value()
         )?;
@@ -67,87 +300,470 @@ pub fn netscape<W: Write>(cookies: &[Cookie<'_>], writer: &mut W) -> io::Result<
     Ok(())
 }
 
-#[cfg(feature = "human")]
-pub fn human<W: Write>(cookies: &[Cookie<'_>], writer: &mut W) -> io::Result<()> {
-    use color_eyre::owo_colors::OwoColorize;
-    use cookie::time::format_description;
-    use itertools::Itertools;
+/// Output cookies in the cookie file format of w3m (`~/.w3m/cookie`).
+///
+/// Each line holds the URL which set the cookie, its name, value, expiration,
+/// domain, path, flags, version, comment, ports and comment URL, separated by tabs.
+/// The session cookies expire at `-1` and are marked to be discarded,
+/// so w3m uses them without saving them back.
+/// The host-only cookies are written for their host, without the flag of the Domain attribute.
+pub(crate) fn w3m<W: Write>(cookies: &[BrowserCookie], writer: &mut W) -> io::Result<()> {
+    // Flags of the cookies in w3m (`COO_*` in `cookie.h`).
+    const USE: u8 = 1;
+    const SECURE: u8 = 2;
+    const DOMAIN: u8 = 4;
+    const PATH: u8 = 8;
+    const DISCARD: u8 = 16;
+
+    for cookie in cookies {
+        let domain = cookie.domain().unwrap_or_default();
+        let path = cookie.path().unwrap_or("/");
+        let secure = cookie.secure().unwrap_or_default();
+        let expires = cookie.expires_datetime().map(|t| t.unix_timestamp());
+
+        let mut flags = USE | PATH;
+        if !cookie.host_only {
+            flags |= DOMAIN;
+        }
+        if secure {
+            flags |= SECURE;
+        }
+        if expires.is_none() {
+            flags |= DISCARD;
+        }
+
+        writeln!(
+            writer,
+            "{scheme}://{domain}{path}\t{name}\t{value}\t{expires}\t{stored_domain}\t{path}\t{flags}\t0\t\t\t",
+            scheme = if secure { "https" } else { "http" },
+            stored_domain = cookie.stored_domain(),
+            name = cookie.name(),
+            value = cookie.value(),
+            expires = expires.unwrap_or(-1),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Output cookies in the `Set-Cookie3` format of the LWP cookie jars (Perl `HTTP::Cookies`
+/// and Python `LWPCookieJar`).
+///
+/// The domain cookies are written with a leading dot, for their domain and its subdomains,
+/// the host-only ones for their host only, and their expiration in UTC (`YYYY-MM-DD hh:mm:ssZ`) if they are not session cookies.
+pub(crate) fn lwp<W: Write>(cookies: &[BrowserCookie], writer: &mut W) -> io::Result<()> {
+    use cookie::time::{format_description, UtcOffset};
+
+    const LWP_HEADER: &str = "#LWP-Cookies-1.0";
+
+    /// Quote the value of an attribute if it is not a token, as `join_header_words` does.
+    fn quote(value: &str) -> String {
+        let is_token = !value.is_empty()
+            && !value.chars().any(|c| {
+                c.is_ascii_control()
+                    || !c.is_ascii()
+                    || c == ' '
+                    || "()<>@,;:\\\"/[]?={}".contains(c)
+            });
+
+        if is_token {
+            value.to_string()
+        } else {
+            format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+        }
+    }
 
     let format =
-        format_description::parse("[weekday], [day] [month] [year] [hour]:[minute]:[second] GMT")
-            .unwrap();
+        format_description::parse("[year]-[month]-[day] [hour]:[minute]:[second]Z").unwrap();
 
-    macro_rules! human_field {
-        ($name:ident, $value:expr) => {
-            format!("{}: {}", stringify!($name).bold(), $value)
-        };
+    writeln!(writer, "{LWP_HEADER}")?;
+
+    for cookie in cookies {
+        write!(
+            writer,
+            "Set-Cookie3: {name}={value}; path={path}; domain={domain}; path_spec",
+            name = cookie.name(),
+            value = quote(cookie.value()),
+            path = quote(cookie.path().unwrap_or("/")),
+            domain = quote(&cookie.stored_domain()),
+        )?;
+        if cookie.secure().unwrap_or_default() {
+            write!(writer, "; secure")?;
+        }
+        match cookie.expires_datetime() {
+            Some(expires) => write!(
+                writer,
+                "; expires={}",
+                quote(&expires.to_offset(UtcOffset::UTC).format(&format).unwrap())
+            )?,
+            None => write!(writer, "; discard")?,
+        }
+        if cookie.http_only().unwrap_or_default() {
+            write!(writer, "; HttpOnly")?;
+        }
+        writeln!(writer, "; version=0")?;
+    }
+
+    Ok(())
+}
+
+/// Output cookies as `Set-Cookie` headers, one per line, with all their attributes.
+pub(crate) fn set_cookie<W: Write>(cookies: &[BrowserCookie], writer: &mut W) -> io::Result<()> {
+    for cookie in cookies {
+        writeln!(writer, "Set-Cookie: {}", set_cookie_value(cookie))?;
     }
 
-    for (domain, cookies) in cookies
+    Ok(())
+}
+
+/// Returns the cookie as it is set by a `Set-Cookie` header,
+/// without the `Domain` attribute if it is host-only.
+fn set_cookie_value(cookie: &BrowserCookie) -> Cookie<'static> {
+    let mut set_cookie = cookie.cookie.clone();
+    if cookie.host_only {
+        set_cookie.unset_domain();
+    }
+
+    set_cookie
+}
+
+/// Output cookies as a `Cookie` header in the syntax of `.wgetrc`, for wget `--config`,
+/// which sends the values as they are instead of parsing a cookies file.
+///
+/// The header is sent with every request whatever the host, so nothing is written without cookies.
+/// As in browsers, the cookies with the longest paths come first.
+pub(crate) fn wgetrc<W: Write>(cookies: &[BrowserCookie], writer: &mut W) -> io::Result<()> {
+    if cookies.is_empty() {
+        return Ok(());
+    }
+
+    let mut cookies = cookies.iter().collect::<Vec<_>>();
+    cookies.sort_by_key(|cookie| std::cmp::Reverse(cookie.path().unwrap_or_default().len()));
+    let header = cookies
         .iter()
-        .into_group_map_by(|cookie| cookie.domain().unwrap())
-        .into_iter()
-        .sorted_by(|c1, c2| {
-            let c1 = if c1.0.starts_with('.') {
-                c1.0.get(1..).unwrap()
-            } else {
-                c1.0
-            };
+        .map(|cookie| format!("{}={}", cookie.name(), cookie.value()))
+        .collect::<Vec<_>>()
+        .join("; ");
 
-            let c2 = if c2.0.starts_with('.') {
-                c2.0.get(1..).unwrap()
-            } else {
-                c2.0
-            };
+    writeln!(writer, "header = Cookie: {header}")
+}
+
+/// Output cookies as CSV (RFC 4180) with a header, with the domain, path, name, value
+/// and expiration of the cookies followed by their flags, for spreadsheets and scripts.
+/// If a classifier is provided, the category of each cookie is in a last `category` column.
+///
+/// The expiration is in RFC 3339, empty for the session cookies, as is the unset `SameSite` attribute.
+pub(crate) fn csv<W: Write>(
+    cookies: &[BrowserCookie],
+    classifier: Option<&Classifier>,
+    writer: &mut W,
+) -> io::Result<()> {
+    use cookie::time::format_description::well_known::Rfc3339;
+
+    /// Quote the field if it contains a separator, a quote or a line break.
+    fn field(value: &str) -> String {
+        if value.contains([',', '"', '\r', '\n']) {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    }
 
-            c1.cmp(c2)
+    write!(
+        writer,
+        "domain,path,name,value,expires,secure,http_only,same_site"
+    )?;
+    if classifier.is_some() {
+        write!(writer, ",category")?;
+    }
+    write!(writer, "\r\n")?;
+
+    for cookie in cookies {
+        write!(
+            writer,
+            "{domain},{path},{name},{value},{expires},{secure},{http_only},{same_site}",
+            domain = field(cookie.domain().unwrap_or_default()),
+            path = field(cookie.path().unwrap_or("/")),
+            name = field(cookie.name()),
+            value = field(cookie.value()),
+            expires = cookie
+                .expires_datetime()
+                .and_then(|expires| expires.format(&Rfc3339).ok())
+                .unwrap_or_default(),
+            secure = cookie.secure().unwrap_or_default(),
+            http_only = cookie.http_only().unwrap_or_default(),
+            same_site = cookie
+                .same_site()
+                .map(|same_site| same_site.to_string())
+                .unwrap_or_default(),
+        )?;
+        if let Some(classifier) = classifier {
+            write!(writer, ",{}", classifier.category_name(cookie))?;
+        }
+        write!(writer, "\r\n")?;
+    }
+
+    Ok(())
+}
+
+/// Output cookies as a JSON array with the fields of [`JsonCookie`],
+/// and the value stored in the database of each cookie read with its raw value if `raw_values` is set.
+/// If a classifier is provided, the category of each cookie is in its `category` field.
+pub(crate) fn json<W: Write>(
+    cookies: &[BrowserCookie],
+    classifier: Option<&Classifier>,
+    raw_values: bool,
+    writer: &mut W,
+) -> io::Result<()> {
+    let category =
+        |cookie: &BrowserCookie| classifier.map(|classifier| classifier.category_name(cookie));
+
+    if raw_values {
+        let cookies = cookies
+            .iter()
+            .map(|cookie| {
+                let mut raw = RawJsonCookie::from(cookie);
+                raw.cookie.category = category(cookie);
+                raw
+            })
+            .collect::<Vec<_>>();
+        serde_json::to_writer(&mut *writer, &cookies)?;
+    } else {
+        let cookies = cookies
+            .iter()
+            .map(|cookie| JsonCookie {
+                category: category(cookie),
+                ..JsonCookie::from(cookie)
+            })
+            .collect::<Vec<_>>();
+        serde_json::to_writer(&mut *writer, &cookies)?;
+    }
+
+    writeln!(writer)
+}
+
+/// Output cookies as SQL `INSERT` statements into the given table, which is created if needed,
+/// in a transaction which can be loaded into SQLite (`sqlite3 db < dump.sql`) or PostgreSQL (`psql -f`).
+///
+/// The columns are the fields of the JSON output, with `NULL` for the session cookies expiration
+/// and the unset `SameSite` attribute. Each part of a qualified table name (`schema.table`)
+/// is quoted separately.
+pub(crate) fn sql<W: Write>(
+    cookies: &[BrowserCookie],
+    table: &str,
+    writer: &mut W,
+) -> io::Result<()> {
+    fn identifier(name: &str) -> String {
+        name.split('.')
+            .map(|part| format!("\"{}\"", part.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+
+    fn string(value: &str) -> String {
+        format!("'{}'", value.replace('\'', "''"))
+    }
+
+    const fn boolean(b: bool) -> &'static str {
+        if b {
+            "TRUE"
+        } else {
+            "FALSE"
+        }
+    }
+
+    let table = identifier(table);
+
+    writeln!(writer, "BEGIN;")?;
+    writeln!(
+        writer,
+        "CREATE TABLE IF NOT EXISTS {table} (name TEXT NOT NULL, value TEXT NOT NULL, \
+         domain TEXT NOT NULL, path TEXT NOT NULL, expires BIGINT, secure BOOLEAN NOT NULL, \
+         http_only BOOLEAN NOT NULL, same_site TEXT, partitioned BOOLEAN NOT NULL);"
+    )?;
+
+    for cookie in cookies {
+        writeln!(
+            writer,
+            "INSERT INTO {table} \
+             (name, value, domain, path, expires, secure, http_only, same_site, partitioned) \
+             VALUES ({name}, {value}, {domain}, {path}, {expires}, {secure}, {http_only}, \
+             {same_site}, {partitioned});",
+            name = string(cookie.name()),
+            value = string(cookie.value()),
+            domain = string(cookie.domain().unwrap_or_default()),
+            path = string(cookie.path().unwrap_or("/")),
+            expires = cookie
+                .expires_datetime()
+                .map_or_else(|| "NULL".to_string(), |t| t.unix_timestamp().to_string()),
+            secure = boolean(cookie.secure().unwrap_or_default()),
+            http_only = boolean(cookie.http_only().unwrap_or_default()),
+            same_site = cookie
+                .same_site()
+                .map_or_else(|| "NULL".to_string(), |s| string(&s.to_string())),
+            partitioned = boolean(cookie.partitioned().unwrap_or_default()),
+        )?;
+    }
+
+    writeln!(writer, "COMMIT;")
+}
+
+/// Output cookies following the template, one line per cookie.
+pub(crate) fn template<W: Write>(
+    cookies: &[BrowserCookie],
+    template: &Template,
+    writer: &mut W,
+) -> io::Result<()> {
+    for cookie in cookies {
+        writeln!(writer, "{}", template.render(cookie))?;
+    }
+
+    Ok(())
+}
+
+/// Options of the human format.
+#[cfg(feature = "human")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct HumanOptions {
+    /// Color the header and the domains.
+    pub color: bool,
+    /// Number of characters after which the values are truncated, `None` to show them entirely.
+    pub max_value_width: Option<usize>,
+}
+
+#[cfg(feature = "human")]
+impl HumanOptions {
+    /// Returns the options for a destination, only colored if it is a terminal.
+    pub fn for_destination(self, terminal: bool) -> Self {
+        Self {
+            color: self.color && terminal,
+            ..self
+        }
+    }
+}
+
+/// Output cookies as a table sorted by domain, with a column per attribute aligned on the widest value.
+/// If a classifier is provided, the category of each cookie is also displayed.
+#[cfg(feature = "human")]
+pub fn human<W: Write>(
+    cookies: &[BrowserCookie],
+    classifier: Option<&Classifier>,
+    options: HumanOptions,
+    writer: &mut W,
+) -> io::Result<()> {
+    use color_eyre::owo_colors::{OwoColorize, Style};
+    use cookie::time::{format_description, UtcOffset};
+    use itertools::Itertools;
+
+    const SEPARATOR: &str = "  ";
+    const ELLIPSIS: char = '…';
+
+    let format =
+        format_description::parse("[year]-[month]-[day] [hour]:[minute]:[second]").unwrap();
+    let style = |style: Style| if options.color { style } else { Style::new() };
+
+    let truncate = |value: &str| match options.max_value_width {
+        Some(width) if value.chars().count() > width => value
+            .chars()
+            .take(width.saturating_sub(1))
+            .chain(std::iter::once(ELLIPSIS))
+            .collect(),
+        _ => value.to_string(),
+    };
+
+    let mut header = vec!["DOMAIN", "PATH", "NAME", "VALUE", "EXPIRES", "FLAGS"];
+    if classifier.is_some() {
+        header.push("CATEGORY");
+    }
+
+    let rows = cookies
+        .iter()
+        .sorted_by_key(|cookie| {
+            let domain = cookie.domain().unwrap_or_default();
+            domain.strip_prefix('.').unwrap_or(domain)
         })
-    {
-        writeln!(writer, "{}", domain.bold().blue())?;
+        .map(|cookie| {
+            let flags = [
+                cookie.secure().unwrap_or_default().then_some("Secure"),
+                cookie.http_only().unwrap_or_default().then_some("HttpOnly"),
+                cookie
+                    .partitioned()
+                    .unwrap_or_default()
+                    .then_some("Partitioned"),
+            ]
+            .into_iter()
+            .flatten()
+            .map(str::to_string)
+            .chain(std::iter::once(cookie.same_site().map_or_else(
+                || "SameSite=Unspecified".to_string(),
+                |same_site| format!("SameSite={same_site}"),
+            )))
+            .join(" ");
 
-        writeln!(writer)?;
+            let mut row = vec![
+                cookie.domain().unwrap_or_default().to_string(),
+                cookie.path().unwrap_or_default().to_string(),
+                cookie.name().to_string(),
+                truncate(cookie.value()),
+                cookie.expires_datetime().map_or_else(
+                    || "Session".to_string(),
+                    |t| t.to_offset(UtcOffset::UTC).format(&format).unwrap(),
+                ),
+                flags,
+            ];
+            if let Some(classifier) = classifier {
+                row.push(classifier.category_name(cookie).to_string());
+            }
 
-        for cookie in cookies {
-            writeln!(writer, "{}", "--------------------".bold().bright_black())?;
+            row
+        })
+        .collect::<Vec<_>>();
 
-            writeln!(writer)?;
+    let widths = header
+        .iter()
+        .enumerate()
+        .map(|(column, title)| {
+            rows.iter()
+                .map(|row| row[column].chars().count())
+                .chain(std::iter::once(title.len()))
+                .max()
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
 
-            writeln!(writer, "{}", human_field!(Name, cookie.name()))?;
-            writeln!(writer, "{}", human_field!(Value, cookie.value()))?;
-            writeln!(
-                writer,
-                "{}",
-                human_field!(Path, cookie.path().unwrap().italic())
-            )?;
-            writeln!(writer, "{}", human_field!(Secure, cookie.secure().unwrap()))?;
-            writeln!(
-                writer,
-                "{}",
-                human_field!(HttpOnly, cookie.http_only().unwrap())
-            )?;
-            writeln!(
-                writer,
-                "{}",
-                human_field!(SameSite, cookie.same_site().unwrap())
-            )?;
-            writeln!(
-                writer,
-                "{}",
-                human_field!(
-                    Expires,
-                    cookie
-                        .expires()
-                        .and_then(|t| t.datetime())
-                        .unwrap()
-                        .format(&format)
-                        .unwrap()
-                )
-            )?;
-
-            writeln!(writer)?;
+    // The last column is not padded, so the lines do not end with spaces.
+    let pad = |column: usize, cell: &str| {
+        if column + 1 == widths.len() {
+            cell.to_string()
+        } else {
+            format!("{cell:<width$}", width = widths[column])
         }
+    };
 
-        writeln!(writer)?;
+    let header = header
+        .iter()
+        .enumerate()
+        .map(|(column, title)| {
+            pad(column, title)
+                .style(style(Style::new().bold()))
+                .to_string()
+        })
+        .join(SEPARATOR);
+    writeln!(writer, "{header}")?;
+
+    for row in rows {
+        let line = row
+            .iter()
+            .enumerate()
+            .map(|(column, cell)| {
+                let cell = pad(column, cell);
+                match column {
+                    0 => cell.style(style(Style::new().blue())).to_string(),
+                    _ => cell,
+                }
+            })
+            .join(SEPARATOR);
+        writeln!(writer, "{line}")?;
     }
 
     Ok(())
@@ -203,9 +819,9 @@ struct RawHttpieSession {
 ///
 /// ## Panics
 ///
-/// Panics if one the cookie's optional parameters is `None` or the expiration date is not a date.
-pub(crate) fn httpie_session<'a, W: Write>(
-    cookies: &[Cookie<'_>],
+/// Panics if the domain or the path of a cookie is `None`.
+pub(crate) fn httpie_session<W: Write>(
+    cookies: &[BrowserCookie],
     writer: &mut W,
 ) -> io::Result<()> {
     let cookies = cookies
@@ -218,7 +834,7 @@ pub(crate) fn httpie_session<'a, W: Write>(
                 .and_then(|d| d.rsplit(':').next().and_then(|p| p.parse().ok())),
             domain: cookie.domain().unwrap().to_string(),
             path: cookie.path().unwrap().to_string(),
-            secure: cookie.secure().unwrap(),
+            secure: cookie.secure().unwrap_or_default(),
             expires: cookie
                 .expires()
                 .and_then(|t| t.datetime())
@@ -247,3 +863,830 @@ pub(crate) fn httpie_session<'a, W: Write>(
 
     Ok(())
 }
+
+/// Output cookies as a mitmproxy addon (`mitmdump -s cookies.py`), which sends them
+/// with the matching requests, replacing the cookies of the same name sent by the client.
+///
+/// The cookies are embedded as the JSON output, in a Python string literal.
+pub(crate) fn mitmproxy<W: Write>(cookies: &[BrowserCookie], writer: &mut W) -> io::Result<()> {
+    const ADDON: &str = include_str!("mitmproxy.py");
+
+    let cookies = cookies.iter().map(JsonCookie::from).collect::<Vec<_>>();
+    // A JSON string is also a valid Python string literal.
+    let literal = serde_json::to_string(&serde_json::to_string(&cookies)?)?;
+
+    writer.write_all(ADDON.replace("GATEAU_COOKIES", &literal).as_bytes())
+}
+
+/// Output cookies as a JSON array of the keyword arguments of `requests.cookies.create_cookie`,
+/// to load them in a `RequestsCookieJar` (or any `http.cookiejar.CookieJar`) from Python.
+///
+/// As in `http.cookiejar`, the session cookies are marked to be discarded,
+/// and the HttpOnly and SameSite attributes are kept in the `rest` field.
+pub(crate) fn requests<W: Write>(cookies: &[BrowserCookie], writer: &mut W) -> io::Result<()> {
+    let cookies = cookies
+        .iter()
+        .map(|cookie| {
+            let mut rest = HashMap::new();
+            if cookie.http_only().unwrap_or_default() {
+                rest.insert("HttpOnly".to_string(), serde_json::Value::Null);
+            }
+            if let Some(same_site) = cookie.same_site() {
+                rest.insert("SameSite".to_string(), same_site.to_string().into());
+            }
+            let expires = cookie.expires_datetime().map(|t| t.unix_timestamp());
+
+            RawHttpieCookieV0 {
+                name: cookie.name().to_string(),
+                value: cookie.value().to_string(),
+                port: None,
+                domain: cookie.domain().unwrap_or_default().to_string(),
+                path: cookie.path().unwrap_or("/").to_string(),
+                secure: cookie.secure().unwrap_or_default(),
+                expires,
+                discard: expires.is_none(),
+                comment: None,
+                comment_url: None,
+                rest,
+                rfc2109: false,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    serde_json::to_writer(&mut *writer, &cookies)?;
+    writeln!(writer)
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct HarLog {
+    log: HarLogContent,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct HarLogContent {
+    version: &'static str,
+    creator: HarCreator,
+    entries: Vec<HarEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct HarCreator {
+    name: &'static str,
+    version: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HarEntry {
+    started_date_time: String,
+    time: u32,
+    request: HarRequest,
+    response: HarResponse,
+    cache: serde_json::Map<String, serde_json::Value>,
+    timings: HarTimings,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HarRequest {
+    method: &'static str,
+    url: String,
+    http_version: &'static str,
+    cookies: Vec<HarCookie>,
+    headers: Vec<HarHeader>,
+    query_string: Vec<HarHeader>,
+    headers_size: i32,
+    body_size: i32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HarResponse {
+    status: u16,
+    status_text: &'static str,
+    http_version: &'static str,
+    cookies: Vec<HarCookie>,
+    headers: Vec<HarHeader>,
+    content: HarContent,
+    #[serde(rename = "redirectURL")]
+    redirect_url: &'static str,
+    headers_size: i32,
+    body_size: i32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct HarHeader {
+    name: &'static str,
+    value: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HarCookie {
+    name: String,
+    value: String,
+    path: String,
+    domain: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires: Option<String>,
+    http_only: bool,
+    secure: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HarContent {
+    size: u32,
+    mime_type: &'static str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct HarTimings {
+    send: u32,
+    wait: u32,
+    receive: u32,
+}
+
+/// Output cookies as an HTTP Archive (HAR 1.2), which can be imported by OWASP ZAP
+/// (Import > Import a HAR File) into its history and HTTP sessions.
+///
+/// Each cookie is set by the `Set-Cookie` header of the response to a `GET` request on its host and path,
+/// over HTTPS if it is secure, so the proxies track it as if it had been set while browsing.
+/// The entries are dated from the last access of the cookies if it is known, or from `now`.
+pub(crate) fn har<W: Write>(
+    cookies: &[BrowserCookie],
+    now: cookie::time::OffsetDateTime,
+    writer: &mut W,
+) -> io::Result<()> {
+    use cookie::time::format_description::well_known::Rfc3339;
+
+    let entries = cookies
+        .iter()
+        .map(|cookie| {
+            let domain = cookie.domain().unwrap_or_default();
+            let path = cookie.path().unwrap_or("/");
+            let scheme = if cookie.secure().unwrap_or_default() {
+                "https"
+            } else {
+                "http"
+            };
+            let har_cookie = HarCookie {
+                name: cookie.name().to_string(),
+                value: cookie.value().to_string(),
+                path: path.to_string(),
+                domain: cookie.stored_domain(),
+                expires: cookie
+                    .expires_datetime()
+                    .and_then(|expires| expires.format(&Rfc3339).ok()),
+                http_only: cookie.http_only().unwrap_or_default(),
+                secure: cookie.secure().unwrap_or_default(),
+            };
+
+            HarEntry {
+                started_date_time: cookie
+                    .last_access
+                    .unwrap_or(now)
+                    .format(&Rfc3339)
+                    .unwrap_or_default(),
+                time: 0,
+                request: HarRequest {
+                    method: "GET",
+                    url: format!("{scheme}://{domain}{path}"),
+                    http_version: "HTTP/1.1",
+                    cookies: Vec::new(),
+                    headers: vec![HarHeader {
+                        name: "Host",
+                        value: domain.to_string(),
+                    }],
+                    query_string: Vec::new(),
+                    headers_size: -1,
+                    body_size: 0,
+                },
+                response: HarResponse {
+                    status: 200,
+                    status_text: "OK",
+                    http_version: "HTTP/1.1",
+                    cookies: vec![har_cookie],
+                    headers: vec![HarHeader {
+                        name: "Set-Cookie",
+                        value: set_cookie_value(cookie).to_string(),
+                    }],
+                    content: HarContent {
+                        size: 0,
+                        mime_type: "text/plain",
+                    },
+                    redirect_url: "",
+                    headers_size: -1,
+                    body_size: 0,
+                },
+                cache: serde_json::Map::new(),
+                timings: HarTimings {
+                    send: 0,
+                    wait: 0,
+                    receive: 0,
+                },
+            }
+        })
+        .collect();
+
+    let har = HarLog {
+        log: HarLogContent {
+            version: "1.2",
+            creator: HarCreator {
+                name: "gateau",
+                version: env!("CARGO_PKG_VERSION"),
+            },
+            entries,
+        },
+    };
+
+    serde_json::to_writer_pretty(&mut *writer, &har)?;
+    writeln!(writer)
+}
+
+#[cfg(test)]
+mod tests {
+    use cookie::{time::OffsetDateTime, Cookie, Expiration};
+
+    use super::*;
+
+    #[test]
+    fn test_handle_partitioned() {
+        let cookies = vec![
+            Cookie::build(("sid", "1")).domain("example.com").into(),
+            BrowserCookie {
+                partition_key: Some("https://example.org".to_string()),
+                ..Cookie::build(("embed", "1"))
+                    .domain("example.com")
+                    .partitioned(true)
+                    .into()
+            },
+        ];
+
+        let mut kept = cookies.clone();
+        handle_partitioned(&mut kept, Some(OutputFormat::Json), OnUnsupported::Drop);
+        assert_eq!(kept.len(), 2);
+        handle_partitioned(
+            &mut kept,
+            Some(OutputFormat::Puppeteer),
+            OnUnsupported::Drop,
+        );
+        assert_eq!(kept.len(), 2);
+
+        let mut dropped = cookies.clone();
+        handle_partitioned(
+            &mut dropped,
+            Some(OutputFormat::Netscape),
+            OnUnsupported::Drop,
+        );
+        assert_eq!(dropped.len(), 1);
+
+        let mut header = cookies;
+        handle_partitioned(&mut header, None, OnUnsupported::Drop);
+        assert_eq!(header.len(), 1);
+    }
+
+    #[test]
+    fn test_netscape() {
+        let cookies = [
+            Cookie::build(("sid", "1"))
+                .domain("example.com")
+                .path("/")
+                .secure(true)
+                .expires(OffsetDateTime::from_unix_timestamp(2_000_000_000).unwrap())
+                .into(),
+            BrowserCookie {
+                host_only: true,
+                ..Cookie::build(("lang", "en"))
+                    .domain("www.example.com")
+                    .path("/app")
+                    .secure(false)
+                    .expires(Expiration::Session)
+                    .into()
+            },
+        ];
+
+        let mut lf = Vec::new();
+        netscape(&cookies, NetscapeOptions::default(), &mut lf).unwrap();
+        assert_eq!(
+            String::from_utf8(lf).unwrap(),
+            "# Netscape HTTP Cookie File\n\
+            .example.com\tTRUE\t/\tTRUE\t2000000000\tsid\t1\n\
+            www.example.com\tFALSE\t/app\tFALSE\t0\tlang\ten\n"
+        );
+
+        let mut crlf = Vec::new();
+        let options = NetscapeOptions {
+            line_ending: LineEnding::CrLf,
+            ..Default::default()
+        };
+        netscape(&cookies, options, &mut crlf).unwrap();
+        let crlf = String::from_utf8(crlf).unwrap();
+        assert!(crlf.ends_with("\r\n"));
+        assert_eq!(crlf.matches("\r\n").count(), 3);
+    }
+
+    #[test]
+    fn test_text_browsers() {
+        let cookies = [
+            Cookie::build(("sid", "1"))
+                .domain("example.com")
+                .path("/")
+                .secure(true)
+                .http_only(true)
+                .expires(OffsetDateTime::from_unix_timestamp(2_000_000_000).unwrap())
+                .into(),
+            BrowserCookie {
+                host_only: true,
+                ..Cookie::build(("lang", "en"))
+                    .domain("www.example.com")
+                    .path("/app")
+                    .secure(false)
+                    .expires(Expiration::Session)
+                    .into()
+            },
+        ];
+
+        let mut output = Vec::new();
+        lynx(&cookies, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            ".example.com\tTRUE\t/\tTRUE\t2000000000\tsid\t1\n\
+            www.example.com\tFALSE\t/app\tFALSE\t0\tlang\ten\n"
+        );
+
+        let mut output = Vec::new();
+        w3m(&cookies, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "https://example.com/\tsid\t1\t2000000000\t.example.com\t/\t15\t0\t\t\t\n\
+            http://www.example.com/app\tlang\ten\t-1\twww.example.com\t/app\t25\t0\t\t\t\n"
+        );
+    }
+
+    #[test]
+    fn test_netscape_http_only_prefix() {
+        let cookies = [
+            Cookie::build(("sid", "1"))
+                .domain("example.com")
+                .path("/")
+                .secure(true)
+                .http_only(true)
+                .into(),
+            Cookie::build(("lang", "en"))
+                .domain("example.com")
+                .path("/")
+                .secure(false)
+                .into(),
+        ];
+
+        let options = NetscapeOptions {
+            http_only_prefix: true,
+            ..Default::default()
+        };
+        let mut output = Vec::new();
+        netscape(&cookies, options, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "# Netscape HTTP Cookie File\n\
+            #HttpOnly_.example.com\tTRUE\t/\tTRUE\t0\tsid\t1\n\
+            .example.com\tTRUE\t/\tFALSE\t0\tlang\ten\n"
+        );
+    }
+
+    #[test]
+    fn test_set_cookie() {
+        let cookies = [
+            Cookie::build(("sid", "1"))
+                .domain("example.com")
+                .path("/")
+                .secure(true)
+                .http_only(true)
+                .same_site(cookie::SameSite::Lax)
+                .expires(OffsetDateTime::from_unix_timestamp(2_000_000_000).unwrap())
+                .into(),
+            BrowserCookie {
+                host_only: true,
+                ..Cookie::build(("lang", "en"))
+                    .domain("www.example.com")
+                    .path("/app")
+                    .expires(Expiration::Session)
+                    .into()
+            },
+        ];
+
+        let mut output = Vec::new();
+        set_cookie(&cookies, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Set-Cookie: sid=1; HttpOnly; SameSite=Lax; Secure; Path=/; Domain=example.com; \
+            Expires=Wed, 18 May 2033 03:33:20 GMT\n\
+            Set-Cookie: lang=en; Path=/app\n"
+        );
+    }
+
+    #[test]
+    fn test_requests() {
+        let cookies = [
+            Cookie::build(("sid", "1"))
+                .domain("example.com")
+                .path("/")
+                .secure(true)
+                .http_only(true)
+                .same_site(cookie::SameSite::Lax)
+                .expires(OffsetDateTime::from_unix_timestamp(2_000_000_000).unwrap())
+                .into(),
+            Cookie::build(("lang", "en"))
+                .domain("www.example.com")
+                .expires(Expiration::Session)
+                .into(),
+        ];
+
+        let mut output = Vec::new();
+        requests(&cookies, &mut output).unwrap();
+        let output: serde_json::Value = serde_json::from_slice(&output).unwrap();
+
+        assert_eq!(
+            output,
+            serde_json::json!([
+                {
+                    "name": "sid", "value": "1", "port": null, "domain": "example.com",
+                    "path": "/", "secure": true, "expires": 2_000_000_000, "discard": false,
+                    "comment": null, "comment_url": null,
+                    "rest": {"HttpOnly": null, "SameSite": "Lax"}, "rfc2109": false
+                },
+                {
+                    "name": "lang", "value": "en", "port": null, "domain": "www.example.com",
+                    "path": "/", "secure": false, "expires": null, "discard": true,
+                    "comment": null, "comment_url": null, "rest": {}, "rfc2109": false
+                }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_csv() {
+        let cookies = [
+            Cookie::build(("sid", "a,\"b\""))
+                .domain("example.com")
+                .path("/")
+                .secure(true)
+                .http_only(true)
+                .same_site(cookie::SameSite::Lax)
+                .expires(OffsetDateTime::from_unix_timestamp(2_000_000_000).unwrap())
+                .into(),
+            Cookie::build(("lang", "en"))
+                .domain("www.example.com")
+                .path("/app")
+                .expires(Expiration::Session)
+                .into(),
+        ];
+
+        let mut output = Vec::new();
+        csv(&cookies, None, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "domain,path,name,value,expires,secure,http_only,same_site\r\n\
+            example.com,/,sid,\"a,\"\"b\"\"\",2033-05-18T03:33:20Z,true,true,Lax\r\n\
+            www.example.com,/app,lang,en,,false,false,\r\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_classified() {
+        let cookies = [
+            Cookie::build(("_ga", "GA1")).domain("example.com").into(),
+            Cookie::build(("sid", "1")).domain("example.com").into(),
+        ];
+
+        let mut output = Vec::new();
+        csv(&cookies, Some(&Classifier::builtin()), &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "domain,path,name,value,expires,secure,http_only,same_site,category\r\n\
+            example.com,/,_ga,GA1,,false,false,,analytics\r\n\
+            example.com,/,sid,1,,false,false,,unknown\r\n"
+        );
+    }
+
+    #[test]
+    fn test_json_classified() {
+        let cookies = [
+            Cookie::build(("_ga", "GA1")).domain("example.com").into(),
+            Cookie::build(("sid", "1")).domain("example.com").into(),
+        ];
+
+        let category = |output: &[u8]| {
+            serde_json::from_slice::<serde_json::Value>(output).unwrap()[0]
+                .get("category")
+                .cloned()
+        };
+
+        let mut output = Vec::new();
+        json(&cookies, None, false, &mut output).unwrap();
+        assert_eq!(category(&output), None);
+
+        let mut output = Vec::new();
+        json(&cookies, Some(&Classifier::builtin()), false, &mut output).unwrap();
+        let output: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(output[0]["category"], "analytics");
+        assert_eq!(output[1]["category"], "unknown");
+
+        let mut output = Vec::new();
+        json(&cookies, Some(&Classifier::builtin()), true, &mut output).unwrap();
+        let output: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(output[0]["category"], "analytics");
+        assert_eq!(output[0]["encrypted_value"], "");
+    }
+
+    #[test]
+    fn test_har() {
+        let cookies = [
+            BrowserCookie {
+                last_access: Some(OffsetDateTime::from_unix_timestamp(1_700_000_000).unwrap()),
+                ..Cookie::build(("sid", "1"))
+                    .domain("example.com")
+                    .path("/")
+                    .secure(true)
+                    .http_only(true)
+                    .same_site(cookie::SameSite::Lax)
+                    .expires(OffsetDateTime::from_unix_timestamp(2_000_000_000).unwrap())
+                    .into()
+            },
+            BrowserCookie {
+                host_only: true,
+                ..Cookie::build(("lang", "en"))
+                    .domain("www.example.com")
+                    .path("/app")
+                    .expires(Expiration::Session)
+                    .into()
+            },
+        ];
+
+        let mut output = Vec::new();
+        har(
+            &cookies,
+            OffsetDateTime::from_unix_timestamp(1_800_000_000).unwrap(),
+            &mut output,
+        )
+        .unwrap();
+        let output: serde_json::Value = serde_json::from_slice(&output).unwrap();
+
+        let entry = |started: &str, url: &str, host: &str, cookie, set_cookie: &str| {
+            serde_json::json!({
+                "startedDateTime": started,
+                "time": 0,
+                "request": {
+                    "method": "GET", "url": url, "httpVersion": "HTTP/1.1", "cookies": [],
+                    "headers": [{"name": "Host", "value": host}], "queryString": [],
+                    "headersSize": -1, "bodySize": 0
+                },
+                "response": {
+                    "status": 200, "statusText": "OK", "httpVersion": "HTTP/1.1",
+                    "cookies": [cookie],
+                    "headers": [{"name": "Set-Cookie", "value": set_cookie}],
+                    "content": {"size": 0, "mimeType": "text/plain"}, "redirectURL": "",
+                    "headersSize": -1, "bodySize": 0
+                },
+                "cache": {},
+                "timings": {"send": 0, "wait": 0, "receive": 0}
+            })
+        };
+        assert_eq!(
+            output,
+            serde_json::json!({
+                "log": {
+                    "version": "1.2",
+                    "creator": {"name": "gateau", "version": env!("CARGO_PKG_VERSION")},
+                    "entries": [
+                        entry(
+                            "2023-11-14T22:13:20Z",
+                            "https://example.com/",
+                            "example.com",
+                            serde_json::json!({
+                                "name": "sid", "value": "1", "path": "/", "domain": ".example.com",
+                                "expires": "2033-05-18T03:33:20Z", "httpOnly": true, "secure": true
+                            }),
+                            "sid=1; HttpOnly; SameSite=Lax; Secure; Path=/; Domain=example.com; \
+                            Expires=Wed, 18 May 2033 03:33:20 GMT",
+                        ),
+                        entry(
+                            "2027-01-15T08:00:00Z",
+                            "http://www.example.com/app",
+                            "www.example.com",
+                            serde_json::json!({
+                                "name": "lang", "value": "en", "path": "/app",
+                                "domain": "www.example.com", "httpOnly": false, "secure": false
+                            }),
+                            "lang=en; Path=/app",
+                        ),
+                    ]
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_mitmproxy() {
+        let cookies = [Cookie::build(("sid", r#"a"'''\b"#))
+            .domain("example.com")
+            .path("/")
+            .into()];
+
+        let mut output = Vec::new();
+        mitmproxy(&cookies, &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let literal = output
+            .lines()
+            .find_map(|line| line.strip_prefix("COOKIES = json.loads("))
+            .and_then(|line| line.strip_suffix(')'))
+            .unwrap();
+        let json = serde_json::from_str::<String>(literal).unwrap();
+        let embedded = serde_json::from_str::<serde_json::Value>(&json).unwrap();
+        assert_eq!(embedded[0]["value"], r#"a"'''\b"#);
+        assert_eq!(embedded[0]["domain"], "example.com");
+        assert_eq!(embedded[0]["host_only"], false);
+    }
+
+    #[test]
+    fn test_lwp() {
+        let cookies = [
+            Cookie::build(("sid", "a b"))
+                .domain("example.com")
+                .path("/")
+                .secure(true)
+                .http_only(true)
+                .expires(OffsetDateTime::from_unix_timestamp(2_000_000_000).unwrap())
+                .into(),
+            BrowserCookie {
+                host_only: true,
+                ..Cookie::build(("lang", "en"))
+                    .domain("www.example.com")
+                    .path("/app")
+                    .expires(Expiration::Session)
+                    .into()
+            },
+        ];
+
+        let mut output = Vec::new();
+        lwp(&cookies, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "#LWP-Cookies-1.0\n\
+            Set-Cookie3: sid=\"a b\"; path=\"/\"; domain=.example.com; path_spec; secure; \
+            expires=\"2033-05-18 03:33:20Z\"; HttpOnly; version=0\n\
+            Set-Cookie3: lang=en; path=\"/app\"; domain=www.example.com; path_spec; discard; \
+            version=0\n"
+        );
+    }
+
+    #[test]
+    fn test_sql() {
+        let cookies = [
+            Cookie::build(("sid", "it's"))
+                .domain("example.com")
+                .path("/")
+                .secure(true)
+                .same_site(cookie::SameSite::Lax)
+                .expires(OffsetDateTime::from_unix_timestamp(2000000000).unwrap())
+                .into(),
+            Cookie::build(("lang", "en"))
+                .domain("example.com")
+                .path("/app")
+                .into(),
+        ];
+
+        let mut output = Vec::new();
+        sql(&cookies, "analytics.my\"cookies", &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines = output.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], "BEGIN;");
+        assert!(lines[1].starts_with(r#"CREATE TABLE IF NOT EXISTS "analytics"."my""cookies" ("#));
+        assert_eq!(
+            lines[2],
+            r#"INSERT INTO "analytics"."my""cookies" (name, value, domain, path, expires, secure, http_only, same_site, partitioned) VALUES ('sid', 'it''s', 'example.com', '/', 2000000000, TRUE, FALSE, 'Lax', FALSE);"#
+        );
+        assert!(lines[3].ends_with(
+            "VALUES ('lang', 'en', 'example.com', '/app', NULL, FALSE, FALSE, NULL, FALSE);"
+        ));
+        assert_eq!(lines[4], "COMMIT;");
+    }
+
+    #[test]
+    fn test_wgetrc() {
+        let cookies = [
+            Cookie::build(("sid", "a=b"))
+                .domain("example.com")
+                .path("/")
+                .into(),
+            Cookie::build(("lang", "en"))
+                .domain("example.com")
+                .path("/app")
+                .into(),
+        ];
+
+        let mut output = Vec::new();
+        wgetrc(&cookies, &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "header = Cookie: lang=en; sid=a=b\n"
+        );
+
+        let mut output = Vec::new();
+        wgetrc(&[], &mut output).unwrap();
+        assert!(output.is_empty());
+    }
+
+    #[cfg(feature = "human")]
+    #[test]
+    fn test_human_same_site() {
+        let cookies = [
+            Cookie::build(("sid", "1"))
+                .domain("example.com")
+                .path("/")
+                .secure(true)
+                .http_only(true)
+                .same_site(cookie::SameSite::Lax)
+                .into(),
+            Cookie::build(("lang", "en"))
+                .domain("example.com")
+                .path("/")
+                .secure(false)
+                .http_only(false)
+                .into(),
+        ];
+
+        let mut output = Vec::new();
+        human(
+            &cookies,
+            None,
+            HumanOptions {
+                color: true,
+                max_value_width: None,
+            },
+            &mut output,
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Lax"));
+        assert!(output.contains("Unspecified"));
+    }
+
+    #[cfg(feature = "human")]
+    #[test]
+    fn test_human_table() {
+        let cookies = [
+            Cookie::build(("token", "0123456789abcdef"))
+                .domain("www.example.org")
+                .path("/app")
+                .secure(true)
+                .expires(OffsetDateTime::from_unix_timestamp(2000000000).unwrap())
+                .into(),
+            Cookie::build(("id", "1"))
+                .domain(".example.com")
+                .path("/")
+                .into(),
+        ];
+
+        let mut output = Vec::new();
+        human(
+            &cookies,
+            None,
+            HumanOptions {
+                color: false,
+                max_value_width: Some(8),
+            },
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "DOMAIN           PATH  NAME   VALUE     EXPIRES              FLAGS\n\
+             example.com      /     id     1         Session              SameSite=Unspecified\n\
+             www.example.org  /app  token  0123456…  2033-05-18 03:33:20  Secure SameSite=Unspecified\n"
+        );
+    }
+
+    #[cfg(feature = "human")]
+    #[test]
+    fn test_human_options_for_destination() {
+        let options = HumanOptions {
+            color: true,
+            max_value_width: None,
+        };
+
+        assert!(options.for_destination(true).color);
+        assert!(!options.for_destination(false).color);
+        assert!(
+            !HumanOptions {
+                color: false,
+                ..options
+            }
+            .for_destination(true)
+            .color
+        );
+    }
+}
@@ -0,0 +1,40 @@
+//! Storage of the exported cookies in a password store managed by `pass` or `gopass`.
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use color_eyre::{
+    eyre::{ensure, Context},
+    Result,
+};
+
+/// Insert `content` in the `entry` of the password store, overwriting it if it exists.
+///
+/// The content is passed on the standard input of `<cmd> insert -m -f <entry>`,
+/// which is supported by both `pass` and `gopass`.
+pub(crate) fn insert(cmd: &str, entry: &str, content: &[u8]) -> Result<()> {
+    let mut child = Command::new(cmd)
+        .args(["insert", "-m", "-f", entry])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()
+        .wrap_err_with(|| format!("Failed to run {cmd}"))?;
+
+    // The standard input is closed when dropped, which ends the multiline input.
+    child
+        .stdin
+        .take()
+        .expect("Standard input is piped")
+        .write_all(content)
+        .wrap_err_with(|| format!("Failed to write the cookies to {cmd}"))?;
+
+    let status = child.wait()?;
+    ensure!(
+        status.success(),
+        "{cmd} failed to insert the cookies in {entry} ({status})"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,339 @@
+//! Local proxy recording the cookies set by the responses during a session.
+//!
+//! The plain HTTP requests are forwarded and the `Set-Cookie` headers of their responses
+//! are recorded as they are received, regardless of how the browser stores its cookies.
+//! The HTTPS connections are tunneled as is, since their traffic is encrypted,
+//! so their cookies cannot be recorded.
+
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead, BufReader, Write},
+    net::{Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use cookie::{
+    time::{OffsetDateTime, PrimitiveDateTime},
+    Cookie, Expiration,
+};
+use gateau::report::BrowserCookie;
+use http::Uri;
+
+/// Cookies recorded so far, indexed by their domain, path and name.
+type Jar = BTreeMap<(String, String, String), BrowserCookie>;
+
+/// Proxy listening on the loopback interface, until gateau exits.
+#[derive(Debug)]
+pub(crate) struct RecordingProxy {
+    addr: SocketAddr,
+    jar: Arc<Mutex<Jar>>,
+    tunnels: Arc<AtomicUsize>,
+}
+
+impl RecordingProxy {
+    /// Start the proxy on a free port.
+    pub fn start() -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let proxy = Self {
+            addr: listener.local_addr()?,
+            jar: Arc::default(),
+            tunnels: Arc::default(),
+        };
+
+        let jar = Arc::clone(&proxy.jar);
+        let tunnels = Arc::clone(&proxy.tunnels);
+        thread::spawn(move || {
+            for client in listener.incoming().flatten() {
+                let jar = Arc::clone(&jar);
+                let tunnels = Arc::clone(&tunnels);
+                thread::spawn(move || {
+                    // The errors only affect the request, which the browser reports.
+                    let _ = handle(client, &jar, &tunnels);
+                });
+            }
+        });
+
+        Ok(proxy)
+    }
+
+    /// Address of the proxy.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Returns the cookies recorded so far.
+    pub fn cookies(&self) -> Vec<BrowserCookie> {
+        self.jar.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Returns the number of HTTPS connections tunneled without being recorded.
+    pub fn tunnels(&self) -> usize {
+        self.tunnels.load(Ordering::Relaxed)
+    }
+}
+
+/// Forward a request of the browser, recording the cookies of the response.
+fn handle(client: TcpStream, jar: &Mutex<Jar>, tunnels: &AtomicUsize) -> io::Result<()> {
+    let mut client_reader = BufReader::new(client.try_clone()?);
+    let head = read_head(&mut client_reader)?;
+    let (request_line, headers) = head.split_first().ok_or(io::ErrorKind::InvalidData)?;
+
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(target), Some(version)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(io::ErrorKind::InvalidData.into());
+    };
+
+    if method.eq_ignore_ascii_case("CONNECT") {
+        let server = TcpStream::connect(target)?;
+        tunnels.fetch_add(1, Ordering::Relaxed);
+        (&client).write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")?;
+
+        return tunnel(client_reader, client, server);
+    }
+
+    let uri = target
+        .parse::<Uri>()
+        .map_err(|_| io::ErrorKind::InvalidData)?;
+    let host = uri.host().ok_or(io::ErrorKind::InvalidData)?;
+    let mut server = TcpStream::connect((host, uri.port_u16().unwrap_or(80)))?;
+
+    // The connections are not reused, so each request is read from its own connection.
+    let path = uri.path_and_query().map_or("/", |path| path.as_str());
+    let mut request = format!("{method} {path} {version}\r\n");
+    for header in headers.iter().filter(|header| !is_hop_by_hop(header)) {
+        request.push_str(header);
+        request.push_str("\r\n");
+    }
+    request.push_str("Connection: close\r\n\r\n");
+    server.write_all(request.as_bytes())?;
+
+    // The body of the request is forwarded while the response is read.
+    let mut server_writer = server.try_clone()?;
+    thread::spawn(move || {
+        let _ = io::copy(&mut client_reader, &mut server_writer);
+    });
+
+    let mut server_reader = BufReader::new(server);
+    let head = read_head(&mut server_reader)?;
+    let now = OffsetDateTime::now_utc();
+
+    let mut response = String::new();
+    for line in head.iter().filter(|line| !is_hop_by_hop(line)) {
+        if let Some(value) = header_value(line, "set-cookie") {
+            record(&mut jar.lock().unwrap(), value, &uri, now);
+        }
+        response.push_str(line);
+        response.push_str("\r\n");
+    }
+    response.push_str("Connection: close\r\n\r\n");
+
+    let mut client = client;
+    client.write_all(response.as_bytes())?;
+    io::copy(&mut server_reader, &mut client)?;
+    client.shutdown(Shutdown::Both)
+}
+
+/// Copy the traffic between the browser and the server, in both directions.
+fn tunnel(
+    mut client_reader: BufReader<TcpStream>,
+    mut client: TcpStream,
+    server: TcpStream,
+) -> io::Result<()> {
+    let mut server_writer = server.try_clone()?;
+    let upload = thread::spawn(move || {
+        let _ = io::copy(&mut client_reader, &mut server_writer);
+        let _ = server_writer.shutdown(Shutdown::Write);
+    });
+
+    io::copy(&mut &server, &mut client)?;
+    let _ = client.shutdown(Shutdown::Write);
+    let _ = upload.join();
+
+    Ok(())
+}
+
+/// Read the start line and the headers of a request or a response.
+fn read_head<R: BufRead>(reader: &mut R) -> io::Result<Vec<String>> {
+    let mut lines = Vec::new();
+
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            return Ok(lines);
+        }
+        lines.push(line.to_string());
+    }
+}
+
+/// Returns the value of the header if it has the given (lowercase) name.
+fn header_value<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let (header, value) = line.split_once(':')?;
+    header
+        .trim()
+        .eq_ignore_ascii_case(name)
+        .then(|| value.trim())
+}
+
+/// Returns whether the header only concerns the connection with the proxy.
+fn is_hop_by_hop(line: &str) -> bool {
+    ["connection", "proxy-connection", "keep-alive"]
+        .iter()
+        .any(|name| header_value(line, name).is_some())
+}
+
+/// Record the cookie set by a response to the URL, as a browser would store it.
+fn record(jar: &mut Jar, set_cookie: &str, uri: &Uri, now: OffsetDateTime) {
+    let Ok(mut cookie) = Cookie::parse(set_cookie.to_string()) else {
+        return;
+    };
+    let host = uri.host().unwrap_or_default().to_ascii_lowercase();
+    // A cookie set without a Domain attribute is only sent back to the host.
+    let host_only = cookie.domain().is_none();
+
+    match cookie.domain().map(str::to_ascii_lowercase) {
+        Some(domain) if host != domain && !host.ends_with(&format!(".{domain}")) => return,
+        Some(_) => {}
+        None => cookie.set_domain(host),
+    }
+    if cookie.path().is_none_or(|path| !path.starts_with('/')) {
+        cookie.set_path(default_path(uri.path()));
+    }
+    // The browsers do not store the secure cookies set over plain HTTP.
+    if cookie.secure().unwrap_or_default() {
+        return;
+    }
+    // The flags are set as for the cookies read from the browsers, where they are always known.
+    cookie.set_secure(false);
+    cookie.set_http_only(cookie.http_only().unwrap_or_default());
+
+    let expires = match (cookie.max_age(), cookie.expires_datetime()) {
+        (Some(max_age), _) => Some(
+            now.checked_add(max_age)
+                .unwrap_or(PrimitiveDateTime::MAX.assume_utc()),
+        ),
+        (None, expires) => expires,
+    };
+    let key = (
+        cookie.domain().unwrap_or_default().to_string(),
+        cookie.path().unwrap_or_default().to_string(),
+        cookie.name().to_string(),
+    );
+
+    match expires {
+        // An expired cookie removes the one which is stored.
+        Some(expires) if expires <= now => {
+            jar.remove(&key);
+        }
+        expires => {
+            cookie.set_max_age(None);
+            cookie.set_expires(expires.map_or(Expiration::Session, Expiration::from));
+            jar.insert(
+                key,
+                BrowserCookie {
+                    host_only,
+                    ..cookie.into_owned().into()
+                },
+            );
+        }
+    }
+}
+
+/// Returns the default path of the cookies set by a response to the path (RFC 6265, section 5.1.4).
+fn default_path(path: &str) -> String {
+    match path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(end) => path[..end].to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let uri = "http://www.example.com/account/login".parse().unwrap();
+        let now = OffsetDateTime::now_utc();
+        let mut jar = Jar::new();
+
+        record(&mut jar, "sid=1; HttpOnly", &uri, now);
+        record(
+            &mut jar,
+            "lang=en; Domain=example.com; Path=/; Max-Age=3600",
+            &uri,
+            now,
+        );
+        record(&mut jar, "_ga=2; Domain=example.org", &uri, now);
+        record(&mut jar, "token=3; Secure", &uri, now);
+
+        let cookies = jar.values().collect::<Vec<_>>();
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(cookies[0].name(), "lang");
+        assert_eq!(cookies[0].domain(), Some("example.com"));
+        assert!(!cookies[0].host_only);
+        assert!(cookies[0].expires_datetime().is_some());
+        assert_eq!(cookies[1].name(), "sid");
+        assert_eq!(cookies[1].domain(), Some("www.example.com"));
+        assert!(cookies[1].host_only);
+        assert_eq!(cookies[1].path(), Some("/account"));
+        assert_eq!(cookies[1].expires(), Some(Expiration::Session));
+        assert_eq!(cookies[1].secure(), Some(false));
+        assert_eq!(cookies[1].http_only(), Some(true));
+
+        record(
+            &mut jar,
+            "remember=1; Max-Age=9223372036854775807",
+            &uri,
+            now,
+        );
+        let remember = jar.values().find(|cookie| cookie.name() == "remember");
+        assert_eq!(remember.unwrap().expires_datetime().unwrap().year(), 9999);
+        jar.retain(|_, cookie| cookie.name() != "remember");
+
+        record(&mut jar, "sid=; Max-Age=0", &uri, now);
+        assert_eq!(jar.len(), 1);
+    }
+
+    #[test]
+    fn test_proxy() {
+        let server = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let server_addr = server.local_addr().unwrap();
+        thread::spawn(move || {
+            let (stream, _) = server.accept().unwrap();
+            let head = read_head(&mut BufReader::new(&stream)).unwrap();
+            assert_eq!(head[0], "GET /login HTTP/1.1");
+            (&stream)
+                .write_all(b"HTTP/1.1 200 OK\r\nSet-Cookie: sid=1\r\nContent-Length: 2\r\n\r\nok")
+                .unwrap();
+        });
+
+        let proxy = RecordingProxy::start().unwrap();
+        let mut client = TcpStream::connect(proxy.addr()).unwrap();
+        write!(
+            client,
+            "GET http://{server_addr}/login HTTP/1.1\r\nHost: {server_addr}\r\n\r\n"
+        )
+        .unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("\r\n\r\nok"));
+        let cookies = proxy.cookies();
+        assert_eq!(cookies.len(), 1);
+        assert_eq!(cookies[0].name(), "sid");
+        assert_eq!(cookies[0].domain(), Some("127.0.0.1"));
+    }
+}
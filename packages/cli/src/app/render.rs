@@ -0,0 +1,90 @@
+//! Rewriting of the cookies for another environment (e.g. staging), with `gateau render`.
+
+use gateau::report::BrowserCookie;
+
+use crate::config::Environment;
+
+/// Rewrite the domains and the names of the cookies for the environment.
+///
+/// If the environment rewrites domains, the cookies whose domain is not rewritten are dropped,
+/// since they are not sent to the environment.
+pub(crate) fn render(cookies: Vec<BrowserCookie>, env: &Environment) -> Vec<BrowserCookie> {
+    cookies
+        .into_iter()
+        .filter_map(|mut cookie| {
+            if !env.domains.is_empty() {
+                let domain = rewrite_domain(cookie.domain()?, env)?;
+                cookie.set_domain(domain);
+            }
+
+            if let Some(name) = env.cookie_names.get(cookie.name()) {
+                cookie.set_name(name.clone());
+            }
+
+            Some(cookie)
+        })
+        .collect()
+}
+
+/// Returns the domain rewritten with the most specific rule which matches it,
+/// keeping its subdomains (e.g. `www.example.com` to `www.staging.example.com`).
+fn rewrite_domain(domain: &str, env: &Environment) -> Option<String> {
+    env.domains
+        .iter()
+        .filter_map(|(from, to)| {
+            let subdomain = domain.strip_suffix(from.as_str())?;
+            (subdomain.is_empty() || subdomain.ends_with('.'))
+                .then(|| (from.len(), format!("{subdomain}{to}")))
+        })
+        .max_by_key(|(len, _)| *len)
+        .map(|(_, domain)| domain)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use cookie::Cookie;
+
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let env = Environment {
+            domains: HashMap::from([
+                ("example.com".to_string(), "staging.example.com".to_string()),
+                ("api.example.com".to_string(), "api.staging.dev".to_string()),
+            ]),
+            cookie_names: HashMap::from([("sid".to_string(), "staging_sid".to_string())]),
+        };
+        let cookies = vec![
+            Cookie::build(("sid", "1")).domain(".example.com").into(),
+            Cookie::build(("lang", "en"))
+                .domain("www.example.com")
+                .into(),
+            Cookie::build(("token", "2"))
+                .domain("v1.api.example.com")
+                .into(),
+            Cookie::build(("_ga", "3")).domain("notexample.com").into(),
+        ];
+
+        let rendered = render(cookies, &env)
+            .iter()
+            .map(|cookie| {
+                (
+                    cookie.name().to_string(),
+                    cookie.domain().unwrap().to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            rendered,
+            [
+                ("staging_sid".to_string(), "staging.example.com".to_string()),
+                ("lang".to_string(), "www.staging.example.com".to_string()),
+                ("token".to_string(), "v1.api.staging.dev".to_string()),
+            ]
+        );
+    }
+}
@@ -1,19 +1,23 @@
 use std::{
     ffi::OsString,
+    fs,
     process::{Command, Stdio},
     sync::Arc,
 };
 
-use color_eyre::eyre::Context;
-use cookie::Cookie;
+use color_eyre::eyre::{bail, Context};
 use http::Uri;
 use tempfile::tempdir;
 
 use crate::app::filter_hosts;
 
+use super::proxy::RecordingProxy;
+
 use gateau::{
     chrome::{self, ChromeManager, ChromeVariant},
-    firefox::{self, FirefoxManager},
+    firefox::{self, FirefoxManager, FirefoxVariant},
+    report::BrowserCookie,
+    webkitgtk::{self, WebKitGtkManager, WebKitGtkVariant},
     Browser,
 };
 
@@ -25,19 +29,28 @@ pub(crate) struct SessionBuilder {
     browser: Browser,
     urls: Vec<Uri>,
     hosts: Vec<Uri>,
+    proxy: bool,
 }
 
-impl<'a> SessionBuilder {
+impl SessionBuilder {
     pub fn new(browser: Browser, urls: Vec<Uri>, hosts: Vec<Uri>) -> Self {
         Self {
             browser,
             urls,
             hosts,
+            proxy: false,
         }
     }
 
+    /// Record the cookies set by the responses through a local proxy,
+    /// instead of reading them from the profile of the session.
+    pub fn record_with_proxy(mut self) -> Self {
+        self.proxy = true;
+        self
+    }
+
     /// Build a browser session.
-    pub fn build(self) -> color_eyre::Result<Session<'a>> {
+    pub fn build(self) -> color_eyre::Result<Session> {
         let session_context = tempdir()?;
 
         eprintln!("Opening a {} session", self.browser);
@@ -46,9 +59,50 @@ impl<'a> SessionBuilder {
 
         let hosts = Arc::from(self.hosts);
 
+        let proxy = if self.proxy {
+            let proxy = RecordingProxy::start().wrap_err("Failed to start the recording proxy")?;
+            eprintln!(
+                "Recording the cookies through the proxy at {}",
+                proxy.addr()
+            );
+            Some(proxy)
+        } else {
+            None
+        };
+
         match self.browser {
-            Browser::Firefox => {
-                let mut child = Command::new("firefox")
+            Browser::FirefoxVariant(firefox_variant) => {
+                let cmd = match firefox_variant {
+                    FirefoxVariant::Firefox => "firefox",
+                    FirefoxVariant::Waterfox => "waterfox",
+                    FirefoxVariant::PaleMoon => "palemoon",
+                    FirefoxVariant::SeaMonkey => "seamonkey",
+                    FirefoxVariant::Thunderbird => "thunderbird",
+                    FirefoxVariant::Floorp => "floorp",
+                    FirefoxVariant::Zen => "zen",
+                    FirefoxVariant::Fork(fork) => &fork.executable,
+                    FirefoxVariant::TorBrowser => {
+                        bail!("Sessions are not supported for {}", self.browser)
+                    }
+                };
+
+                if let Some(proxy) = &proxy {
+                    let addr = proxy.addr();
+                    fs::write(
+                        session_context.path().join("user.js"),
+                        format!(
+                            "user_pref(\"network.proxy.type\", 1);\n\
+                            user_pref(\"network.proxy.http\", \"{ip}\");\n\
+                            user_pref(\"network.proxy.http_port\", {port});\n\
+                            user_pref(\"network.proxy.ssl\", \"{ip}\");\n\
+                            user_pref(\"network.proxy.ssl_port\", {port});\n",
+                            ip = addr.ip(),
+                            port = addr.port()
+                        ),
+                    )?;
+                }
+
+                let mut child = Command::new(cmd)
                     .arg("-no-remote")
                     .arg("-profile")
                     .arg(session_context.path())
@@ -57,18 +111,21 @@ impl<'a> SessionBuilder {
                     .stderr(Stdio::null())
                     .stdout(Stdio::null())
                     .spawn()
-                    .wrap_err("Failed to run firefox")?;
+                    .wrap_err_with(|| format!("Failed to run {cmd}"))?;
 
                 child.wait()?;
 
+                if let Some(proxy) = proxy {
+                    return Ok(Session::recorded(&proxy, &hosts));
+                }
+
                 let path_provider = firefox::PathProvider::from_root(session_context.path());
 
-                let hosts = Arc::from(hosts);
                 let hosts = Arc::clone(&hosts);
                 let filter = Box::from(move |host: &str| filter_hosts(host, &hosts));
 
                 let manager = FirefoxManager::new(path_provider, Some(filter), false)?;
-                let cookies = manager.get_cookies()?;
+                let cookies = manager.get_cookies_with_report()?.cookies;
 
                 Ok(Session { cookies })
             }
@@ -78,8 +135,22 @@ impl<'a> SessionBuilder {
 
                 let cmd = match chrome_variant {
                     ChromeVariant::Chrome => "google-chrome",
+                    ChromeVariant::ChromeBeta => "google-chrome-beta",
+                    ChromeVariant::ChromeDev => "google-chrome-unstable",
+                    ChromeVariant::ChromeCanary => "google-chrome-canary",
                     ChromeVariant::Chromium => "chromium",
                     ChromeVariant::Edge => "edge",
+                    ChromeVariant::EdgeBeta => "microsoft-edge-beta",
+                    ChromeVariant::EdgeDev => "microsoft-edge-dev",
+                    ChromeVariant::EdgeCanary => "microsoft-edge-canary",
+                    ChromeVariant::Arc => "arc",
+                    ChromeVariant::Yandex => "yandex-browser",
+                    ChromeVariant::Whale => "naver-whale",
+                    ChromeVariant::Custom(custom) => custom.executable.as_str(),
+                    // Falkon selects its profiles by name, not by directory.
+                    ChromeVariant::Falkon => {
+                        bail!("Sessions are not supported for {}", self.browser)
+                    }
                 };
 
                 let user_data_arg = {
@@ -94,6 +165,11 @@ impl<'a> SessionBuilder {
                 let mut child = Command::new(cmd)
                     .arg("--new-window")
                     .arg(user_data_arg)
+                    .args(
+                        proxy
+                            .as_ref()
+                            .map(|proxy| format!("--proxy-server={}", proxy.addr())),
+                    )
                     .args(url)
                     .stderr(Stdio::null())
                     .stdout(Stdio::null())
@@ -102,14 +178,51 @@ impl<'a> SessionBuilder {
 
                 child.wait()?;
 
+                if let Some(proxy) = proxy {
+                    return Ok(Session::recorded(&proxy, &hosts));
+                }
+
                 let path_provider = chrome::PathProvider::from_root(session_context.path());
 
-                let hosts = Arc::from(hosts);
                 let hosts = Arc::clone(&hosts);
                 let filter = Box::from(move |host: &str| filter_hosts(host, &hosts));
                 let manager =
                     ChromeManager::new(chrome_variant, path_provider, Some(filter), false)?;
-                let cookies = manager.get_cookies()?;
+                let cookies = manager.get_cookies_with_report()?.cookies;
+
+                Ok(Session { cookies })
+            }
+
+            Browser::InternetExplorerVariant(_) => {
+                bail!("Sessions are not supported for {}", self.browser)
+            }
+
+            Browser::WebKitGtkVariant(webkitgtk_variant) => {
+                let cmd = match webkitgtk_variant {
+                    WebKitGtkVariant::Epiphany => "epiphany",
+                };
+                // The proxy of WebKitGTK can only be set in the settings of the system.
+                if proxy.is_some() {
+                    bail!("The recording proxy is not supported for {}", self.browser);
+                }
+
+                let mut child = Command::new(cmd)
+                    .arg("--profile")
+                    .arg(session_context.path())
+                    .args(url)
+                    .stderr(Stdio::null())
+                    .stdout(Stdio::null())
+                    .spawn()
+                    .wrap_err_with(|| format!("Failed to run {cmd}"))?;
+
+                child.wait()?;
+
+                let path_provider = webkitgtk::PathProvider::from_root(session_context.path());
+
+                let hosts = Arc::clone(&hosts);
+                let filter = Box::from(move |host: &str| filter_hosts(host, &hosts));
+                let manager = WebKitGtkManager::new(path_provider, Some(filter), false)?;
+                let cookies = manager.get_cookies_with_report()?.cookies;
 
                 Ok(Session { cookies })
             }
@@ -117,12 +230,27 @@ impl<'a> SessionBuilder {
     }
 }
 
-pub(crate) struct Session<'a> {
-    cookies: Vec<Cookie<'a>>,
+pub(crate) struct Session {
+    cookies: Vec<BrowserCookie>,
 }
 
-impl<'a> Session<'a> {
-    pub fn cookies(&self) -> &[Cookie<'a>] {
+impl Session {
+    /// Returns the session with the cookies recorded by the proxy for the hosts.
+    fn recorded(proxy: &RecordingProxy, hosts: &[Uri]) -> Self {
+        if proxy.tunnels() > 0 {
+            eprintln!(
+                "Warning: the cookies of {} HTTPS connection(s) could not be recorded by the proxy",
+                proxy.tunnels()
+            );
+        }
+
+        let mut cookies = proxy.cookies();
+        cookies.retain(|cookie| filter_hosts(cookie.domain().unwrap_or_default(), hosts));
+
+        Self { cookies }
+    }
+
+    pub fn cookies(&self) -> &[BrowserCookie] {
         &self.cookies
     }
 }
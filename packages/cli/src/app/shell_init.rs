@@ -0,0 +1,148 @@
+//! Shell functions calling `gateau wrap` in place of the wrapped commands, printed by `shell-init`.
+
+use std::io::{self, Write};
+
+use crate::{wrapped::WrappedCmd, Shell};
+
+/// Write the functions wrapping each command, named after the command with the prefix
+/// (e.g. `gcurl` for `curl`), which run `program OPTIONS wrap COMMAND -- ARGS`
+/// with the provided options of gateau.
+///
+/// The commands whose name cannot be used in a function name are skipped with a warning.
+/// The functions complete like the wrapped commands in zsh and fish.
+pub(crate) fn shell_init<W: Write>(
+    shell: Shell,
+    program: &str,
+    options: &[String],
+    prefix: &str,
+    commands: &[&WrappedCmd],
+    writer: &mut W,
+) -> io::Result<()> {
+    writeln!(
+        writer,
+        "# gateau shell integration, loaded with: {}",
+        match shell {
+            Shell::Bash => "eval \"$(gateau shell-init bash)\"",
+            Shell::Zsh => "eval \"$(gateau shell-init zsh)\"",
+            Shell::Fish => "gateau shell-init fish | source",
+        }
+    )?;
+
+    let quote = match shell {
+        Shell::Bash | Shell::Zsh => quote_posix,
+        Shell::Fish => quote_fish,
+    };
+    let gateau = std::iter::once(program)
+        .chain(options.iter().map(String::as_str))
+        .map(quote)
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    for command in commands {
+        if !is_function_name(&command.name) {
+            eprintln!(
+                "Warning: no function for {}, which cannot be used in a function name",
+                command.name
+            );
+            continue;
+        }
+
+        let function = format!("{prefix}{}", command.name);
+        match shell {
+            Shell::Bash | Shell::Zsh => {
+                writeln!(
+                    writer,
+                    "{function}() {{ {gateau} wrap {} -- \"$@\"; }}",
+                    command.name
+                )?;
+                if shell == Shell::Zsh {
+                    writeln!(
+                        writer,
+                        "(( $+functions[compdef] )) && compdef {function}={}",
+                        quote_posix(&command.binary)
+                    )?;
+                }
+            }
+            Shell::Fish => {
+                writeln!(
+                    writer,
+                    "function {function} --wraps {} --description {}",
+                    quote_fish(&command.binary),
+                    quote_fish(&format!("{} with the cookies of the browser", command.name))
+                )?;
+                writeln!(writer, "    {gateau} wrap {} -- $argv", command.name)?;
+                writeln!(writer, "end")?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns whether the name can be used in a function name by all the shells, without quoting.
+fn is_function_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// Quote the word for bash and zsh.
+fn quote_posix(word: &str) -> String {
+    format!("'{}'", word.replace('\'', r"'\''"))
+}
+
+/// Quote the word for fish.
+fn quote_fish(word: &str) -> String {
+    format!("'{}'", word.replace('\\', r"\\").replace('\'', r"\'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::wrapped::WrappedCmds;
+
+    use super::*;
+
+    fn init(shell: Shell) -> String {
+        let commands = WrappedCmds::builtin();
+        let curl = commands.get("curl").unwrap();
+        let invalid = WrappedCmd {
+            name: "it's".to_string(),
+            ..curl.clone()
+        };
+
+        let mut output = Vec::new();
+        shell_init(
+            shell,
+            "/opt/it's/gateau",
+            &["--browser".to_string(), "chrome".to_string()],
+            "g",
+            &[curl, &invalid],
+            &mut output,
+        )
+        .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_shell_init() {
+        assert_eq!(
+            init(Shell::Bash),
+            "# gateau shell integration, loaded with: eval \"$(gateau shell-init bash)\"\n\
+            gcurl() { '/opt/it'\\''s/gateau' '--browser' 'chrome' wrap curl -- \"$@\"; }\n"
+        );
+        assert_eq!(
+            init(Shell::Zsh),
+            "# gateau shell integration, loaded with: eval \"$(gateau shell-init zsh)\"\n\
+            gcurl() { '/opt/it'\\''s/gateau' '--browser' 'chrome' wrap curl -- \"$@\"; }\n\
+            (( $+functions[compdef] )) && compdef gcurl='curl'\n"
+        );
+        assert_eq!(
+            init(Shell::Fish),
+            "# gateau shell integration, loaded with: gateau shell-init fish | source\n\
+            function gcurl --wraps 'curl' --description 'curl with the cookies of the browser'\n    \
+            '/opt/it\\'s/gateau' '--browser' 'chrome' wrap curl -- $argv\n\
+            end\n"
+        );
+    }
+}
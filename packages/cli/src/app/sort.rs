@@ -0,0 +1,107 @@
+//! Order of the cookies in the output, so the outputs of several runs can be compared.
+
+use gateau::report::{cookie_key, BrowserCookie};
+
+use crate::SortKey;
+
+/// Sort the cookies by the key, then by domain, path and name,
+/// the domain cookies coming before the host-only ones of the same domain.
+///
+/// The session cookies, and the cookies whose last access time is unknown,
+/// come first in ascending order.
+pub(crate) fn sort(cookies: &mut [BrowserCookie], key: SortKey, reverse: bool) {
+    cookies.sort_by_cached_key(|cookie| {
+        let time = match key {
+            SortKey::Expiry => cookie.expires_datetime(),
+            SortKey::LastAccess => cookie.last_access,
+            SortKey::Domain | SortKey::Name => None,
+        };
+        let name = match key {
+            SortKey::Name => cookie.name().to_string(),
+            _ => String::new(),
+        };
+
+        (
+            time,
+            name,
+            cookie.domain().unwrap_or_default().to_string(),
+            cookie_key(cookie),
+        )
+    });
+
+    if reverse {
+        cookies.reverse();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cookie::{time::OffsetDateTime, Cookie};
+
+    use super::*;
+
+    fn cookies() -> Vec<BrowserCookie> {
+        vec![
+            BrowserCookie {
+                last_access: Some(OffsetDateTime::from_unix_timestamp(1700000000).unwrap()),
+                ..Cookie::build(("b", "1"))
+                    .domain("www.example.com")
+                    .path("/")
+                    .expires(OffsetDateTime::from_unix_timestamp(2000000000).unwrap())
+                    .into()
+            },
+            Cookie::build(("c", "2"))
+                .domain("example.com")
+                .path("/")
+                .into(),
+            Cookie::build(("a", "3"))
+                .domain("example.org")
+                .path("/")
+                .expires(OffsetDateTime::from_unix_timestamp(1900000000).unwrap())
+                .into(),
+        ]
+    }
+
+    fn names(cookies: &[BrowserCookie]) -> Vec<&str> {
+        cookies.iter().map(|cookie| cookie.name()).collect()
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut sorted = cookies();
+        sort(&mut sorted, SortKey::Domain, false);
+        assert_eq!(names(&sorted), ["c", "a", "b"]);
+
+        sort(&mut sorted, SortKey::Name, true);
+        assert_eq!(names(&sorted), ["c", "b", "a"]);
+
+        sort(&mut sorted, SortKey::Expiry, false);
+        assert_eq!(names(&sorted), ["c", "a", "b"]);
+
+        sort(&mut sorted, SortKey::LastAccess, true);
+        assert_eq!(names(&sorted), ["b", "a", "c"]);
+    }
+
+    #[test]
+    fn test_sort_host_only() {
+        let domain_cookie: BrowserCookie = Cookie::build(("sid", "1"))
+            .domain("example.com")
+            .path("/")
+            .into();
+        let host_only_cookie = BrowserCookie {
+            host_only: true,
+            ..Cookie::build(("sid", "2"))
+                .domain("example.com")
+                .path("/")
+                .into()
+        };
+
+        for mut sorted in [
+            vec![domain_cookie.clone(), host_only_cookie.clone()],
+            vec![host_only_cookie.clone(), domain_cookie.clone()],
+        ] {
+            sort(&mut sorted, SortKey::Domain, false);
+            assert_eq!(sorted, [domain_cookie.clone(), host_only_cookie.clone()]);
+        }
+    }
+}
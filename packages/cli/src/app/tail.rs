@@ -0,0 +1,212 @@
+//! Live tailing of the cookies of a browser.
+//!
+//! The cookies database is polled at a regular interval and the cookies which have been
+//! added, changed or removed since the previous poll are printed as JSON lines.
+
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use color_eyre::Result;
+use cookie::Cookie;
+use gateau::report::{cookie_key, BrowserCookie, CookieKey};
+use serde::Serialize;
+
+use crate::interrupt;
+
+/// Cookies indexed by their domain, path and name.
+type Snapshot = BTreeMap<CookieKey, BrowserCookie>;
+
+/// Kind of change of a cookie between two polls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum ChangeKind {
+    Added,
+    Changed,
+    Removed,
+}
+
+/// Change of a cookie, as it is printed.
+#[derive(Debug, Clone, Serialize)]
+struct CookieChange<'a> {
+    change: ChangeKind,
+    name: &'a str,
+    value: &'a str,
+    domain: Option<&'a str>,
+    path: Option<&'a str>,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<String>,
+    /// The cookie's expiration date, in seconds since the Unix epoch.
+    expires: Option<i64>,
+}
+
+impl<'a> CookieChange<'a> {
+    fn new(change: ChangeKind, cookie: &'a Cookie<'static>) -> Self {
+        Self {
+            change,
+            name: cookie.name(),
+            value: cookie.value(),
+            domain: cookie.domain(),
+            path: cookie.path(),
+            secure: cookie.secure().unwrap_or_default(),
+            http_only: cookie.http_only().unwrap_or_default(),
+            same_site: cookie.same_site().map(|same_site| same_site.to_string()),
+            expires: cookie
+                .expires()
+                .and_then(|t| t.datetime())
+                .map(|t| t.unix_timestamp()),
+        }
+    }
+}
+
+/// Modification time and size of a database and of its write-ahead log,
+/// which change when the browser writes to the database or replaces it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DatabaseStamp([Option<(SystemTime, u64)>; 2]);
+
+impl DatabaseStamp {
+    pub fn of(database: &Path) -> Self {
+        let mut wal = OsString::from(database.as_os_str());
+        wal.push("-wal");
+        let stamp = |path: &Path| {
+            let metadata = fs::metadata(path).ok()?;
+            Some((metadata.modified().ok()?, metadata.len()))
+        };
+
+        Self([stamp(database), stamp(&PathBuf::from(wal))])
+    }
+}
+
+fn snapshot(cookies: Vec<BrowserCookie>) -> Snapshot {
+    cookies
+        .into_iter()
+        .map(|cookie| (cookie_key(&cookie), cookie))
+        .collect()
+}
+
+/// Write the changes between two snapshots as JSON lines.
+fn write_changes<W: Write>(
+    previous: &Snapshot,
+    current: &Snapshot,
+    writer: &mut W,
+) -> io::Result<()> {
+    let added_or_changed = current
+        .iter()
+        .filter_map(|(key, cookie)| match previous.get(key) {
+            None => Some(CookieChange::new(ChangeKind::Added, cookie)),
+            Some(previous) if previous != cookie => {
+                Some(CookieChange::new(ChangeKind::Changed, cookie))
+            }
+            Some(_) => None,
+        });
+    let removed = previous
+        .iter()
+        .filter(|(key, _)| !current.contains_key(*key))
+        .map(|(_, cookie)| CookieChange::new(ChangeKind::Removed, cookie));
+
+    for change in added_or_changed.chain(removed) {
+        serde_json::to_writer(&mut *writer, &change)?;
+        writeln!(writer)?;
+    }
+
+    Ok(())
+}
+
+/// Poll the cookies every `interval` and write the changes to `writer`,
+/// until gateau is interrupted or an I/O error occurs.
+/// The cookies which exist at the first poll are not printed.
+///
+/// The interruptions are only handled between two polls, so the changes are never partially written.
+///
+/// Errors while reading the cookies (e.g. when the browser is writing to the database)
+/// are reported as warnings and the poll is retried at the next interval.
+pub(crate) fn tail<F, W>(mut poll: F, interval: Duration, writer: &mut W) -> Result<()>
+where
+    F: FnMut() -> Result<Vec<BrowserCookie>>,
+    W: Write,
+{
+    let mut known = snapshot(poll()?);
+
+    loop {
+        interrupt::sleep(interval);
+        if interrupt::interrupted() {
+            return Ok(());
+        }
+
+        let current = match poll() {
+            Ok(cookies) => snapshot(cookies),
+            Err(e) => {
+                eprintln!("Warning: {e}: {}", e.root_cause());
+                continue;
+            }
+        };
+
+        write_changes(&known, &current, writer)?;
+        writer.flush()?;
+
+        known = current;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cookie(name: &'static str, value: &'static str) -> BrowserCookie {
+        Cookie::build((name, value))
+            .domain("example.com")
+            .path("/")
+            .into()
+    }
+
+    #[test]
+    fn test_database_stamp() {
+        let dir = tempfile::tempdir().unwrap();
+        let database = dir.path().join("cookies.sqlite");
+        fs::write(&database, "db").unwrap();
+
+        let stamp = DatabaseStamp::of(&database);
+        assert_eq!(stamp, DatabaseStamp::of(&database));
+
+        fs::write(dir.path().join("cookies.sqlite-wal"), "wal").unwrap();
+        assert_ne!(stamp, DatabaseStamp::of(&database));
+    }
+
+    #[test]
+    fn test_write_changes() {
+        let previous = snapshot(vec![cookie("a", "1"), cookie("b", "2"), cookie("c", "3")]);
+        let current = snapshot(vec![cookie("a", "1"), cookie("b", "4"), cookie("d", "5")]);
+
+        let mut output = Vec::new();
+        write_changes(&previous, &current, &mut output).unwrap();
+
+        let changes = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .map(|change| {
+                (
+                    change["change"].as_str().unwrap().to_string(),
+                    change["name"].as_str().unwrap().to_string(),
+                    change["value"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            changes,
+            [
+                ("changed", "b", "4"),
+                ("added", "d", "5"),
+                ("removed", "c", "3")
+            ]
+            .map(|(change, name, value)| (change.into(), name.into(), value.into()))
+        );
+    }
+}
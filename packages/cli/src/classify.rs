@@ -0,0 +1,241 @@
+//! Classification of cookies in categories (advertising, analytics, functional).
+//!
+//! The rules are embedded in the binary and are derived from the
+//! [Open Cookie Database](https://github.com/jkwakman/Open-Cookie-Database).
+//! They can be replaced by the `categories.csv` file of the data directory (`--data-dir`).
+
+use std::{fmt::Display, path::Path, str::FromStr, sync::OnceLock};
+
+use color_eyre::{
+    eyre::{eyre, Context},
+    Result,
+};
+use cookie::Cookie;
+use serde::Deserialize;
+
+/// Built-in classification rules.
+const BUILTIN_RULES: &str = include_str!("../data/categories.csv");
+
+/// Classifier used by the process, with the rules of the data directory if they are loaded.
+static CLASSIFIER: OnceLock<Classifier> = OnceLock::new();
+
+/// Category of a cookie.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Category {
+    Advertising,
+    Analytics,
+    Functional,
+}
+
+impl Category {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Category::Advertising => "advertising",
+            Category::Analytics => "analytics",
+            Category::Functional => "functional",
+        }
+    }
+}
+
+impl Display for Category {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Category {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "advertising" => Ok(Category::Advertising),
+            "analytics" => Ok(Category::Analytics),
+            "functional" => Ok(Category::Functional),
+            _ => Err(format!(
+                "'{s}' is not one of the supported categories (advertising, analytics, functional)"
+            )),
+        }
+    }
+}
+
+/// Pattern matching the name of a cookie.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum NamePattern {
+    Exact(String),
+    Prefix(String),
+}
+
+impl NamePattern {
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            NamePattern::Exact(pattern) => pattern == name,
+            NamePattern::Prefix(prefix) => name.starts_with(prefix.as_str()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    category: Category,
+    name: NamePattern,
+    domain: Option<String>,
+}
+
+impl Rule {
+    fn matches(&self, cookie: &Cookie<'_>) -> bool {
+        self.name.matches(cookie.name())
+            && self.domain.as_deref().is_none_or(|domain| {
+                let cookie_domain = cookie.domain().unwrap_or_default();
+                let cookie_domain = cookie_domain.strip_prefix('.').unwrap_or(cookie_domain);
+
+                cookie_domain == domain
+                    || cookie_domain
+                        .strip_suffix(domain)
+                        .is_some_and(|sub| sub.ends_with('.'))
+            })
+    }
+}
+
+/// Classifier matching cookies against a list of rules.
+#[derive(Debug, Clone)]
+pub(crate) struct Classifier {
+    rules: Vec<Rule>,
+}
+
+impl Classifier {
+    /// Create a classifier with the built-in rules.
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN_RULES).expect("Built-in classification rules are invalid")
+    }
+
+    /// Use the rules of the file instead of the built-in ones for the rest of the process.
+    ///
+    /// Has no effect if the classifier of the process has already been used.
+    pub fn load(path: &Path) -> Result<()> {
+        let rules = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+        let classifier =
+            Self::parse(&rules).wrap_err_with(|| format!("Failed to parse {}", path.display()))?;
+
+        let _ = CLASSIFIER.set(classifier);
+        Ok(())
+    }
+
+    /// Returns the classifier of the process,
+    /// with the rules of the data directory if they are loaded, or the built-in ones.
+    pub fn current() -> &'static Self {
+        CLASSIFIER.get_or_init(Self::builtin)
+    }
+
+    /// Parse classification rules.
+    ///
+    /// Each line has the form `category,name,domain`, where `name` can end with a `*`
+    /// to match any suffix and `domain` can be empty.
+    /// Empty lines and lines starting with `#` are ignored.
+    pub fn parse(rules: &str) -> Result<Self> {
+        let rules = rules
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(line_number, line)| {
+                let mut fields = line.splitn(3, ',').map(str::trim);
+
+                let (Some(category), Some(name)) = (fields.next(), fields.next()) else {
+                    return Err(eyre!("Invalid classification rule at line {line_number}"));
+                };
+
+                let category = category
+                    .parse()
+                    .map_err(|e| eyre!("Invalid classification rule at line {line_number}: {e}"))?;
+
+                let name = match name.strip_suffix('*') {
+                    Some(prefix) => NamePattern::Prefix(prefix.to_string()),
+                    None => NamePattern::Exact(name.to_string()),
+                };
+
+                let domain = fields
+                    .next()
+                    .filter(|domain| !domain.is_empty())
+                    .map(|domain| domain.trim_start_matches('.').to_string());
+
+                Ok(Rule {
+                    category,
+                    name,
+                    domain,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { rules })
+    }
+
+    /// Get the category of a cookie, if it matches one of the rules.
+    pub fn classify(&self, cookie: &Cookie<'_>) -> Option<Category> {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(cookie))
+            .map(|rule| rule.category)
+    }
+
+    /// Get the name of the category of a cookie, `unknown` if it matches none of the rules.
+    pub fn category_name(&self, cookie: &Cookie<'_>) -> &'static str {
+        self.classify(cookie)
+            .map_or("unknown", |category| category.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cookie(name: &'static str, domain: &'static str) -> Cookie<'static> {
+        Cookie::build((name, "value")).domain(domain).into()
+    }
+
+    #[test]
+    fn test_builtin_rules() {
+        let classifier = Classifier::builtin();
+
+        assert_eq!(
+            classifier.classify(&cookie("_ga", ".example.com")),
+            Some(Category::Analytics)
+        );
+        assert_eq!(
+            classifier.classify(&cookie("_ga_ABCDEF", ".example.com")),
+            Some(Category::Analytics)
+        );
+        assert_eq!(
+            classifier.classify(&cookie("IDE", ".doubleclick.net")),
+            Some(Category::Advertising)
+        );
+        assert_eq!(
+            classifier.classify(&cookie("cf_clearance", "example.com")),
+            Some(Category::Functional)
+        );
+        assert_eq!(classifier.classify(&cookie("session", "example.com")), None);
+    }
+
+    #[test]
+    fn test_domain_rule() {
+        let classifier = Classifier::parse("advertising,IDE,doubleclick.net").unwrap();
+
+        assert_eq!(
+            classifier.classify(&cookie("IDE", "ad.doubleclick.net")),
+            Some(Category::Advertising)
+        );
+        assert_eq!(classifier.classify(&cookie("IDE", "example.com")), None);
+        assert_eq!(
+            classifier.classify(&cookie("IDE", "notdoubleclick.net")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        assert!(Classifier::parse("tracking,_ga,").is_err());
+        assert!(Classifier::parse("analytics").is_err());
+        assert!(Classifier::parse("# comment\n\nanalytics,_ga").is_ok());
+    }
+}
@@ -0,0 +1,317 @@
+//! Configuration file.
+//!
+//! The configuration file is written in TOML and is read from
+//! `$XDG_CONFIG_HOME/gateau/config.toml` (or the platform equivalent),
+//! unless another path is provided with `--config`.
+//!
+//! ```toml
+//! # Categories of cookies which are always excluded from exports
+//! drop-categories = ["advertising", "analytics"]
+//!
+//! # Domains which are also matched when filtering by an alias, with --resolve-aliases
+//! [host-aliases]
+//! "dev.local" = ["api.example.com", "example.com"]
+//!
+//! # Defaults applied when wrapping a command
+//! [wrap.curl]
+//! args = ["--compressed"]
+//! drop-categories = ["advertising"]
+//!
+//! # Rewrites applied by `gateau render --env staging`
+//! [environments.staging.domains]
+//! "example.com" = "staging.example.com"
+//! [environments.staging.cookie-names]
+//! "session" = "staging_session"
+//!
+//! # Commands which can be wrapped, in addition to curl, wget and httpie
+//! [[wrapped-commands]]
+//! name = "yt-dlp"
+//! binary = "yt-dlp"
+//! cookie-flag = "--cookies"
+//! format = "netscape"
+//! # Whether the cookie file is passed before or after the forwarded arguments
+//! position = "before"
+//!
+//! # Chromium-based browsers which are not supported out of the box
+//! [[chromium-browsers]]
+//! name = "ungoogled-chromium"
+//! data-dir = "ungoogled-chromium"
+//! keyring-service = "Chromium Safe Storage"
+//! keyring-account = "chromium"
+//! executable = "ungoogled-chromium"
+//! # Whether it is an Electron or WebView2 application rather than a browser
+//! app = false
+//!
+//! # Firefox forks which are not supported out of the box
+//! [[firefox-browsers]]
+//! name = "mercury"
+//! vendor-dir = "Mercury"
+//! executable = "mercury-browser"
+//! ```
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use color_eyre::{eyre::Context, Result};
+use gateau::{
+    chrome::{self, CustomVariant},
+    firefox::{self, ForkVariant},
+};
+use serde::Deserialize;
+
+use crate::classify::Category;
+use crate::wrapped::WrappedCmd;
+
+/// Configuration of gateau.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct Config {
+    /// Categories of cookies which are always excluded.
+    pub drop_categories: Vec<Category>,
+
+    /// Domains matched by each host alias.
+    pub host_aliases: HashMap<String, Vec<String>>,
+
+    /// Defaults applied when wrapping each command, by name.
+    pub wrap: HashMap<String, WrapDefaults>,
+
+    /// Rewrites applied to the cookies for each environment, by name.
+    pub environments: HashMap<String, Environment>,
+
+    /// Commands which can be wrapped, in addition to the built-in ones.
+    pub wrapped_commands: Vec<WrappedCmd>,
+
+    /// Custom Chromium-based browsers.
+    pub chromium_browsers: Vec<CustomVariant>,
+
+    /// Firefox forks.
+    pub firefox_browsers: Vec<ForkVariant>,
+}
+
+/// Defaults applied when wrapping a command.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct WrapDefaults {
+    /// Arguments passed to the command before the forwarded ones.
+    pub args: Vec<String>,
+
+    /// Categories of cookies which are excluded, in addition to the global ones.
+    pub drop_categories: Vec<Category>,
+}
+
+/// Rewrites applied to the cookies to use them in another environment (e.g. staging).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct Environment {
+    /// Domain of the environment, by domain of the cookies (including their subdomains).
+    pub domains: HashMap<String, String>,
+
+    /// Name of the cookies in the environment, by name of the cookies.
+    pub cookie_names: HashMap<String, String>,
+}
+
+impl Config {
+    /// Returns the default path of the configuration file.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs_next::config_dir().map(|dir| dir.join("gateau").join("config.toml"))
+    }
+
+    /// Load the configuration from the provided path,
+    /// or from the default path if it exists.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path.to_owned(),
+            None => match Config::default_path().filter(|path| path.exists()) {
+                Some(path) => path,
+                None => return Ok(Config::default()),
+            },
+        };
+
+        let content = std::fs::read_to_string(&path)
+            .wrap_err_with(|| format!("Failed to read configuration file {}", path.display()))?;
+
+        Config::parse(&content)
+            .wrap_err_with(|| format!("Failed to parse configuration file {}", path.display()))
+    }
+
+    /// Register the custom browsers, so they can be selected by their name.
+    pub fn register_browsers(&self) {
+        for browser in &self.chromium_browsers {
+            chrome::register_variant(browser.clone());
+        }
+
+        for browser in &self.firefox_browsers {
+            firefox::register_fork(browser.clone());
+        }
+    }
+
+    /// Parse the configuration from a TOML string.
+    pub fn parse(content: &str) -> Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = Config::parse(r#"drop-categories = ["advertising", "analytics"]"#).unwrap();
+
+        assert_eq!(
+            config.drop_categories,
+            vec![Category::Advertising, Category::Analytics]
+        );
+    }
+
+    #[test]
+    fn test_parse_empty_config() {
+        let config = Config::parse("").unwrap();
+
+        assert!(config.drop_categories.is_empty());
+    }
+
+    #[test]
+    fn test_parse_chromium_browsers() {
+        let config = Config::parse(
+            r#"
+            [[chromium-browsers]]
+            name = "slimjet"
+            data-dir = "slimjet"
+            keyring-service = "Slimjet Safe Storage"
+            keyring-account = "slimjet"
+            executable = "flashpeak-slimjet"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.chromium_browsers.len(), 1);
+        assert_eq!(config.chromium_browsers[0].name, "slimjet");
+        assert_eq!(config.chromium_browsers[0].executable, "flashpeak-slimjet");
+        assert!(!config.chromium_browsers[0].app);
+    }
+
+    #[test]
+    fn test_parse_firefox_browsers() {
+        let config = Config::parse(
+            r#"
+            [[firefox-browsers]]
+            name = "mercury"
+            vendor-dir = "Mercury"
+            executable = "mercury-browser"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.firefox_browsers.len(), 1);
+        assert_eq!(config.firefox_browsers[0].vendor_dir, "Mercury");
+    }
+
+    #[test]
+    fn test_parse_wrap_defaults() {
+        let config = Config::parse(
+            r#"
+            [wrap.curl]
+            args = ["--compressed"]
+            drop-categories = ["analytics"]
+
+            [wrap.http]
+            args = ["--print=hb"]
+            "#,
+        )
+        .unwrap();
+
+        let curl = &config.wrap["curl"];
+        assert_eq!(curl.args, vec!["--compressed".to_string()]);
+        assert_eq!(curl.drop_categories, vec![Category::Analytics]);
+        assert!(config.wrap["http"].drop_categories.is_empty());
+    }
+
+    #[test]
+    fn test_parse_wrapped_commands() {
+        let config = Config::parse(
+            r#"
+            [[wrapped-commands]]
+            name = "yt-dlp"
+            binary = "yt-dlp"
+            cookie-flag = "--cookies"
+            format = "netscape"
+            position = "after"
+            header-args = ["--add-headers", "Cookie:{cookie_header}"]
+            header-position = "after"
+
+            [[wrapped-commands]]
+            name = "httpx"
+            binary = "httpx"
+            cookie-flag = "--cookies"
+            cookies = "pairs"
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.wrapped_commands.len(), 2);
+        assert_eq!(
+            config.wrapped_commands[0].format,
+            crate::OutputFormat::Netscape
+        );
+        assert_eq!(
+            config.wrapped_commands[0].position,
+            crate::wrapped::ArgPosition::After
+        );
+        assert_eq!(
+            config.wrapped_commands[0].header_args,
+            ["--add-headers", "Cookie:{cookie_header}"]
+        );
+        assert_eq!(
+            config.wrapped_commands[0].header_position,
+            crate::wrapped::ArgPosition::After
+        );
+        assert_eq!(
+            config.wrapped_commands[1].cookies,
+            crate::wrapped::CookieArgs::Pairs
+        );
+        assert!(config.wrapped_commands[1].header_args.is_empty());
+    }
+
+    #[test]
+    fn test_parse_environments() {
+        let config = Config::parse(
+            r#"
+            [environments.staging.domains]
+            "example.com" = "staging.example.com"
+
+            [environments.staging.cookie-names]
+            "session" = "staging_session"
+            "#,
+        )
+        .unwrap();
+
+        let staging = &config.environments["staging"];
+        assert_eq!(staging.domains["example.com"], "staging.example.com");
+        assert_eq!(staging.cookie_names["session"], "staging_session");
+    }
+
+    #[test]
+    fn test_parse_host_aliases() {
+        let config = Config::parse(
+            r#"
+            [host-aliases]
+            "dev.local" = ["api.example.com"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.host_aliases.get("dev.local"),
+            Some(&vec!["api.example.com".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_category() {
+        assert!(Config::parse(r#"drop-categories = ["tracking"]"#).is_err());
+    }
+}
@@ -0,0 +1,351 @@
+//! Extraction of browser profiles from Docker or Podman containers, Kubernetes pods
+//! and remote machines.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    str::FromStr,
+};
+
+use color_eyre::{
+    eyre::{bail, ensure, Context},
+    Result,
+};
+use tempfile::TempDir;
+
+use crate::interrupt;
+
+/// Container engines, in order of preference.
+const ENGINES: [&str; 2] = ["docker", "podman"];
+
+/// Path of a browser profile inside a container, written as `name:path`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ContainerPath {
+    container: String,
+    path: String,
+}
+
+impl FromStr for ContainerPath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some((container, path)) if !container.is_empty() && !path.is_empty() => Ok(Self {
+                container: container.to_string(),
+                path: path.to_string(),
+            }),
+            _ => Err(format!(
+                "'{s}' is not a valid container path (expected <name>:<path>)"
+            )),
+        }
+    }
+}
+
+/// Path of a browser profile inside a Kubernetes pod, written as `pod/name:path`
+/// (the `pod/` prefix is optional).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PodPath {
+    pod: String,
+    path: String,
+}
+
+impl FromStr for PodPath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let ContainerPath { container, path } =
+            s.strip_prefix("pod/").unwrap_or(s).parse().map_err(|_| {
+                format!("'{s}' is not a valid pod path (expected pod/<name>:<path>)")
+            })?;
+
+        Ok(Self {
+            pod: container,
+            path,
+        })
+    }
+}
+
+/// Path of a browser profile on a remote machine, written as `ssh://[user@]host[:port]/path`.
+/// Paths starting with `/~/` are relative to the home directory of the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SshPath {
+    host: String,
+    port: Option<u16>,
+    path: String,
+}
+
+impl SshPath {
+    /// Parse the root path provided by the user if it is an SSH URL.
+    pub fn from_root_path(root_path: &Path) -> Option<Result<Self, String>> {
+        root_path
+            .to_str()
+            .filter(|root_path| root_path.starts_with("ssh://"))
+            .map(str::parse)
+    }
+}
+
+impl FromStr for SshPath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            || format!("'{s}' is not a valid SSH path (expected ssh://[user@]host[:port]/path)");
+
+        let (authority, path) = s
+            .strip_prefix("ssh://")
+            .and_then(|rest| rest.split_once('/'))
+            .ok_or_else(invalid)?;
+
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port.parse().map_err(|_| invalid())?)),
+            None => (authority, None),
+        };
+
+        if host.is_empty() || path.is_empty() {
+            return Err(invalid());
+        }
+
+        // scp resolves relative paths from the home directory.
+        let path = match path.strip_prefix("~/") {
+            Some(path) => path.to_string(),
+            None => format!("/{path}"),
+        };
+
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            path,
+        })
+    }
+}
+
+/// Profile copied from a container, removed when dropped.
+#[derive(Debug)]
+pub(crate) struct CopiedProfile {
+    dir: TempDir,
+}
+
+impl CopiedProfile {
+    /// Copy the profile out of the container with `docker cp` (or `podman cp`
+    /// if Docker is not installed).
+    pub fn from_container(source: &ContainerPath) -> Result<Self> {
+        let dir = Self::tempdir()?;
+        let destination = Self::destination(&dir);
+
+        let src = format!("{}:{}", source.container, source.path);
+
+        for engine in ENGINES {
+            let status = match Command::new(engine)
+                .arg("cp")
+                .arg(&src)
+                .arg(&destination)
+                .stdout(Stdio::null())
+                .status()
+            {
+                Ok(status) => status,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e).wrap_err_with(|| format!("Failed to run {engine}")),
+            };
+
+            ensure!(status.success(), "{engine} failed to copy {src} ({status})");
+
+            return Ok(Self { dir });
+        }
+
+        bail!("Cannot copy {src}, neither docker nor podman is installed")
+    }
+
+    /// Copy the profile out of a pod with `kubectl cp`, which requires `tar` in the pod.
+    pub fn from_pod(source: &PodPath, namespace: Option<&str>) -> Result<Self> {
+        let dir = Self::tempdir()?;
+        let destination = Self::destination(&dir);
+
+        let src = format!("{}:{}", source.pod, source.path);
+
+        let mut command = Command::new("kubectl");
+        if let Some(namespace) = namespace {
+            command.args(["--namespace", namespace]);
+        }
+
+        let status = match command
+            .arg("cp")
+            .arg(&src)
+            .arg(&destination)
+            .stdout(Stdio::null())
+            .status()
+        {
+            Ok(status) => status,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                bail!("Cannot copy {src}, kubectl is not installed")
+            }
+            Err(e) => return Err(e).wrap_err("Failed to run kubectl"),
+        };
+
+        ensure!(status.success(), "kubectl failed to copy {src} ({status})");
+
+        Ok(Self { dir })
+    }
+
+    /// Copy the files of the profile from a remote machine with `scp`.
+    ///
+    /// Only the given files are copied, with their path relative to the root path,
+    /// since profiles also contain caches which can be large.
+    /// Each required file is given by its candidate locations, the first one which can be copied
+    /// is used. The optional files (e.g. SQLite journals) are ignored if they cannot be copied.
+    pub fn from_ssh(
+        source: &SshPath,
+        required: &[Vec<PathBuf>],
+        optional: &[PathBuf],
+    ) -> Result<Self> {
+        let dir = Self::tempdir()?;
+        let destination = Self::destination(&dir);
+
+        for candidates in required {
+            let mut copied = false;
+            for (i, file) in candidates.iter().enumerate() {
+                // Only the failure of the last candidate is reported.
+                if Self::scp(source, file, &destination, i + 1 < candidates.len())? {
+                    copied = true;
+                    break;
+                }
+            }
+
+            ensure!(
+                copied,
+                "scp failed to copy {} from {}",
+                candidates
+                    .iter()
+                    .map(|file| file.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(" or "),
+                source.host
+            );
+        }
+
+        for file in optional {
+            Self::scp(source, file, &destination, true)?;
+        }
+
+        Ok(Self { dir })
+    }
+
+    /// Copy a file of the profile with `scp`, returning whether it has been copied.
+    fn scp(source: &SshPath, file: &Path, destination: &Path, quiet: bool) -> Result<bool> {
+        let local = destination.join(file);
+        fs::create_dir_all(local.parent().unwrap_or(destination))?;
+
+        let remote_path = Path::new(&source.path).join(file);
+        let src = format!("{}:{}", source.host, remote_path.display());
+
+        let mut command = Command::new("scp");
+        command.arg("-q");
+        if let Some(port) = source.port {
+            command.args(["-P", &port.to_string()]);
+        }
+
+        // The source cannot be taken as an option, even if the host starts with a dash.
+        match command
+            .arg("--")
+            .arg(&src)
+            .arg(&local)
+            .stderr(if quiet {
+                Stdio::null()
+            } else {
+                Stdio::inherit()
+            })
+            .status()
+        {
+            Ok(status) => Ok(status.success()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                bail!("Cannot copy {src}, scp is not installed")
+            }
+            Err(e) => Err(e).wrap_err("Failed to run scp"),
+        }
+    }
+
+    fn tempdir() -> Result<TempDir> {
+        let dir = tempfile::Builder::new()
+            .prefix("gateau-container-")
+            .tempdir()?;
+        interrupt::remove_on_interrupt(dir.path());
+
+        Ok(dir)
+    }
+
+    fn destination(dir: &TempDir) -> PathBuf {
+        dir.path().join("profile")
+    }
+
+    /// Path of the copied profile, which is used as the root path of the browser.
+    pub fn path(&self) -> PathBuf {
+        Self::destination(&self.dir)
+    }
+}
+
+impl Drop for CopiedProfile {
+    fn drop(&mut self) {
+        interrupt::forget(self.dir.path());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_container_path() {
+        assert_eq!(
+            "ci:/home/runner/.config/chromium".parse(),
+            Ok(ContainerPath {
+                container: "ci".to_string(),
+                path: "/home/runner/.config/chromium".to_string()
+            })
+        );
+        assert_eq!(
+            "ci:C:/profile".parse(),
+            Ok(ContainerPath {
+                container: "ci".to_string(),
+                path: "C:/profile".to_string()
+            })
+        );
+        assert!("ci".parse::<ContainerPath>().is_err());
+        assert!(":/profile".parse::<ContainerPath>().is_err());
+        assert!("ci:".parse::<ContainerPath>().is_err());
+    }
+
+    #[test]
+    fn test_parse_ssh_path() {
+        assert_eq!(
+            "ssh://admin@server:2222/home/admin/.config/chromium".parse(),
+            Ok(SshPath {
+                host: "admin@server".to_string(),
+                port: Some(2222),
+                path: "/home/admin/.config/chromium".to_string(),
+            })
+        );
+        assert_eq!(
+            "ssh://server/~/.mozilla/firefox/abcd.default".parse(),
+            Ok(SshPath {
+                host: "server".to_string(),
+                port: None,
+                path: ".mozilla/firefox/abcd.default".to_string(),
+            })
+        );
+        assert!("ssh://server".parse::<SshPath>().is_err());
+        assert!("ssh://server:port/path".parse::<SshPath>().is_err());
+        assert!(SshPath::from_root_path(Path::new("/home/admin/.config/chromium")).is_none());
+    }
+
+    #[test]
+    fn test_parse_pod_path() {
+        let expected = PodPath {
+            pod: "chrome-0".to_string(),
+            path: "/data/chromium".to_string(),
+        };
+        assert_eq!("pod/chrome-0:/data/chromium".parse(), Ok(expected.clone()));
+        assert_eq!("chrome-0:/data/chromium".parse(), Ok(expected));
+        assert!("pod/chrome-0".parse::<PodPath>().is_err());
+        assert!("pod/:/data/chromium".parse::<PodPath>().is_err());
+    }
+}
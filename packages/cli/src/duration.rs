@@ -0,0 +1,116 @@
+//! Durations written with units (e.g. `30m`, `12h`, `7d`).
+
+use std::{fmt::Display, str::FromStr, time::Duration};
+
+/// Units of the durations, from the largest to the smallest, with their length in seconds.
+const UNITS: [(&str, u64); 5] = [
+    ("w", 7 * 24 * 60 * 60),
+    ("d", 24 * 60 * 60),
+    ("h", 60 * 60),
+    ("m", 60),
+    ("s", 1),
+];
+
+/// Duration parsed from a number followed by a unit
+/// (`s` for seconds, `m` for minutes, `h` for hours, `d` for days and `w` for weeks).
+/// Several durations can be combined, such as `1d12h`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct HumanDuration(pub Duration);
+
+impl HumanDuration {
+    pub const fn from_days(days: u64) -> Self {
+        Self(Duration::from_secs(days * 24 * 60 * 60))
+    }
+}
+
+impl FromStr for HumanDuration {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!("'{s}' is not a valid duration (expected a number followed by s, m, h, d or w)")
+        };
+
+        if s.is_empty() {
+            return Err(invalid());
+        }
+
+        let mut seconds = 0u64;
+        let mut rest = s;
+
+        while !rest.is_empty() {
+            let digits = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .ok_or_else(invalid)?;
+            let (number, unit_rest) = rest.split_at(digits);
+            let number: u64 = number.parse().map_err(|_| invalid())?;
+
+            let (unit, length) = UNITS
+                .iter()
+                .find(|(unit, _)| unit_rest.starts_with(unit))
+                .ok_or_else(invalid)?;
+
+            seconds = number
+                .checked_mul(*length)
+                .and_then(|duration| seconds.checked_add(duration))
+                .ok_or_else(invalid)?;
+            rest = &unit_rest[unit.len()..];
+        }
+
+        Ok(Self(Duration::from_secs(seconds)))
+    }
+}
+
+impl Display for HumanDuration {
+    /// Display the duration with its two largest units (e.g. `2d3h`).
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut seconds = self.0.as_secs();
+
+        let Some(largest) = UNITS.iter().position(|(_, length)| seconds >= *length) else {
+            return f.write_str("0s");
+        };
+
+        for (unit, length) in UNITS.iter().skip(largest).take(2) {
+            if seconds >= *length {
+                write!(f, "{}{unit}", seconds / length)?;
+                seconds %= length;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!("7d".parse(), Ok(HumanDuration::from_days(7)));
+        assert_eq!("2w".parse(), Ok(HumanDuration::from_days(14)));
+        assert_eq!(
+            "1d12h".parse(),
+            Ok(HumanDuration(Duration::from_secs(36 * 60 * 60)))
+        );
+        assert_eq!("90s".parse(), Ok(HumanDuration(Duration::from_secs(90))));
+        assert!("".parse::<HumanDuration>().is_err());
+        assert!("7".parse::<HumanDuration>().is_err());
+        assert!("d".parse::<HumanDuration>().is_err());
+        assert!("7y".parse::<HumanDuration>().is_err());
+    }
+
+    #[test]
+    fn test_display_duration() {
+        assert_eq!(HumanDuration::from_days(7).to_string(), "1w");
+        assert_eq!(
+            HumanDuration(Duration::from_secs(2 * 24 * 60 * 60 + 3 * 60 * 60 + 59)).to_string(),
+            "2d3h"
+        );
+        assert_eq!(
+            HumanDuration(Duration::from_secs(8 * 24 * 60 * 60 + 5 * 60 * 60)).to_string(),
+            "1w1d"
+        );
+        assert_eq!(HumanDuration(Duration::ZERO).to_string(), "0s");
+    }
+}
@@ -0,0 +1,309 @@
+//! Handling of the interruptions (SIGINT and SIGTERM).
+//!
+//! The temporary files and directories are removed when gateau is interrupted,
+//! after the content of the files holding cookies has been overwritten.
+//! gateau exits right away, unless it is streaming cookies in a graceful section:
+//! the output then stops at the next write, so what has been written can be flushed
+//! and marked as truncated, instead of leaving a half-written jar which tools silently accept.
+//! While a wrapped command is running, the interruptions are left to the command,
+//! and gateau waits for it to exit before removing the temporary files it reads.
+
+use std::{
+    fs, io,
+    io::{Seek, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex, Once,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Exit code used when gateau is terminated by a signal (128 + SIGINT).
+pub(crate) const SIGNAL_EXIT_CODE: i32 = 130;
+
+/// Temporary file or directory which must be removed if gateau is interrupted.
+#[derive(Debug)]
+struct PendingPath {
+    path: PathBuf,
+    /// Whether the content of the file is overwritten before it is removed.
+    shred: bool,
+}
+
+/// Paths of the temporary files and directories which must be removed if gateau is interrupted.
+static PENDING_PATHS: Mutex<Vec<PendingPath>> = Mutex::new(Vec::new());
+
+/// Whether gateau has been interrupted.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Number of graceful sections in progress.
+static GRACEFUL_SECTIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of deferred sections in progress.
+static DEFERRED_SECTIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Install the handler of the interruptions, once.
+pub(crate) fn install_handler() {
+    static INSTALL: Once = Once::new();
+
+    INSTALL.call_once(|| {
+        let result = ctrlc::set_handler(|| {
+            INTERRUPTED.store(true, Ordering::SeqCst);
+            if DEFERRED_SECTIONS.load(Ordering::SeqCst) > 0 {
+                return;
+            }
+
+            let mut pending = PENDING_PATHS.lock().unwrap_or_else(|e| e.into_inner());
+            for PendingPath {
+                path,
+                shred: overwrite,
+            } in pending.drain(..)
+            {
+                if overwrite {
+                    let _ = shred(&path);
+                }
+                let _ = fs::remove_file(&path).or_else(|_| fs::remove_dir_all(&path));
+            }
+
+            if GRACEFUL_SECTIONS.load(Ordering::SeqCst) == 0 {
+                // The lock is not released, so no file can be created afterwards.
+                std::process::exit(SIGNAL_EXIT_CODE);
+            }
+        });
+
+        if let Err(e) = result {
+            eprintln!("Warning: temporary files will not be removed on termination: {e}");
+        }
+    });
+}
+
+/// Remove the file or the directory if gateau is interrupted.
+pub(crate) fn remove_on_interrupt(path: &Path) {
+    register(path, false);
+}
+
+/// Overwrite the content of the file, then remove it, if gateau is interrupted.
+pub(crate) fn shred_on_interrupt(path: &Path) {
+    register(path, true);
+}
+
+fn register(path: &Path, shred: bool) {
+    install_handler();
+    PENDING_PATHS.lock().unwrap().push(PendingPath {
+        path: path.to_owned(),
+        shred,
+    });
+}
+
+/// Overwrite the content of the file with zeros, and write it to the disk,
+/// so the content cannot be recovered from the blocks of the file once it is removed.
+pub(crate) fn shred(path: &Path) -> io::Result<()> {
+    shred_file(&mut fs::OpenOptions::new().write(true).open(path)?)
+}
+
+/// Overwrite the content of the opened file with zeros, and write it to the disk.
+pub(crate) fn shred_file(file: &mut fs::File) -> io::Result<()> {
+    const ZEROS: [u8; 4096] = [0; 4096];
+
+    let mut remaining = file.metadata()?.len();
+    file.rewind()?;
+    while remaining > 0 {
+        let len = remaining.min(ZEROS.len() as u64);
+        file.write_all(&ZEROS[..len as usize])?;
+        remaining -= len;
+    }
+
+    file.sync_all()
+}
+
+/// Stop removing the file or the directory if gateau is interrupted, once it has been removed.
+pub(crate) fn forget(path: &Path) {
+    PENDING_PATHS
+        .lock()
+        .unwrap()
+        .retain(|pending| pending.path != path);
+}
+
+/// Returns whether the file or the directory is removed if gateau is interrupted.
+#[cfg(test)]
+pub(crate) fn will_remove(path: &Path) -> bool {
+    PENDING_PATHS
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|pending| pending.path == path)
+}
+
+/// Returns whether gateau has been interrupted.
+pub(crate) fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Section in which gateau does not exit right away when it is interrupted,
+/// until the guard is dropped.
+#[derive(Debug)]
+pub(crate) struct Graceful(());
+
+impl Graceful {
+    pub fn enter() -> Self {
+        install_handler();
+        GRACEFUL_SECTIONS.fetch_add(1, Ordering::SeqCst);
+        Self(())
+    }
+}
+
+impl Drop for Graceful {
+    fn drop(&mut self) {
+        GRACEFUL_SECTIONS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Section in which the interruptions are left to a wrapped command, which receives them as well:
+/// gateau neither exits nor removes the temporary files until the guard is dropped,
+/// so the command can still read them.
+#[derive(Debug)]
+pub(crate) struct Deferred(());
+
+impl Deferred {
+    pub fn enter() -> Self {
+        install_handler();
+        DEFERRED_SECTIONS.fetch_add(1, Ordering::SeqCst);
+        Self(())
+    }
+}
+
+impl Drop for Deferred {
+    fn drop(&mut self) {
+        DEFERRED_SECTIONS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Sleep for the duration, or until gateau is interrupted.
+pub(crate) fn sleep(duration: Duration) {
+    const STEP: Duration = Duration::from_millis(100);
+
+    let deadline = Instant::now() + duration;
+    while !interrupted() {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        thread::sleep(remaining.min(STEP));
+    }
+}
+
+/// Error returned by [`Interruptible`] once gateau has been interrupted.
+#[derive(Debug)]
+struct Interrupted;
+
+impl std::fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "interrupted")
+    }
+}
+
+impl std::error::Error for Interrupted {}
+
+/// Returns whether the error has been returned because gateau has been interrupted.
+pub(crate) fn is_interruption(error: &io::Error) -> bool {
+    error
+        .get_ref()
+        .is_some_and(|error| error.is::<Interrupted>())
+}
+
+/// Writer which fails once gateau has been interrupted,
+/// and holds back the last line until it is complete, so it can be dropped if the output is truncated.
+#[derive(Debug)]
+pub(crate) struct Interruptible<W: io::Write> {
+    inner: W,
+    /// Incomplete line, not written yet.
+    line: Vec<u8>,
+}
+
+impl<W: io::Write> Interruptible<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            line: Vec::new(),
+        }
+    }
+
+    /// Flush the complete lines which have been written, dropping the incomplete record at the end,
+    /// followed by a comment marking the output as truncated.
+    pub fn write_truncation_comment(&mut self) -> io::Result<()> {
+        self.line.clear();
+        self.inner
+            .write_all(b"# Interrupted: the output is truncated\n")?;
+        self.inner.flush()
+    }
+
+    /// Flush what has been written.
+    pub fn flush_written(&mut self) -> io::Result<()> {
+        self.inner.write_all(&self.line)?;
+        self.line.clear();
+        self.inner.flush()
+    }
+}
+
+impl<W: io::Write> io::Write for Interruptible<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if interrupted() {
+            return Err(io::Error::other(Interrupted));
+        }
+
+        match buf.iter().rposition(|&byte| byte == b'\n') {
+            Some(end) => {
+                self.inner.write_all(&self.line)?;
+                self.line.clear();
+                self.inner.write_all(&buf[..=end])?;
+                self.line.extend_from_slice(&buf[end + 1..]);
+            }
+            None => self.line.extend_from_slice(buf),
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flush_written()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+
+    #[test]
+    fn test_truncation_comment() {
+        let mut writer = Interruptible::new(Vec::new());
+        writer
+            .write_all(b"# Netscape HTTP Cookie File\nexample.com\tFALSE\t/\tFALSE\t0\tsid\t1\nexample.org")
+            .unwrap();
+        writer.write_truncation_comment().unwrap();
+
+        assert_eq!(
+            String::from_utf8(writer.inner).unwrap(),
+            "# Netscape HTTP Cookie File\nexample.com\tFALSE\t/\tFALSE\t0\tsid\t1\n# Interrupted: the output is truncated\n"
+        );
+
+        let mut writer = Interruptible::new(Vec::new());
+        writer.write_all(b"{\"name\":").unwrap();
+        writer.flush().unwrap();
+        assert_eq!(writer.inner, b"{\"name\":");
+        assert!(is_interruption(&io::Error::other(Interrupted)));
+        assert!(!is_interruption(&io::Error::other("other")));
+    }
+
+    #[test]
+    fn test_shred() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        fs::write(file.path(), b"sid=abc").unwrap();
+
+        shred(file.path()).unwrap();
+
+        assert_eq!(fs::read(file.path()).unwrap(), [0; 7]);
+    }
+}
@@ -0,0 +1,46 @@
+//! Encryption keys written in hexadecimal (e.g. `fd621fe5a2b402539dfa147ca9272778`).
+
+use std::str::FromStr;
+
+use zeroize::Zeroizing;
+
+/// Key of 16 bytes used by Chromium to encrypt the v10 cookie values (AES-128-CBC).
+#[derive(Debug, Clone)]
+pub(crate) struct ChromiumKey(pub Zeroizing<[u8; 16]>);
+
+impl FromStr for ChromiumKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("'{s}' is not a key of 16 bytes written in hexadecimal");
+
+        if s.len() != 32 || !s.is_ascii() {
+            return Err(error());
+        }
+
+        let mut key = Zeroizing::new([0; 16]);
+        for (byte, digits) in key.iter_mut().zip(s.as_bytes().chunks(2)) {
+            let digits = std::str::from_utf8(digits).map_err(|_| error())?;
+            *byte = u8::from_str_radix(digits, 16).map_err(|_| error())?;
+        }
+
+        Ok(Self(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_key() {
+        let key: ChromiumKey = "fd621fe5a2b402539dfa147ca9272778".parse().unwrap();
+        assert_eq!(key.0[0], 0xfd);
+        assert_eq!(key.0[15], 0x78);
+
+        assert!("fd621fe5".parse::<ChromiumKey>().is_err());
+        assert!("zz621fe5a2b402539dfa147ca9272778"
+            .parse::<ChromiumKey>()
+            .is_err());
+    }
+}
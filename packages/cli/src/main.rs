@@ -1,73 +1,250 @@
 #![deny(unsafe_code)]
 
-use std::{ffi::OsString, path::PathBuf, process::ExitCode, str::FromStr};
+use std::{
+    ffi::OsString,
+    fmt::Display,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    str::FromStr,
+};
 
 use app::App;
 use bpaf::Bpaf;
 use color_eyre::Result;
 use http::Uri;
+use serde::Deserialize;
 
+mod aliases;
 mod app;
+mod classify;
+mod config;
+mod container;
+mod duration;
+mod interrupt;
+mod key;
+mod policy;
+mod template;
+mod timestamp;
 mod url;
+mod wrapped;
 
-use gateau::Browser;
+use classify::Category;
+use config::Config;
+use container::{ContainerPath, PodPath};
+use duration::HumanDuration;
+use gateau::firefox::FirefoxChannel;
+use key::ChromiumKey;
+use policy::Policy;
+use template::Template;
+use timestamp::Timestamp;
 
-#[derive(Debug, Clone)]
-enum WrappedCmd {
-    Curl,
-    Wget,
-    HttpieHttp,
-    HttpieHttps,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum OutputFormat {
+    Netscape,
+    #[cfg(feature = "human")]
+    Human,
+    #[serde(alias = "httpie")]
+    HttpieSession,
+    Json,
+    Requests,
+    Mitmproxy,
+    #[cfg(feature = "msgpack")]
+    Msgpack,
+    #[cfg(feature = "cbor")]
+    Cbor,
+    Puppeteer,
+    #[serde(alias = "cookie-editor")]
+    EditThisCookie,
+    Lwp,
+    SetCookie,
+    Wgetrc,
+    Csv,
+    Har,
+    Lynx,
+    W3m,
+    Sql,
+    Template,
+    HttpieDir,
 }
 
-impl FromStr for WrappedCmd {
+impl FromStr for OutputFormat {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "curl" => Ok(WrappedCmd::Curl),
-            "wget" => Ok(WrappedCmd::Wget),
-            "httpie" | "https" => Ok(WrappedCmd::HttpieHttps),
-            "http" => Ok(WrappedCmd::HttpieHttp),
+            "netscape" => Ok(OutputFormat::Netscape),
+            #[cfg(feature = "human")]
+            "human" => Ok(OutputFormat::Human),
+            "httpie-session" | "httpie" => Ok(OutputFormat::HttpieSession),
+            "json" => Ok(OutputFormat::Json),
+            "requests" => Ok(OutputFormat::Requests),
+            "mitmproxy" => Ok(OutputFormat::Mitmproxy),
+            #[cfg(feature = "msgpack")]
+            "msgpack" | "messagepack" => Ok(OutputFormat::Msgpack),
+            #[cfg(feature = "cbor")]
+            "cbor" => Ok(OutputFormat::Cbor),
+            "puppeteer" => Ok(OutputFormat::Puppeteer),
+            "edit-this-cookie" | "cookie-editor" => Ok(OutputFormat::EditThisCookie),
+            "lwp" => Ok(OutputFormat::Lwp),
+            "set-cookie" => Ok(OutputFormat::SetCookie),
+            "wgetrc" => Ok(OutputFormat::Wgetrc),
+            "csv" => Ok(OutputFormat::Csv),
+            "har" => Ok(OutputFormat::Har),
+            "lynx" => Ok(OutputFormat::Lynx),
+            "w3m" => Ok(OutputFormat::W3m),
+            "sql" => Ok(OutputFormat::Sql),
+            "template" => Ok(OutputFormat::Template),
+            "httpie-dir" => Ok(OutputFormat::HttpieDir),
             _ => Err(format!(
-                "'{s}' is not one of the supported commands (curl, wget, http(s))"
+                "'{s}' is not one of the supported output formats (netscape, httpie-session, json, requests, mitmproxy, msgpack, cbor, puppeteer, edit-this-cookie, lwp, set-cookie, wgetrc, csv, har, lynx, w3m, sql, template, httpie-dir)"
             )),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-enum OutputFormat {
-    Netscape,
-    #[cfg(feature = "human")]
-    Human,
-    HttpieSession,
+impl OutputFormat {
+    /// Returns whether the format keeps the partition key of the partitioned cookies.
+    fn keeps_partition_key(self) -> bool {
+        match self {
+            OutputFormat::Json | OutputFormat::Puppeteer | OutputFormat::EditThisCookie => true,
+            #[cfg(feature = "msgpack")]
+            OutputFormat::Msgpack => true,
+            #[cfg(feature = "cbor")]
+            OutputFormat::Cbor => true,
+            _ => false,
+        }
+    }
 }
 
-impl FromStr for OutputFormat {
+/// Handling of the cookies which cannot be represented by the output format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OnUnsupported {
+    Drop,
+    Warn,
+    Force,
+}
+
+impl FromStr for OnUnsupported {
     type Err = String;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "netscape" => Ok(OutputFormat::Netscape),
-            #[cfg(feature = "human")]
-            "human" => Ok(OutputFormat::Human),
-            "httpie-session" | "httpie" => Ok(OutputFormat::HttpieSession),
+            "drop" => Ok(OnUnsupported::Drop),
+            "warn" => Ok(OnUnsupported::Warn),
+            "force" => Ok(OnUnsupported::Force),
             _ => Err(format!(
-                "'{s}' is not one of the supported output formats (netscape, httpie-session)"
+                "'{s}' is not one of the supported policies (drop, warn, force)"
             )),
         }
     }
 }
 
-fn not_help(s: OsString) -> Option<OsString> {
-    if s == "--help" {
-        None
-    } else {
-        Some(s)
+impl Display for OnUnsupported {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OnUnsupported::Drop => write!(f, "drop"),
+            OnUnsupported::Warn => write!(f, "warn"),
+            OnUnsupported::Force => write!(f, "force"),
+        }
+    }
+}
+
+/// SameSite attribute given to the cookies set without one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct UnspecifiedSameSite(cookie::SameSite);
+
+impl FromStr for UnspecifiedSameSite {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "strict" => Ok(Self(cookie::SameSite::Strict)),
+            "lax" => Ok(Self(cookie::SameSite::Lax)),
+            "none" => Ok(Self(cookie::SameSite::None)),
+            _ => Err(format!(
+                "'{s}' is not one of the SameSite attributes (strict, lax, none)"
+            )),
+        }
+    }
+}
+
+/// Order of the cookies in the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SortKey {
+    Domain,
+    Name,
+    Expiry,
+    LastAccess,
+}
+
+impl FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "domain" => Ok(SortKey::Domain),
+            "name" => Ok(SortKey::Name),
+            "expiry" => Ok(SortKey::Expiry),
+            "last-access" => Ok(SortKey::LastAccess),
+            _ => Err(format!(
+                "'{s}' is not one of the sort keys (domain, name, expiry, last-access)"
+            )),
+        }
+    }
+}
+
+/// Shell for which the integration is printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+impl FromStr for Shell {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            _ => Err(format!(
+                "'{s}' is not one of the supported shells (bash, zsh, fish)"
+            )),
+        }
     }
 }
 
+/// Format of the explanations of the filtering decisions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExplainFormat {
+    Text,
+    Json,
+}
+
+impl FromStr for ExplainFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(ExplainFormat::Text),
+            "json" => Ok(ExplainFormat::Json),
+            _ => Err(format!(
+                "'{s}' is not one of the supported explanation formats (text, json)"
+            )),
+        }
+    }
+}
+
+fn comma_separated<T: FromStr>(s: Option<String>) -> Result<Vec<T>, T::Err> {
+    s.map_or_else(
+        || Ok(Vec::new()),
+        |s| s.split(',').map(|s| s.parse()).collect(),
+    )
+}
+
 #[derive(Debug, Clone, Bpaf)]
 enum Mode {
     /// Output cookies to stdout in the specified format
@@ -75,36 +252,261 @@ enum Mode {
     Output {
         /// Output format
         ///
-        /// Supported formats: netscape, httpie-session
+        /// Supported formats: netscape, httpie-session, json, requests, mitmproxy, puppeteer, edit-this-cookie, lwp, set-cookie,
+        /// wgetrc, csv, har, lynx, w3m, sql, template (with --template), httpie-dir,
+        /// and msgpack and cbor when built with the features of the same name
         format: Option<OutputFormat>,
 
+        /// Store the cookies in the given entry of the password store instead of printing them
+        #[bpaf(argument("ENTRY"))]
+        to_pass: Option<String>,
+
+        /// Password store command used with --to-pass (pass or gopass)
+        #[bpaf(argument("CMD"), fallback("pass".to_string()), display_fallback)]
+        pass_command: String,
+
+        /// Directory of the HTTPie sessions where the httpie-dir format writes a session
+        /// for each host, instead of the one of HTTPie (e.g. ~/.config/httpie/sessions)
+        #[bpaf(argument("DIR"))]
+        output: Option<PathBuf>,
+
+        /// Name of the HTTPie sessions written with --format httpie-dir
+        #[bpaf(argument("NAME"), fallback("gateau".to_string()), display_fallback)]
+        httpie_session: String,
+
+        /// Create a Firefox cookies database (cookies.sqlite) with the cookies
+        /// instead of printing them, e.g. to seed a Firefox profile
+        #[bpaf(argument("PATH"))]
+        to_firefox_db: Option<PathBuf>,
+
+        /// Create a Chromium cookies database (Cookies) with the cookies
+        /// instead of printing them, e.g. to seed a Chromium automation profile
+        #[bpaf(argument("PATH"))]
+        to_chromium_db: Option<PathBuf>,
+
+        /// Key of 16 bytes, in hexadecimal, encrypting the values written with --to-chromium-db
+        /// as v10 values (e.g. fd621fe5a2b402539dfa147ca9272778 on Linux without keyring),
+        /// instead of storing them in plain text
+        #[bpaf(argument("HEX"))]
+        chromium_key: Option<ChromiumKey>,
+
+        /// Also output the values of Chrome as stored in its database, before their decryption,
+        /// with the way they are encrypted (with --format json)
+        #[bpaf(long)]
+        raw_and_decrypted: bool,
+
+        /// Only output the cookies which are new or have changed since a previous export,
+        /// written with --format json
+        #[bpaf(argument("FILE"))]
+        changed_since_file: Option<PathBuf>,
+
+        /// Merge the cookies into the Netscape cookies file instead of printing them,
+        /// replacing the ones with the same domain, path and name, to refresh a long-lived cookie jar
+        #[bpaf(argument("FILE"))]
+        merge: Option<PathBuf>,
+
         /// Hosts to filter cookies by
         #[bpaf(positional("HOSTS"), many)]
         hosts: Vec<Uri>,
     },
 
+    /// Output cookies to stdout for another environment (e.g. staging),
+    /// with the domains and names rewritten as declared in the configuration file
+    #[bpaf(command)]
+    Render {
+        /// Name of the environment, declared in the environments table of the configuration file
+        #[bpaf(argument("ENV"))]
+        env: String,
+
+        /// Output format
+        ///
+        /// Supported formats: netscape, httpie-session, json, requests, mitmproxy, puppeteer, edit-this-cookie, lwp, set-cookie,
+        /// wgetrc, csv, har, lynx, w3m, sql, template (with --template),
+        /// and msgpack and cbor when built with the features of the same name
+        format: Option<OutputFormat>,
+
+        /// Hosts to filter cookies by, before they are rewritten
+        #[bpaf(positional("HOSTS"), many)]
+        hosts: Vec<Uri>,
+    },
+
+    /// Print the value of the Cookie header sent by the browser with a request to the URL
+    #[bpaf(command)]
+    Header {
+        /// URL of the request (considered secure without scheme)
+        #[bpaf(positional("URL"))]
+        url: Uri,
+    },
+
+    /// Check the cookies sent to a host against the limits of the browsers and the servers
+    /// (size of each cookie, number of cookies, size of the Cookie header),
+    /// and exit with a non-zero status if any is exceeded
+    #[bpaf(command)]
+    Lint {
+        /// Host or URL to check (considered secure without scheme)
+        #[bpaf(positional("HOST"))]
+        url: Uri,
+    },
+
     /// Wrap a command with the imported cookies
     #[bpaf(command)]
     Wrap {
+        /// Pass the cookies through a file descriptor (/dev/fd/N) instead of a temporary file,
+        /// so they are never written to the file system
+        #[cfg(unix)]
+        fd: bool,
+
+        /// Replace gateau by the command (execvp) on Unix, so it receives the signals
+        /// and its exit status is kept as is; the cookies are passed through a file descriptor
+        exec: bool,
+
+        /// Pass all the cookies, instead of the ones of the URLs in the arguments of the command
+        all_cookies: bool,
+
+        /// Pass the Cookie header sent to the URL in the arguments with the header option
+        /// of the command (e.g. curl -H), instead of a cookie file
+        /// (the arguments are visible to the other users in the list of processes)
+        as_header: bool,
+
+        /// Read the cookies again at this interval (e.g. 10m) while the command is running,
+        /// and replace the cookie file with them, for long-running commands which read it again
+        /// (curl, wget and aria2c only read it once, when they start)
+        #[bpaf(argument("DURATION"))]
+        refresh: Option<HumanDuration>,
+
         /// Command which should be wrapped
         ///
-        /// Supported commands: curl, wget, http, https
+        /// Supported commands: curl, wget, http, https, httpx,
+        /// the commands declared in the configuration file,
+        /// and exec to run any command, replacing {cookie_file} and {cookie_header} in its arguments.
+        /// The options of gateau and of wrap precede it, and the arguments following it
+        /// are all forwarded to it as they are, including --help and --,
+        /// except for a -- right after it, which only separates them
+        // Resolved once the configuration file is loaded, since it can declare commands.
         #[bpaf(positional("COMMAND"))]
-        command: WrappedCmd,
+        command: String,
 
         /// Arguments for the wrapped command
-        #[bpaf(any("ARGS", not_help), many)]
+        // Split from the arguments of gateau before they are parsed, by `parse_args`.
+        #[bpaf(pure(Vec::new()))]
         forwarded_args: Vec<OsString>,
     },
+
+    /// List the cookies which will expire soon, grouped by domain
+    #[bpaf(command)]
+    Expiring {
+        /// Period in which the cookies expire (e.g. 12h, 7d, 2w)
+        #[bpaf(
+            argument("DURATION"),
+            fallback(HumanDuration::from_days(7)),
+            display_fallback
+        )]
+        within: HumanDuration,
+
+        /// Hosts to filter cookies by
+        #[bpaf(positional("HOSTS"), many)]
+        hosts: Vec<Uri>,
+    },
+
+    /// Print the cookies as they are added, changed or removed, as JSON lines
+    #[bpaf(command)]
+    Tail {
+        /// Interval between two reads of the cookies database, in seconds
+        #[bpaf(argument("SECONDS"), fallback(1), display_fallback)]
+        interval: u64,
+
+        /// Hosts to filter cookies by
+        #[bpaf(positional("HOSTS"), many)]
+        hosts: Vec<Uri>,
+    },
+
+    /// List the user data directories of the Chromium-based browsers found on the system,
+    /// including the ones installed in non-default locations, to use with --root-path
+    #[bpaf(command)]
+    Browsers,
+
+    /// Print facts about a cookies database in JSON
+    /// (browser family, schema version, row counts, encryption versions)
+    #[bpaf(command("inspect-db"))]
+    InspectDb {
+        /// Path to the cookies database
+        #[bpaf(positional("PATH"))]
+        path: PathBuf,
+    },
+
+    /// Print shell functions calling gateau wrap in place of the wrapped commands
+    /// (e.g. gcurl for curl), to evaluate in the shell startup file
+    #[bpaf(command("shell-init"))]
+    ShellInit {
+        /// Prefix of the names of the functions, which can be empty
+        /// for the functions to take the names of the commands
+        #[bpaf(argument("PREFIX"), fallback("g".to_string()), display_fallback)]
+        prefix: String,
+
+        /// Shell for which the functions are printed (bash, zsh or fish)
+        #[bpaf(positional("SHELL"))]
+        shell: Shell,
+
+        /// Options of gateau passed by the functions, given before shell-init
+        // Taken from the arguments of gateau, by `parse_args`.
+        #[bpaf(pure(Vec::new()))]
+        options: Vec<OsString>,
+    },
+}
+
+impl Mode {
+    /// Returns the name of the command.
+    fn name(&self) -> &'static str {
+        match self {
+            Mode::Output { .. } => "output",
+            Mode::Render { .. } => "render",
+            Mode::Header { .. } => "header",
+            Mode::Lint { .. } => "lint",
+            Mode::Wrap { .. } => "wrap",
+            Mode::Expiring { .. } => "expiring",
+            Mode::Tail { .. } => "tail",
+            Mode::Browsers => "browsers",
+            Mode::InspectDb { .. } => "inspect-db",
+            Mode::ShellInit { .. } => "shell-init",
+        }
+    }
+
+    /// Returns the hosts to filter cookies by, if the mode has any.
+    fn hosts(&self) -> &[Uri] {
+        match self {
+            Mode::Output { hosts, .. }
+            | Mode::Render { hosts, .. }
+            | Mode::Expiring { hosts, .. }
+            | Mode::Tail { hosts, .. } => hosts,
+            Mode::Header { url } | Mode::Lint { url } => std::slice::from_ref(url),
+            Mode::Wrap { .. }
+            | Mode::Browsers
+            | Mode::InspectDb { .. }
+            | Mode::ShellInit { .. } => &[],
+        }
+    }
 }
 
 #[derive(Debug, Clone, Bpaf)]
 #[bpaf(options, version)]
 /// A simple wrapper to import cookies from browsers for curl, wget and httpie.
 struct Args {
-    /// Browser root path
+    /// Browser root path, which can be repeated to merge the cookies of several instances
+    /// (e.g. Chromium run with different --user-data-dir), the first ones taking precedence
     #[bpaf(short, long)]
-    root_path: Option<PathBuf>,
+    root_path: Vec<PathBuf>,
+
+    /// Copy the browser root path from a Docker or Podman container and use it
+    #[bpaf(long, argument("NAME:PATH"))]
+    container: Option<ContainerPath>,
+
+    /// Copy the browser root path from a Kubernetes pod with kubectl and use it
+    #[bpaf(long, argument("pod/NAME:PATH"))]
+    kube: Option<PodPath>,
+
+    /// Namespace of the pod given with --kube
+    #[bpaf(short('n'), long, argument("NAMESPACE"))]
+    namespace: Option<String>,
 
     /// Open the browser in a new context and use the saved cookies when it closes
     #[bpaf(long)]
@@ -114,28 +516,402 @@ struct Args {
     #[bpaf(long)]
     session_urls: Vec<Uri>,
 
-    /// Browser(s) to import cookies from
+    /// Record the cookies set by the plain HTTP responses of the session through a local proxy,
+    /// instead of reading the profile of the session (HTTPS traffic cannot be recorded)
+    #[bpaf(long)]
+    session_proxy: bool,
+
+    /// Browser to import cookies from, which can be repeated to merge the cookies
+    /// of several browsers when wrapping a command, the first ones taking precedence
     ///
-    /// Supported browsers: chrome, chromium, firefox, edge
-    #[bpaf(short, long)]
-    browser: Option<Browser>,
+    /// Supported browsers: chrome, chrome-beta, chrome-dev, chrome-canary, chromium, firefox, tor-browser, waterfox, palemoon, seamonkey, thunderbird, floorp, zen, edge, edge-beta, edge-dev, edge-canary, arc, yandex, whale, falkon, epiphany,
+    /// ie and edge-legacy (on Windows),
+    /// and the Chromium-based browsers and Firefox forks declared in the configuration file.
+    /// auto selects the browser whose cookies database was modified the most recently
+    /// (preferring the ones with cookies for the hosts)
+    // Parsed once the configuration file is loaded, since it can declare browsers.
+    #[bpaf(short, long, argument("BROWSER"), many)]
+    browser: Vec<String>,
+
+    /// Merge the cookies of all the browsers with a default profile when wrapping a command,
+    /// in the order of the supported browsers
+    #[bpaf(long)]
+    all_browsers: bool,
+
+    /// Data directory of an Electron or WebView2 application to import cookies from,
+    /// instead of a browser (e.g. ~/.config/Slack)
+    #[bpaf(long, argument("DIR"))]
+    app: Option<PathBuf>,
+
+    /// Channel of Firefox whose default profile is used
+    ///
+    /// Supported channels: release, beta, developer, nightly, esr
+    #[bpaf(long, argument("CHANNEL"))]
+    channel: Option<FirefoxChannel>,
+
+    /// Do not decrypt the cookie values, for databases which store them in plain text
+    /// (e.g. pulled from Chrome on Android)
+    #[bpaf(long)]
+    unencrypted: bool,
+
+    /// Give this SameSite attribute (strict, lax or none) to the cookies of Chrome set without one,
+    /// which are otherwise output without SameSite attribute
+    #[bpaf(long, argument("ATTRIBUTE"))]
+    unspecified_same_site: Option<UnspecifiedSameSite>,
+
+    /// Exclude the cookies created or updated after this time (YYYY-MM-DD,
+    /// RFC 3339 or UNIX timestamp), to approximate the cookies at that time
+    #[bpaf(long, argument("TIME"))]
+    as_of: Option<Timestamp>,
+
+    /// Also read the cookies set by the pages of the extensions,
+    /// from the Extension Cookies database kept by some Chromium builds
+    #[bpaf(long)]
+    include_extension_cookies: bool,
+
+    /// Print how many cookie values of Chrome were plaintext or encrypted with each scheme,
+    /// and how many could not be decrypted
+    #[bpaf(long)]
+    decryption_stats: bool,
 
     /// Bypass the lock on the database (can cause read errors)
     #[bpaf(long)]
     bypass_lock: bool,
 
+    /// Wait for the browser to exit if it is using the profile (for at most 10 minutes)
+    #[bpaf(long)]
+    wait_for_close: bool,
+
+    /// Also match the domains of the host aliases declared in the configuration file
+    /// or in the hosts file of the system (names listed on the same line)
+    #[bpaf(long)]
+    resolve_aliases: bool,
+
+    /// Categories of cookies to exclude
+    ///
+    /// Supported categories: advertising, analytics, functional
+    #[bpaf(
+        long,
+        argument::<String>("CATEGORIES"),
+        optional,
+        parse(comma_separated)
+    )]
+    drop_categories: Vec<Category>,
+
+    /// What to do with partitioned cookies, whose partition key cannot be exported
+    ///
+    /// Supported policies: drop, warn (export them with a warning), force (export them silently).
+    /// The json, msgpack, cbor and edit-this-cookie formats export the partition key,
+    /// so the policy does not apply to them
+    #[bpaf(
+        long,
+        argument("POLICY"),
+        fallback(OnUnsupported::Warn),
+        display_fallback
+    )]
+    on_unsupported: OnUnsupported,
+
+    /// Terminate the lines of the netscape output with CRLF, for the Windows tools
+    /// which reject LF-only cookie files
+    #[bpaf(long)]
+    crlf: bool,
+
+    /// Prefix the domain of the HttpOnly cookies with #HttpOnly_ in the netscape output, as curl does,
+    /// to keep this flag (the cookies are then ignored by the tools which do not know it, like wget)
+    #[bpaf(long)]
+    http_only_prefix: bool,
+
+    /// Name of the table into which the cookies are inserted by the sql output, which can be qualified
+    /// by a schema (e.g. analytics.cookies)
+    #[bpaf(long, argument("NAME"), fallback("cookies".to_string()))]
+    sql_table: String,
+
+    /// Line written for each cookie by the template output, with the variables {{name}}, {{value}},
+    /// {{domain}}, {{path}}, {{expires}}, {{expires_rfc3339}}, {{secure}}, {{http_only}}, {{same_site}}
+    /// and {{partitioned}}, and the escapes \t, \n and \\
+    #[bpaf(long, argument("TEMPLATE"))]
+    template: Option<Template>,
+
+    /// Sort the cookies before writing them, by domain, name, expiry or last-access,
+    /// then by domain, path and name (the session cookies and the cookies whose last access
+    /// is unknown come first)
+    #[bpaf(long, argument("KEY"))]
+    sort: Option<SortKey>,
+
+    /// Sort the cookies in descending order (with --sort)
+    #[bpaf(long)]
+    reverse: bool,
+
+    /// Explain on stderr why each cookie is included or excluded by the filters
+    ///
+    /// Supported formats: text, json (one object per line)
+    #[bpaf(long, argument("FORMAT"))]
+    explain: Option<ExplainFormat>,
+
+    /// Print the cookies to a terminal without asking for confirmation
+    #[bpaf(short, long)]
+    yes: bool,
+
+    /// Path to the configuration file
+    #[bpaf(long)]
+    config: Option<PathBuf>,
+
+    /// Directory with the data files replacing the built-in ones:
+    /// public_suffix_list.dat (base domains) and categories.csv (cookie categories)
+    #[bpaf(long, argument("DIR"))]
+    data_dir: Option<PathBuf>,
+
+    /// Classify cookies in categories (advertising, analytics, functional),
+    /// shown in a column of the human and CSV outputs and in a field of the JSON output
+    #[bpaf(long)]
+    classify: bool,
+
+    /// Do not color the human output, which is also the case when the NO_COLOR environment variable
+    /// is set or the output is not a terminal
+    #[cfg(feature = "human")]
+    #[bpaf(long)]
+    no_color: bool,
+
+    /// Number of characters after which the values are truncated in the human output,
+    /// 0 to show them entirely
+    #[cfg(feature = "human")]
+    #[bpaf(long, argument("WIDTH"), fallback(40))]
+    max_value_width: usize,
+
     #[bpaf(external)]
     mode: Mode,
 }
 
+/// Parse the arguments of gateau, without parsing the ones forwarded to the wrapped command.
+///
+/// The options of gateau precede the command of `wrap`, and all the arguments following it
+/// are forwarded as they are, in the same order and even if they are not valid UTF-8,
+/// except for a `--` right after the command, which only separates them.
+/// The options given before `shell-init` are kept for the functions it prints.
+fn parse_args(argv: &[OsString], name: Option<&str>) -> Result<Args, bpaf::ParseFailure> {
+    let parse = |argv: &[OsString]| {
+        let input = bpaf::Args::from(argv);
+        args().run_inner(match name {
+            Some(name) => input.set_name(name),
+            None => input,
+        })
+    };
+
+    // The command is the last argument of the shortest list of arguments selecting it.
+    let command_end = argv
+        .iter()
+        .any(|arg| arg == "wrap")
+        .then(|| {
+            (1..=argv.len()).find(|&end| {
+                matches!(
+                    parse(&argv[..end]),
+                    Ok(Args {
+                        mode: Mode::Wrap { .. },
+                        ..
+                    })
+                )
+            })
+        })
+        .flatten();
+    let Some(command_end) = command_end else {
+        let mut args = parse(argv)?;
+        if let Mode::ShellInit { options, .. } = &mut args.mode {
+            // The options end where the arguments left are a command on their own.
+            let options_end = (0..argv.len())
+                .find(|&start| {
+                    argv[start] == "shell-init"
+                        && matches!(
+                            parse(&argv[start..]),
+                            Ok(Args {
+                                mode: Mode::ShellInit { .. },
+                                ..
+                            })
+                        )
+                })
+                .unwrap_or_default();
+            *options = argv[..options_end].to_vec();
+        }
+
+        return Ok(args);
+    };
+
+    let (command, forwarded) = argv.split_at(command_end);
+    let mut args = parse(command)?;
+    let forwarded = match forwarded {
+        [separator, forwarded @ ..] if separator == "--" => forwarded,
+        forwarded => forwarded,
+    };
+
+    if let Mode::Wrap { forwarded_args, .. } = &mut args.mode {
+        *forwarded_args = forwarded.to_vec();
+    }
+
+    Ok(args)
+}
+
 fn main() -> Result<ExitCode> {
     color_eyre::install()?;
-    let args = args().run();
+    let mut argv = std::env::args_os();
+    let name = argv
+        .next()
+        .and_then(|name| Some(Path::new(&name).file_name()?.to_str()?.to_owned()));
+    let args = match parse_args(&argv.collect::<Vec<_>>(), name.as_deref()) {
+        Ok(args) => args,
+        Err(failure) => {
+            failure.print_message(100);
+            std::process::exit(failure.exit_code());
+        }
+    };
+    let config = Config::load(args.config.as_deref())?;
+    config.register_browsers();
+    let policy = Policy::load()?;
 
-    if let Some(status) = App::new(args).run()? {
+    if let Some(status) = App::new(args, config, policy).run()? {
         let status: u8 = status.try_into().unwrap();
         Ok(ExitCode::from(status))
     } else {
         Ok(ExitCode::SUCCESS)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forwarded(argv: &[&str]) -> (Args, Vec<OsString>) {
+        let argv = argv.iter().map(OsString::from).collect::<Vec<_>>();
+        let mut args = parse_args(&argv, None).unwrap();
+        let Mode::Wrap { forwarded_args, .. } = &mut args.mode else {
+            panic!("not a wrap command");
+        };
+        let forwarded_args = std::mem::take(forwarded_args);
+
+        (args, forwarded_args)
+    }
+
+    #[test]
+    fn test_forwarded_args() {
+        let (args, forwarded_args) = forwarded(&[
+            "-b",
+            "chromium",
+            "wrap",
+            "--refresh",
+            "1m",
+            "curl",
+            "-b",
+            "a=1",
+            "--help",
+            "--version",
+            "--bypass-lock",
+        ]);
+        assert_eq!(args.browser, ["chromium"]);
+        assert!(!args.bypass_lock);
+        assert_eq!(
+            forwarded_args,
+            ["-b", "a=1", "--help", "--version", "--bypass-lock"]
+        );
+
+        let (args, forwarded_args) = forwarded(&[
+            "--bypass-lock",
+            "wrap",
+            "exec",
+            "--",
+            "show",
+            "--",
+            "{cookie_file}",
+        ]);
+        assert!(args.bypass_lock);
+        assert_eq!(forwarded_args, ["show", "--", "{cookie_file}"]);
+
+        let (_, forwarded_args) = forwarded(&["wrap", "curl", "--", "-X", "POST"]);
+        assert_eq!(forwarded_args, ["-X", "POST"]);
+
+        // The arguments after the command are never options of gateau, even if they parse as such.
+        let (args, forwarded_args) = forwarded(&[
+            "--root-path",
+            "/tmp/firefox",
+            "wrap",
+            "--all-cookies",
+            "curl",
+            "-r",
+            "0-100",
+            "--",
+            "https://example.com",
+        ]);
+        assert_eq!(args.root_path, [PathBuf::from("/tmp/firefox")]);
+        assert_eq!(forwarded_args, ["-r", "0-100", "--", "https://example.com"]);
+    }
+
+    #[test]
+    fn test_wrap_command_help() {
+        // The options of gateau and of wrap precede the command.
+        let (args, forwarded_args) = forwarded(&[
+            "--bypass-lock",
+            "wrap",
+            "--all-cookies",
+            "wget",
+            "https://example.com",
+        ]);
+        assert!(args.bypass_lock);
+        assert!(matches!(
+            args.mode,
+            Mode::Wrap {
+                all_cookies: true,
+                ..
+            }
+        ));
+        assert_eq!(forwarded_args, ["https://example.com"]);
+
+        // The arguments following it are all forwarded, including --help and --.
+        let (args, forwarded_args) =
+            forwarded(&["wrap", "wget", "--help", "--bypass-lock", "--", "-x"]);
+        assert!(!args.bypass_lock);
+        assert_eq!(forwarded_args, ["--help", "--bypass-lock", "--", "-x"]);
+
+        // Except for a -- right after it, which only separates them.
+        let (_, forwarded_args) = forwarded(&["wrap", "wget", "--", "--", "--help"]);
+        assert_eq!(forwarded_args, ["--", "--help"]);
+    }
+
+    #[test]
+    fn test_shell_init_options() {
+        let argv = [
+            "--config",
+            "shell-init",
+            "shell-init",
+            "--prefix",
+            "",
+            "zsh",
+        ]
+        .map(OsString::from);
+
+        let args = parse_args(&argv, None).unwrap();
+        let Mode::ShellInit {
+            prefix, options, ..
+        } = args.mode
+        else {
+            panic!("not a shell-init command");
+        };
+        assert_eq!(prefix, "");
+        assert_eq!(options, ["--config", "shell-init"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_forwarded_args() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let invalid = OsString::from_vec(vec![b'-', b'o', 0xff]);
+        let argv = [
+            OsString::from("wrap"),
+            OsString::from("wget"),
+            invalid.clone(),
+        ];
+
+        let args = parse_args(&argv, None).unwrap();
+        let Mode::Wrap { forwarded_args, .. } = args.mode else {
+            panic!("not a wrap command");
+        };
+        assert_eq!(forwarded_args, [invalid]);
+    }
+}
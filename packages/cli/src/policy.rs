@@ -0,0 +1,174 @@
+//! Policy file managed by the administrators of the machine.
+//!
+//! Unlike the configuration file, the policy file is always read from a fixed location,
+//! `/etc/gateau/policy.toml` (`%ProgramData%\gateau\policy.toml` on Windows),
+//! and cannot be overridden by the user. Everything is allowed if it does not exist.
+//!
+//! ```toml
+//! # Browsers whose cookies can be read (all of them if omitted)
+//! allowed-browsers = ["firefox", "chrome"]
+//!
+//! # Hosts whose cookies can be exported, including their subdomains (all of them if omitted)
+//! allowed-hosts = ["example.com", "intranet.corp"]
+//!
+//! # Commands which cannot be run
+//! denied-commands = ["tail", "wrap"]
+//!
+//! # Always ask for confirmation before printing cookies to a terminal, even with --yes
+//! require-confirmation = true
+//! ```
+
+use std::path::PathBuf;
+
+use color_eyre::{
+    eyre::{ensure, Context},
+    Result,
+};
+use gateau::{report::BrowserCookie, Browser};
+use serde::Deserialize;
+
+use crate::url::normalize_host;
+
+/// Restrictions set by the administrators of the machine.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct Policy {
+    /// Names of the browsers whose cookies can be read, or `None` to allow all of them.
+    pub allowed_browsers: Option<Vec<String>>,
+
+    /// Hosts whose cookies can be exported, or `None` to allow all of them.
+    pub allowed_hosts: Option<Vec<String>>,
+
+    /// Names of the commands which cannot be run.
+    pub denied_commands: Vec<String>,
+
+    /// Whether the confirmation before printing cookies to a terminal cannot be skipped.
+    pub require_confirmation: bool,
+}
+
+impl Policy {
+    /// Returns the path of the policy file.
+    pub fn path() -> PathBuf {
+        if cfg!(windows) {
+            std::env::var_os("ProgramData")
+                .map(PathBuf::from)
+                .unwrap_or_else(|| PathBuf::from(r"C:\ProgramData"))
+                .join("gateau")
+                .join("policy.toml")
+        } else {
+            PathBuf::from("/etc/gateau/policy.toml")
+        }
+    }
+
+    /// Load the policy from its file, if it exists.
+    pub fn load() -> Result<Self> {
+        let path = Policy::path();
+        if !path.exists() {
+            return Ok(Policy::default());
+        }
+
+        let content = std::fs::read_to_string(&path)
+            .wrap_err_with(|| format!("Failed to read policy file {}", path.display()))?;
+
+        Policy::parse(&content)
+            .wrap_err_with(|| format!("Failed to parse policy file {}", path.display()))
+    }
+
+    /// Parse the policy from a TOML string.
+    pub fn parse(content: &str) -> Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+
+    /// Returns whether the cookies of the browser can be read.
+    ///
+    /// The names which are not supported (e.g. a browser declared in another configuration file)
+    /// are ignored.
+    pub fn allows_browser(&self, browser: Browser) -> bool {
+        self.allowed_browsers.as_ref().is_none_or(|browsers| {
+            browsers
+                .iter()
+                .any(|name| name.parse::<Browser>() == Ok(browser))
+        })
+    }
+
+    /// Ensure that the command can be run.
+    pub fn check_command(&self, command: &str) -> Result<()> {
+        ensure!(
+            !self.denied_commands.iter().any(|denied| denied == command),
+            "The {command} command is denied by the policy file {}",
+            Policy::path().display()
+        );
+
+        Ok(())
+    }
+
+    /// Remove the cookies whose domain is neither an allowed host nor one of its subdomains.
+    pub fn retain_allowed(&self, cookies: &mut Vec<BrowserCookie>) {
+        let Some(allowed_hosts) = &self.allowed_hosts else {
+            return;
+        };
+
+        cookies.retain(|cookie| {
+            cookie.domain().is_some_and(|domain| {
+                let domain = normalize_host(domain.strip_prefix('.').unwrap_or(domain));
+
+                allowed_hosts.iter().any(|host| {
+                    let host = normalize_host(host);
+
+                    domain == host
+                        || domain
+                            .strip_suffix(host.as_str())
+                            .is_some_and(|prefix| prefix.ends_with('.'))
+                })
+            })
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cookie::Cookie;
+    use gateau::{chrome::ChromeVariant, firefox::FirefoxVariant};
+
+    use super::*;
+
+    #[test]
+    fn test_parse_policy() {
+        let policy = Policy::parse(
+            r#"
+            allowed-browsers = ["firefox"]
+            denied-commands = ["tail"]
+            "#,
+        )
+        .unwrap();
+
+        assert!(policy.allows_browser(Browser::FirefoxVariant(FirefoxVariant::Firefox)));
+        assert!(!policy.allows_browser(Browser::ChromeVariant(ChromeVariant::Chrome)));
+        assert!(policy.check_command("tail").is_err());
+        assert!(policy.check_command("output").is_ok());
+        assert!(!policy.require_confirmation);
+    }
+
+    #[test]
+    fn test_retain_allowed() {
+        let mut cookies = vec![
+            Cookie::build(("sid", "1")).domain(".example.com").into(),
+            Cookie::build(("sid", "2")).domain("www.example.com").into(),
+            Cookie::build(("sid", "3")).domain("example.org").into(),
+            Cookie::build(("sid", "4")).domain("notexample.com").into(),
+            Cookie::build(("sid", "5"))
+                .domain("WWW.Example.com.")
+                .into(),
+        ];
+
+        Policy::parse(r#"allowed-hosts = ["Example.COM."]"#)
+            .unwrap()
+            .retain_allowed(&mut cookies);
+
+        let values = cookies
+            .iter()
+            .map(|cookie| cookie.value())
+            .collect::<Vec<_>>();
+        assert_eq!(values, ["1", "2", "5"]);
+    }
+}
@@ -0,0 +1,188 @@
+//! Template of the `template` output format, rendered once per cookie.
+//!
+//! The variables are written between double braces (`{{name}}`), and `\t`, `\n` and `\\`
+//! are replaced by a tab, a newline and a backslash, as they are hard to pass in a shell.
+
+use std::str::FromStr;
+
+use cookie::{time::format_description::well_known::Rfc3339, Cookie};
+
+/// Variable of a template, replaced by an attribute of the cookie.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Variable {
+    Name,
+    Value,
+    Domain,
+    Path,
+    /// UNIX timestamp of the expiration, empty for the session cookies.
+    Expires,
+    /// Expiration in RFC 3339 format, empty for the session cookies.
+    ExpiresRfc3339,
+    Secure,
+    HttpOnly,
+    /// `Strict`, `Lax` or `None`, empty if the attribute is not set.
+    SameSite,
+    Partitioned,
+}
+
+impl Variable {
+    const ALL: [(&'static str, Variable); 10] = [
+        ("name", Variable::Name),
+        ("value", Variable::Value),
+        ("domain", Variable::Domain),
+        ("path", Variable::Path),
+        ("expires", Variable::Expires),
+        ("expires_rfc3339", Variable::ExpiresRfc3339),
+        ("secure", Variable::Secure),
+        ("http_only", Variable::HttpOnly),
+        ("same_site", Variable::SameSite),
+        ("partitioned", Variable::Partitioned),
+    ];
+
+    fn render(self, cookie: &Cookie<'_>) -> String {
+        match self {
+            Variable::Name => cookie.name().to_string(),
+            Variable::Value => cookie.value().to_string(),
+            Variable::Domain => cookie.domain().unwrap_or_default().to_string(),
+            Variable::Path => cookie.path().unwrap_or("/").to_string(),
+            Variable::Expires => cookie
+                .expires_datetime()
+                .map(|expires| expires.unix_timestamp().to_string())
+                .unwrap_or_default(),
+            Variable::ExpiresRfc3339 => cookie
+                .expires_datetime()
+                .and_then(|expires| expires.format(&Rfc3339).ok())
+                .unwrap_or_default(),
+            Variable::Secure => cookie.secure().unwrap_or_default().to_string(),
+            Variable::HttpOnly => cookie.http_only().unwrap_or_default().to_string(),
+            Variable::SameSite => cookie
+                .same_site()
+                .map(|same_site| same_site.to_string())
+                .unwrap_or_default(),
+            Variable::Partitioned => cookie.partitioned().unwrap_or_default().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Variable(Variable),
+}
+
+/// Line format given with `--template`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    /// Render the template for the cookie, without a line terminator.
+    pub(crate) fn render(&self, cookie: &Cookie<'_>) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(literal) => literal.clone(),
+                Part::Variable(variable) => variable.render(cookie),
+            })
+            .collect()
+    }
+}
+
+fn unescape(literal: &str) -> String {
+    let mut unescaped = String::with_capacity(literal.len());
+    let mut chars = literal.chars();
+
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some(escaped @ ('t' | 'n' | '\\'))) => {
+                chars.next();
+                unescaped.push(match escaped {
+                    't' => '\t',
+                    'n' => '\n',
+                    _ => '\\',
+                });
+            }
+            _ => unescaped.push(c),
+        }
+    }
+
+    unescaped
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut rest = s;
+
+        while let Some(start) = rest.find("{{") {
+            if start > 0 {
+                parts.push(Part::Literal(unescape(&rest[..start])));
+            }
+            let end = rest[start..]
+                .find("}}")
+                .ok_or_else(|| format!("'{}' is not closed with }}}}", &rest[start..]))?;
+            let name = rest[start + 2..start + end].trim();
+            let variable = Variable::ALL
+                .iter()
+                .find(|(variable, _)| *variable == name)
+                .map(|(_, variable)| *variable)
+                .ok_or_else(|| {
+                    format!(
+                        "'{name}' is not one of the template variables ({})",
+                        Variable::ALL.map(|(variable, _)| variable).join(", ")
+                    )
+                })?;
+            parts.push(Part::Variable(variable));
+            rest = &rest[start + end + 2..];
+        }
+        if !rest.is_empty() {
+            parts.push(Part::Literal(unescape(rest)));
+        }
+
+        Ok(Template { parts })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cookie::{time::OffsetDateTime, SameSite};
+
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let cookie = Cookie::build(("sid", "abc"))
+            .domain("example.com")
+            .path("/")
+            .secure(true)
+            .same_site(SameSite::Lax)
+            .expires(OffsetDateTime::from_unix_timestamp(2000000000).unwrap())
+            .build();
+        let session = Cookie::build(("lang", "en")).domain("example.com").build();
+
+        let template = r"{{domain}}\t{{ name }}={{value}} {{expires}} {{expires_rfc3339}} {{secure}} {{same_site}}\\"
+            .parse::<Template>()
+            .unwrap();
+        assert_eq!(
+            template.render(&cookie),
+            "example.com\tsid=abc 2000000000 2033-05-18T03:33:20Z true Lax\\"
+        );
+        assert_eq!(template.render(&session), "example.com\tlang=en   false \\");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!("{{unknown}}".parse::<Template>().is_err());
+        assert!("{{name".parse::<Template>().is_err());
+        assert_eq!(
+            "}} {{name}}"
+                .parse::<Template>()
+                .unwrap()
+                .render(&Cookie::new("sid", "abc")),
+            "}} sid"
+        );
+    }
+}
@@ -0,0 +1,58 @@
+//! Points in time written as dates (e.g. `2024-05-01`, `2024-05-01T12:30:00Z`)
+//! or UNIX timestamps (e.g. `1714566600`).
+
+use std::str::FromStr;
+
+use cookie::time::{
+    format_description::{self, well_known::Rfc3339},
+    Date, OffsetDateTime,
+};
+
+/// Point in time parsed from an RFC 3339 date and time, a date (at midnight UTC),
+/// or a number of seconds since the UNIX epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Timestamp(pub OffsetDateTime);
+
+impl FromStr for Timestamp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Ok(seconds) = s.parse::<i64>() {
+            return OffsetDateTime::from_unix_timestamp(seconds)
+                .map(Self)
+                .map_err(|e| format!("'{s}' is not a valid timestamp ({e})"));
+        }
+
+        OffsetDateTime::parse(s, &Rfc3339)
+            .or_else(|_| {
+                let format = format_description::parse("[year]-[month]-[day]").unwrap();
+                Date::parse(s, &format).map(|date| date.midnight().assume_utc())
+            })
+            .map(Self)
+            .map_err(|_| {
+                format!(
+                    "'{s}' is not a valid timestamp (expected YYYY-MM-DD, \
+                    an RFC 3339 date and time or a UNIX timestamp)"
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp() {
+        let expected = Ok(Timestamp(
+            OffsetDateTime::from_unix_timestamp(1714521600).unwrap(),
+        ));
+
+        assert_eq!("1714521600".parse(), expected);
+        assert_eq!("2024-05-01".parse(), expected);
+        assert_eq!("2024-05-01T00:00:00Z".parse(), expected);
+        assert_eq!("2024-05-01T02:00:00+02:00".parse(), expected);
+        assert!("2024-05".parse::<Timestamp>().is_err());
+        assert!("yesterday".parse::<Timestamp>().is_err());
+    }
+}
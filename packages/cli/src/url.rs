@@ -1,7 +1,11 @@
-use std::{net::Ipv4Addr, str::FromStr};
+use std::{collections::HashSet, net::Ipv4Addr, path::Path, str::FromStr, sync::OnceLock};
 
+use color_eyre::{eyre::Context, Result};
 use http::Uri;
 
+/// Public suffix list loaded from the data directory, if any.
+static PUBLIC_SUFFIXES: OnceLock<PublicSuffixList> = OnceLock::new();
+
 /// Trait for extracting the base domain from a URL.
 pub trait BaseDomain {
     /// Returns the base domain of the URL, if it is a valid domain.
@@ -11,6 +15,11 @@ pub trait BaseDomain {
 impl BaseDomain for Uri {
     fn base_domain(&self) -> Option<String> {
         self.host().filter(is_domain).and_then(|host| {
+            let host = normalize_host(host);
+            if let Some(suffixes) = PUBLIC_SUFFIXES.get() {
+                return suffixes.registrable_domain(&host);
+            }
+
             let mut parts = host.rsplitn(3, '.');
             let ext = parts.next()?;
             let base_domain = parts.next()?;
@@ -20,6 +29,124 @@ impl BaseDomain for Uri {
     }
 }
 
+/// Returns the host or the domain in lowercase, without its trailing dot,
+/// so `Example.COM.` and `example.com` are compared as the same host.
+pub fn normalize_host(host: &str) -> String {
+    host.strip_suffix('.').unwrap_or(host).to_ascii_lowercase()
+}
+
+/// Rules of the [public suffix list](https://publicsuffix.org/list/).
+#[derive(Debug, Default)]
+pub struct PublicSuffixList {
+    rules: HashSet<String>,
+    wildcards: HashSet<String>,
+    exceptions: HashSet<String>,
+}
+
+impl PublicSuffixList {
+    /// Use the public suffix list of the file to find the base domains, for the rest of the process.
+    ///
+    /// Has no effect if a list has already been loaded.
+    pub fn load(path: &Path) -> Result<()> {
+        let list = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("Failed to read {}", path.display()))?;
+
+        let _ = PUBLIC_SUFFIXES.set(Self::parse(&list));
+        Ok(())
+    }
+
+    /// Parse the public suffix list, in its `.dat` format.
+    pub fn parse(list: &str) -> Self {
+        let mut suffixes = Self::default();
+
+        for line in list.lines() {
+            // The rules end at the first whitespace
+            let Some(rule) = line.split_whitespace().next() else {
+                continue;
+            };
+            if rule.starts_with("//") {
+                continue;
+            }
+
+            let rule = rule.to_ascii_lowercase();
+            if let Some(exception) = rule.strip_prefix('!') {
+                suffixes.exceptions.insert(exception.to_string());
+            } else if let Some(parent) = rule.strip_prefix("*.") {
+                suffixes.wildcards.insert(parent.to_string());
+            } else {
+                suffixes.rules.insert(rule);
+            }
+        }
+
+        suffixes
+    }
+
+    /// Returns the domain registered under a public suffix which the host belongs to,
+    /// or `None` if the host is itself a public suffix.
+    ///
+    /// The hosts which do not match any rule are under a public suffix of one label.
+    pub fn registrable_domain(&self, host: &str) -> Option<String> {
+        let host = normalize_host(host);
+        let labels = host.split('.').collect::<Vec<_>>();
+
+        let suffix_len = (0..labels.len())
+            .find_map(|start| {
+                let candidate = labels[start..].join(".");
+                if self.exceptions.contains(&candidate) {
+                    Some(labels.len() - start - 1)
+                } else if self.rules.contains(&candidate) {
+                    Some(labels.len() - start)
+                } else if start > 0 && self.wildcards.contains(&candidate) {
+                    Some(labels.len() - start + 1)
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(1);
+
+        (labels.len() > suffix_len).then(|| labels[labels.len() - suffix_len - 1..].join("."))
+    }
+}
+
+/// Returns the host of the URL if it is a wildcard pattern:
+/// `*.` matches any subdomain and `.*` any suffix, e.g. `*.example.com` or `example.*`.
+pub fn host_pattern(uri: &Uri) -> Option<&str> {
+    uri.host()
+        .filter(|host| host.starts_with("*.") || host.ends_with(".*"))
+}
+
+/// Returns whether the domain matches the wildcard pattern, ignoring the case.
+///
+/// A leading `*.` matches one or more labels before the rest of the pattern,
+/// and a trailing `.*` one or more labels after it. The domain itself is not matched,
+/// so `*.example.com` matches `www.example.com` but not `example.com`,
+/// and `example.*` matches `example.com` and `example.co.uk` but not `www.example.com`.
+pub fn matches_host_pattern(pattern: &str, domain: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+    let domain = domain.to_ascii_lowercase();
+
+    let (any_prefix, rest) = pattern
+        .strip_prefix("*.")
+        .map_or((false, pattern.as_str()), |rest| (true, rest));
+    let (any_suffix, middle) = rest
+        .strip_suffix(".*")
+        .map_or((false, rest), |middle| (true, middle));
+
+    let labels = domain.split('.').collect::<Vec<_>>();
+    let middle = middle.split('.').collect::<Vec<_>>();
+    if middle.len() > labels.len() {
+        return false;
+    }
+
+    (0..=labels.len() - middle.len()).any(|start| {
+        let end = start + middle.len();
+
+        labels[start..end] == middle[..]
+            && (start > 0) == any_prefix
+            && (end < labels.len()) == any_suffix
+    })
+}
+
 fn is_domain(host: &&str) -> bool {
     !host.starts_with('[') && Ipv4Addr::from_str(host).is_err()
 }
@@ -52,6 +179,50 @@ mod tests {
         assert_eq!(url.base_domain(), None);
     }
 
+    #[test]
+    fn test_registrable_domain() {
+        let suffixes = PublicSuffixList::parse(
+            "// ===BEGIN ICANN DOMAINS===\n\
+            com\n\
+            uk\n\
+            co.uk\n\
+            *.ck\n\
+            !www.ck\n\
+            \n\
+            github.io // Comment\n",
+        );
+
+        let domain = |host| suffixes.registrable_domain(host);
+        assert_eq!(domain("www.example.com"), Some("example.com".into()));
+        assert_eq!(domain("shop.example.co.uk"), Some("example.co.uk".into()));
+        assert_eq!(domain("user.github.io"), Some("user.github.io".into()));
+        assert_eq!(domain("a.b.example.ck"), Some("b.example.ck".into()));
+        assert_eq!(domain("www.ck"), Some("www.ck".into()));
+        assert_eq!(domain("example.internal"), Some("example.internal".into()));
+        assert_eq!(domain("co.uk"), None);
+        assert_eq!(domain("example.ck"), None);
+    }
+
+    #[test]
+    fn test_matches_host_pattern() {
+        assert!(matches_host_pattern("*.example.com", "www.example.com"));
+        assert!(matches_host_pattern("*.example.com", "a.b.Example.com"));
+        assert!(!matches_host_pattern("*.example.com", "example.com"));
+        assert!(!matches_host_pattern("*.example.com", "notexample.com"));
+        assert!(matches_host_pattern("example.*", "example.com"));
+        assert!(matches_host_pattern("example.*", "example.co.uk"));
+        assert!(!matches_host_pattern("example.*", "www.example.com"));
+        assert!(!matches_host_pattern("example.*", "example"));
+        assert!(matches_host_pattern("*.example.*", "www.example.org"));
+        assert!(!matches_host_pattern("*.example.*", "example.org"));
+    }
+
+    #[test]
+    fn test_base_domain_with_case_and_trailing_dot() {
+        let url = Uri::from_static("https://WWW.Example.COM./");
+        assert_eq!(url.base_domain(), Some(String::from("example.com")));
+    }
+
     #[test]
     fn test_base_domain_with_subdomain() {
         let url = Uri::from_static("https://www.example.com");
@@ -0,0 +1,556 @@
+//! Commands which can be wrapped, and how the cookies are passed to them.
+//!
+//! The built-in commands can be completed (or overridden) by the ones
+//! declared in the configuration file:
+//!
+//! ```toml
+//! [[wrapped-commands]]
+//! name = "yt-dlp"
+//! binary = "yt-dlp"
+//! cookie-flag = "--cookies"
+//! format = "netscape"
+//! ```
+//!
+//! curl takes a config file (`-K`) which reads the cookie file (`cookies = "curl-config"`),
+//! so the cookie options given to it (`-b`, `--cookie`) are kept as they are.
+//!
+//! The commands without cookie file option (e.g. `httpx`) take the cookies sent to the URL
+//! in their arguments as name and value pairs after the option (`cookies = "pairs"`).
+//!
+//! With `wrap --as-header`, the `Cookie` header of the URL is passed instead,
+//! through the arguments of the command in which `{cookie_header}` is replaced
+//! (e.g. `header-args = ["-H", "Cookie: {cookie_header}"]`).
+//! Unlike a cookie file, the arguments can be read by the other users in the list of processes.
+//!
+//! Any other command can be wrapped with `exec`, which replaces the placeholders
+//! in its arguments by the path of a cookie file and the `Cookie` header of its URL.
+
+use std::{
+    ffi::{OsStr, OsString},
+    path::Path,
+};
+
+use gateau::report::BrowserCookie;
+use http::{uri::Scheme, Uri};
+use serde::Deserialize;
+
+use crate::OutputFormat;
+
+/// Position of the cookie file relative to the forwarded arguments.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ArgPosition {
+    /// The cookie file is passed before the forwarded arguments.
+    #[default]
+    Before,
+    /// The cookie file is passed after the forwarded arguments.
+    After,
+}
+
+/// How the cookies are passed to the wrapped command.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum CookieArgs {
+    /// The path of a cookie file in the format of the command follows the option.
+    #[default]
+    File,
+    /// The name and value of each cookie sent to the URL of the command follow the option,
+    /// repeated for each cookie (e.g. `--cookies NAME VALUE`).
+    Pairs,
+    /// The path of a curl config file which reads the cookie file follows the option (`-K`).
+    #[serde(rename = "curl-config")]
+    CurlConfig,
+    /// The placeholders in the forwarded arguments are replaced, as done by `exec`.
+    #[serde(skip)]
+    Placeholders,
+}
+
+/// Name of the command wrapping the command given as its first argument.
+pub(crate) const EXEC: &str = "exec";
+/// Placeholder replaced by the path of the cookie file.
+pub(crate) const COOKIE_FILE: &str = "{cookie_file}";
+/// Placeholder replaced by the value of the `Cookie` header sent to the URL of the command.
+pub(crate) const COOKIE_HEADER: &str = "{cookie_header}";
+
+/// Command which can be wrapped.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct WrappedCmd {
+    /// Name used to select the command (e.g. `curl`).
+    pub name: String,
+    /// Executable which is run.
+    pub binary: String,
+    /// Option followed by the path of the cookie file (e.g. `-b`).
+    pub cookie_flag: String,
+    /// Format of the cookie file.
+    #[serde(default = "default_format")]
+    pub format: OutputFormat,
+    /// Position of the cookie file relative to the forwarded arguments.
+    #[serde(default)]
+    pub position: ArgPosition,
+    /// How the cookies are passed.
+    #[serde(default)]
+    pub cookies: CookieArgs,
+    /// Arguments passing the `Cookie` header, in which the placeholder is replaced by its value,
+    /// or none if the command cannot take a header.
+    #[serde(default)]
+    pub header_args: Vec<String>,
+    /// Position of the arguments passing the `Cookie` header relative to the forwarded arguments.
+    #[serde(default)]
+    pub header_position: ArgPosition,
+}
+
+const fn default_format() -> OutputFormat {
+    OutputFormat::Netscape
+}
+
+impl WrappedCmd {
+    fn builtin(name: &str, binary: &str, cookie_flag: &str, format: OutputFormat) -> Self {
+        Self {
+            name: name.to_string(),
+            binary: binary.to_string(),
+            cookie_flag: cookie_flag.to_string(),
+            format,
+            position: ArgPosition::Before,
+            cookies: CookieArgs::File,
+            header_args: Vec::new(),
+            header_position: ArgPosition::Before,
+        }
+    }
+
+    /// Returns the command with the arguments passing the `Cookie` header at the position.
+    fn with_header_args(self, header_args: &[&str], header_position: ArgPosition) -> Self {
+        Self {
+            header_args: header_args.iter().map(ToString::to_string).collect(),
+            header_position,
+            ..self
+        }
+    }
+
+    /// Returns the command wrapped by `exec`, which takes the cookies through placeholders.
+    pub fn exec(binary: String) -> Self {
+        Self {
+            binary,
+            cookies: CookieArgs::Placeholders,
+            ..Self::builtin(EXEC, "", "", OutputFormat::Netscape)
+        }
+    }
+
+    /// Returns the arguments of the command, with the cookie file at its position.
+    pub fn args<A: AsRef<OsStr>>(&self, cookie_file: &Path, forwarded_args: &[A]) -> Vec<OsString> {
+        let cookie_args = [OsStr::new(&self.cookie_flag), cookie_file.as_os_str()];
+
+        place(
+            self.position,
+            cookie_args.into_iter().map(OsString::from),
+            forwarded_args,
+        )
+    }
+
+    /// Returns the arguments of the command, with the name and value of each cookie
+    /// after the cookie option, at its position.
+    pub fn pair_args<A: AsRef<OsStr>>(
+        &self,
+        cookies: &[&BrowserCookie],
+        forwarded_args: &[A],
+    ) -> Vec<OsString> {
+        let cookie_args = cookies.iter().flat_map(|cookie| {
+            [
+                OsString::from(&self.cookie_flag),
+                OsString::from(cookie.name()),
+                OsString::from(cookie.value()),
+            ]
+        });
+
+        place(self.position, cookie_args, forwarded_args)
+    }
+
+    /// Returns the arguments of the command, with the arguments passing the `Cookie` header
+    /// at their position, unless the header is empty.
+    pub fn header_args<A: AsRef<OsStr>>(
+        &self,
+        cookie_header: &str,
+        forwarded_args: &[A],
+    ) -> Vec<OsString> {
+        let header_args = self
+            .header_args
+            .iter()
+            .filter(|_| !cookie_header.is_empty())
+            .map(|arg| OsString::from(arg.replace(COOKIE_HEADER, cookie_header)));
+
+        place(self.header_position, header_args, forwarded_args)
+    }
+}
+
+/// Returns the forwarded arguments, with the cookie arguments at the position.
+fn place<I, A>(position: ArgPosition, cookie_args: I, forwarded_args: &[A]) -> Vec<OsString>
+where
+    I: IntoIterator<Item = OsString>,
+    A: AsRef<OsStr>,
+{
+    let forwarded_args = forwarded_args.iter().map(|arg| arg.as_ref().to_os_string());
+
+    match position {
+        ArgPosition::Before => cookie_args.into_iter().chain(forwarded_args).collect(),
+        ArgPosition::After => forwarded_args.chain(cookie_args).collect(),
+    }
+}
+
+/// Returns the content of a curl config file which reads the cookie file.
+pub(crate) fn curl_config(cookie_file: &Path) -> String {
+    let path = cookie_file
+        .to_string_lossy()
+        .replace('\\', r"\\")
+        .replace('"', r#"\""#);
+
+    format!("cookie = \"{path}\"\n")
+}
+
+/// Returns whether one of the arguments contains the placeholder.
+pub(crate) fn uses_placeholder<A: AsRef<OsStr>>(forwarded_args: &[A], placeholder: &str) -> bool {
+    forwarded_args
+        .iter()
+        .filter_map(|arg| arg.as_ref().to_str())
+        .any(|arg| arg.contains(placeholder))
+}
+
+/// Returns the arguments with the placeholders replaced by the path of the cookie file
+/// and the `Cookie` header, the ones without value being kept as is.
+/// The arguments which are not valid UTF-8 are forwarded unchanged.
+pub(crate) fn substitute_placeholders<A: AsRef<OsStr>>(
+    forwarded_args: &[A],
+    cookie_file: Option<&Path>,
+    cookie_header: Option<&str>,
+) -> Vec<OsString> {
+    forwarded_args
+        .iter()
+        .map(|arg| {
+            let Some(mut rest) = arg.as_ref().to_str() else {
+                return arg.as_ref().to_os_string();
+            };
+
+            let mut substituted = OsString::new();
+            while let Some((start, placeholder)) = [COOKIE_FILE, COOKIE_HEADER]
+                .into_iter()
+                .filter_map(|placeholder| Some((rest.find(placeholder)?, placeholder)))
+                .min()
+            {
+                substituted.push(&rest[..start]);
+                match (placeholder, cookie_file, cookie_header) {
+                    (COOKIE_FILE, Some(path), _) => substituted.push(path),
+                    (COOKIE_HEADER, _, Some(header)) => substituted.push(header),
+                    _ => substituted.push(placeholder),
+                }
+                rest = &rest[start + placeholder.len()..];
+            }
+            substituted.push(rest);
+
+            substituted
+        })
+        .collect()
+}
+
+/// Returns the URLs requested by the wrapped command: the HTTP(S) URLs in its arguments,
+/// and the arguments which start with a host name (e.g. `example.com/path`),
+/// as curl, wget and HTTPie accept them without scheme.
+pub(crate) fn request_urls<A: AsRef<OsStr>>(forwarded_args: &[A]) -> Vec<Uri> {
+    forwarded_args
+        .iter()
+        .filter_map(|arg| arg.as_ref().to_str())
+        .filter(|arg| !arg.starts_with('-'))
+        .filter_map(|arg| match arg.parse::<Uri>() {
+            Ok(url) if url.scheme().is_some() => is_http(&url).then_some(url),
+            _ => format!("https://{arg}")
+                .parse::<Uri>()
+                .ok()
+                .filter(|url| url.host().is_some_and(is_host_name)),
+        })
+        .collect()
+}
+
+fn is_http(url: &Uri) -> bool {
+    matches!(url.scheme(), Some(scheme) if *scheme == Scheme::HTTP || *scheme == Scheme::HTTPS)
+        && url.host().is_some()
+}
+
+/// Returns whether the host is `localhost`, an IPv4 address, or a domain whose last label
+/// is alphabetic. File names such as `data.json` are also accepted, but have no cookies.
+fn is_host_name(host: &str) -> bool {
+    let labels = host.split('.').collect::<Vec<_>>();
+
+    host == "localhost"
+        || host.parse::<std::net::Ipv4Addr>().is_ok()
+        || (labels.len() > 1
+            && labels.iter().all(|label| {
+                !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            })
+            && labels
+                .last()
+                .is_some_and(|tld| tld.chars().all(|c| c.is_ascii_alphabetic())))
+}
+
+/// Returns the first argument which is an HTTP(S) URL, the one requested by the wrapped command.
+pub(crate) fn request_url<A: AsRef<OsStr>>(forwarded_args: &[A]) -> Option<Uri> {
+    forwarded_args
+        .iter()
+        .filter_map(|arg| arg.as_ref().to_str()?.parse::<Uri>().ok())
+        .find(is_http)
+}
+
+/// Registry of the commands which can be wrapped.
+#[derive(Debug, Clone)]
+pub(crate) struct WrappedCmds(Vec<WrappedCmd>);
+
+impl WrappedCmds {
+    /// Returns the registry of the built-in commands.
+    pub fn builtin() -> Self {
+        Self(vec![
+            WrappedCmd {
+                cookies: CookieArgs::CurlConfig,
+                ..WrappedCmd::builtin("curl", "curl", "-K", OutputFormat::Netscape)
+            }
+            .with_header_args(&["-H", "Cookie: {cookie_header}"], ArgPosition::Before),
+            WrappedCmd::builtin("wget", "wget", "--load-cookies", OutputFormat::Netscape)
+                .with_header_args(
+                    &["--header", "Cookie: {cookie_header}"],
+                    ArgPosition::Before,
+                ),
+            // The headers are request items for HTTPie, which follow the URL.
+            WrappedCmd::builtin("http", "http", "--session", OutputFormat::HttpieSession)
+                .with_header_args(&["Cookie:{cookie_header}"], ArgPosition::After),
+            WrappedCmd::builtin("https", "https", "--session", OutputFormat::HttpieSession)
+                .with_header_args(&["Cookie:{cookie_header}"], ArgPosition::After),
+            WrappedCmd::builtin("httpie", "https", "--session", OutputFormat::HttpieSession)
+                .with_header_args(&["Cookie:{cookie_header}"], ArgPosition::After),
+            WrappedCmd {
+                cookies: CookieArgs::Pairs,
+                ..WrappedCmd::builtin("httpx", "httpx", "--cookies", OutputFormat::Netscape)
+            }
+            .with_header_args(
+                &["--headers", "Cookie", "{cookie_header}"],
+                ArgPosition::Before,
+            ),
+        ])
+    }
+
+    /// Register the commands, which take precedence over the ones with the same name.
+    pub fn register<I: IntoIterator<Item = WrappedCmd>>(&mut self, commands: I) {
+        self.0.extend(commands);
+    }
+
+    /// Returns the commands which can be wrapped, once per name, in the order of registration.
+    pub fn commands(&self) -> Vec<&WrappedCmd> {
+        let mut names = Vec::new();
+        for command in &self.0 {
+            if !names.contains(&command.name.as_str()) {
+                names.push(command.name.as_str());
+            }
+        }
+
+        names
+            .into_iter()
+            .filter_map(|name| self.get(name).ok())
+            .collect()
+    }
+
+    /// Returns the command with the given name.
+    pub fn get(&self, name: &str) -> Result<&WrappedCmd, String> {
+        self.0
+            .iter()
+            .rev()
+            .find(|command| command.name == name)
+            .ok_or_else(|| {
+                let names = self
+                    .0
+                    .iter()
+                    .map(|command| command.name.as_str())
+                    .collect::<Vec<_>>();
+
+                format!(
+                    "'{name}' is not one of the supported commands ({})",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cookie::Cookie;
+
+    use super::*;
+
+    #[test]
+    fn test_curl_config() {
+        assert_eq!(
+            curl_config(Path::new("/tmp/gateau-abc")),
+            "cookie = \"/tmp/gateau-abc\"\n"
+        );
+        assert_eq!(
+            curl_config(Path::new(r#"C:\Temp\"gateau""#)),
+            r#"cookie = "C:\\Temp\\\"gateau\"""#.to_string() + "\n"
+        );
+    }
+
+    #[test]
+    fn test_registry() {
+        let mut commands = WrappedCmds::builtin();
+        assert_eq!(commands.get("httpie").unwrap().binary, "https");
+        assert!(commands.get("yt-dlp").is_err());
+
+        commands.register([
+            WrappedCmd::builtin("yt-dlp", "yt-dlp", "--cookies", OutputFormat::Netscape),
+            WrappedCmd::builtin("curl", "curlie", "-b", OutputFormat::Netscape),
+        ]);
+        assert_eq!(commands.get("yt-dlp").unwrap().cookie_flag, "--cookies");
+        assert_eq!(
+            commands
+                .commands()
+                .iter()
+                .map(|command| (command.name.as_str(), command.binary.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                ("curl", "curlie"),
+                ("wget", "wget"),
+                ("http", "http"),
+                ("https", "https"),
+                ("httpie", "https"),
+                ("httpx", "httpx"),
+                ("yt-dlp", "yt-dlp"),
+            ]
+        );
+        assert_eq!(commands.get("curl").unwrap().binary, "curlie");
+    }
+
+    #[test]
+    fn test_args_position() {
+        let mut command = WrappedCmd::builtin("curl", "curl", "-b", OutputFormat::Netscape);
+        let cookie_file = Path::new("/tmp/cookies");
+
+        assert_eq!(
+            command.args(cookie_file, &["example.com"]),
+            ["-b", "/tmp/cookies", "example.com"]
+        );
+
+        command.position = ArgPosition::After;
+        assert_eq!(
+            command.args(cookie_file, &["example.com"]),
+            ["example.com", "-b", "/tmp/cookies"]
+        );
+    }
+
+    #[test]
+    fn test_pair_args() {
+        let command = WrappedCmds::builtin().get("httpx").unwrap().clone();
+        let cookies = [
+            BrowserCookie::from(Cookie::new("sid", "1")),
+            BrowserCookie::from(Cookie::new("lang", "en")),
+        ];
+        let args = [
+            "-m",
+            "POST",
+            "https://example.com/login",
+            "https://example.org",
+        ];
+
+        assert_eq!(
+            command.pair_args(&cookies.iter().collect::<Vec<_>>(), &args),
+            [
+                "--cookies",
+                "sid",
+                "1",
+                "--cookies",
+                "lang",
+                "en",
+                "-m",
+                "POST",
+                "https://example.com/login",
+                "https://example.org"
+            ]
+        );
+        assert_eq!(
+            request_url(&args),
+            Some(Uri::from_static("https://example.com/login"))
+        );
+        assert_eq!(request_url(&["example.com", "ftp://example.com"]), None);
+    }
+
+    #[test]
+    fn test_header_args() {
+        let commands = WrappedCmds::builtin();
+        let url = ["https://example.com"];
+
+        assert_eq!(
+            commands
+                .get("curl")
+                .unwrap()
+                .header_args("sid=1; lang=en", &url),
+            ["-H", "Cookie: sid=1; lang=en", "https://example.com"]
+        );
+        assert_eq!(
+            commands.get("http").unwrap().header_args("sid=1", &url),
+            ["https://example.com", "Cookie:sid=1"]
+        );
+        assert_eq!(
+            commands.get("httpx").unwrap().header_args("sid=1", &url),
+            ["--headers", "Cookie", "sid=1", "https://example.com"]
+        );
+        assert_eq!(
+            commands.get("wget").unwrap().header_args("", &url),
+            ["https://example.com"]
+        );
+    }
+
+    #[test]
+    fn test_substitute_placeholders() {
+        let args = [
+            "--load-cookies={cookie_file}",
+            "-H",
+            "Cookie: {cookie_header}",
+            "{cookie_header}{cookie_file}",
+            "https://example.com",
+        ];
+        assert!(uses_placeholder(&args, COOKIE_FILE));
+        assert!(!uses_placeholder(&["https://example.com"], COOKIE_HEADER));
+
+        assert_eq!(
+            substitute_placeholders(&args, Some(Path::new("/tmp/cookies")), Some("sid=1")),
+            [
+                "--load-cookies=/tmp/cookies",
+                "-H",
+                "Cookie: sid=1",
+                "sid=1/tmp/cookies",
+                "https://example.com"
+            ]
+        );
+        assert_eq!(
+            substitute_placeholders(&args[..2], None, Some("{cookie_file}")),
+            ["--load-cookies={cookie_file}", "-H"]
+        );
+    }
+
+    #[test]
+    fn test_request_urls() {
+        let urls = request_urls(&[
+            "-X",
+            "POST",
+            "-d",
+            "@-",
+            "httpbin.org/post",
+            "https://www.example.com/login?next=/",
+            "ftp://example.org",
+            "--referer=https://example.net",
+            "Accept:application/json",
+            "name=value",
+            "localhost:8080/api",
+        ]);
+
+        assert_eq!(
+            urls.iter()
+                .map(|url| url.host().unwrap())
+                .collect::<Vec<_>>(),
+            ["httpbin.org", "www.example.com", "localhost"]
+        );
+        assert!(request_urls(&["-K", "config"]).is_empty());
+    }
+}
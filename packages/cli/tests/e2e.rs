@@ -0,0 +1,218 @@
+//! End-to-end tests running real browsers in a container.
+//!
+//! The browsers log in to a local test server, then gateau reads the cookies from their profile,
+//! which guards against changes of the database schema or of the encryption by the browsers.
+//! They are ignored by default, as they need Docker or Podman and download the browsers:
+//!
+//! ```bash
+//! cargo test -p cli --test e2e -- --ignored
+//! ```
+//!
+//! The container engine can be set with the `GATEAU_E2E_ENGINE` environment variable.
+#![cfg(target_os = "linux")]
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    path::Path,
+    process::{Command, Output},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, OnceLock,
+    },
+    thread,
+};
+
+use serde_json::Value;
+
+const IMAGE: &str = "gateau-e2e";
+const SESSION: &str = "3f1c2a9e7b";
+
+fn engine() -> String {
+    std::env::var("GATEAU_E2E_ENGINE").unwrap_or_else(|_| "docker".to_string())
+}
+
+fn check(output: Output, what: &str) -> Output {
+    assert!(
+        output.status.success(),
+        "{what} failed ({}): {}",
+        output.status,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+}
+
+/// Build the image with the browsers once for all the tests.
+fn image() -> &'static str {
+    static BUILT: OnceLock<()> = OnceLock::new();
+
+    BUILT.get_or_init(|| {
+        let context = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/e2e");
+        let output = Command::new(engine())
+            .args(["build", "-t", IMAGE, "-f"])
+            .arg(context.join("Containerfile"))
+            .arg(&context)
+            .output()
+            .unwrap_or_else(|e| panic!("Failed to run {}: {e}", engine()));
+        check(output, "Building the browser image");
+    });
+
+    IMAGE
+}
+
+fn id(flag: &str) -> String {
+    let output = check(Command::new("id").arg(flag).output().unwrap(), "id");
+    String::from_utf8(output.stdout).unwrap().trim().to_string()
+}
+
+/// Login server which sets the session cookie on `/login`
+/// and records whether the browser sent it back on `/account`.
+struct LoginServer {
+    port: u16,
+    logged_in: Arc<AtomicBool>,
+}
+
+impl LoginServer {
+    fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let logged_in = Arc::new(AtomicBool::new(false));
+
+        let flag = logged_in.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                Self::respond(stream, &flag);
+            }
+        });
+
+        Self { port, logged_in }
+    }
+
+    fn respond(mut stream: TcpStream, logged_in: &AtomicBool) {
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).is_err() {
+            return;
+        }
+
+        let mut cookie = String::new();
+        let mut line = String::new();
+        while reader.read_line(&mut line).is_ok_and(|read| read > 2) {
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("cookie") {
+                    cookie = value.trim().to_string();
+                }
+            }
+            line.clear();
+        }
+
+        let response = match request_line.split_whitespace().nth(1) {
+            Some("/login") => format!(
+                "HTTP/1.1 302 Found\r\nLocation: /account\r\n\
+                 Set-Cookie: session={SESSION}; Max-Age=3600; Path=/; HttpOnly\r\n\
+                 Content-Length: 0\r\nConnection: close\r\n\r\n"
+            ),
+            Some("/account") => {
+                let authenticated = cookie.contains(&format!("session={SESSION}"));
+                logged_in.fetch_or(authenticated, Ordering::SeqCst);
+                let body = if authenticated {
+                    "Welcome"
+                } else {
+                    "Anonymous"
+                };
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\n\
+                     Connection: close\r\n\r\n{body}",
+                    body.len()
+                )
+            }
+            _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                .to_string(),
+        };
+        let _ = stream.write_all(response.as_bytes());
+    }
+
+    fn login_url(&self) -> String {
+        format!("http://127.0.0.1:{}/login", self.port)
+    }
+}
+
+/// Run a command in the browser image, with the profile directory mounted on `/profile`
+/// and the network of the host to reach the login server.
+fn run_browser(profile: &Path, command: &str) {
+    let output = Command::new(engine())
+        .args(["run", "--rm", "--network", "host", "--user"])
+        .arg(format!("{}:{}", id("-u"), id("-g")))
+        .args(["-e", "HOME=/tmp", "-v"])
+        .arg(format!("{}:/profile", profile.display()))
+        .args([image(), "sh", "-c", command])
+        .output()
+        .unwrap();
+    check(output, "Running the browser");
+}
+
+fn gateau_cookies(browser: &str, profile: &Path) -> Vec<Value> {
+    let home = tempfile::tempdir().unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_cli"))
+        .env("HOME", home.path())
+        .args(["--browser", browser, "--root-path"])
+        .arg(profile)
+        .args(["output", "--format", "json"])
+        .output()
+        .unwrap();
+    let output = check(output, "gateau");
+
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+fn assert_session_cookie(cookies: &[Value]) {
+    let session = cookies
+        .iter()
+        .find(|cookie| cookie["name"] == "session")
+        .unwrap_or_else(|| panic!("No session cookie in {cookies:?}"));
+
+    assert_eq!(session["value"], SESSION);
+    assert_eq!(session["domain"], "127.0.0.1");
+    assert_eq!(session["path"], "/");
+    assert_eq!(session["http_only"], true);
+}
+
+#[test]
+#[ignore = "needs a container engine and downloads the browsers"]
+fn test_chromium_login() {
+    let server = LoginServer::start();
+    let profile = tempfile::tempdir().unwrap();
+
+    // The basic password store encrypts the values with the v10 key, as without a keyring.
+    run_browser(
+        profile.path(),
+        &format!(
+            "chromium --headless --no-sandbox --disable-gpu --password-store=basic \
+             --user-data-dir=/profile --dump-dom {}",
+            server.login_url()
+        ),
+    );
+
+    assert!(server.logged_in.load(Ordering::SeqCst));
+    assert_session_cookie(&gateau_cookies("chromium", profile.path()));
+}
+
+#[test]
+#[ignore = "needs a container engine and downloads the browsers"]
+fn test_firefox_login() {
+    let server = LoginServer::start();
+    let profile = tempfile::tempdir().unwrap();
+
+    // Firefox never exits by itself in headless mode, but writes the cookies on termination.
+    run_browser(
+        profile.path(),
+        &format!(
+            "timeout -s TERM 20 firefox-esr --headless --no-remote --profile /profile {}; \
+             test -s /profile/cookies.sqlite",
+            server.login_url()
+        ),
+    );
+
+    assert!(server.logged_in.load(Ordering::SeqCst));
+    assert_session_cookie(&gateau_cookies("firefox", profile.path()));
+}
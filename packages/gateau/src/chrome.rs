@@ -37,14 +37,23 @@
 //! ```
 //!
 
+use std::{cell::Cell, collections::HashSet};
+
 use cookie::{time::OffsetDateTime, Cookie, CookieBuilder, Expiration, SameSite};
 use once_cell::unsync::OnceCell;
 
 use rusqlite::{functions::FunctionFlags, Connection};
 use thiserror::Error;
+use zeroize::Zeroizing;
 
+use crate::report::{clamped_datetime, BrowserCookie, CookieSet, CookieWarning};
+use crate::time::{chrome_to_unix_timestamp_nanos, unix_to_chrome_timestamp};
 use crate::CookiePathProvider;
 
+#[cfg(any(target_os = "linux", target_os = "macos", windows))]
+use self::key_cache::KeyId;
+use self::key_cache::SharedKey;
+use self::same_site::ChromeSameSite;
 use super::get_connection;
 
 #[cfg(all(unix, not(target_os = "macos")))]
@@ -60,9 +69,22 @@ use self::encrypted_value::mac;
 use self::encrypted_value::windows;
 
 pub(crate) mod encrypted_value;
+mod key_cache;
 mod paths;
-
+mod raw_value;
+mod registry;
+mod same_site;
+mod scan;
+mod stats;
+mod writer;
+
+pub use key_cache::clear_key_cache;
 pub use paths::PathProvider;
+pub use raw_value::RawValue;
+pub use registry::{custom_variant, register_variant, CustomVariant};
+pub use scan::{scan_installations, Installation};
+pub use stats::DecryptionStats;
+pub use writer::write_database;
 
 use super::HostFilterFn;
 
@@ -82,42 +104,83 @@ struct ChromeCookie {
     path: String,
     expires: i64,
     secure: bool,
-    same_site: i64,
+    same_site: ChromeSameSite,
     http_only: bool,
+    /// Top-level site of the partitioned cookies, empty for the others.
+    top_frame_site_key: String,
+    last_access: i64,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ChromeVariant {
     Chromium,
     Chrome,
+    ChromeBeta,
+    ChromeDev,
+    ChromeCanary,
     Edge,
+    EdgeBeta,
+    EdgeDev,
+    EdgeCanary,
+    Arc,
+    /// Yandex Browser, which frames the values before encrypting them
+    /// (see [`ChromeVariant::value_prefix_len`]).
+    Yandex,
+    /// Naver Whale.
+    Whale,
+    /// Falkon, or any other browser based on QtWebEngine.
+    Falkon,
+    /// Browser declared with [`register_variant`].
+    Custom(&'static CustomVariant),
 }
 
-// Offset of UNIX epoch (1970-01-01 00:00:00 UTC) from Windows FILETIME epoch
-// (1601-01-01 00:00:00 UTC), in microseconds. This value is derived from the
-// following: ((1970-1601)*365+89)*24*60*60*1000*1000, where 89 is the number
-// of leap year days between 1601 and 1970: (1970-1601)/4 excluding 1700,
-// 1800, and 1900.
-const WINDOWS_UNIX_EPOCH_OFFSET_MICROS: i64 = 11644473600000000;
-
-// From Chromium source code:
-// Time is stored internally as microseconds
-// since the Windows epoch (1601-01-01 00:00:00 UTC).
-/// Convert a Chrome timestamp (based on Windows epoch) in microseconds
-/// to a UNIX timestamp (based on UNIX epoch) in nanoseconds.
-fn chrome_to_unix_timestamp_nanos(chrome_time: i64) -> i128 {
-    const WINDOWS_UNIX_EPOCH_OFFSET_NANOS: i128 = WINDOWS_UNIX_EPOCH_OFFSET_MICROS as i128 * 1000;
+impl ChromeVariant {
+    /// Returns the number of bytes which precede the value in the decrypted plaintext.
+    ///
+    /// Yandex Browser prepends a SHA-256 digest (32 bytes) to each value before encrypting it,
+    /// to bind the value to its cookie.
+    pub const fn value_prefix_len(&self) -> usize {
+        /// Length of the digest prepended by Yandex Browser.
+        const YANDEX_DIGEST_LEN: usize = 32;
+
+        match self {
+            ChromeVariant::Yandex => YANDEX_DIGEST_LEN,
+            _ => 0,
+        }
+    }
+}
 
-    let nanos = chrome_time as i128 * 1000;
+/// Names of the cookies which are known to be bound to the device
+/// by Device Bound Session Credentials (DBSC).
+///
+/// Google binds the short-lived cookies of its sessions to a key held by the TPM,
+/// and refreshes them only when the browser proves it holds the key.
+const DEVICE_BOUND_COOKIES: &[&str] = &["__Secure-1PSIDTS", "__Secure-3PSIDTS"];
+
+/// Returns whether the cookie is known to be bound to the device which created it
+/// by Device Bound Session Credentials (DBSC).
+///
+/// Such cookies expire within minutes, and cannot be refreshed on another device,
+/// so the sessions which rely on them stop working soon after being exported.
+/// The detection is based on the names of the cookies bound by known sites,
+/// since the sessions registered by each site are not stored with the cookies.
+pub fn is_device_bound(cookie: &Cookie<'_>) -> bool {
+    DEVICE_BOUND_COOKIES.contains(&cookie.name())
+}
 
-    nanos - WINDOWS_UNIX_EPOCH_OFFSET_NANOS
+/// Returns whether the cookie was set by the page of an extension,
+/// whose domain is the identifier of the extension (32 letters from `a` to `p`).
+pub fn is_extension_cookie(cookie: &Cookie<'_>) -> bool {
+    cookie.domain().is_some_and(|domain| {
+        domain.len() == 32 && domain.bytes().all(|b| (b'a'..=b'p').contains(&b))
+    })
 }
 
 #[derive(Debug, Error)]
 pub enum DecryptChromeCookieError {
     #[error("Failed to decrypt cookie value: {source}")]
     CookieValueDecrypt {
-        raw_key: Box<[u8]>,
+        raw_key: Zeroizing<Box<[u8]>>,
         raw_value: Box<[u8]>,
         source: Box<dyn std::error::Error + Send + Sync>,
     },
@@ -131,6 +194,9 @@ pub enum DecryptChromeCookieError {
     #[error("Failed to decrypt value due to invalid length")]
     InvalidInputLength,
 
+    #[error("Cookie value is encrypted, but decryption is disabled")]
+    DecryptionDisabled,
+
     #[error("Key not found in the local state")]
     KeyNotFound,
 
@@ -146,6 +212,19 @@ pub enum DecryptChromeCookieError {
     },
 }
 
+impl DecryptChromeCookieError {
+    /// Returns whether the error only concerns the value being decrypted,
+    /// unlike the ones about the keys, which prevent decrypting any value.
+    pub fn is_value_error(&self) -> bool {
+        matches!(
+            self,
+            DecryptChromeCookieError::CookieValueDecrypt { .. }
+                | DecryptChromeCookieError::CookieValueUtf8Decode { .. }
+                | DecryptChromeCookieError::InvalidInputLength
+        )
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum ChromeManagerError {
     #[error("Failed to open cookies database: {source}")]
@@ -165,6 +244,18 @@ pub enum ChromeManagerError {
 
     #[error("Failed to create SQLite function: {source}")]
     SqliteFunctionCreate { source: rusqlite::Error },
+
+    #[error("Failed to write cookies database: {source}")]
+    DatabaseWrite {
+        path: String,
+        source: rusqlite::Error,
+    },
+
+    #[error("The key must be 16 bytes long to encrypt the values")]
+    InvalidKeyLength,
+
+    #[error("Encrypting the values is only supported on Unix")]
+    EncryptionUnsupported,
 }
 
 /// Chrome cookies manager.
@@ -173,7 +264,18 @@ pub struct ChromeManager<P: CookiePathProvider> {
     #[allow(unused)]
     variant: ChromeVariant,
     path_provider: P,
-    key_cache: OnceCell<Vec<u8>>,
+    /// Key of the manager, also kept in the cache of the process to be shared with the others.
+    key_cache: OnceCell<SharedKey>,
+    decrypt: bool,
+    as_of: Option<OffsetDateTime>,
+    /// Statistics of the last call to [`ChromeManager::get_cookies`].
+    stats: Cell<DecryptionStats>,
+    /// Whether the values are kept as stored in the database.
+    keep_raw_values: bool,
+    /// SameSite attribute given to the cookies set without one.
+    unspecified_same_site: Option<SameSite>,
+    /// Version of the last value decrypted with the key of another version.
+    decryption_fallback: Cell<Option<&'static str>>,
 }
 
 impl<P: CookiePathProvider> ChromeManager<P> {
@@ -198,7 +300,7 @@ impl<P: CookiePathProvider> ChromeManager<P> {
         if let Some(mut filter) = filter.take() {
             conn.create_scalar_function("host_filter", 1, FunctionFlags::default(), move |ctx| {
                 let host = &ctx.get::<String>(0)?;
-                Ok(filter(&host))
+                Ok(filter(host))
             })
             .map_err(|source| ChromeManagerError::SqliteFunctionCreate { source })?;
         }
@@ -208,8 +310,78 @@ impl<P: CookiePathProvider> ChromeManager<P> {
             variant,
             path_provider,
             key_cache: OnceCell::new(),
+            decrypt: true,
+            as_of: None,
+            stats: Cell::default(),
+            keep_raw_values: false,
+            unspecified_same_site: None,
+            decryption_fallback: Cell::new(None),
         })
     }
+
+    /// Disable the decryption of the cookie values, for databases whose values
+    /// are stored in plain text (e.g. pulled from Chrome on Android).
+    ///
+    /// The keyring of the system is then never accessed, and reading an encrypted value
+    /// fails with [`DecryptChromeCookieError::DecryptionDisabled`].
+    pub fn without_decryption(mut self) -> Self {
+        self.decrypt = false;
+        self
+    }
+
+    /// Keep the values of the cookies as stored in the database, before their decryption,
+    /// with the cookies returned by [`ChromeManager::get_cookies_with_report`].
+    pub fn keep_raw_values(mut self) -> Self {
+        self.keep_raw_values = true;
+        self
+    }
+
+    /// Give the SameSite attribute to the cookies set without one, instead of leaving it unset.
+    ///
+    /// This is lossy, as the cookies can no longer be told apart from the ones
+    /// which were set with this attribute (e.g. `Strict`, as they were read before).
+    pub fn coerce_unspecified_same_site(mut self, same_site: SameSite) -> Self {
+        self.unspecified_same_site = Some(same_site);
+        self
+    }
+
+    /// Only get the cookies which were created and last updated at or before the given time,
+    /// to approximate the state of the database at that time.
+    ///
+    /// Older databases lack the time of the last update (`last_update_utc`),
+    /// in which case only the creation time is used.
+    pub fn as_of(mut self, time: OffsetDateTime) -> Self {
+        self.as_of = Some(time);
+        self
+    }
+
+    /// Returns the identifier of the key of the given version in the cache of the process.
+    #[cfg(any(target_os = "linux", target_os = "macos", windows))]
+    fn key_id(&self, version: &'static str, source: Option<std::path::PathBuf>) -> KeyId {
+        KeyId {
+            variant: self.variant,
+            version,
+            source,
+        }
+    }
+
+    /// Returns the names of the columns of the `cookies` table.
+    fn table_columns(&self) -> Result<HashSet<String>, ChromeManagerError> {
+        let query = "SELECT name FROM pragma_table_info('cookies')";
+        let sqlite_error = |source| ChromeManagerError::SqliteQuery {
+            query: query.to_string(),
+            source,
+        };
+
+        let mut stmt = self.conn.prepare(query).map_err(sqlite_error)?;
+        let columns = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(sqlite_error)?
+            .collect::<Result<_, _>>()
+            .map_err(sqlite_error)?;
+
+        Ok(columns)
+    }
 }
 
 impl ChromeManager<PathProvider> {
@@ -230,22 +402,68 @@ impl ChromeManager<PathProvider> {
     }
 
     /// Get cookies from the database.
+    ///
+    /// Cookies with a top-level site key (CHIPS), which is missing from databases
+    /// created before Chrome 104, are marked as partitioned,
+    /// and the key is kept as their partition key by [`ChromeManager::get_cookies_with_report`].
     pub fn get_cookies(&self) -> Result<Vec<Cookie<'static>>, ChromeManagerError> {
-        let query = "SELECT name, value, encrypted_value, 
+        self.get_cookies_with_report().map(|set| {
+            set.cookies
+                .into_iter()
+                .map(|cookie| cookie.cookie)
+                .collect()
+        })
+    }
+
+    /// Get cookies from the database, with the warnings about the rows which could not be read,
+    /// the expiration times which were clamped and the values decrypted with another key.
+    ///
+    /// The cookies whose value cannot be decrypted are skipped with a warning
+    /// and counted as failed in the [`DecryptionStats`], while a key which cannot be retrieved
+    /// fails the whole extraction.
+    pub fn get_cookies_with_report(&self) -> Result<CookieSet, ChromeManagerError> {
+        self.stats.take();
+        let columns = self.table_columns()?;
+
+        let query = format!(
+            "SELECT name, value, encrypted_value, 
                         host_key, path, expires_utc, 
-                        is_secure, samesite, is_httponly
+                        is_secure, samesite, is_httponly, {top_frame_site_key},
+                        {last_access_utc}
         FROM cookies
-        WHERE host_filter(host_key)";
+        WHERE host_filter(host_key) {as_of}",
+            top_frame_site_key = if columns.contains("top_frame_site_key") {
+                "top_frame_site_key"
+            } else {
+                "''"
+            },
+            last_access_utc = if columns.contains("last_access_utc") {
+                "last_access_utc"
+            } else {
+                "0"
+            },
+            as_of = match self.as_of {
+                Some(time) => {
+                    let as_of = unix_to_chrome_timestamp(time);
+                    if columns.contains("last_update_utc") {
+                        format!("AND creation_utc <= {as_of} AND last_update_utc <= {as_of}")
+                    } else {
+                        format!("AND creation_utc <= {as_of}")
+                    }
+                }
+                None => String::new(),
+            },
+        );
 
         let mut stmt =
             self.conn
-                .prepare(query)
+                .prepare(&query)
                 .map_err(|source| ChromeManagerError::SqliteQuery {
-                    query: query.to_string(),
+                    query: query.clone(),
                     source,
                 })?;
 
-        let cookies = stmt
+        let rows = stmt
             .query_map([], |row| {
                 Ok(ChromeCookie {
                     name: row.get::<_, String>(0)?,
@@ -255,57 +473,129 @@ impl ChromeManager<PathProvider> {
                     path: row.get::<_, String>(4)?,
                     expires: row.get::<_, i64>(5)?,
                     secure: row.get::<_, bool>(6)?,
-                    same_site: row.get::<_, i64>(7)?,
+                    same_site: row.get::<_, i64>(7)?.into(),
                     http_only: row.get::<_, bool>(8)?,
+                    top_frame_site_key: row.get::<_, String>(9)?,
+                    last_access: row.get::<_, i64>(10)?,
                 })
             })
             .map_err(|source| ChromeManagerError::SqliteQuery {
-                query: query.to_string(),
+                query: query.clone(),
                 source,
-            })?
-            .filter_map(|cookie| cookie.ok())
-            .map(
-                |ChromeCookie {
-                     name,
-                     value,
-                     encrypted_value,
-                     host,
-                     path,
-                     expires,
-                     secure,
-                     same_site,
-                     http_only,
-                 }|
-                 -> Result<Cookie<'static>, ChromeManagerError> {
-                    let value = if encrypted_value.is_empty() {
-                        value
-                    } else {
-                        self.decrypt_cookie_value(encrypted_value)
-                            .map_err(|source| ChromeManagerError::CookieValueDecrypt { source })?
-                    };
-
-                    Ok(CookieBuilder::new(name, value)
-                        .domain(host)
-                        .path(path)
-                        .expires(Expiration::from(
-                            OffsetDateTime::from_unix_timestamp_nanos(
-                                chrome_to_unix_timestamp_nanos(expires),
-                            )
-                            .expect("Invalid date"),
+            })?;
+
+        let mut set = CookieSet::default();
+        for row in rows {
+            let ChromeCookie {
+                name,
+                value,
+                encrypted_value,
+                host,
+                path,
+                expires,
+                secure,
+                same_site,
+                http_only,
+                top_frame_site_key,
+                last_access,
+            } = match row {
+                Ok(row) => row,
+                Err(e) => {
+                    set.warnings.push(CookieWarning::SkippedRow {
+                        reason: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+
+            let mut stats = self.stats.get();
+            stats.record(&encrypted_value);
+            let raw_value = self.keep_raw_values.then(|| RawValue {
+                encrypted_value: encrypted_value.clone(),
+            });
+
+            let value = if encrypted_value.is_empty() {
+                Ok(value)
+            } else if !self.decrypt {
+                Err(DecryptChromeCookieError::DecryptionDisabled)
+            } else {
+                self.decrypt_cookie_value(encrypted_value)
+            };
+
+            stats.failed += usize::from(value.is_err());
+            self.stats.set(stats);
+            let value = match value {
+                Ok(value) => value,
+                // Only this value is corrupted or encrypted with another key, the others can be read.
+                Err(e) if e.is_value_error() => {
+                    set.warnings.push(CookieWarning::UndecryptableValue {
+                        host,
+                        name,
+                        reason: e.to_string(),
+                    });
+                    continue;
+                }
+                Err(source) => return Err(ChromeManagerError::CookieValueDecrypt { source }),
+            };
+            if let Some(version) = self.decryption_fallback.take() {
+                set.warnings.push(CookieWarning::DecryptionFallback {
+                    host: host.clone(),
+                    name: name.clone(),
+                    version,
+                });
+            }
+
+            let expires_nanos = chrome_to_unix_timestamp_nanos(expires);
+            let expires_time = OffsetDateTime::from_unix_timestamp_nanos(expires_nanos)
+                .unwrap_or_else(|_| {
+                    set.warnings.push(CookieWarning::ClampedExpiration {
+                        host: host.clone(),
+                        name: name.clone(),
+                        stored: expires,
+                    });
+                    // Microseconds stored in an `i64` always fit in an `i64` once in seconds.
+                    clamped_datetime(expires_nanos.div_euclid(1_000_000_000) as i64).0
+                });
+
+            // The domain cookies are stored with a leading dot, the host-only ones without.
+            let host_only = !host.starts_with('.');
+            let mut cookie: Cookie<'static> = CookieBuilder::new(name, value)
+                .domain(host)
+                .path(path)
+                .expires(Expiration::from(expires_time))
+                .secure(secure)
+                .http_only(http_only)
+                .partitioned(!top_frame_site_key.is_empty())
+                .into();
+            cookie.set_same_site(same_site.to_attribute(self.unspecified_same_site));
+
+            set.cookies.push(BrowserCookie {
+                cookie,
+                host_only,
+                partition_key: Some(top_frame_site_key).filter(|key| !key.is_empty()),
+                raw_value,
+                source: None,
+                // The time is 0 when the cookie was never accessed (e.g. written by another tool).
+                last_access: (last_access > 0)
+                    .then(|| {
+                        OffsetDateTime::from_unix_timestamp_nanos(chrome_to_unix_timestamp_nanos(
+                            last_access,
                         ))
-                        .secure(secure)
-                        .same_site(match same_site {
-                            0 => SameSite::None,
-                            1 => SameSite::Lax,
-                            _ => SameSite::Strict,
-                        })
-                        .http_only(http_only)
-                        .into())
-                },
-            )
-            .collect::<Result<Vec<_>, _>>()?;
+                    })
+                    .and_then(Result::ok),
+            });
+        }
+
+        Ok(set)
+    }
 
-        Ok(cookies)
+    /// Returns how many values were read by the last call to [`ChromeManager::get_cookies`],
+    /// by the way they were stored, and how many could not be decrypted.
+    ///
+    /// The reading stops at the first value which cannot be decrypted,
+    /// so the statistics only cover the values read until then in this case.
+    pub fn decryption_stats(&self) -> DecryptionStats {
+        self.stats.get()
     }
 
     /// Placeholder for the decryption function, which is platform-dependent.
@@ -320,6 +610,10 @@ impl ChromeManager<PathProvider> {
     }
 
     /// Decrypt a cookie value.
+    ///
+    /// Profiles migrated between desktops (e.g. from a session without keyring)
+    /// mix values encrypted with the v10 and the v11 keys,
+    /// so the other key is tried before failing.
     #[cfg(all(unix, not(target_os = "macos")))]
     fn decrypt_cookie_value<V: AsRef<[u8]>>(
         &self,
@@ -330,41 +624,69 @@ impl ChromeManager<PathProvider> {
 
         let encrypted_value = encrypted_value.as_ref();
 
-        let key = match encrypted_value.get(..HEADER_LEN) {
-            #[cfg(target_os = "linux")]
-            Some(b"v11") => Some(
-                self.key_cache
-                    .get_or_try_init(|| linux::get_v11_key(self.variant))
-                    .map_err(|source| DecryptChromeCookieError::GetKey {
-                        key_variant: "v11",
-                        source: source.into(),
-                    })?
-                    .as_slice(),
-            ),
-            #[cfg(not(target_os = "linux"))]
-            Some(b"v11") => unimplemented!("v11 key is not implemented for this platform"),
-            Some(b"v10") => Some(posix::CHROME_V10_KEY.as_slice()),
-            _ => None,
-        };
-
-        if let Some(key) = key {
+        let decrypt = |key: &[u8]| {
             encrypted_value::decrypt_value(
                 key,
                 encrypted_value
                     .get(HEADER_LEN..)
                     .expect("No data after the header"),
+                self.variant.value_prefix_len(),
             )
             .map_err(|source| DecryptChromeCookieError::CookieValueDecrypt {
-                raw_key: key.into(),
+                raw_key: Zeroizing::new(key.into()),
                 raw_value: encrypted_value.into(),
                 source: source.into(),
             })
-        } else {
+        };
+
+        match encrypted_value.get(..HEADER_LEN) {
+            Some(b"v10") => decrypt(posix::CHROME_V10_KEY.as_slice()).or_else(|error| {
+                let value = self
+                    .v11_key()
+                    .ok()
+                    .and_then(|key| decrypt(key.as_slice()).ok())
+                    .ok_or(error)?;
+                self.decryption_fallback.set(Some("v10"));
+                Ok(value)
+            }),
+            Some(b"v11") => self
+                .v11_key()
+                .and_then(|key| decrypt(key.as_slice()))
+                .or_else(|error| {
+                    let value = decrypt(posix::CHROME_V10_KEY.as_slice()).map_err(|_| error)?;
+                    self.decryption_fallback.set(Some("v11"));
+                    Ok(value)
+                }),
             // We assume that it's not encrypted
-            String::from_utf8(encrypted_value.into()).map_err(From::from)
+            _ => String::from_utf8(encrypted_value.into()).map_err(From::from),
         }
     }
 
+    /// Returns the v11 key, retrieved from the keyring.
+    #[cfg(target_os = "linux")]
+    fn v11_key(&self) -> Result<SharedKey, DecryptChromeCookieError> {
+        self.key_cache
+            .get_or_try_init(|| {
+                key_cache::get_or_try_init(self.key_id("v11", None), || {
+                    linux::get_v11_key(self.variant)
+                })
+            })
+            .cloned()
+            .map_err(|source| DecryptChromeCookieError::GetKey {
+                key_variant: "v11",
+                source: source.into(),
+            })
+    }
+
+    /// Returns the v11 key, which is only supported on Linux.
+    #[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+    fn v11_key(&self) -> Result<SharedKey, DecryptChromeCookieError> {
+        Err(DecryptChromeCookieError::GetKey {
+            key_variant: "v11",
+            source: "v11 key is not implemented for this platform".into(),
+        })
+    }
+
     /// Decrypt a cookie value.
     #[cfg(target_os = "macos")]
     fn decrypt_cookie_value<V: AsRef<[u8]>>(
@@ -379,7 +701,11 @@ impl ChromeManager<PathProvider> {
         let key = match encrypted_value.get(..HEADER_LEN) {
             Some(b"v10") => Some(
                 self.key_cache
-                    .get_or_try_init(|| mac::get_v10_key(self.variant))
+                    .get_or_try_init(|| {
+                        key_cache::get_or_try_init(self.key_id("v10", None), || {
+                            mac::get_v10_key(self.variant)
+                        })
+                    })
                     .map_err(|source| DecryptChromeCookieError::GetKey {
                         key_variant: "v10",
                         source: source.into(),
@@ -390,13 +716,14 @@ impl ChromeManager<PathProvider> {
 
         if let Some(key) = key {
             encrypted_value::decrypt_value(
-                key,
+                key.as_slice(),
                 encrypted_value
                     .get(HEADER_LEN..)
                     .ok_or_else(|| DecryptChromeCookieError::InvalidInputLength)?,
+                self.variant.value_prefix_len(),
             )
             .map_err(|source| DecryptChromeCookieError::CookieValueDecrypt {
-                raw_key: key.as_slice().into(),
+                raw_key: Zeroizing::new(key.as_slice().into()),
                 raw_value: encrypted_value.into(),
                 source: source.into(),
             })
@@ -439,32 +766,39 @@ impl ChromeManager<PathProvider> {
         const HEADER_LEN: usize = 3;
 
         let key = match encrypted_value_ref.get(..HEADER_LEN) {
-            Some(b"v10") => Some(self.key_cache.get_or_try_init(
-                || -> Result<Vec<u8>, DecryptChromeCookieError> {
-                    let local_state = self.get_local_state()?;
-
-                    let encrypted_key = windows::get_encrypted_key(&local_state)
-                        .ok_or_else(|| DecryptChromeCookieError::KeyNotFound)?;
-                    windows::decrypt_dpapi_encrypted_key(encrypted_key).map_err(|source| {
-                        DecryptChromeCookieError::GetKey {
-                            key_variant: "v10",
-                            source: source.into(),
-                        }
-                    })
-                },
-            )?),
+            Some(b"v10") => Some(self.key_cache.get_or_try_init(|| {
+                // The key is stored in the local state of each user data directory.
+                let id = self.key_id("v10", Some(self.path_provider.local_state()));
+
+                key_cache::get_or_try_init(
+                    id,
+                    || -> Result<Zeroizing<Vec<u8>>, DecryptChromeCookieError> {
+                        let local_state = self.get_local_state()?;
+
+                        let encrypted_key = windows::get_encrypted_key(&local_state)
+                            .ok_or_else(|| DecryptChromeCookieError::KeyNotFound)?;
+                        windows::decrypt_dpapi_encrypted_key(encrypted_key).map_err(|source| {
+                            DecryptChromeCookieError::GetKey {
+                                key_variant: "v10",
+                                source: source.into(),
+                            }
+                        })
+                    },
+                )
+            })?),
             _ => None,
         };
 
         if let Some(key) = key {
             encrypted_value::decrypt_value(
-                key,
+                key.as_slice(),
                 encrypted_value_ref
                     .get(HEADER_LEN..)
                     .ok_or_else(|| DecryptChromeCookieError::InvalidInputLength)?,
+                self.variant.value_prefix_len(),
             )
             .map_err(|source| DecryptChromeCookieError::CookieValueDecrypt {
-                raw_key: key.as_slice().into(),
+                raw_key: Zeroizing::new(key.as_slice().into()),
                 raw_value: encrypted_value_ref.into(),
                 source: source.into(),
             })
@@ -472,14 +806,15 @@ impl ChromeManager<PathProvider> {
             // Values seems to be always encrypted on Windows, at least with DPAPI
             // if not with AES-256-GCM
             let encrypted_value = encrypted_value.as_mut();
-            let raw_value = windows::decrypt_dpapi(encrypted_value).map_err(|source| {
+            let mut raw_value = windows::decrypt_dpapi(encrypted_value).map_err(|source| {
                 DecryptChromeCookieError::CookieValueDecrypt {
-                    raw_key: Vec::new().into(),
+                    raw_key: Zeroizing::new(Box::default()),
                     raw_value: encrypted_value.as_ref().into(),
                     source: source.into(),
                 }
             })?;
-            String::from_utf8(raw_value).map_err(From::from)
+            // The plaintext is moved into the value rather than copied, so no copy is left behind.
+            String::from_utf8(std::mem::take(&mut *raw_value)).map_err(From::from)
         }
     }
 }
@@ -10,6 +10,8 @@ pub(crate) mod posix;
 #[cfg(windows)]
 pub(crate) mod windows;
 
+use zeroize::Zeroizing;
+
 #[derive(Debug, thiserror::Error)]
 pub enum DecryptError {
     #[error("Failed to decrypt value due to invalid input/key length")]
@@ -28,10 +30,12 @@ pub enum DecryptError {
 
 /// Decrypts a cookie value encrypted by Chrome on Unix platforms (including macOS)
 /// (with AES-128-CBC).
+/// The first `prefix_len` bytes of the plaintext, which are not part of the value, are skipped.
 #[cfg(unix)]
 pub(crate) fn decrypt_value<K: AsRef<[u8]>, V: AsRef<[u8]>>(
     key: K,
     encrypted_value: V,
+    prefix_len: usize,
 ) -> Result<String, DecryptError> {
     use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, KeyIvInit};
 
@@ -43,21 +47,45 @@ pub(crate) fn decrypt_value<K: AsRef<[u8]>, V: AsRef<[u8]>>(
     // Chrome's initialization vector.
     const IV: [u8; IVBLOCK_SIZE_AES128] = [b' '; IVBLOCK_SIZE_AES128];
 
-    let mut output_buffer = vec![0u8; encrypted_value.as_ref().len()];
+    // The buffer holds the plaintext (and its padding), so it is wiped when dropped.
+    let mut output_buffer = Zeroizing::new(vec![0u8; encrypted_value.as_ref().len()]);
 
     let value = Aes128CbcDec::new(key.as_ref().into(), &IV.into())
-        .decrypt_padded_b2b_mut::<Pkcs7>(encrypted_value.as_ref(), output_buffer.as_mut())
+        .decrypt_padded_b2b_mut::<Pkcs7>(encrypted_value.as_ref(), output_buffer.as_mut_slice())
         .map_err(|_| DecryptError::InvalidInputLength)?;
 
+    let value = value
+        .get(prefix_len..)
+        .ok_or(DecryptError::InvalidInputLength)?;
+
     Ok(String::from_utf8(value.into())?)
 }
 
+/// Encrypts a cookie value as Chrome does on Unix platforms (including macOS)
+/// (with AES-128-CBC), without the version header.
+#[cfg(unix)]
+pub(crate) fn encrypt_value<K: AsRef<[u8]>>(key: K, value: &str) -> Result<Vec<u8>, DecryptError> {
+    use aes::cipher::{block_padding::Pkcs7, BlockEncryptMut, KeyIvInit};
+
+    type Aes128CbcEnc = cbc::Encryptor<aes::Aes128>;
+
+    // Chrome's initialization vector.
+    const IV: [u8; 16] = [b' '; 16];
+
+    let encryptor = Aes128CbcEnc::new_from_slices(key.as_ref(), &IV)
+        .map_err(|_| DecryptError::InvalidInputLength)?;
+
+    Ok(encryptor.encrypt_padded_vec_mut::<Pkcs7>(value.as_bytes()))
+}
+
 /// Decrypts a cookie value encrypted by Chrome on Windows
 /// (with AES-256-GCM).
+/// The first `prefix_len` bytes of the plaintext, which are not part of the value, are skipped.
 #[cfg(windows)]
 pub(crate) fn decrypt_value<K: AsRef<[u8]>, V: AsRef<[u8]>>(
     key: K,
     encrypted_value: V,
+    prefix_len: usize,
 ) -> Result<String, DecryptError> {
     use aes_gcm::{aead::Aead, Aes256Gcm, KeyInit};
 
@@ -76,9 +104,19 @@ pub(crate) fn decrypt_value<K: AsRef<[u8]>, V: AsRef<[u8]>>(
         .get(AEAD_NONCE_SIZE..)
         .ok_or_else(|| DecryptError::InvalidInputLength)?;
 
-    Ok(String::from_utf8(
+    let mut value = Zeroizing::new(
         cipher
             .decrypt(nonce.into(), ciphertext)
             .map_err(|_| DecryptError::InvalidInput)?,
-    )?)
+    );
+
+    if value.len() < prefix_len {
+        return Err(DecryptError::InvalidInputLength);
+    }
+    // The prefix is removed in place, so the plaintext is moved into the value rather than copied,
+    // and the bytes left past the end of the value are wiped.
+    value.drain(..prefix_len);
+    zeroize::Zeroize::zeroize(value.spare_capacity_mut());
+
+    Ok(String::from_utf8(std::mem::take(&mut *value))?)
 }
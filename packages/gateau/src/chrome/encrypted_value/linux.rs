@@ -15,6 +15,8 @@ use pbkdf2::{
     Algorithm, Params, Pbkdf2,
 };
 
+use zeroize::Zeroizing;
+
 use crate::chrome::ChromeVariant;
 
 /// Salt for symmetric key derivation.
@@ -39,7 +41,7 @@ type Result<T> = std::result::Result<T, Error>;
 
 /// Derives a key from a password using the same parameters as Chrome for
 /// Linux platform.
-fn derive_key_from_password<P: AsRef<[u8]>>(password: P) -> Result<Vec<u8>> {
+fn derive_key_from_password<P: AsRef<[u8]>>(password: P) -> Result<Zeroizing<Vec<u8>>> {
     let salt = SaltString::encode_b64(SYMMETRIC_SALT)?;
 
     let key = Pbkdf2.hash_password_customized(
@@ -53,16 +55,27 @@ fn derive_key_from_password<P: AsRef<[u8]>>(password: P) -> Result<Vec<u8>> {
         &salt,
     )?;
 
-    Ok(key.hash.unwrap().as_bytes().to_vec())
+    Ok(Zeroizing::new(key.hash.unwrap().as_bytes().to_vec()))
 }
 
 /// Gets the password used to encrypt cookies in Chrome on Linux using the
 /// the secret service API.
-fn get_v11_password(variant: ChromeVariant) -> Result<String> {
+fn get_v11_password(variant: ChromeVariant) -> Result<Zeroizing<String>> {
     let variant = match variant {
         ChromeVariant::Chromium => "chromium",
-        ChromeVariant::Chrome => "chrome",
-        ChromeVariant::Edge => "edge",
+        ChromeVariant::Chrome
+        | ChromeVariant::ChromeBeta
+        | ChromeVariant::ChromeDev
+        | ChromeVariant::ChromeCanary => "chrome",
+        ChromeVariant::Edge
+        | ChromeVariant::EdgeBeta
+        | ChromeVariant::EdgeDev
+        | ChromeVariant::EdgeCanary => "edge",
+        ChromeVariant::Arc => "arc",
+        ChromeVariant::Yandex => "yandex-browser",
+        ChromeVariant::Whale => "naver-whale",
+        ChromeVariant::Falkon => "qtwebengine",
+        ChromeVariant::Custom(custom) => custom.keyring_account.as_str(),
     };
     let credential = PlatformCredential::Linux(LinuxCredential {
         collection: String::from("default"),
@@ -71,12 +84,12 @@ fn get_v11_password(variant: ChromeVariant) -> Result<String> {
     });
     let entry = Entry::new_with_credential(&credential)?;
 
-    Ok(entry.get_password()?)
+    Ok(Zeroizing::new(entry.get_password()?))
 }
 
 /// Gets the key used to encrypt cookies in Chrome on Linux by deriving it from
 /// the password retrieved with the secret service API.
-pub(crate) fn get_v11_key(variant: ChromeVariant) -> Result<Vec<u8>> {
+pub(crate) fn get_v11_key(variant: ChromeVariant) -> Result<Zeroizing<Vec<u8>>> {
     let password = get_v11_password(variant)?;
-    derive_key_from_password(password)
+    derive_key_from_password(password.as_bytes())
 }
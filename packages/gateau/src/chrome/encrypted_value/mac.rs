@@ -11,6 +11,8 @@ use pbkdf2::{
     Algorithm, Params, Pbkdf2,
 };
 
+use zeroize::Zeroizing;
+
 use super::super::ChromeVariant;
 
 /// Error returned when failing to decrypt a value.
@@ -43,11 +45,26 @@ const DERIVED_KEY_LENGTH: usize = 128;
 
 /// Gets the password used to encrypt cookies in Chrome on macOS using the
 /// the keychain API.
-pub(crate) fn get_v10_password(variant: ChromeVariant) -> Result<String> {
+pub(crate) fn get_v10_password(variant: ChromeVariant) -> Result<Zeroizing<String>> {
     let (service, account) = match variant {
         ChromeVariant::Chromium => ("Chromium Safe Storage", "Chromium"),
-        ChromeVariant::Chrome => ("Chrome Safe Storage", "Chrome"),
+        // All the channels of Chrome share the same keychain entry.
+        ChromeVariant::Chrome
+        | ChromeVariant::ChromeBeta
+        | ChromeVariant::ChromeDev
+        | ChromeVariant::ChromeCanary => ("Chrome Safe Storage", "Chrome"),
         ChromeVariant::Edge => ("Edge Safe Storage", "Edge"),
+        ChromeVariant::EdgeBeta => ("Edge Beta Safe Storage", "Edge Beta"),
+        ChromeVariant::EdgeDev => ("Edge Dev Safe Storage", "Edge Dev"),
+        ChromeVariant::EdgeCanary => ("Edge Canary Safe Storage", "Edge Canary"),
+        ChromeVariant::Arc => ("Arc Safe Storage", "Arc"),
+        ChromeVariant::Yandex => ("Yandex Safe Storage", "Yandex"),
+        ChromeVariant::Whale => ("Whale Safe Storage", "Whale"),
+        ChromeVariant::Falkon => ("QtWebEngineCore Safe Storage", "QtWebEngineCore"),
+        ChromeVariant::Custom(custom) => (
+            custom.keyring_service.as_str(),
+            custom.keyring_account.as_str(),
+        ),
     };
 
     let credential = PlatformCredential::Mac(MacCredential {
@@ -58,12 +75,12 @@ pub(crate) fn get_v10_password(variant: ChromeVariant) -> Result<String> {
 
     let entry = Entry::new_with_credential(&credential)?;
 
-    Ok(entry.get_password()?)
+    Ok(Zeroizing::new(entry.get_password()?))
 }
 
 /// Derives a key from a password using the same parameters as Chrome for
 /// macOS platform.
-fn derive_key_from_password<P: AsRef<[u8]>>(password: P) -> Result<Vec<u8>> {
+fn derive_key_from_password<P: AsRef<[u8]>>(password: P) -> Result<Zeroizing<Vec<u8>>> {
     let salt = SaltString::encode_b64(SYMMETRIC_SALT)?;
 
     let key = Pbkdf2.hash_password_customized(
@@ -77,11 +94,11 @@ fn derive_key_from_password<P: AsRef<[u8]>>(password: P) -> Result<Vec<u8>> {
         &salt,
     )?;
 
-    Ok(key.hash.unwrap().as_bytes().to_vec())
+    Ok(Zeroizing::new(key.hash.unwrap().as_bytes().to_vec()))
 }
 
 /// Gets the key used to encrypt cookies on macOS.
-pub(crate) fn get_v10_key(variant: ChromeVariant) -> Result<Vec<u8>> {
+pub(crate) fn get_v10_key(variant: ChromeVariant) -> Result<Zeroizing<Vec<u8>>> {
     let password = get_v10_password(variant)?;
-    derive_key_from_password(password)
+    derive_key_from_password(password.as_bytes())
 }
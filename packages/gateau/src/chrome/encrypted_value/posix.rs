@@ -26,8 +26,26 @@ mod test {
         ];
 
         assert_eq!(
-            decrypt_value(CHROME_V10_KEY, &ENCRYPTED_EXAMPLE[3..]).unwrap(),
+            decrypt_value(CHROME_V10_KEY, &ENCRYPTED_EXAMPLE[3..], 0).unwrap(),
             "PENDING+400"
         );
     }
+
+    #[test]
+    fn test_decrypt_value_with_prefix() {
+        use aes::cipher::{block_padding::Pkcs7, BlockEncryptMut, KeyIvInit};
+
+        type Aes128CbcEnc = cbc::Encryptor<aes::Aes128>;
+
+        // Values framed by Yandex Browser start with a 32 bytes digest.
+        let plaintext = [[0xab; 32].as_slice(), b"PENDING+400"].concat();
+        let encrypted = Aes128CbcEnc::new(&CHROME_V10_KEY.into(), &[b' '; 16].into())
+            .encrypt_padded_vec_mut::<Pkcs7>(&plaintext);
+
+        assert_eq!(
+            decrypt_value(CHROME_V10_KEY, &encrypted, 32).unwrap(),
+            "PENDING+400"
+        );
+        assert!(decrypt_value(CHROME_V10_KEY, &encrypted[..16], 32).is_err());
+    }
 }
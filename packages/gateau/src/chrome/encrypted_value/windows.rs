@@ -9,14 +9,15 @@ use windows::Win32::{
     Foundation::{LocalFree, HLOCAL},
     Security::Cryptography::{CryptUnprotectData, CRYPT_INTEGER_BLOB},
 };
+use zeroize::Zeroizing;
 
 use super::super::LocalState;
 
 #[derive(Debug, thiserror::Error)]
 pub enum DecryptDpapiValueError {
-    #[error("Failed to decrypt value, buffer is too long")]
+    #[error("Failed to decrypt value, buffer is too long ({len} bytes)")]
     BufferTooLong {
-        buffer: Box<[u8]>,
+        len: usize,
         source: std::num::TryFromIntError,
     },
 
@@ -33,11 +34,13 @@ pub enum DecryptDpapiValueError {
 /// For the function call to be safe, `encrypted_value` must be a valid buffer for the entire duration of the call,
 /// which is normally guaranteed by the borrow checker.
 #[allow(unsafe_code)]
-pub(crate) fn decrypt_dpapi(encrypted_value: &mut [u8]) -> Result<Vec<u8>, DecryptDpapiValueError> {
+pub(crate) fn decrypt_dpapi(
+    encrypted_value: &mut [u8],
+) -> Result<Zeroizing<Vec<u8>>, DecryptDpapiValueError> {
     let data_in = CRYPT_INTEGER_BLOB {
         cbData: u32::try_from(encrypted_value.len()).map_err(|source| {
             DecryptDpapiValueError::BufferTooLong {
-                buffer: encrypted_value.to_vec().into_boxed_slice(),
+                len: encrypted_value.len(),
                 source,
             }
         })?,
@@ -61,7 +64,11 @@ pub(crate) fn decrypt_dpapi(encrypted_value: &mut [u8]) -> Result<Vec<u8>, Decry
 
         assert!(!data_out.pbData.is_null(), "CryptUnprotectData failed");
 
-        let data = std::slice::from_raw_parts(data_out.pbData, data_out.cbData as usize).to_vec();
+        let data = Zeroizing::new(
+            std::slice::from_raw_parts(data_out.pbData, data_out.cbData as usize).to_vec(),
+        );
+        // Wipe the plaintext before giving the buffer back to the system.
+        std::ptr::write_bytes(data_out.pbData, 0, data_out.cbData as usize);
         LocalFree(HLOCAL(data_out.pbData.cast()));
 
         Ok(data)
@@ -111,7 +118,7 @@ pub enum DecryptDpapiKeyError {
 /// Decrypts the key encrypted with DPAPI and encoded in Base64.
 pub(crate) fn decrypt_dpapi_encrypted_key<S: AsRef<str>>(
     encrypted_key: S,
-) -> Result<Vec<u8>, DecryptDpapiKeyError> {
+) -> Result<Zeroizing<Vec<u8>>, DecryptDpapiKeyError> {
     let mut encrypted_key = Base64::decode_vec(encrypted_key.as_ref()).map_err(|source| {
         DecryptDpapiKeyError::InvalidKeyFormat {
             key: encrypted_key.as_ref().to_string(),
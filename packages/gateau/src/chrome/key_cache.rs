@@ -0,0 +1,78 @@
+//! Process-wide cache of the keys used to decrypt the cookie values.
+//!
+//! The keys are shared by all the managers of the process, so the keyring (or the keychain)
+//! is only consulted once per browser, even when several profiles are read,
+//! possibly from several threads.
+
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use once_cell::sync::{Lazy, OnceCell};
+use zeroize::Zeroizing;
+
+use super::ChromeVariant;
+
+/// Decryption key, shared between the managers.
+pub(crate) type SharedKey = Arc<Zeroizing<Vec<u8>>>;
+
+/// Identifier of a decryption key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct KeyId {
+    /// Browser which encrypted the values.
+    pub variant: ChromeVariant,
+    /// Version of the key, from the header of the values (e.g. `v10`).
+    pub version: &'static str,
+    /// File the key is read from, if it depends on the user data directory
+    /// (e.g. the `Local State` file on Windows).
+    pub source: Option<PathBuf>,
+}
+
+/// Keys retrieved so far, each one initialized at most once at a time.
+static KEYS: Lazy<Mutex<HashMap<KeyId, Arc<OnceCell<SharedKey>>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Returns the key with the given identifier, retrieving it with `init` if it is not cached.
+///
+/// If another thread is already retrieving the same key, this waits for it instead.
+/// The keys which cannot be retrieved are not cached, so they are retried the next time.
+pub(crate) fn get_or_try_init<E, F>(id: KeyId, init: F) -> Result<SharedKey, E>
+where
+    F: FnOnce() -> Result<Zeroizing<Vec<u8>>, E>,
+{
+    // The lock of the map is released before retrieving the key,
+    // so the other keys can be retrieved meanwhile.
+    let cell = Arc::clone(KEYS.lock().unwrap().entry(id).or_default());
+
+    cell.get_or_try_init(|| init().map(Arc::new)).cloned()
+}
+
+/// Forget the keys retrieved so far, which are zeroed once they are no longer used.
+pub fn clear_key_cache() {
+    KEYS.lock().unwrap().clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_or_try_init() {
+        let id = KeyId {
+            variant: ChromeVariant::Chromium,
+            version: "test",
+            source: None,
+        };
+
+        let failed = get_or_try_init(id.clone(), || Err("keyring locked"));
+        assert_eq!(failed, Err("keyring locked"));
+
+        let key = get_or_try_init::<(), _>(id.clone(), || Ok(Zeroizing::new(vec![1, 2, 3])));
+        assert_eq!(key.unwrap().as_slice(), [1, 2, 3]);
+
+        let cached = get_or_try_init::<(), _>(id, || panic!("The key should be cached"));
+        assert_eq!(cached.unwrap().as_slice(), [1, 2, 3]);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::CookiePathProvider;
+use crate::{confinement, CookiePathProvider};
 
 use super::ChromeVariant;
 
@@ -8,10 +8,13 @@ use std::{
 };
 
 /// Path provider for Chrome.
+#[derive(Clone)]
 pub struct PathProvider {
     _base_dir: PathBuf,
     _profile: OsString,
     profile_dir: PathBuf,
+    /// Whether the `Extension Cookies` database is read instead of the `Cookies` one.
+    extension_cookies: bool,
 }
 
 impl PathProvider {
@@ -32,6 +35,7 @@ impl PathProvider {
             },
             _profile: profile.to_owned(),
             _base_dir: base_dir,
+            extension_cookies: false,
         }
     }
 
@@ -39,38 +43,256 @@ impl PathProvider {
         Self::new::<_, &OsStr>(root_dir, None)
     }
 
+    /// Create a new path provider for the given profile of a QtWebEngine browser (e.g. Falkon),
+    /// which keeps its profiles directly in the root dir, without `User Data` folder on Windows.
+    fn from_qt_webengine_root<R: AsRef<Path>, P: AsRef<OsStr>>(root_dir: R, profile: P) -> Self {
+        let base_dir = root_dir.as_ref().to_owned();
+
+        Self {
+            profile_dir: base_dir.join(profile.as_ref()),
+            _profile: profile.as_ref().to_owned(),
+            _base_dir: base_dir,
+            extension_cookies: false,
+        }
+    }
+
+    /// Create a new path provider for the data directory of an Electron or WebView2 application
+    /// (e.g. `~/.config/Slack`), which is not laid out like the one of a browser.
+    ///
+    /// Electron applications keep their cookies next to the `Local State` file,
+    /// without profiles (nor `User Data` folder on Windows),
+    /// while WebView2 applications keep a profile in an `EBWebView` folder.
+    pub fn from_app_dir<P: AsRef<Path>>(app_dir: P) -> Self {
+        let app_dir = app_dir.as_ref();
+        let webview_dir = app_dir.join("EBWebView");
+        let base_dir = if webview_dir.is_dir() {
+            webview_dir
+        } else {
+            app_dir.to_owned()
+        };
+
+        let default_profile_dir = base_dir.join("Default");
+        let (profile, profile_dir) = if default_profile_dir.is_dir() {
+            (OsString::from("Default"), default_profile_dir)
+        } else {
+            (OsString::new(), base_dir.clone())
+        };
+
+        Self {
+            _base_dir: base_dir,
+            _profile: profile,
+            profile_dir,
+            extension_cookies: false,
+        }
+    }
+
+    /// Create a new path provider for the root directory of the given variant,
+    /// as [`PathProvider::from_app_dir`] if the variant is an application,
+    /// or as [`PathProvider::from_root`] otherwise.
+    pub fn from_variant_root<P: AsRef<Path>>(variant: ChromeVariant, root_dir: P) -> Self {
+        match variant {
+            ChromeVariant::Custom(custom) if custom.app => Self::from_app_dir(root_dir),
+            ChromeVariant::Falkon => Self::from_qt_webengine_root(root_dir, "Default"),
+            _ => Self::from_root(root_dir),
+        }
+    }
+
     /// Returns a path provider for the default profile of the given browser variant.
+    ///
+    /// If the variant can be installed in several locations (e.g. as a snap package on Linux),
+    /// the location which contains a cookies database is preferred.
     pub fn default_profile(variant: ChromeVariant) -> Self {
+        let mut candidates = PathProvider::variant_root_dirs(variant)
+            .into_iter()
+            .map(|root_dir| match variant {
+                ChromeVariant::Custom(custom) if custom.app => Self::from_app_dir(root_dir),
+                ChromeVariant::Falkon => {
+                    let profile = PathProvider::variant_default_profile(variant, &root_dir);
+                    Self::from_qt_webengine_root(root_dir, profile)
+                }
+                _ => {
+                    let profile = PathProvider::variant_default_profile(variant, &root_dir);
+                    Self::new(root_dir, Some(profile))
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let index = candidates
+            .iter()
+            .position(|candidate| candidate.cookies_database().exists())
+            .unwrap_or(0);
+
+        candidates.swap_remove(index)
+    }
+
+    /// Returns the root directories where the variant can store its data,
+    /// in order of preference.
+    fn variant_root_dirs(variant: ChromeVariant) -> Vec<PathBuf> {
+        let base_folder = PathProvider::variant_base_folder(variant);
         let root_dir = if cfg!(windows) {
             dirs_next::data_local_dir()
         } else {
-            dirs_next::config_dir()
+            confinement::config_dir()
         }
         .unwrap()
-        .join(PathProvider::variant_base_folder(variant));
+        .join(base_folder);
+
+        let snap_dir = PathProvider::variant_snap_folder(variant)
+            .filter(|_| cfg!(target_os = "linux"))
+            .and_then(|folder| Some(confinement::home_dir()?.join(folder)));
+
+        let packaged_dirs = if cfg!(windows) {
+            PathProvider::packaged_root_dirs(base_folder)
+        } else {
+            Vec::new()
+        };
+
+        std::iter::once(root_dir)
+            .chain(packaged_dirs)
+            .chain(snap_dir)
+            .collect()
+    }
+
+    /// Returns the root directories of the MSIX packages (e.g. installed from the Microsoft Store)
+    /// which store data in the base folder.
+    ///
+    /// The writes of packaged applications to the local application data directory
+    /// are redirected to `Packages/<package>/LocalCache/Local`,
+    /// and the name of the package is not known in advance, so every package is probed.
+    fn packaged_root_dirs(base_folder: &str) -> Vec<PathBuf> {
+        // The base folder of some variants is already inside their package.
+        if base_folder.starts_with("Packages/") {
+            return Vec::new();
+        }
+
+        let Some(entries) = dirs_next::data_local_dir()
+            .and_then(|dir| std::fs::read_dir(dir.join("Packages")).ok())
+        else {
+            return Vec::new();
+        };
+
+        entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path().join("LocalCache/Local").join(base_folder))
+            .filter(|root_dir| root_dir.is_dir())
+            .collect()
+    }
 
+    /// Returns the name of the default profile of the variant.
+    ///
+    /// Falkon names its profiles in lowercase and declares the one it starts with
+    /// in the `profiles.ini` file of its profiles directory.
+    fn variant_default_profile(variant: ChromeVariant, root_dir: &Path) -> String {
         const DEFAULT_PROFILE: &str = "Default";
+        const FALKON_DEFAULT_PROFILE: &str = "default";
+
+        match variant {
+            ChromeVariant::Falkon => tini::Ini::from_file(&root_dir.join("profiles.ini"))
+                .ok()
+                .and_then(|profiles| profiles.get("Profiles", "startProfile"))
+                .unwrap_or_else(|| FALKON_DEFAULT_PROFILE.to_string()),
+            _ => DEFAULT_PROFILE.to_string(),
+        }
+    }
 
-        Self::new(root_dir, Some(DEFAULT_PROFILE))
+    /// Returns the subpath of the home directory where the variant stores its data
+    /// when it is installed as a snap package on Linux (e.g. Chromium on Ubuntu).
+    const fn variant_snap_folder(variant: ChromeVariant) -> Option<&'static str> {
+        match variant {
+            ChromeVariant::Chromium => Some("snap/chromium/common/chromium"),
+            _ => None,
+        }
     }
 
     /// Returns the subpath of the base directory which changes depending on the variant.
     const fn variant_base_folder(variant: ChromeVariant) -> &'static str {
-        if cfg!(any(windows, target_os = "macos")) {
+        if cfg!(windows) {
+            match variant {
+                ChromeVariant::Chromium => "Chromium",
+                ChromeVariant::Chrome => "Google/Chrome",
+                ChromeVariant::ChromeBeta => "Google/Chrome Beta",
+                ChromeVariant::ChromeDev => "Google/Chrome Dev",
+                ChromeVariant::ChromeCanary => "Google/Chrome SxS",
+                ChromeVariant::Edge => "Microsoft/Edge",
+                ChromeVariant::EdgeBeta => "Microsoft/Edge Beta",
+                ChromeVariant::EdgeDev => "Microsoft/Edge Dev",
+                ChromeVariant::EdgeCanary => "Microsoft/Edge SxS",
+                // Arc is only distributed as a MSIX package on Windows.
+                ChromeVariant::Arc => {
+                    "Packages/TheBrowserCompany.Arc_ttt1ap7aakyb4/LocalCache/Local/Arc"
+                }
+                ChromeVariant::Yandex => "Yandex/YandexBrowser",
+                ChromeVariant::Whale => "Naver/Naver Whale",
+                ChromeVariant::Falkon => "falkon/profiles",
+                ChromeVariant::Custom(custom) => custom.data_dir.as_str(),
+            }
+        } else if cfg!(target_os = "macos") {
             match variant {
                 ChromeVariant::Chromium => "Chromium",
                 ChromeVariant::Chrome => "Google/Chrome",
+                ChromeVariant::ChromeBeta => "Google/Chrome Beta",
+                ChromeVariant::ChromeDev => "Google/Chrome Dev",
+                ChromeVariant::ChromeCanary => "Google/Chrome Canary",
                 ChromeVariant::Edge => "Microsoft/Edge",
+                ChromeVariant::EdgeBeta => "Microsoft/Edge Beta",
+                ChromeVariant::EdgeDev => "Microsoft/Edge Dev",
+                ChromeVariant::EdgeCanary => "Microsoft/Edge Canary",
+                // Arc keeps a Windows-like "User Data" tree on macOS.
+                ChromeVariant::Arc => "Arc/User Data",
+                ChromeVariant::Yandex => "Yandex/YandexBrowser",
+                ChromeVariant::Whale => "Naver/Whale",
+                ChromeVariant::Falkon => "falkon/profiles",
+                ChromeVariant::Custom(custom) => custom.data_dir.as_str(),
             }
         } else {
             match variant {
                 ChromeVariant::Chromium => "chromium",
                 ChromeVariant::Chrome => "google-chrome",
+                ChromeVariant::ChromeBeta => "google-chrome-beta",
+                ChromeVariant::ChromeDev => "google-chrome-unstable",
+                ChromeVariant::ChromeCanary => "google-chrome-canary",
                 ChromeVariant::Edge => "microsoft-edge",
+                ChromeVariant::EdgeBeta => "microsoft-edge-beta",
+                ChromeVariant::EdgeDev => "microsoft-edge-dev",
+                ChromeVariant::EdgeCanary => "microsoft-edge-canary",
+                ChromeVariant::Arc => "arc",
+                ChromeVariant::Yandex => "yandex-browser",
+                ChromeVariant::Whale => "naver-whale",
+                ChromeVariant::Falkon => "falkon/profiles",
+                ChromeVariant::Custom(custom) => custom.data_dir.as_str(),
             }
         }
     }
 
+    /// Use the `Extension Cookies` database of the profile, which holds the cookies set
+    /// by the pages of the extensions, instead of the `Cookies` one.
+    ///
+    /// Only some Chromium builds keep this database, see [`PathProvider::has_extension_cookies`].
+    pub fn extension_cookies(mut self) -> Self {
+        self.extension_cookies = true;
+        self
+    }
+
+    /// Returns whether the profile has an `Extension Cookies` database.
+    pub fn has_extension_cookies(&self) -> bool {
+        self.profile_dir.join("Extension Cookies").exists()
+    }
+
+    /// Returns the paths where the cookies database may be stored, from the newest layout
+    /// to the oldest one, without checking which one exists.
+    pub fn cookies_database_candidates(&self) -> Vec<PathBuf> {
+        if self.extension_cookies {
+            return vec![self.profile_dir.join("Extension Cookies")];
+        }
+
+        // The cookies database is stored in a subfolder called "Network" in newer versions of
+        // Chromium (on Windows it seems), and directly in the profile in older ones.
+        vec![
+            self.profile_dir.join("Network").join("Cookies"),
+            self.profile_dir.join("Cookies"),
+        ]
+    }
+
     /// Returns the path to the local state file.
     #[cfg(windows)]
     pub(crate) fn local_state(&self) -> PathBuf {
@@ -80,14 +302,79 @@ impl PathProvider {
 
 impl CookiePathProvider for PathProvider {
     fn cookies_database(&self) -> PathBuf {
-        // The cookies database is stored in a subfolder called "Network" in newer versions of
-        // Chromium (on Windows it seems). If this folder does not exist, we fall back to the old location.
-        let new_path = self.profile_dir.join("Network").join("Cookies");
+        // If the newest location does not exist, we fall back to the old one.
+        let mut candidates = self.cookies_database_candidates();
+        let last = candidates.pop().expect("at least one candidate");
+        candidates
+            .into_iter()
+            .find(|path| path.exists())
+            .unwrap_or(last)
+    }
 
-        if new_path.exists() {
-            new_path
+    fn lock_files(&self) -> Vec<PathBuf> {
+        if cfg!(windows) {
+            vec![self._base_dir.join("User Data").join("lockfile")]
         } else {
-            self.profile_dir.join("Cookies")
+            vec![self._base_dir.join("SingletonLock")]
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_app_dir() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // Electron applications keep their cookies at the root.
+        std::fs::create_dir_all(dir.path().join("Network")).unwrap();
+        std::fs::write(dir.path().join("Network").join("Cookies"), "").unwrap();
+        assert_eq!(
+            PathProvider::from_app_dir(dir.path()).cookies_database(),
+            dir.path().join("Network").join("Cookies")
+        );
+
+        // WebView2 applications keep a default profile in their `EBWebView` folder.
+        let webview_dir = dir.path().join("EBWebView");
+        std::fs::create_dir_all(webview_dir.join("Default")).unwrap();
+        let path_provider = PathProvider::from_app_dir(dir.path());
+        assert_eq!(
+            path_provider.cookies_database(),
+            webview_dir.join("Default").join("Cookies")
+        );
+    }
+
+    #[test]
+    fn test_falkon_profile() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("profiles.ini"),
+            "[Profiles]\nstartProfile=work\n",
+        )
+        .unwrap();
+        let profile = PathProvider::variant_default_profile(ChromeVariant::Falkon, dir.path());
+
+        // The profiles are not in a `User Data` folder, even on Windows.
+        assert_eq!(
+            PathProvider::from_qt_webengine_root(dir.path(), profile).cookies_database(),
+            dir.path().join("work").join("Cookies")
+        );
+        assert_eq!(
+            PathProvider::from_variant_root(ChromeVariant::Falkon, dir.path()).cookies_database(),
+            dir.path().join("Default").join("Cookies")
+        );
+    }
+
+    #[test]
+    fn test_extension_cookies() {
+        let path_provider = PathProvider::from_root("chromium");
+        assert_eq!(
+            path_provider.extension_cookies().cookies_database(),
+            PathProvider::from_root("chromium")
+                .profile_dir
+                .join("Extension Cookies")
+        );
+    }
+}
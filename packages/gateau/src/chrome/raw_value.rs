@@ -0,0 +1,55 @@
+//! Values of the cookies as stored in the database, before their decryption.
+
+use serde::Serialize;
+
+/// Value of a cookie as stored in the `encrypted_value` column, kept as evidence
+/// alongside the decrypted value.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RawValue {
+    /// Content of the `encrypted_value` column, including its version header.
+    pub encrypted_value: Vec<u8>,
+}
+
+impl RawValue {
+    /// Returns the way the value is encrypted (`v10`, `v11`, `v20` or `dpapi`),
+    /// or `None` if it is stored in plain text.
+    pub fn version(&self) -> Option<&'static str> {
+        match self.encrypted_value.get(..3) {
+            _ if self.encrypted_value.is_empty() => None,
+            Some(b"v10") => Some("v10"),
+            Some(b"v11") => Some("v11"),
+            Some(b"v20") => Some("v20"),
+            // Values without header are encrypted with DPAPI on Windows, and not encrypted elsewhere
+            _ if cfg!(windows) => Some("dpapi"),
+            _ => None,
+        }
+    }
+
+    /// Returns the content of the `encrypted_value` column in hexadecimal.
+    pub fn encrypted_value_hex(&self) -> String {
+        self.encrypted_value
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version() {
+        let raw_value = |encrypted_value: &[u8]| RawValue {
+            encrypted_value: encrypted_value.into(),
+        };
+
+        assert_eq!(raw_value(b"").version(), None);
+        assert_eq!(raw_value(b"v10\x01\xff").version(), Some("v10"));
+        assert_eq!(
+            raw_value(b"v10\x01\xff").encrypted_value_hex(),
+            "76313001ff"
+        );
+        assert_eq!(raw_value(b"v20abc").version(), Some("v20"));
+    }
+}
@@ -0,0 +1,82 @@
+//! Registry of the Chromium-based browsers declared at runtime.
+
+use std::sync::Mutex;
+
+use super::ChromeVariant;
+
+/// Chromium-based browser which is not supported out of the box
+/// (e.g. Ungoogled Chromium or a corporate build), declared with [`register_variant`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct CustomVariant {
+    /// Name used to select the browser (e.g. `ungoogled-chromium`).
+    pub name: String,
+    /// Path of the user data directory, relative to the configuration directory
+    /// on Linux and macOS, or to the local application data directory on Windows
+    /// (without the `User Data` folder).
+    pub data_dir: String,
+    /// Service of the keychain entry which holds the encryption password on macOS
+    /// (e.g. `Chromium Safe Storage`).
+    pub keyring_service: String,
+    /// Account of the keychain entry on macOS (e.g. `Chromium`),
+    /// or `application` attribute of the secret service entry on Linux (e.g. `chromium`).
+    pub keyring_account: String,
+    /// Name of the executable, used to open sessions.
+    pub executable: String,
+    /// Whether the data directory is the one of an Electron or WebView2 application,
+    /// laid out as described in [`super::PathProvider::from_app_dir`].
+    #[serde(default)]
+    pub app: bool,
+}
+
+/// Variants registered so far, which live until the end of the program.
+static CUSTOM_VARIANTS: Mutex<Vec<&'static CustomVariant>> = Mutex::new(Vec::new());
+
+/// Register a custom Chromium-based browser and return its variant.
+///
+/// Once registered, the browser can also be parsed from its name as a [`crate::Browser`].
+/// If several variants share the same name, the last one registered is used.
+pub fn register_variant(variant: CustomVariant) -> ChromeVariant {
+    let variant: &'static CustomVariant = Box::leak(Box::new(variant));
+    CUSTOM_VARIANTS.lock().unwrap().push(variant);
+
+    ChromeVariant::Custom(variant)
+}
+
+/// Returns the registered variant with the given name, if any.
+pub fn custom_variant(name: &str) -> Option<ChromeVariant> {
+    CUSTOM_VARIANTS
+        .lock()
+        .unwrap()
+        .iter()
+        .rev()
+        .find(|variant| variant.name == name)
+        .map(|variant| ChromeVariant::Custom(variant))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variant(name: &str, data_dir: &str) -> CustomVariant {
+        CustomVariant {
+            name: name.to_string(),
+            data_dir: data_dir.to_string(),
+            keyring_service: "Chromium Safe Storage".to_string(),
+            keyring_account: "chromium".to_string(),
+            executable: name.to_string(),
+            app: false,
+        }
+    }
+
+    #[test]
+    fn test_register_variant() {
+        assert_eq!(custom_variant("test-slimjet"), None);
+
+        let registered = register_variant(variant("test-slimjet", "slimjet"));
+        assert_eq!(custom_variant("test-slimjet"), Some(registered));
+
+        let replaced = register_variant(variant("test-slimjet", "slimjet-beta"));
+        assert_eq!(custom_variant("test-slimjet"), Some(replaced));
+    }
+}
@@ -0,0 +1,88 @@
+//! SameSite attribute as stored by Chromium, in the `samesite` column.
+
+use cookie::SameSite;
+
+/// SameSite attribute of a cookie in the database of Chromium
+/// (`CookieSameSite` in its source code).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ChromeSameSite {
+    /// The cookie was set without SameSite attribute (`-1`), and is treated as `Lax` by Chromium.
+    Unspecified,
+    NoRestriction,
+    Lax,
+    Strict,
+}
+
+impl From<i64> for ChromeSameSite {
+    /// Unknown values, which could be added by newer versions, are read as unspecified.
+    fn from(value: i64) -> Self {
+        match value {
+            0 => Self::NoRestriction,
+            1 => Self::Lax,
+            2 => Self::Strict,
+            _ => Self::Unspecified,
+        }
+    }
+}
+
+impl From<ChromeSameSite> for i64 {
+    fn from(same_site: ChromeSameSite) -> Self {
+        match same_site {
+            ChromeSameSite::Unspecified => -1,
+            ChromeSameSite::NoRestriction => 0,
+            ChromeSameSite::Lax => 1,
+            ChromeSameSite::Strict => 2,
+        }
+    }
+}
+
+impl From<Option<SameSite>> for ChromeSameSite {
+    fn from(same_site: Option<SameSite>) -> Self {
+        match same_site {
+            Some(SameSite::None) => Self::NoRestriction,
+            Some(SameSite::Lax) => Self::Lax,
+            Some(SameSite::Strict) => Self::Strict,
+            None => Self::Unspecified,
+        }
+    }
+}
+
+impl ChromeSameSite {
+    /// Returns the SameSite attribute of the cookie, `None` if it is unspecified
+    /// unless a value is given to coerce it into.
+    pub fn to_attribute(self, unspecified: Option<SameSite>) -> Option<SameSite> {
+        match self {
+            Self::Unspecified => unspecified,
+            Self::NoRestriction => Some(SameSite::None),
+            Self::Lax => Some(SameSite::Lax),
+            Self::Strict => Some(SameSite::Strict),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_site() {
+        for value in -1..=2 {
+            assert_eq!(i64::from(ChromeSameSite::from(value)), value);
+        }
+        assert_eq!(ChromeSameSite::from(3), ChromeSameSite::Unspecified);
+
+        assert_eq!(ChromeSameSite::from(-1).to_attribute(None), None);
+        assert_eq!(
+            ChromeSameSite::from(-1).to_attribute(Some(SameSite::Strict)),
+            Some(SameSite::Strict)
+        );
+        assert_eq!(
+            ChromeSameSite::from(0).to_attribute(Some(SameSite::Strict)),
+            Some(SameSite::None)
+        );
+        assert_eq!(
+            ChromeSameSite::from(Some(SameSite::Lax)),
+            ChromeSameSite::Lax
+        );
+    }
+}
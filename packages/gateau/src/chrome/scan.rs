@@ -0,0 +1,238 @@
+//! Discovery of the user data directories of Chromium-based browsers,
+//! including the ones relocated at install time (e.g. Opera or Vivaldi standalone installs).
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::confinement;
+
+/// User data directory of a Chromium-based browser found on the system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Installation {
+    /// Name of the browser, from its directory or its registration on Windows.
+    pub name: String,
+    /// Root directory, as expected by [`super::PathProvider::from_root`].
+    pub root_dir: PathBuf,
+}
+
+/// Find the user data directories of the Chromium-based browsers.
+///
+/// The configuration directory (or the local application data directory on Windows)
+/// is scanned two levels deep, and on Windows, the installation directories of the browsers
+/// registered in `Software\Clients\StartMenuInternet` are probed as well,
+/// since standalone installs keep their data next to the executable.
+pub fn scan_installations() -> Vec<Installation> {
+    let mut installations = Vec::new();
+
+    let base_dir = if cfg!(windows) {
+        dirs_next::data_local_dir()
+    } else {
+        confinement::config_dir()
+    };
+
+    if let Some(base_dir) = base_dir {
+        for dir in child_dirs(&base_dir)
+            .into_iter()
+            .flat_map(|dir| [child_dirs(&dir), vec![dir]].concat())
+        {
+            if is_root_dir(&dir) {
+                installations.push(Installation {
+                    name: dir
+                        .strip_prefix(&base_dir)
+                        .unwrap_or(&dir)
+                        .to_string_lossy()
+                        .into_owned(),
+                    root_dir: dir,
+                });
+            }
+        }
+    }
+
+    #[cfg(windows)]
+    for (name, install_dir) in registry::registered_install_dirs() {
+        // The executable is either in the installation directory or in its `Application` folder.
+        let root_dir = [Some(install_dir.as_path()), install_dir.parent()]
+            .into_iter()
+            .flatten()
+            .find(|dir| is_root_dir(dir));
+
+        if let Some(root_dir) = root_dir {
+            installations.push(Installation {
+                name,
+                root_dir: root_dir.to_owned(),
+            });
+        }
+    }
+
+    installations.sort_by(|a, b| a.root_dir.cmp(&b.root_dir));
+    installations.dedup_by(|a, b| a.root_dir == b.root_dir);
+
+    installations
+}
+
+/// Returns whether the directory is the root directory of a Chromium-based browser,
+/// with a `Local State` file and a default profile.
+fn is_root_dir(dir: &Path) -> bool {
+    let user_data_dir = if cfg!(windows) {
+        dir.join("User Data")
+    } else {
+        dir.to_owned()
+    };
+
+    user_data_dir.join("Local State").is_file() && user_data_dir.join("Default").is_dir()
+}
+
+/// Returns the subdirectories of the directory, or nothing if it cannot be read.
+fn child_dirs(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect()
+}
+
+#[cfg(windows)]
+mod registry {
+    use std::path::PathBuf;
+
+    use windows::{
+        core::{HSTRING, PWSTR},
+        Win32::{
+            Foundation::ERROR_SUCCESS,
+            System::Registry::{
+                RegCloseKey, RegEnumKeyExW, RegGetValueW, RegOpenKeyExW, HKEY, HKEY_CURRENT_USER,
+                HKEY_LOCAL_MACHINE, KEY_READ, RRF_RT_REG_SZ,
+            },
+        },
+    };
+
+    /// Key under which the browsers register themselves.
+    const START_MENU_INTERNET: &str = r"SOFTWARE\Clients\StartMenuInternet";
+
+    /// Returns the name and the installation directory of the registered browsers.
+    pub(super) fn registered_install_dirs() -> Vec<(String, PathBuf)> {
+        [HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE]
+            .into_iter()
+            .flat_map(|root| {
+                subkeys(root, START_MENU_INTERNET)
+                    .into_iter()
+                    .filter_map(move |name| {
+                        let command = string_value(
+                            root,
+                            &format!(r"{START_MENU_INTERNET}\{name}\shell\open\command"),
+                        )?;
+                        let executable = PathBuf::from(command.trim().trim_matches('"'));
+
+                        Some((name, executable.parent()?.to_owned()))
+                    })
+            })
+            .collect()
+    }
+
+    /// Returns the names of the subkeys of the key.
+    #[allow(unsafe_code)]
+    fn subkeys(root: HKEY, path: &str) -> Vec<String> {
+        /// Maximum length of a key name, in characters.
+        const MAX_KEY_LEN: usize = 256;
+
+        let mut key = HKEY::default();
+        let mut names = Vec::new();
+
+        // SAFETY: the key is only used if it has been opened successfully, and is closed afterwards.
+        // The name buffer outlives the calls, and its length is given in characters.
+        unsafe {
+            if RegOpenKeyExW(root, &HSTRING::from(path), 0, KEY_READ, &mut key) != ERROR_SUCCESS {
+                return names;
+            }
+
+            let mut buffer = [0u16; MAX_KEY_LEN];
+            for index in 0.. {
+                let mut len = buffer.len() as u32;
+                if RegEnumKeyExW(
+                    key,
+                    index,
+                    PWSTR(buffer.as_mut_ptr()),
+                    &mut len,
+                    None,
+                    PWSTR::null(),
+                    None,
+                    None,
+                ) != ERROR_SUCCESS
+                {
+                    break;
+                }
+
+                names.push(String::from_utf16_lossy(&buffer[..len as usize]));
+            }
+
+            let _ = RegCloseKey(key);
+        }
+
+        names
+    }
+
+    /// Returns the default string value of the key.
+    #[allow(unsafe_code)]
+    fn string_value(root: HKEY, path: &str) -> Option<String> {
+        let path = HSTRING::from(path);
+        let mut size = 0u32;
+
+        // SAFETY: the size of the value is queried first, then the buffer is allocated
+        // with that size (in bytes) before being filled.
+        unsafe {
+            if RegGetValueW(
+                root,
+                &path,
+                None,
+                RRF_RT_REG_SZ,
+                None,
+                None,
+                Some(&mut size),
+            ) != ERROR_SUCCESS
+            {
+                return None;
+            }
+
+            let mut buffer = vec![0u16; size as usize / 2];
+            if RegGetValueW(
+                root,
+                &path,
+                None,
+                RRF_RT_REG_SZ,
+                None,
+                Some(buffer.as_mut_ptr().cast()),
+                Some(&mut size),
+            ) != ERROR_SUCCESS
+            {
+                return None;
+            }
+
+            let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+            Some(String::from_utf16_lossy(&buffer[..len]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_root_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let user_data_dir = if cfg!(windows) {
+            dir.path().join("User Data")
+        } else {
+            dir.path().to_path_buf()
+        };
+        fs::create_dir_all(user_data_dir.join("Default")).unwrap();
+        assert!(!is_root_dir(dir.path()));
+
+        fs::write(user_data_dir.join("Local State"), "{}").unwrap();
+        assert!(is_root_dir(dir.path()));
+    }
+}
@@ -0,0 +1,78 @@
+//! Statistics on the decryption of the cookie values.
+
+use std::fmt;
+
+use serde::Serialize;
+
+/// Number of cookie values read by a [`ChromeManager`](super::ChromeManager),
+/// by the way they were stored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct DecryptionStats {
+    /// Values stored in plain text.
+    pub plaintext: usize,
+    /// Values encrypted with the v10 key.
+    pub v10: usize,
+    /// Values encrypted with the v11 key.
+    pub v11: usize,
+    /// Values encrypted with the v20 (app-bound) key.
+    pub v20: usize,
+    /// Values only encrypted with DPAPI, on Windows.
+    pub dpapi: usize,
+    /// Values which could not be decrypted, whatever the way they were stored.
+    pub failed: usize,
+}
+
+impl DecryptionStats {
+    /// Count a value, from its `encrypted_value` column.
+    pub(crate) fn record(&mut self, encrypted_value: &[u8]) {
+        let count = match encrypted_value.get(..3) {
+            _ if encrypted_value.is_empty() => &mut self.plaintext,
+            Some(b"v10") => &mut self.v10,
+            Some(b"v11") => &mut self.v11,
+            Some(b"v20") => &mut self.v20,
+            // Values without header are encrypted with DPAPI on Windows, and not encrypted elsewhere
+            _ if cfg!(windows) => &mut self.dpapi,
+            _ => &mut self.plaintext,
+        };
+
+        *count += 1;
+    }
+
+    /// Returns the total number of values.
+    pub fn total(&self) -> usize {
+        self.plaintext + self.v10 + self.v11 + self.v20 + self.dpapi
+    }
+}
+
+impl fmt::Display for DecryptionStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} plaintext, {} v10, {} v11, {} v20, {} DPAPI, {} failed",
+            self.plaintext, self.v10, self.v11, self.v20, self.dpapi, self.failed
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let mut stats = DecryptionStats::default();
+        for value in [&b""[..], b"v10abc", b"v10def", b"v11abc", b"v20abc"] {
+            stats.record(value);
+        }
+
+        assert_eq!(stats.plaintext, 1);
+        assert_eq!(stats.v10, 2);
+        assert_eq!(stats.v11, 1);
+        assert_eq!(stats.v20, 1);
+        assert_eq!(stats.total(), 5);
+        assert_eq!(
+            stats.to_string(),
+            "1 plaintext, 2 v10, 1 v11, 1 v20, 0 DPAPI, 0 failed"
+        );
+    }
+}
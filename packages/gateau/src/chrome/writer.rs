@@ -0,0 +1,318 @@
+//! Creation of a Chromium `Cookies` database from cookies of any browser.
+
+use std::path::Path;
+
+use cookie::time::OffsetDateTime;
+use rusqlite::{params, Connection};
+
+use super::{same_site::ChromeSameSite, ChromeManagerError};
+use crate::{report::BrowserCookie, time::unix_to_chrome_timestamp};
+
+/// Version of the schema written, which Chromium migrates to its current one on startup.
+const SCHEMA_VERSION: u32 = 18;
+
+/// Create a `Cookies` database with the `cookies` table of Chromium, populated with the cookies.
+///
+/// The values are stored in plain text, or encrypted with the v10 `key` if it is given,
+/// which is only supported on Unix (AES-128-CBC, e.g. the key derived from `peanuts`
+/// used by Chromium on Linux without keyring).
+/// The cookies are stored for their domain and its subdomains.
+/// Fails if the database already has a `cookies` table.
+pub fn write_database<P: AsRef<Path>>(
+    path: P,
+    cookies: &[BrowserCookie],
+    key: Option<&[u8]>,
+) -> Result<(), ChromeManagerError> {
+    let database_error = |source| ChromeManagerError::DatabaseWrite {
+        path: path.as_ref().to_string_lossy().to_string(),
+        source,
+    };
+
+    #[cfg(not(unix))]
+    if key.is_some() {
+        return Err(ChromeManagerError::EncryptionUnsupported);
+    }
+
+    let mut conn = Connection::open(path.as_ref()).map_err(database_error)?;
+    let tx = conn.transaction().map_err(database_error)?;
+
+    tx.execute_batch(&format!(
+        "CREATE TABLE meta (key LONGVARCHAR NOT NULL UNIQUE PRIMARY KEY, value LONGVARCHAR);
+        INSERT INTO meta VALUES ('version', '{SCHEMA_VERSION}'),
+            ('last_compatible_version', '{SCHEMA_VERSION}');
+        CREATE TABLE cookies (
+            creation_utc INTEGER NOT NULL,
+            host_key TEXT NOT NULL,
+            top_frame_site_key TEXT NOT NULL,
+            name TEXT NOT NULL,
+            value TEXT NOT NULL,
+            encrypted_value BLOB NOT NULL,
+            path TEXT NOT NULL,
+            expires_utc INTEGER NOT NULL,
+            is_secure INTEGER NOT NULL,
+            is_httponly INTEGER NOT NULL,
+            last_access_utc INTEGER NOT NULL,
+            has_expires INTEGER NOT NULL,
+            is_persistent INTEGER NOT NULL,
+            priority INTEGER NOT NULL,
+            samesite INTEGER NOT NULL,
+            source_scheme INTEGER NOT NULL,
+            source_port INTEGER NOT NULL,
+            is_same_party INTEGER NOT NULL,
+            last_update_utc INTEGER NOT NULL
+        );
+        CREATE UNIQUE INDEX cookies_unique_index
+            ON cookies(host_key, top_frame_site_key, name, path);"
+    ))
+    .map_err(database_error)?;
+
+    let now = unix_to_chrome_timestamp(OffsetDateTime::now_utc());
+
+    {
+        let mut stmt = tx
+            .prepare(
+                "INSERT OR REPLACE INTO cookies VALUES
+                    (?1, ?2, '', ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?1, ?10, ?10, 1, ?11, ?12, -1, 0, ?1)",
+            )
+            .map_err(database_error)?;
+
+        for cookie in cookies {
+            let (value, encrypted_value) = match key {
+                #[cfg(unix)]
+                Some(key) => {
+                    let encrypted = super::encrypted_value::encrypt_value(key, cookie.value())
+                        .map_err(|_| ChromeManagerError::InvalidKeyLength)?;
+                    ("", [b"v10".as_slice(), &encrypted].concat())
+                }
+                _ => (cookie.value(), Vec::new()),
+            };
+            // The session cookies have no expiration time.
+            let expires = cookie.expires_datetime();
+            let same_site = i64::from(ChromeSameSite::from(cookie.same_site()));
+            let secure = cookie.secure().unwrap_or_default();
+
+            stmt.execute(params![
+                now,
+                cookie.stored_domain(),
+                cookie.name(),
+                value,
+                encrypted_value,
+                cookie.path().unwrap_or("/"),
+                expires.map_or(0, unix_to_chrome_timestamp),
+                secure,
+                cookie.http_only().unwrap_or_default(),
+                expires.is_some(),
+                same_site,
+                // Scheme which set the cookie: secure (2) or not (1).
+                if secure { 2 } else { 1 },
+            ])
+            .map_err(database_error)?;
+        }
+    }
+
+    tx.commit().map_err(database_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use cookie::{Cookie, SameSite};
+
+    use crate::{
+        chrome::{ChromeManager, ChromeVariant, PathProvider},
+        CookiePathProvider,
+    };
+
+    use super::*;
+
+    fn cookies() -> [BrowserCookie; 2] {
+        [
+            Cookie::build(("sid", "abc"))
+                .domain("example.com")
+                .path("/")
+                .secure(true)
+                .same_site(SameSite::Lax)
+                .expires(OffsetDateTime::from_unix_timestamp(2000000000).unwrap())
+                .into(),
+            BrowserCookie {
+                host_only: true,
+                ..Cookie::build(("lang", "en"))
+                    .domain("www.example.com")
+                    .path("/app")
+                    .into()
+            },
+        ]
+    }
+
+    #[test]
+    fn test_write_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let path_provider = PathProvider::from_root(dir.path());
+        let path = path_provider.cookies_database();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+        write_database(&path, &cookies(), None).unwrap();
+        assert!(write_database(&path, &cookies(), None).is_err());
+
+        let manager = ChromeManager::new(
+            ChromeVariant::Chromium,
+            path_provider,
+            Some(Box::new(|_| true)),
+            false,
+        )
+        .unwrap()
+        .without_decryption()
+        .keep_raw_values();
+        let set = manager.get_cookies_with_report().unwrap();
+        let read = manager.get_cookies().unwrap();
+
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[0].value(), "abc");
+        assert_eq!(read[0].domain(), Some("example.com"));
+        assert_eq!(read[0].same_site(), Some(SameSite::Lax));
+        assert_eq!(
+            read[0]
+                .expires_datetime()
+                .map(OffsetDateTime::unix_timestamp),
+            Some(2000000000)
+        );
+        assert_eq!(read[1].path(), Some("/app"));
+        assert_eq!(read[1].same_site(), None);
+        assert!(!set.cookies[0].host_only);
+        assert!(set.cookies[1].host_only);
+        assert_eq!(
+            set.cookies[0].raw_value,
+            Some(crate::chrome::RawValue {
+                encrypted_value: Vec::new()
+            })
+        );
+        // The cookies are written as accessed when they are created.
+        assert!(set
+            .cookies
+            .iter()
+            .all(|cookie| cookie.last_access.unwrap() <= OffsetDateTime::now_utc()));
+    }
+
+    #[test]
+    fn test_clamped_expiration_warning() {
+        let dir = tempfile::tempdir().unwrap();
+        let path_provider = PathProvider::from_root(dir.path());
+        let path = path_provider.cookies_database();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+        write_database(&path, &cookies(), None).unwrap();
+        Connection::open(&path)
+            .unwrap()
+            .execute(
+                "UPDATE cookies SET expires_utc = ?1 WHERE name = 'sid'",
+                [i64::MAX],
+            )
+            .unwrap();
+
+        let set = ChromeManager::new(
+            ChromeVariant::Chromium,
+            path_provider,
+            Some(Box::new(|_| true)),
+            false,
+        )
+        .unwrap()
+        .without_decryption()
+        .get_cookies_with_report()
+        .unwrap();
+
+        assert_eq!(set.cookies.len(), 2);
+        assert_eq!(
+            set.cookies[0]
+                .expires_datetime()
+                .map(OffsetDateTime::unix_timestamp),
+            Some(253402300799)
+        );
+        assert!(matches!(
+            &set.warnings[..],
+            [crate::report::CookieWarning::ClampedExpiration { name, .. }] if name == "sid"
+        ));
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn test_undecryptable_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let path_provider = PathProvider::from_root(dir.path());
+        let path = path_provider.cookies_database();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+
+        let mut cookies = cookies().to_vec();
+        cookies.extend(["a", "b", "c"].map(|name| {
+            BrowserCookie::from(
+                Cookie::build((name, "value"))
+                    .domain("example.com")
+                    .path("/"),
+            )
+        }));
+        write_database(&path, &cookies, None).unwrap();
+        // Values without header are not encrypted, and these ones are not valid UTF-8.
+        Connection::open(&path)
+            .unwrap()
+            .execute(
+                "UPDATE cookies SET value = '', encrypted_value = X'FFFEFD' \
+                WHERE name IN ('a', 'b', 'c')",
+                [],
+            )
+            .unwrap();
+
+        let manager = ChromeManager::new(
+            ChromeVariant::Chromium,
+            path_provider,
+            Some(Box::new(|_| true)),
+            false,
+        )
+        .unwrap();
+        let set = manager.get_cookies_with_report().unwrap();
+
+        assert_eq!(
+            set.cookies
+                .iter()
+                .map(|cookie| cookie.name())
+                .collect::<Vec<_>>(),
+            ["sid", "lang"]
+        );
+        assert_eq!(manager.decryption_stats().failed, 3);
+        assert_eq!(manager.decryption_stats().total(), 5);
+        assert!(matches!(
+            &set.warnings[..],
+            [
+                crate::report::CookieWarning::UndecryptableValue { name: a, .. },
+                crate::report::CookieWarning::UndecryptableValue { name: b, .. },
+                crate::report::CookieWarning::UndecryptableValue { name: c, .. },
+            ] if a == "a" && b == "b" && c == "c"
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_encrypted_database() {
+        use crate::chrome::encrypted_value::{decrypt_value, posix::CHROME_V10_KEY};
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cookies");
+
+        write_database(&path, &cookies(), Some(&CHROME_V10_KEY)).unwrap();
+        assert!(write_database(dir.path().join("Invalid"), &cookies(), Some(b"short")).is_err());
+
+        let conn = Connection::open(&path).unwrap();
+        let (value, encrypted_value) = conn
+            .query_row(
+                "SELECT value, encrypted_value FROM cookies WHERE name = 'sid'",
+                [],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, Vec<u8>>(1)?)),
+            )
+            .unwrap();
+        drop(conn);
+
+        assert!(value.is_empty());
+        assert_eq!(&encrypted_value[..3], b"v10");
+        assert_eq!(
+            decrypt_value(CHROME_V10_KEY, &encrypted_value[3..], 0).unwrap(),
+            "abc"
+        );
+    }
+}
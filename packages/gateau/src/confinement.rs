@@ -0,0 +1,180 @@
+//! Detection of the mandatory access control policies (snap confinement, AppArmor, SELinux)
+//! which can prevent the browser profiles from being read on Linux.
+//!
+//! When gateau runs confined in a snap, `$HOME` and the XDG directories point to
+//! the private directory of the snap (`~/snap/<name>/<revision>`),
+//! so the directories of the user are resolved from `$SNAP_REAL_HOME` instead.
+
+use std::{
+    env, fs,
+    path::{Component, Path, PathBuf},
+};
+
+/// Security policy confining the current process.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Confinement {
+    /// Strict confinement of a snap package.
+    Snap { name: String },
+    /// AppArmor profile in enforce mode.
+    AppArmor { profile: String },
+    /// SELinux domain in enforcing mode.
+    SELinux { context: String },
+}
+
+impl Confinement {
+    /// Detect the policy confining the current process, if any.
+    pub fn detect() -> Option<Self> {
+        if !cfg!(target_os = "linux") {
+            return None;
+        }
+
+        let attr = fs::read_to_string("/proc/self/attr/apparmor/current")
+            .or_else(|_| fs::read_to_string("/proc/self/attr/current"))
+            .ok()?;
+        let selinux_enforcing = fs::read_to_string("/sys/fs/selinux/enforce")
+            .is_ok_and(|enforce| enforce.trim() == "1");
+
+        Self::from_attr(attr.trim_end_matches(['\0', '\n']), selinux_enforcing)
+    }
+
+    /// Parse the security attribute of a process
+    /// (`profile (mode)` for AppArmor, `user:role:type:level` for SELinux).
+    fn from_attr(attr: &str, selinux_enforcing: bool) -> Option<Self> {
+        if let Some(profile) = attr.strip_suffix(" (enforce)") {
+            return match profile.strip_prefix("snap.") {
+                Some(snap) => Some(Confinement::Snap {
+                    name: snap.split('.').next().unwrap_or(snap).to_string(),
+                }),
+                None => Some(Confinement::AppArmor {
+                    profile: profile.to_string(),
+                }),
+            };
+        }
+
+        let selinux_type = attr.split(':').nth(2)?;
+        (selinux_enforcing && selinux_type != "unconfined_t").then(|| Confinement::SELinux {
+            context: attr.to_string(),
+        })
+    }
+
+    /// Returns a suggestion to allow access to `path`.
+    pub fn hint<P: AsRef<Path>>(&self, path: P) -> String {
+        let path = path.as_ref();
+
+        match self {
+            Confinement::Snap { name } => {
+                let relative = home_dir().and_then(|home| path.strip_prefix(home).ok());
+
+                match relative.map(|relative| relative.components().collect::<Vec<_>>()) {
+                    Some(components)
+                        if components.first() == Some(&Component::Normal("snap".as_ref())) =>
+                    {
+                        format!(
+                            "{} belongs to another snap, which cannot be accessed from the {name} snap, \
+                            install gateau with classic confinement or outside of a snap instead",
+                            path.display()
+                        )
+                    }
+                    Some(components)
+                        if components.iter().any(|component| {
+                            component.as_os_str().to_string_lossy().starts_with('.')
+                        }) =>
+                    {
+                        format!(
+                            "Hidden directories of the home directory are only accessible through \
+                            the personal-files interface, run `snap connect {name}:personal-files`"
+                        )
+                    }
+                    Some(_) => format!("Run `snap connect {name}:home` to allow access to the home directory"),
+                    None => format!(
+                        "Run `snap connect {name}:removable-media` if {} is on a removable drive",
+                        path.display()
+                    ),
+                }
+            }
+
+            Confinement::AppArmor { profile } => format!(
+                "The AppArmor profile {profile} denies access to {}, \
+                allow it by adding `owner {}/** rk,` to /etc/apparmor.d/local/{profile}",
+                path.display(),
+                path.parent().unwrap_or(path).display()
+            ),
+
+            Confinement::SELinux { context } => format!(
+                "SELinux denies access to {} from the {context} context, \
+                check the denials with `ausearch -m avc -ts recent` and allow them with `audit2allow`",
+                path.display()
+            ),
+        }
+    }
+}
+
+/// Returns the real home directory of the user when running in a snap.
+fn snap_real_home() -> Option<PathBuf> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+
+    env::var_os("SNAP_REAL_HOME")
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Returns the home directory of the user.
+pub(crate) fn home_dir() -> Option<PathBuf> {
+    snap_real_home().or_else(dirs_next::home_dir)
+}
+
+/// Returns the configuration directory of the user.
+pub(crate) fn config_dir() -> Option<PathBuf> {
+    snap_real_home()
+        .map(|home| home.join(".config"))
+        .or_else(dirs_next::config_dir)
+}
+
+/// Returns the data directory of the user.
+pub(crate) fn data_dir() -> Option<PathBuf> {
+    snap_real_home()
+        .map(|home| home.join(".local/share"))
+        .or_else(dirs_next::data_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confinement_from_attr() {
+        assert_eq!(
+            Confinement::from_attr("snap.gateau.gateau (enforce)", false),
+            Some(Confinement::Snap {
+                name: "gateau".to_string()
+            })
+        );
+        assert_eq!(
+            Confinement::from_attr("/usr/bin/gateau (enforce)", false),
+            Some(Confinement::AppArmor {
+                profile: "/usr/bin/gateau".to_string()
+            })
+        );
+        assert_eq!(
+            Confinement::from_attr("/usr/bin/gateau (complain)", false),
+            None
+        );
+        assert_eq!(Confinement::from_attr("unconfined", false), None);
+        assert_eq!(
+            Confinement::from_attr("unconfined_u:unconfined_r:unconfined_t:s0", true),
+            None
+        );
+        assert_eq!(
+            Confinement::from_attr("system_u:system_r:container_t:s0:c1,c2", true),
+            Some(Confinement::SELinux {
+                context: "system_u:system_r:container_t:s0:c1,c2".to_string()
+            })
+        );
+        assert_eq!(
+            Confinement::from_attr("system_u:system_r:container_t:s0", false),
+            None
+        );
+    }
+}
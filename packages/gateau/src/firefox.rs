@@ -25,12 +25,17 @@
 //! );
 //! ```
 
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::str::FromStr;
+
 use cookie::time::OffsetDateTime;
 use cookie::{Cookie, CookieBuilder, Expiration, SameSite};
 
 use rusqlite::functions::FunctionFlags;
 use rusqlite::Connection;
 
+use crate::report::{clamped_datetime, BrowserCookie, CookieSet, CookieWarning};
 use crate::CookiePathProvider;
 
 use super::get_connection;
@@ -38,7 +43,88 @@ use super::get_connection;
 use super::HostFilterFn;
 
 mod paths;
+mod registry;
+mod writer;
+
 pub use paths::PathProvider;
+pub use registry::{fork_variant, register_fork, ForkVariant};
+pub use writer::write_database;
+
+/// Firefox and its derivatives, which share the same cookies database format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FirefoxVariant {
+    Firefox,
+    TorBrowser,
+    Waterfox,
+    PaleMoon,
+    SeaMonkey,
+    Thunderbird,
+    Floorp,
+    Zen,
+    /// Fork declared with [`register_fork`].
+    Fork(&'static ForkVariant),
+}
+
+/// Release channels of Firefox, which have their own default profile
+/// since Firefox 67, even when they share the same root directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FirefoxChannel {
+    Release,
+    Beta,
+    DeveloperEdition,
+    Nightly,
+    Esr,
+}
+
+impl FirefoxChannel {
+    /// Returns the name given by Firefox to the default profile of the channel.
+    pub(crate) const fn profile_name(self) -> &'static str {
+        match self {
+            FirefoxChannel::Release => "default-release",
+            FirefoxChannel::Beta => "default-beta",
+            FirefoxChannel::DeveloperEdition => "dev-edition-default",
+            FirefoxChannel::Nightly => "default-nightly",
+            FirefoxChannel::Esr => "default-esr",
+        }
+    }
+}
+
+impl Display for FirefoxChannel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FirefoxChannel::Release => write!(f, "Firefox"),
+            FirefoxChannel::Beta => write!(f, "Firefox Beta"),
+            FirefoxChannel::DeveloperEdition => write!(f, "Firefox Developer Edition"),
+            FirefoxChannel::Nightly => write!(f, "Firefox Nightly"),
+            FirefoxChannel::Esr => write!(f, "Firefox ESR"),
+        }
+    }
+}
+
+impl FromStr for FirefoxChannel {
+    type Err = String;
+
+    /// Parse a channel from a string.
+    ///
+    /// Supported channels are:
+    /// - release
+    /// - beta
+    /// - developer
+    /// - nightly
+    /// - esr
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "release" => Ok(FirefoxChannel::Release),
+            "beta" => Ok(FirefoxChannel::Beta),
+            "developer" | "dev-edition" => Ok(FirefoxChannel::DeveloperEdition),
+            "nightly" => Ok(FirefoxChannel::Nightly),
+            "esr" => Ok(FirefoxChannel::Esr),
+            _ => Err(format!(
+                "'{s}' is not one of the supported channels (release, beta, developer, nightly, esr)"
+            )),
+        }
+    }
+}
 
 pub type Result<T, E = FirefoxManagerError> = std::result::Result<T, E>;
 
@@ -52,12 +138,16 @@ pub enum FirefoxManagerError {
 
     #[error("Failed to get cookies from Firefox database")]
     SqliteQuery { source: rusqlite::Error },
+
+    #[error("Failed to write Firefox cookies database")]
+    SqliteWrite { source: rusqlite::Error },
 }
 
 /// Firefox cookie database manager.
 pub struct FirefoxManager<P: CookiePathProvider> {
     path_provider: P,
     conn: Connection,
+    as_of: Option<OffsetDateTime>,
 }
 
 impl<P: CookiePathProvider> FirefoxManager<P> {
@@ -80,67 +170,331 @@ impl<P: CookiePathProvider> FirefoxManager<P> {
         Ok(Self {
             path_provider,
             conn,
+            as_of: None,
         })
     }
 
+    /// Only get the cookies which were created at or before the given time,
+    /// to approximate the state of the database at that time.
+    ///
+    /// Firefox keeps the creation time of a cookie when its value is updated,
+    /// so the values of the cookies updated since then cannot be excluded.
+    /// This has no effect on schemas predating the `creationTime` column.
+    pub fn as_of(mut self, time: OffsetDateTime) -> Self {
+        self.as_of = Some(time);
+        self
+    }
+
     /// Get the path provider.
     pub fn path_provider(&self) -> &P {
         &self.path_provider
     }
 
+    /// Returns the names of the columns of the `moz_cookies` table.
+    fn table_columns(&self) -> Result<HashSet<String>> {
+        let mut stmt = self
+            .conn
+            .prepare("SELECT name FROM pragma_table_info('moz_cookies')")
+            .map_err(|source| FirefoxManagerError::SqliteQuery { source })?;
+
+        let columns = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|source| FirefoxManagerError::SqliteQuery { source })?
+            .collect::<Result<_, _>>()
+            .map_err(|source| FirefoxManagerError::SqliteQuery { source })?;
+
+        Ok(columns)
+    }
+
     /// Get all cookies from the database.
     ///
+    /// The query is built from the columns which are available in the database,
+    /// since older schemas (legacy Gecko browsers such as Pale Moon and SeaMonkey,
+    /// or old Firefox profiles) lack some of them.
+    /// Missing columns are replaced by their default value (e.g. `SameSite=None`
+    /// for schemas predating the `sameSite` column).
+    ///
+    /// Cookies whose origin attributes contain a partition key (set by the
+    /// Total Cookie Protection or for CHIPS) are marked as partitioned.
+    ///
     /// ## Limitations
     ///
     /// The expiry time is clamped to the maximum UNIX timestamp value supported by the underlying
     /// library (253402300799), despite the fact that Firefox uses a 64-bit integer to store the expiry
-    /// time.
+    /// time, which is reported by [`FirefoxManager::get_cookies_with_report`].
     pub fn get_cookies(&self) -> Result<Vec<Cookie<'static>>> {
-        let query = "SELECT name, value, host, path, 
-                        expiry, isSecure, sameSite, 
-                        isHttpOnly
-        FROM moz_cookies
-        WHERE host_filter(host)";
+        self.get_cookies_with_report().map(|set| {
+            set.cookies
+                .into_iter()
+                .map(|cookie| cookie.cookie)
+                .collect()
+        })
+    }
+
+    /// Get cookies from the database, with the warnings about the rows which could not be read
+    /// and the expiration times which were clamped.
+    pub fn get_cookies_with_report(&self) -> Result<CookieSet> {
+        let columns = self.table_columns()?;
+        let column_or = |column: &'static str, default: &'static str| {
+            if columns.contains(column) {
+                column
+            } else {
+                default
+            }
+        };
+
+        let query = format!(
+            "SELECT name, value, host, path, 
+                expiry, isSecure, {same_site}, 
+                {http_only}, {origin_attributes}, {last_accessed}
+            FROM moz_cookies
+            WHERE host_filter(host) {as_of}",
+            same_site = column_or("sameSite", "0"),
+            http_only = column_or("isHttpOnly", "0"),
+            origin_attributes = column_or("originAttributes", "''"),
+            last_accessed = column_or("lastAccessed", "NULL"),
+            // The creation time is stored in microseconds since the UNIX epoch.
+            as_of = match self.as_of {
+                Some(time) if columns.contains("creationTime") => {
+                    format!("AND creationTime <= {}", time.unix_timestamp_nanos() / 1000)
+                }
+                _ => String::new(),
+            },
+        );
 
         let mut stmt = self
             .conn
-            .prepare(query)
+            .prepare(&query)
             .map_err(|source| FirefoxManagerError::SqliteQuery { source })?;
 
-        let cookies = stmt
+        let rows = stmt
             .query_map([], |row| {
-                Ok(
-                    CookieBuilder::new(row.get::<_, String>(0)?, row.get::<_, String>(1)?)
-                        .domain(row.get::<_, String>(2)?)
-                        .path(row.get::<_, String>(3)?)
-                        .expires(Expiration::from(
-                            OffsetDateTime::from_unix_timestamp(
-                                row.get::<_, i64>(4)?.min(253402300799),
-                            )
-                            .expect("Invalid timestamp"),
-                        ))
-                        .secure(row.get::<_, isize>(5)? != 0)
-                        .same_site(match row.get(6)? {
-                            0 => SameSite::None,
-                            1 => SameSite::Lax,
-                            _ => SameSite::Strict,
-                        })
-                        .http_only(row.get::<_, isize>(7)? != 0)
-                        .into(),
-                )
+                let expiry = row.get::<_, i64>(4)?;
+                let (expires, clamped) = clamped_datetime(expiry);
+                let host = row.get::<_, String>(2)?;
+                let origin_attributes = row.get::<_, String>(8)?;
+                // The domain cookies are stored with a leading dot, the host-only ones without.
+                let host_only = !host.starts_with('.');
+                let cookie = CookieBuilder::new(row.get::<_, String>(0)?, row.get::<_, String>(1)?)
+                    .domain(host)
+                    .path(row.get::<_, String>(3)?)
+                    .expires(Expiration::from(expires))
+                    .secure(row.get::<_, isize>(5)? != 0)
+                    .same_site(match row.get(6)? {
+                        0 => SameSite::None,
+                        1 => SameSite::Lax,
+                        _ => SameSite::Strict,
+                    })
+                    .http_only(row.get::<_, isize>(7)? != 0)
+                    .partitioned(origin_attributes.contains("partitionKey="))
+                    .build();
+
+                // The last access time is stored in microseconds since the UNIX epoch.
+                let last_access = row
+                    .get::<_, Option<i64>>(9)?
+                    .filter(|&last_access| last_access > 0)
+                    .and_then(|last_access| {
+                        OffsetDateTime::from_unix_timestamp_nanos(i128::from(last_access) * 1000)
+                            .ok()
+                    });
+
+                Ok((
+                    BrowserCookie {
+                        cookie,
+                        host_only,
+                        partition_key: partition_key(&origin_attributes),
+                        raw_value: None,
+                        source: None,
+                        last_access,
+                    },
+                    clamped.then_some(expiry),
+                ))
             })
-            .map_err(|source| FirefoxManagerError::SqliteQuery { source })?
-            .filter_map(|c| c.ok())
-            .collect::<Vec<_>>();
+            .map_err(|source| FirefoxManagerError::SqliteQuery { source })?;
+
+        let mut set = CookieSet::default();
+        for row in rows {
+            match row {
+                Ok((cookie, clamped)) => {
+                    if let Some(stored) = clamped {
+                        set.warnings.push(CookieWarning::ClampedExpiration {
+                            host: cookie.domain().unwrap_or_default().to_string(),
+                            name: cookie.name().to_string(),
+                            stored,
+                        });
+                    }
+                    set.cookies.push(cookie);
+                }
+                Err(e) => set.warnings.push(CookieWarning::SkippedRow {
+                    reason: e.to_string(),
+                }),
+            }
+        }
 
-        Ok(cookies)
+        Ok(set)
     }
 }
 
 impl FirefoxManager<PathProvider> {
-    /// Create a new Firefox manager with the default profile.
-    pub fn default_profile(filter: Option<Box<HostFilterFn>>, bypass_lock: bool) -> Result<Self> {
-        let path_provider = PathProvider::default_profile();
+    /// Create a new Firefox manager with the default profile of the given variant.
+    pub fn default_profile(
+        variant: FirefoxVariant,
+        filter: Option<Box<HostFilterFn>>,
+        bypass_lock: bool,
+    ) -> Result<Self> {
+        let path_provider = PathProvider::default_profile(variant);
         Self::new(path_provider, filter, bypass_lock)
     }
 }
+
+/// Returns the top-level site of the partition key in the origin attributes of a cookie
+/// (e.g. `https://example.org` for `^partitionKey=%28https%2Cexample.org%29`).
+///
+/// The key holds the scheme and the site, followed by the port if it is not the default one
+/// and by whether an ancestor is cross-site (`(https,example.org,8443,f)`).
+/// Keys written by older versions only hold the site.
+fn partition_key(origin_attributes: &str) -> Option<String> {
+    let key = origin_attributes
+        .trim_start_matches('^')
+        .split('&')
+        .find_map(|attribute| attribute.strip_prefix("partitionKey="))?;
+    let key = percent_decode(key)?;
+
+    let Some(key) = key.strip_prefix('(').and_then(|key| key.strip_suffix(')')) else {
+        return Some(key);
+    };
+    let mut parts = key.split(',');
+    let (scheme, site) = (parts.next()?, parts.next()?);
+
+    Some(
+        match parts.next().filter(|port| port.parse::<u16>().is_ok()) {
+            Some(port) => format!("{scheme}://{site}:{port}"),
+            None => format!("{scheme}://{site}"),
+        },
+    )
+}
+
+/// Decode the `%XX` escapes of an origin attribute.
+fn percent_decode(value: &str) -> Option<String> {
+    let mut bytes = value.bytes();
+    let mut decoded = Vec::with_capacity(value.len());
+
+    while let Some(byte) = bytes.next() {
+        decoded.push(if byte == b'%' {
+            let digits = [bytes.next()?, bytes.next()?];
+            u8::from_str_radix(std::str::from_utf8(&digits).ok()?, 16).ok()?
+        } else {
+            byte
+        });
+    }
+
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    struct TestPathProvider(PathBuf);
+
+    impl CookiePathProvider for TestPathProvider {
+        fn cookies_database(&self) -> PathBuf {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn test_get_cookies_old_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cookies.sqlite");
+
+        // Schema of Firefox 3.0
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE moz_cookies (id INTEGER PRIMARY KEY, name TEXT, value TEXT,
+                host TEXT, path TEXT, expiry INTEGER, lastAccessed INTEGER, isSecure INTEGER);
+            INSERT INTO moz_cookies VALUES (1, 'sid', 'abc', '.example.com', '/', 2000000000, 0, 1);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let manager =
+            FirefoxManager::new(TestPathProvider(path), Some(Box::new(|_| true)), false).unwrap();
+        let cookies = manager.get_cookies().unwrap();
+
+        assert_eq!(cookies.len(), 1);
+        assert_eq!(cookies[0].name_value(), ("sid", "abc"));
+        assert_eq!(cookies[0].secure(), Some(true));
+        assert_eq!(cookies[0].http_only(), Some(false));
+        assert_eq!(cookies[0].same_site(), Some(SameSite::None));
+        assert_eq!(cookies[0].partitioned(), Some(false));
+    }
+
+    #[test]
+    fn test_get_cookies_current_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cookies.sqlite");
+
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE moz_cookies (id INTEGER PRIMARY KEY,
+                originAttributes TEXT NOT NULL DEFAULT '', name TEXT, value TEXT,
+                host TEXT, path TEXT, expiry INTEGER, lastAccessed INTEGER,
+                creationTime INTEGER, isSecure INTEGER, isHttpOnly INTEGER,
+                inBrowserElement INTEGER DEFAULT 0, sameSite INTEGER DEFAULT 0);
+            INSERT INTO moz_cookies VALUES
+                (1, '', 'sid', 'abc', '.example.com', '/', 2000000000, 0, 1600000000000000, 1, 1, 0, 1),
+                (2, '^partitionKey=%28https%2Cexample.org%29', 'embed', 'xyz',
+                    'widget.example.com', '/', 2000000000, 0, 1700000000000000, 1, 0, 0, 0);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let manager = FirefoxManager::new(
+            TestPathProvider(path.clone()),
+            Some(Box::new(|_| true)),
+            false,
+        )
+        .unwrap();
+        let cookies = manager.get_cookies().unwrap();
+
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(cookies[0].partitioned(), Some(false));
+        assert_eq!(cookies[1].partitioned(), Some(true));
+        let set = manager.get_cookies_with_report().unwrap();
+        assert_eq!(set.cookies[0].partition_key, None);
+        assert_eq!(
+            set.cookies[1].partition_key.as_deref(),
+            Some("https://example.org")
+        );
+
+        let manager = FirefoxManager::new(TestPathProvider(path), Some(Box::new(|_| true)), false)
+            .unwrap()
+            .as_of(OffsetDateTime::from_unix_timestamp(1650000000).unwrap());
+        let cookies = manager.get_cookies().unwrap();
+
+        assert_eq!(cookies.len(), 1);
+        assert_eq!(cookies[0].name(), "sid");
+    }
+
+    #[test]
+    fn test_partition_key() {
+        assert_eq!(partition_key(""), None);
+        assert_eq!(partition_key("^userContextId=1"), None);
+        assert_eq!(
+            partition_key("^userContextId=1&partitionKey=%28http%2Clocalhost%2C8080%2Cf%29")
+                .as_deref(),
+            Some("http://localhost:8080")
+        );
+        assert_eq!(
+            partition_key("^partitionKey=%28https%2Cexample.org%2Cf%29").as_deref(),
+            Some("https://example.org")
+        );
+        assert_eq!(
+            partition_key("^partitionKey=example.org").as_deref(),
+            Some("example.org")
+        );
+    }
+}
@@ -1,9 +1,12 @@
 use std::{
     ffi::OsStr,
+    fs,
     path::{Path, PathBuf},
 };
 
-use crate::CookiePathProvider;
+use crate::{confinement, CookiePathProvider};
+
+use super::{FirefoxChannel, FirefoxVariant};
 
 /// Path provider for Firefox.
 pub struct PathProvider {
@@ -31,37 +34,264 @@ impl PathProvider {
         Self::new::<_, &OsStr>(root_dir, None)
     }
 
-    /// Returns a path provider for the default profile.
+    /// Returns a path provider for the default profile of the given variant.
+    ///
+    /// If the variant can be installed in several locations (e.g. as a snap package on Linux),
+    /// and each of them has a `profiles.ini` file, the location whose default profile
+    /// has the most recently modified cookies database is preferred,
+    /// since the other one is usually a leftover from a migration.
     ///
     /// # Panics
     ///
     /// This function panics if no default profile can be found.
-    pub fn default_profile() -> Self {
-        let root_dir = if cfg!(any(windows, target_os = "macos")) {
-            dirs_next::config_dir()
+    pub fn default_profile(variant: FirefoxVariant) -> Self {
+        match variant {
+            FirefoxVariant::TorBrowser => {
+                Self::tor_browser_default_profile().expect("Cannot find Tor Browser installation")
+            }
+            _ => Self::find_default_profile(variant).expect("Cannot get default profile"),
+        }
+    }
+
+    /// Returns a path provider for the default profile of the given variant,
+    /// or `None` if the variant is not installed.
+    ///
+    /// The default profile is selected as in [`PathProvider::default_profile`].
+    pub fn find_default_profile(variant: FirefoxVariant) -> Option<Self> {
+        match variant {
+            FirefoxVariant::TorBrowser => Self::tor_browser_default_profile(),
+            _ => Self::most_recent_profile(variant, PathProvider::get_default_profile_path),
+        }
+    }
+
+    /// Returns a path provider for the default profile of the given channel of the variant
+    /// (e.g. Firefox Developer Edition or Nightly), or `None` if the channel has never been used.
+    ///
+    /// The locations are handled in the same way as in [`PathProvider::default_profile`].
+    pub fn channel_profile(variant: FirefoxVariant, channel: FirefoxChannel) -> Option<Self> {
+        Self::most_recent_profile(variant, |profiles| {
+            PathProvider::get_channel_profile_path(profiles, channel)
+        })
+    }
+
+    /// Returns the profile selected from the `profiles.ini` file of each root directory
+    /// of the variant, whose cookies database is the most recently modified.
+    fn most_recent_profile<F>(variant: FirefoxVariant, select: F) -> Option<Self>
+    where
+        F: Fn(&tini::Ini) -> Option<String>,
+    {
+        PathProvider::variant_root_dirs(variant)
+            .into_iter()
+            .filter_map(|root_dir| Self::profiles_ini_profile(root_dir, &select))
+            // `max_by_key` returns the last maximum, so the candidates are reversed
+            // to prefer the main location when no cookies database is found.
+            .rev()
+            .max_by_key(|candidate| {
+                fs::metadata(candidate.cookies_database())
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+            })
+    }
+
+    /// Returns the root directories of the given variant, which contain the `profiles.ini` file,
+    /// in order of preference.
+    fn variant_root_dirs(variant: FirefoxVariant) -> Vec<PathBuf> {
+        let sandbox_dir = PathProvider::variant_sandbox_folder(variant)
+            .filter(|_| cfg!(target_os = "linux"))
+            .and_then(|folder| Some(confinement::home_dir()?.join(folder)));
+
+        [PathProvider::variant_root_dir(variant), sandbox_dir]
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    /// Returns the subpath of the home directory where the variant stores its profiles
+    /// when it is installed as a snap package (e.g. Firefox and Thunderbird on Ubuntu)
+    /// or as a Flatpak on Linux.
+    const fn variant_sandbox_folder(variant: FirefoxVariant) -> Option<&'static str> {
+        match variant {
+            FirefoxVariant::Firefox => Some("snap/firefox/common/.mozilla/firefox"),
+            FirefoxVariant::Thunderbird => Some("snap/thunderbird/common/.thunderbird"),
+            FirefoxVariant::Floorp => Some(".var/app/one.ablaze.floorp/.floorp"),
+            FirefoxVariant::Zen => Some(".var/app/app.zen_browser.zen/.zen"),
+            _ => None,
+        }
+    }
+
+    /// Returns the root directory of the given variant, which contains the `profiles.ini` file.
+    /// Returns `None` if the variant does not have a fixed root directory.
+    fn variant_root_dir(variant: FirefoxVariant) -> Option<PathBuf> {
+        match variant {
+            FirefoxVariant::Floorp => return PathProvider::fork_root_dir("Floorp"),
+            FirefoxVariant::Zen => return PathProvider::fork_root_dir("zen"),
+            FirefoxVariant::Fork(fork) => return PathProvider::fork_root_dir(&fork.vendor_dir),
+            _ => {}
+        }
+
+        let base_dir = if cfg!(any(windows, target_os = "macos")) {
+            confinement::config_dir()
         } else {
-            dirs_next::home_dir()
+            confinement::home_dir()
+        }
+        .unwrap();
+
+        // Thunderbird does not use the Application Support directory on macOS.
+        if cfg!(target_os = "macos") && variant == FirefoxVariant::Thunderbird {
+            return Some(confinement::home_dir()?.join("Library/Thunderbird"));
         }
-        .unwrap()
-        .join(if cfg!(any(windows, target_os = "macos")) {
-            "Mozilla/Firefox"
+
+        let folder = if cfg!(any(windows, target_os = "macos")) {
+            match variant {
+                FirefoxVariant::Firefox => "Mozilla/Firefox",
+                FirefoxVariant::Waterfox => "Waterfox",
+                FirefoxVariant::PaleMoon if cfg!(windows) => "Moonchild Productions/Pale Moon",
+                FirefoxVariant::PaleMoon => "Pale Moon",
+                FirefoxVariant::SeaMonkey if cfg!(windows) => "Mozilla/SeaMonkey",
+                FirefoxVariant::SeaMonkey => "SeaMonkey",
+                FirefoxVariant::Thunderbird => "Thunderbird",
+                FirefoxVariant::TorBrowser
+                | FirefoxVariant::Floorp
+                | FirefoxVariant::Zen
+                | FirefoxVariant::Fork(_) => return None,
+            }
         } else {
-            ".mozilla/firefox"
-        });
+            match variant {
+                FirefoxVariant::Firefox => ".mozilla/firefox",
+                FirefoxVariant::Waterfox => ".waterfox",
+                FirefoxVariant::PaleMoon => ".moonchild productions/pale moon",
+                FirefoxVariant::SeaMonkey => ".mozilla/seamonkey",
+                FirefoxVariant::Thunderbird => ".thunderbird",
+                FirefoxVariant::TorBrowser
+                | FirefoxVariant::Floorp
+                | FirefoxVariant::Zen
+                | FirefoxVariant::Fork(_) => return None,
+            }
+        };
+
+        Some(base_dir.join(folder))
+    }
 
-        let profiles = tini::Ini::from_file(&root_dir.join("profiles.ini"))
-            .expect("Cannot parse Firefox profiles.ini file");
+    /// Returns the root directory of a Firefox fork from the name of its vendor directory.
+    ///
+    /// The vendor directory is looked up in the application data directory on Windows and macOS,
+    /// and lowercased and prefixed with a dot in the home directory on Linux (e.g. `~/.floorp`).
+    fn fork_root_dir(vendor_dir: &str) -> Option<PathBuf> {
+        if cfg!(any(windows, target_os = "macos")) {
+            Some(confinement::config_dir()?.join(vendor_dir))
+        } else {
+            Some(confinement::home_dir()?.join(format!(".{}", vendor_dir.to_lowercase())))
+        }
+    }
 
-        let default = PathProvider::get_default_profile_path(profiles)
-            .expect("Cannot get Firefox default profile");
+    /// Returns a path provider for the profile selected
+    /// in the `profiles.ini` file of the root directory, if it can be read.
+    fn profiles_ini_profile<F>(root_dir: PathBuf, select: F) -> Option<Self>
+    where
+        F: Fn(&tini::Ini) -> Option<String>,
+    {
+        let profiles = tini::Ini::from_file(&root_dir.join("profiles.ini")).ok()?;
 
-        Self::new(root_dir, Some(default))
+        let profile = select(&profiles)?;
+        let profile_dir = PathProvider::resolve_profile_dir(&profiles, &root_dir, &profile);
+
+        Some(Self {
+            _base_dir: root_dir,
+            profile_dir,
+        })
+    }
+
+    /// Returns the directory of the profile with the given path in the profiles config.
+    ///
+    /// The path is relative to the root directory, unless the profile is declared
+    /// with `IsRelative=0` (e.g. when it has been created elsewhere with the profile manager),
+    /// in which case it is absolute.
+    fn resolve_profile_dir(profile_config: &tini::Ini, root_dir: &Path, path: &str) -> PathBuf {
+        let is_relative = profile_config
+            .iter()
+            .filter(|(name, _)| name.starts_with("Profile"))
+            .map(|(_, section)| section)
+            .find(|section| section.get::<String>("Path").as_deref() == Some(path))
+            .and_then(|section| section.get::<String>("IsRelative"))
+            .map_or_else(|| Path::new(path).is_relative(), |value| value != "0");
+
+        if is_relative {
+            root_dir.join(path)
+        } else {
+            PathBuf::from(path)
+        }
+    }
+
+    /// Returns a path provider for the Tor Browser profile.
+    ///
+    /// The Tor Browser bundle is portable and keeps its profile inside the installation
+    /// directory (`Browser/TorBrowser/Data/Browser/profile.default`),
+    /// so the usual installation directories are probed.
+    /// On macOS, the profile is stored in the `TorBrowser-Data` directory instead.
+    fn tor_browser_default_profile() -> Option<Self> {
+        /// Name of the profile used by the Tor Browser bundle.
+        const TOR_BROWSER_PROFILE: &str = "profile.default";
+
+        if cfg!(target_os = "macos") {
+            let root_dir = confinement::config_dir()?.join("TorBrowser-Data/Browser");
+
+            let profile = tini::Ini::from_file(&root_dir.join("profiles.ini"))
+                .ok()
+                .and_then(|profiles| PathProvider::get_default_profile_path(&profiles))
+                .unwrap_or_else(|| TOR_BROWSER_PROFILE.to_string());
+
+            return Some(Self::new(root_dir, Some(profile)));
+        }
+
+        let root_dir = PathProvider::tor_browser_install_dirs()
+            .into_iter()
+            .map(|install_dir| install_dir.join("Browser/TorBrowser/Data/Browser"))
+            .find(|root_dir| root_dir.join(TOR_BROWSER_PROFILE).exists())?;
+
+        Some(Self::new(root_dir, Some(TOR_BROWSER_PROFILE)))
+    }
+
+    /// Returns the directories where the Tor Browser bundle is usually installed.
+    fn tor_browser_install_dirs() -> Vec<PathBuf> {
+        let home_dir = confinement::home_dir().unwrap();
+
+        if cfg!(windows) {
+            dirs_next::desktop_dir()
+                .into_iter()
+                .chain([home_dir])
+                .map(|dir| dir.join("Tor Browser"))
+                .collect()
+        } else {
+            let launcher_dirs = [
+                confinement::data_dir().unwrap(),
+                home_dir.join(".var/app/org.torproject.torbrowser-launcher/data"),
+            ]
+            .into_iter()
+            .flat_map(|data_dir| {
+                ["x86_64", "i686"].map(|arch| {
+                    data_dir
+                        .join("torbrowser/tbb")
+                        .join(arch)
+                        .join("tor-browser")
+                })
+            });
+
+            launcher_dirs
+                .chain([
+                    home_dir.join("tor-browser"),
+                    home_dir.join("Desktop/tor-browser"),
+                    home_dir.join("Downloads/tor-browser"),
+                ])
+                .collect()
+        }
     }
 
     /// Get the default profile's path from the profiles config.
     /// It selects the profile which is in the first `Install$INSTALL_HASH$` section found,
     /// or the first `Profile` section with `Default=1` if no `Install$INSTALL_HASH$` section is found.
-    fn get_default_profile_path(profile_config: tini::Ini) -> Option<String> {
+    /// Legacy Gecko browsers may not mark any profile as default when there is only one,
+    /// in which case the first `Profile` section is selected.
+    fn get_default_profile_path(profile_config: &tini::Ini) -> Option<String> {
         if let Some(section) = profile_config
             .iter()
             .filter(|(name, _)| name.starts_with("Install"))
@@ -70,21 +300,54 @@ impl PathProvider {
         {
             section.get("Default")
         } else {
-            profile_config
+            let mut profiles = profile_config
                 .iter()
                 .filter(|(name, _)| name.starts_with("Profile"))
-                .filter(|(_, section)| section.get::<String>("Default").as_deref() == Some("1"))
                 .map(|(_, section)| section)
-                .next()
+                .peekable();
+            let first = profiles.peek().copied();
+
+            profiles
+                .find(|section| section.get::<String>("Default").as_deref() == Some("1"))
+                .or(first)
                 .and_then(|section| section.get("Path"))
         }
     }
+
+    /// Get the path of the default profile of the channel from the profiles config.
+    /// Firefox names it after the channel, with a timestamp appended
+    /// if the name is already taken (e.g. `default-release-1602083895780`).
+    fn get_channel_profile_path(
+        profile_config: &tini::Ini,
+        channel: FirefoxChannel,
+    ) -> Option<String> {
+        profile_config
+            .iter()
+            .filter(|(name, _)| name.starts_with("Profile"))
+            .map(|(_, section)| section)
+            .find(|section| {
+                section
+                    .get::<String>("Name")
+                    .is_some_and(|name| name.starts_with(channel.profile_name()))
+            })
+            .and_then(|section| section.get("Path"))
+    }
 }
 
 impl CookiePathProvider for PathProvider {
     fn cookies_database(&self) -> PathBuf {
         self.profile_dir.join("cookies.sqlite")
     }
+
+    fn lock_files(&self) -> Vec<PathBuf> {
+        if cfg!(windows) {
+            vec![self.profile_dir.join("parent.lock")]
+        } else if cfg!(target_os = "macos") {
+            vec![self.profile_dir.join(".parentlock")]
+        } else {
+            vec![self.profile_dir.join("lock")]
+        }
+    }
 }
 
 #[cfg(test)]
@@ -134,17 +397,116 @@ Path=npf4bci2.default-release-1602083895780
 StartWithLastProfile=1
 Version=2"#;
 
+    const LEGACY_PROFILE: &str = r#"
+[General]
+StartWithLastProfile=1
+
+[Profile0]
+Name=default
+IsRelative=1
+Path=Profiles/x8v3bv2n.default"#;
+
+    #[test]
+    fn test_get_legacy_default_profile() {
+        let profiles = tini::Ini::from_string(LEGACY_PROFILE).unwrap();
+        assert_eq!(
+            PathProvider::get_default_profile_path(&profiles),
+            Some("Profiles/x8v3bv2n.default".to_string())
+        );
+    }
+
+    const CHANNELS_PROFILE: &str = r#"
+[Install4F96D1932A9F858E]
+Default=npf4bci2.default-release
+Locked=1
+
+[Install6C5F7E7D1F1A2B3C]
+Default=k2m9qx1a.dev-edition-default
+Locked=1
+
+[Profile2]
+Name=dev-edition-default
+IsRelative=1
+Path=k2m9qx1a.dev-edition-default
+
+[Profile1]
+Name=default-release
+IsRelative=1
+Path=npf4bci2.default-release
+
+[Profile0]
+Name=default
+IsRelative=1
+Path=1fi7auz8.default
+Default=1"#;
+
+    #[test]
+    fn test_get_channel_profile() {
+        let channel_profile = |config: &str, channel| {
+            let profiles = tini::Ini::from_string(config).unwrap();
+            PathProvider::get_channel_profile_path(&profiles, channel)
+        };
+
+        assert_eq!(
+            channel_profile(CHANNELS_PROFILE, FirefoxChannel::DeveloperEdition),
+            Some("k2m9qx1a.dev-edition-default".to_string())
+        );
+        assert_eq!(
+            channel_profile(CHANNELS_PROFILE, FirefoxChannel::Release),
+            Some("npf4bci2.default-release".to_string())
+        );
+        assert_eq!(
+            channel_profile(CHANNELS_PROFILE, FirefoxChannel::Nightly),
+            None
+        );
+        assert_eq!(
+            channel_profile(LINUX_PROFILE, FirefoxChannel::Release),
+            Some("npf4bci2.default-release-1602083895780".to_string())
+        );
+    }
+
+    const ABSOLUTE_PROFILE: &str = r#"
+[Install4F96D1932A9F858E]
+Default=/data/firefox/work
+Locked=1
+
+[Profile1]
+Name=work
+IsRelative=0
+Path=/data/firefox/work
+
+[Profile0]
+Name=default-release
+IsRelative=1
+Path=npf4bci2.default-release"#;
+
+    #[test]
+    fn test_resolve_profile_dir() {
+        let profiles = tini::Ini::from_string(ABSOLUTE_PROFILE).unwrap();
+        let root_dir = Path::new("/home/user/.mozilla/firefox");
+
+        let default_profile = PathProvider::get_default_profile_path(&profiles).unwrap();
+        assert_eq!(
+            PathProvider::resolve_profile_dir(&profiles, root_dir, &default_profile),
+            PathBuf::from("/data/firefox/work")
+        );
+        assert_eq!(
+            PathProvider::resolve_profile_dir(&profiles, root_dir, "npf4bci2.default-release"),
+            root_dir.join("npf4bci2.default-release")
+        );
+    }
+
     #[test]
     fn test_get_default_profile() {
         let profiles = tini::Ini::from_string(WINDOWS_PROFILE).unwrap();
         assert_eq!(
-            PathProvider::get_default_profile_path(profiles),
+            PathProvider::get_default_profile_path(&profiles),
             Some("Profiles/i5izpoj2.default-release".to_string())
         );
 
         let profiles = tini::Ini::from_string(LINUX_PROFILE).unwrap();
         assert_eq!(
-            PathProvider::get_default_profile_path(profiles),
+            PathProvider::get_default_profile_path(&profiles),
             Some("npf4bci2.default-release-1602083895780".to_string())
         );
     }
@@ -0,0 +1,64 @@
+//! Registry of the Firefox forks declared at runtime.
+
+use std::sync::Mutex;
+
+use super::FirefoxVariant;
+
+/// Firefox fork which is not supported out of the box, declared with [`register_fork`].
+///
+/// Its profiles are discovered from the vendor directory,
+/// in the same way as for Floorp and Zen.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ForkVariant {
+    /// Name used to select the browser (e.g. `mercury`).
+    pub name: String,
+    /// Name of the vendor directory which contains the `profiles.ini` file (e.g. `Mercury`),
+    /// stored in the application data directory on Windows and macOS,
+    /// and lowercased and prefixed with a dot in the home directory on Linux.
+    pub vendor_dir: String,
+    /// Name of the executable, used to open sessions.
+    pub executable: String,
+}
+
+/// Forks registered so far, which live until the end of the program.
+static FORK_VARIANTS: Mutex<Vec<&'static ForkVariant>> = Mutex::new(Vec::new());
+
+/// Register a Firefox fork and return its variant.
+///
+/// Once registered, the browser can also be parsed from its name as a [`crate::Browser`].
+/// If several forks share the same name, the last one registered is used.
+pub fn register_fork(variant: ForkVariant) -> FirefoxVariant {
+    let variant: &'static ForkVariant = Box::leak(Box::new(variant));
+    FORK_VARIANTS.lock().unwrap().push(variant);
+
+    FirefoxVariant::Fork(variant)
+}
+
+/// Returns the registered fork with the given name, if any.
+pub fn fork_variant(name: &str) -> Option<FirefoxVariant> {
+    FORK_VARIANTS
+        .lock()
+        .unwrap()
+        .iter()
+        .rev()
+        .find(|variant| variant.name == name)
+        .map(|variant| FirefoxVariant::Fork(variant))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_register_fork() {
+        assert_eq!(fork_variant("test-mercury"), None);
+
+        let registered = register_fork(ForkVariant {
+            name: "test-mercury".to_string(),
+            vendor_dir: "Mercury".to_string(),
+            executable: "mercury-browser".to_string(),
+        });
+        assert_eq!(fork_variant("test-mercury"), Some(registered));
+    }
+}
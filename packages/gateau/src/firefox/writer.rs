@@ -0,0 +1,163 @@
+//! Creation of a Firefox cookies database from cookies of any browser.
+
+use std::path::Path;
+
+use cookie::{
+    time::{Duration, OffsetDateTime},
+    SameSite,
+};
+use rusqlite::{params, Connection};
+
+use super::{FirefoxManagerError, Result};
+use crate::report::BrowserCookie;
+
+/// Version of the schema written, which Firefox 104+ migrates to its current one on startup.
+const SCHEMA_VERSION: u32 = 12;
+
+/// Lifetime given to the session cookies, which Firefox does not store in its database:
+/// the maximum lifetime of a cookie in the browsers (400 days).
+const SESSION_COOKIE_LIFETIME: Duration = Duration::days(400);
+
+/// Create a `cookies.sqlite` database with the `moz_cookies` table of Firefox,
+/// populated with the cookies.
+///
+/// The cookies are stored for their domain and its subdomains,
+/// and the session cookies are stored as persistent cookies expiring in 400 days.
+/// Fails if the database already has a `moz_cookies` table.
+pub fn write_database<P: AsRef<Path>>(path: P, cookies: &[BrowserCookie]) -> Result<()> {
+    let mut conn =
+        Connection::open(path).map_err(|source| FirefoxManagerError::SqliteWrite { source })?;
+    let tx = conn
+        .transaction()
+        .map_err(|source| FirefoxManagerError::SqliteWrite { source })?;
+
+    tx.execute_batch(&format!(
+        "CREATE TABLE moz_cookies (
+            id INTEGER PRIMARY KEY,
+            originAttributes TEXT NOT NULL DEFAULT '',
+            name TEXT,
+            value TEXT,
+            host TEXT,
+            path TEXT,
+            expiry INTEGER,
+            lastAccessed INTEGER,
+            creationTime INTEGER,
+            isSecure INTEGER,
+            isHttpOnly INTEGER,
+            inBrowserElement INTEGER DEFAULT 0,
+            sameSite INTEGER DEFAULT 0,
+            rawSameSite INTEGER DEFAULT 0,
+            schemeMap INTEGER DEFAULT 0,
+            CONSTRAINT moz_uniqueid UNIQUE (name, host, path, originAttributes)
+        );
+        PRAGMA user_version = {SCHEMA_VERSION};"
+    ))
+    .map_err(|source| FirefoxManagerError::SqliteWrite { source })?;
+
+    let now = OffsetDateTime::now_utc();
+    // The access and creation times are stored in microseconds since the UNIX epoch.
+    let now_micros = (now.unix_timestamp_nanos() / 1000) as i64;
+
+    {
+        let mut stmt = tx
+            .prepare(
+                "INSERT OR REPLACE INTO moz_cookies (name, value, host, path, expiry,
+                    lastAccessed, creationTime, isSecure, isHttpOnly, sameSite, rawSameSite)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6, ?7, ?8, ?9, ?9)",
+            )
+            .map_err(|source| FirefoxManagerError::SqliteWrite { source })?;
+
+        for cookie in cookies {
+            let expiry = cookie
+                .expires_datetime()
+                .unwrap_or(now + SESSION_COOKIE_LIFETIME)
+                .unix_timestamp();
+            let same_site = match cookie.same_site() {
+                Some(SameSite::Strict) => 2,
+                Some(SameSite::Lax) => 1,
+                Some(SameSite::None) | None => 0,
+            };
+
+            stmt.execute(params![
+                cookie.name(),
+                cookie.value(),
+                cookie.stored_domain(),
+                cookie.path().unwrap_or("/"),
+                expiry,
+                now_micros,
+                cookie.secure().unwrap_or_default(),
+                cookie.http_only().unwrap_or_default(),
+                same_site,
+            ])
+            .map_err(|source| FirefoxManagerError::SqliteWrite { source })?;
+        }
+    }
+
+    tx.commit()
+        .map_err(|source| FirefoxManagerError::SqliteWrite { source })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use cookie::Cookie;
+
+    use crate::{firefox::FirefoxManager, CookiePathProvider};
+
+    use super::*;
+
+    struct TestPathProvider(PathBuf);
+
+    impl CookiePathProvider for TestPathProvider {
+        fn cookies_database(&self) -> PathBuf {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn test_write_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cookies.sqlite");
+        let cookies: [BrowserCookie; 2] = [
+            Cookie::build(("sid", "abc"))
+                .domain("example.com")
+                .path("/")
+                .secure(true)
+                .http_only(true)
+                .same_site(SameSite::Lax)
+                .expires(OffsetDateTime::from_unix_timestamp(2000000000).unwrap())
+                .into(),
+            BrowserCookie {
+                host_only: true,
+                ..Cookie::build(("lang", "en"))
+                    .domain("www.example.com")
+                    .path("/app")
+                    .into()
+            },
+        ];
+
+        write_database(&path, &cookies).unwrap();
+        assert!(write_database(&path, &cookies).is_err());
+
+        let manager =
+            FirefoxManager::new(TestPathProvider(path), Some(Box::new(|_| true)), false).unwrap();
+        let read = manager.get_cookies_with_report().unwrap().cookies;
+
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[0].name(), "sid");
+        assert_eq!(read[0].domain(), Some("example.com"));
+        assert_eq!(read[0].same_site(), Some(SameSite::Lax));
+        assert_eq!(read[0].http_only(), Some(true));
+        assert_eq!(
+            read[0]
+                .expires_datetime()
+                .map(OffsetDateTime::unix_timestamp),
+            Some(2000000000)
+        );
+        assert!(!read[0].host_only);
+        assert_eq!(read[1].path(), Some("/app"));
+        assert!(read[1].host_only);
+        assert!(read[1].expires_datetime().unwrap() > OffsetDateTime::now_utc());
+    }
+}
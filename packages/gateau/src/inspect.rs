@@ -0,0 +1,219 @@
+//! Introspection of cookies databases.
+//!
+//! This module gathers facts about a cookies database (browser family, schema version,
+//! row counts, encryption versions of the values), which are useful to diagnose
+//! extraction issues without disclosing the cookies themselves.
+
+use std::{collections::BTreeMap, path::Path};
+
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::get_connection;
+
+#[derive(Debug, thiserror::Error)]
+pub enum InspectError {
+    #[error("Failed to open database: {source}")]
+    DatabaseOpen { source: rusqlite::Error },
+
+    #[error("Failed to execute SQL query: {source}")]
+    SqliteQuery {
+        query: String,
+        source: rusqlite::Error,
+    },
+
+    #[error("Unknown database schema, no cookies table found")]
+    UnknownSchema,
+}
+
+pub type Result<T, E = InspectError> = std::result::Result<T, E>;
+
+/// Family of the browser which created a cookies database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BrowserFamily {
+    /// Firefox and its derivatives (`moz_cookies` table).
+    Firefox,
+    /// Chrome and its derivatives (`cookies` and `meta` tables).
+    Chromium,
+}
+
+/// Number of cookie values for each encryption version.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct EncryptionHistogram {
+    /// Values which are not encrypted.
+    pub plaintext: u64,
+    /// Values encrypted with a hardcoded or OS-specific key (`v10` prefix).
+    pub v10: u64,
+    /// Values encrypted with a key stored in the keyring (`v11` prefix).
+    pub v11: u64,
+    /// Values encrypted with an app-bound key (`v20` prefix).
+    pub v20: u64,
+    /// Values with an unknown prefix (e.g. DPAPI-only on Windows).
+    pub unknown: u64,
+}
+
+/// Facts about a cookies database.
+#[derive(Debug, Clone, Serialize)]
+pub struct DatabaseInfo {
+    /// Detected browser family.
+    pub family: BrowserFamily,
+    /// Version of the schema, as stored by the browser.
+    pub schema_version: Option<i64>,
+    /// Number of rows of each table.
+    pub row_counts: BTreeMap<String, u64>,
+    /// Histogram of the encryption versions of the cookie values.
+    pub encryption: EncryptionHistogram,
+}
+
+/// Inspect the cookies database at the given path.
+pub fn inspect_database<P: AsRef<Path>>(path: P, bypass_lock: bool) -> Result<DatabaseInfo> {
+    let conn = get_connection(path, bypass_lock)
+        .map_err(|source| InspectError::DatabaseOpen { source })?;
+
+    let tables = query_column::<String>(
+        &conn,
+        "SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name",
+    )?;
+
+    let family = if tables.iter().any(|t| t == "moz_cookies") {
+        BrowserFamily::Firefox
+    } else if tables.iter().any(|t| t == "cookies") && tables.iter().any(|t| t == "meta") {
+        BrowserFamily::Chromium
+    } else {
+        return Err(InspectError::UnknownSchema);
+    };
+
+    let row_counts = tables
+        .iter()
+        .map(|table| {
+            let query = format!("SELECT COUNT(*) FROM \"{}\"", table.replace('"', "\"\""));
+            let count = query_column::<u64>(&conn, &query)?
+                .into_iter()
+                .next()
+                .unwrap_or_default();
+
+            Ok((table.clone(), count))
+        })
+        .collect::<Result<BTreeMap<_, _>>>()?;
+
+    let (schema_version, encryption) = match family {
+        BrowserFamily::Firefox => {
+            let version = query_column::<i64>(&conn, "PRAGMA user_version")?;
+
+            let encryption = EncryptionHistogram {
+                plaintext: row_counts.get("moz_cookies").copied().unwrap_or_default(),
+                ..Default::default()
+            };
+
+            (version.into_iter().next(), encryption)
+        }
+
+        BrowserFamily::Chromium => {
+            let version =
+                query_column::<String>(&conn, "SELECT value FROM meta WHERE key = 'version'")?
+                    .into_iter()
+                    .next()
+                    .and_then(|v| v.parse().ok());
+
+            let mut encryption = EncryptionHistogram::default();
+            // The prefix is compared as bytes, since the encrypted values are not valid text.
+            let query = "SELECT CASE
+                    WHEN length(encrypted_value) = 0 THEN x''
+                    ELSE CAST(substr(encrypted_value, 1, 3) AS BLOB)
+                END AS prefix, COUNT(*)
+                FROM cookies
+                GROUP BY prefix";
+
+            let map_err = |source| InspectError::SqliteQuery {
+                query: query.to_string(),
+                source,
+            };
+
+            let mut stmt = conn.prepare(query).map_err(map_err)?;
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok((row.get::<_, Option<Vec<u8>>>(0)?, row.get::<_, u64>(1)?))
+                })
+                .map_err(map_err)?;
+
+            for row in rows {
+                let (prefix, count) = row.map_err(map_err)?;
+
+                let bucket = match prefix.as_deref() {
+                    Some(b"") => &mut encryption.plaintext,
+                    Some(b"v10") => &mut encryption.v10,
+                    Some(b"v11") => &mut encryption.v11,
+                    Some(b"v20") => &mut encryption.v20,
+                    _ => &mut encryption.unknown,
+                };
+                *bucket += count;
+            }
+
+            (version, encryption)
+        }
+    };
+
+    Ok(DatabaseInfo {
+        family,
+        schema_version,
+        row_counts,
+        encryption,
+    })
+}
+
+/// Execute a query and collect the first column of each row.
+fn query_column<T: rusqlite::types::FromSql>(conn: &Connection, query: &str) -> Result<Vec<T>> {
+    let map_err = |source| InspectError::SqliteQuery {
+        query: query.to_string(),
+        source,
+    };
+
+    let mut stmt = conn.prepare(query).map_err(map_err)?;
+    let values = stmt
+        .query_map([], |row| row.get::<_, T>(0))
+        .map_err(map_err)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(map_err)?;
+
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inspect_chromium_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Cookies");
+
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE meta (key LONGVARCHAR NOT NULL UNIQUE PRIMARY KEY, value LONGVARCHAR);
+            INSERT INTO meta VALUES ('version', '18');
+            CREATE TABLE cookies (name TEXT NOT NULL, encrypted_value BLOB NOT NULL);
+            INSERT INTO cookies VALUES ('a', x''), ('b', CAST('v10abc' AS BLOB)),
+                ('c', CAST('v11abc' AS BLOB)), ('d', CAST('v11def' AS BLOB)), ('e', x'01000000'),
+                ('f', x'ffd8ff00');",
+        )
+        .unwrap();
+        drop(conn);
+
+        let info = inspect_database(&path, false).unwrap();
+
+        assert_eq!(info.family, BrowserFamily::Chromium);
+        assert_eq!(info.schema_version, Some(18));
+        assert_eq!(info.row_counts.get("cookies"), Some(&6));
+        assert_eq!(
+            info.encryption,
+            EncryptionHistogram {
+                plaintext: 1,
+                v10: 1,
+                v11: 2,
+                v20: 0,
+                unknown: 2,
+            }
+        );
+    }
+}
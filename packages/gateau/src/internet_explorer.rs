@@ -0,0 +1,276 @@
+//! Internet Explorer and Edge Legacy cookie management.
+//!
+//! Internet Explorer (up to Windows 8) and Edge Legacy (EdgeHTML) store their cookies
+//! in text files, one per site, in which each cookie is a record of 9 lines:
+//!
+//! ```text
+//! name
+//! value
+//! host/path
+//! flags
+//! expiration time (low 32 bits)
+//! expiration time (high 32 bits)
+//! creation time (low 32 bits)
+//! creation time (high 32 bits)
+//! *
+//! ```
+//!
+//! The times are Windows FILETIMEs, in 100 nanoseconds since 1601-01-01.
+//! Internet Explorer 11 on Windows 8.1 and later keeps its cookies in the
+//! `WebCacheV01.dat` ESE database instead, which is not supported.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use cookie::time::OffsetDateTime;
+use cookie::{Cookie, CookieBuilder, Expiration};
+
+use crate::CookiePathProvider;
+
+use super::HostFilterFn;
+
+mod paths;
+pub use paths::PathProvider;
+
+/// Browsers which store their cookies in the text format of Internet Explorer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InternetExplorerVariant {
+    InternetExplorer,
+    EdgeLegacy,
+}
+
+pub type Result<T, E = InternetExplorerManagerError> = std::result::Result<T, E>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum InternetExplorerManagerError {
+    #[error(
+        "Cookies directory {} not found (cookies stored in WebCacheV01.dat are not supported)",
+        path.display()
+    )]
+    CookiesDirNotFound { path: PathBuf },
+
+    #[error("Failed to read cookies from {}", path.display())]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+/// Flag of the cookies which are only sent over secure connections.
+const FLAG_SECURE: u32 = 0x1;
+/// Flag of the cookies which are not accessible to scripts.
+const FLAG_HTTP_ONLY: u32 = 0x2000;
+
+// Offset of UNIX epoch (1970-01-01 00:00:00 UTC) from Windows FILETIME epoch
+// (1601-01-01 00:00:00 UTC), in seconds.
+const WINDOWS_UNIX_EPOCH_OFFSET_SECS: i64 = 11644473600;
+
+/// Number of FILETIME intervals (100 nanoseconds) in a second.
+const FILETIME_INTERVALS_PER_SEC: i64 = 10_000_000;
+
+/// Cookie record from a cookie file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CookieRecord<'a> {
+    name: &'a str,
+    value: &'a str,
+    host: &'a str,
+    path: &'a str,
+    flags: u32,
+    expires: i64,
+    creation: i64,
+}
+
+/// Internet Explorer cookies manager.
+pub struct InternetExplorerManager<P: CookiePathProvider> {
+    path_provider: P,
+    filter: Option<Mutex<Box<HostFilterFn>>>,
+    as_of: Option<OffsetDateTime>,
+}
+
+impl<P: CookiePathProvider> InternetExplorerManager<P> {
+    /// Create a new Internet Explorer manager.
+    pub fn new(path_provider: P, filter: Option<Box<HostFilterFn>>) -> Result<Self> {
+        let path = path_provider.cookies_database();
+        if !path.is_dir() {
+            return Err(InternetExplorerManagerError::CookiesDirNotFound { path });
+        }
+
+        Ok(Self {
+            path_provider,
+            filter: filter.map(Mutex::new),
+            as_of: None,
+        })
+    }
+
+    /// Get the path provider.
+    pub fn path_provider(&self) -> &P {
+        &self.path_provider
+    }
+
+    /// Only get the cookies which were created at or before the given time.
+    pub fn as_of(mut self, time: OffsetDateTime) -> Self {
+        self.as_of = Some(time);
+        self
+    }
+
+    /// Get all cookies from the cookie files of the directory,
+    /// and of its `Low` subdirectory (used by the protected mode).
+    pub fn get_cookies(&self) -> Result<Vec<Cookie<'static>>> {
+        let cookies_dir = self.path_provider.cookies_database();
+        let as_of = self
+            .as_of
+            .map(|time| unix_to_filetime(time.unix_timestamp()));
+
+        let mut cookies = Vec::new();
+        for dir in [cookies_dir.clone(), cookies_dir.join("Low")] {
+            for path in cookie_files(&dir)? {
+                let content = fs::read(&path)
+                    .map_err(|source| InternetExplorerManagerError::Read { path, source })?;
+                let content = String::from_utf8_lossy(&content);
+
+                cookies.extend(
+                    parse_cookie_file(&content)
+                        .into_iter()
+                        .filter(|record| as_of.is_none_or(|as_of| record.creation <= as_of))
+                        .filter(|record| self.filter_host(record.host))
+                        .map(CookieRecord::into_cookie),
+                );
+            }
+        }
+
+        Ok(cookies)
+    }
+
+    fn filter_host(&self, host: &str) -> bool {
+        self.filter
+            .as_ref()
+            .is_none_or(|filter| (filter.lock().unwrap())(host))
+    }
+}
+
+impl InternetExplorerManager<PathProvider> {
+    /// Create a new Internet Explorer manager with the default cookies directory of the variant.
+    pub fn default_profile(
+        variant: InternetExplorerVariant,
+        filter: Option<Box<HostFilterFn>>,
+    ) -> Result<Self> {
+        Self::new(PathProvider::default_profile(variant), filter)
+    }
+}
+
+impl CookieRecord<'_> {
+    fn into_cookie(self) -> Cookie<'static> {
+        let expires = (self.expires / FILETIME_INTERVALS_PER_SEC - WINDOWS_UNIX_EPOCH_OFFSET_SECS)
+            .clamp(0, 253402300799);
+
+        CookieBuilder::new(self.name.to_string(), self.value.to_string())
+            .domain(self.host.to_string())
+            .path(self.path.to_string())
+            .expires(Expiration::from(
+                OffsetDateTime::from_unix_timestamp(expires).expect("Invalid timestamp"),
+            ))
+            .secure(self.flags & FLAG_SECURE != 0)
+            .http_only(self.flags & FLAG_HTTP_ONLY != 0)
+            .into()
+    }
+}
+
+/// Returns the cookie files (`*.txt` or `*.cookie`) of the directory, if it exists.
+fn cookie_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(source) => {
+            return Err(InternetExplorerManagerError::Read {
+                path: dir.to_owned(),
+                source,
+            })
+        }
+    };
+
+    Ok(entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|ext| ext == "txt" || ext == "cookie")
+        })
+        .collect())
+}
+
+/// Parse the records of a cookie file, skipping the invalid ones.
+fn parse_cookie_file(content: &str) -> Vec<CookieRecord<'_>> {
+    let lines = content.lines().collect::<Vec<_>>();
+
+    lines
+        .split(|line| *line == "*")
+        .filter_map(|record| {
+            let [name, value, host_path, flags, expires_low, expires_high, creation_low, creation_high] =
+                record
+            else {
+                return None;
+            };
+
+            let filetime = |low: &str, high: &str| -> Option<i64> {
+                Some(((high.parse::<u32>().ok()? as i64) << 32) | low.parse::<u32>().ok()? as i64)
+            };
+            let (host, path) = match host_path.find('/') {
+                Some(index) => host_path.split_at(index),
+                None => (*host_path, "/"),
+            };
+
+            Some(CookieRecord {
+                name,
+                value,
+                host,
+                path,
+                flags: flags.parse().ok()?,
+                expires: filetime(expires_low, expires_high)?,
+                creation: filetime(creation_low, creation_high)?,
+            })
+        })
+        .collect()
+}
+
+/// Convert a UNIX timestamp (in seconds) to a Windows FILETIME.
+const fn unix_to_filetime(timestamp: i64) -> i64 {
+    (timestamp + WINDOWS_UNIX_EPOCH_OFFSET_SECS) * FILETIME_INTERVALS_PER_SEC
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COOKIE_FILE: &str = "sid\nabc\nexample.com/\n8193\n2457927680\n31226545\n\
+        3329032192\n31070023\n*\n\
+        lang\nen\nwww.example.com/docs/\n0\n2457927680\n31226545\n3329032192\n31070023\n*\n\
+        broken\nrecord\n*\n";
+
+    #[test]
+    fn test_parse_cookie_file() {
+        let records = parse_cookie_file(COOKIE_FILE);
+
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].host, "example.com");
+        assert_eq!(records[0].path, "/");
+        assert_eq!(records[1].host, "www.example.com");
+        assert_eq!(records[1].path, "/docs/");
+
+        let cookie = records[0].clone().into_cookie();
+        assert_eq!(cookie.name_value(), ("sid", "abc"));
+        assert_eq!(cookie.secure(), Some(true));
+        assert_eq!(cookie.http_only(), Some(true));
+        assert_eq!(
+            cookie.expires_datetime().map(|time| time.year()),
+            Some(2026)
+        );
+    }
+
+    #[test]
+    fn test_unix_to_filetime() {
+        assert_eq!(unix_to_filetime(0), 116444736000000000);
+    }
+}
@@ -0,0 +1,66 @@
+use std::path::{Path, PathBuf};
+
+use crate::CookiePathProvider;
+
+use super::InternetExplorerVariant;
+
+/// Path provider for Internet Explorer and Edge Legacy.
+pub struct PathProvider {
+    cookies_dir: PathBuf,
+}
+
+impl PathProvider {
+    /// Create a new path provider for the given cookies directory.
+    pub fn from_root<R: AsRef<Path>>(root_dir: R) -> Self {
+        Self {
+            cookies_dir: root_dir.as_ref().to_owned(),
+        }
+    }
+
+    /// Returns a path provider for the cookies directory of the given variant.
+    ///
+    /// Internet Explorer moved its cookies directory in Windows 8,
+    /// so the location which exists is preferred.
+    pub fn default_profile(variant: InternetExplorerVariant) -> Self {
+        let mut candidates = PathProvider::variant_cookies_dirs(variant)
+            .into_iter()
+            .map(Self::from_root)
+            .collect::<Vec<_>>();
+
+        let index = candidates
+            .iter()
+            .position(|candidate| candidate.cookies_database().is_dir())
+            .unwrap_or(0);
+
+        candidates.swap_remove(index)
+    }
+
+    /// Returns the directories where the variant can store its cookies,
+    /// in order of preference.
+    fn variant_cookies_dirs(variant: InternetExplorerVariant) -> Vec<PathBuf> {
+        let local_dir = dirs_next::data_local_dir();
+
+        match variant {
+            InternetExplorerVariant::InternetExplorer => [
+                local_dir.map(|dir| dir.join("Microsoft/Windows/INetCookies")),
+                dirs_next::data_dir().map(|dir| dir.join("Microsoft/Windows/Cookies")),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+            InternetExplorerVariant::EdgeLegacy => local_dir
+                .map(|dir| {
+                    dir.join("Packages/Microsoft.MicrosoftEdge_8wekyb3d8bbwe/AC/#!001/MicrosoftEdge/Cookies")
+                })
+                .into_iter()
+                .collect(),
+        }
+    }
+}
+
+impl CookiePathProvider for PathProvider {
+    /// Returns the path to the directory of the cookie files.
+    fn cookies_database(&self) -> PathBuf {
+        self.cookies_dir.clone()
+    }
+}
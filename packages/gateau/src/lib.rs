@@ -1,6 +1,7 @@
 //! Library to read cookies from browsers.
 //!
-//! It supports Firefox and Chromium-based browsers.
+//! It supports Firefox, Chromium-based and WebKitGTK browsers,
+//! as well as Internet Explorer and Edge Legacy on Windows.
 
 use std::path::PathBuf;
 use std::str::FromStr;
@@ -9,9 +10,20 @@ use std::{ffi::OsString, path::Path};
 use rusqlite::{Connection, OpenFlags};
 
 use self::chrome::ChromeVariant;
+use self::firefox::FirefoxVariant;
+use self::internet_explorer::InternetExplorerVariant;
+use self::webkitgtk::WebKitGtkVariant;
 
 pub mod chrome;
+pub mod confinement;
 pub mod firefox;
+pub mod inspect;
+pub mod internet_explorer;
+pub mod lock;
+pub mod output;
+pub mod report;
+pub mod time;
+pub mod webkitgtk;
 
 /// Function to filter hosts.
 pub type HostFilterFn = dyn FnMut(&str) -> bool + Send + Sync;
@@ -19,21 +31,111 @@ pub type HostFilterFn = dyn FnMut(&str) -> bool + Send + Sync;
 /// Represents the supported browsers.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Browser {
-    Firefox,
+    FirefoxVariant(FirefoxVariant),
     ChromeVariant(ChromeVariant),
+    WebKitGtkVariant(WebKitGtkVariant),
+    InternetExplorerVariant(InternetExplorerVariant),
 }
 
 impl std::fmt::Display for Browser {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Browser::Firefox => write!(f, "Firefox"),
+            Browser::FirefoxVariant(FirefoxVariant::Firefox) => write!(f, "Firefox"),
+            Browser::FirefoxVariant(FirefoxVariant::TorBrowser) => write!(f, "Tor Browser"),
+            Browser::FirefoxVariant(FirefoxVariant::Waterfox) => write!(f, "Waterfox"),
+            Browser::FirefoxVariant(FirefoxVariant::PaleMoon) => write!(f, "Pale Moon"),
+            Browser::FirefoxVariant(FirefoxVariant::SeaMonkey) => write!(f, "SeaMonkey"),
+            Browser::FirefoxVariant(FirefoxVariant::Thunderbird) => write!(f, "Thunderbird"),
+            Browser::FirefoxVariant(FirefoxVariant::Floorp) => write!(f, "Floorp"),
+            Browser::FirefoxVariant(FirefoxVariant::Zen) => write!(f, "Zen Browser"),
+            Browser::FirefoxVariant(FirefoxVariant::Fork(fork)) => write!(f, "{}", fork.name),
             Browser::ChromeVariant(ChromeVariant::Chromium) => write!(f, "Chromium"),
             Browser::ChromeVariant(ChromeVariant::Chrome) => write!(f, "Google Chrome"),
+            Browser::ChromeVariant(ChromeVariant::ChromeBeta) => write!(f, "Google Chrome Beta"),
+            Browser::ChromeVariant(ChromeVariant::ChromeDev) => write!(f, "Google Chrome Dev"),
+            Browser::ChromeVariant(ChromeVariant::ChromeCanary) => {
+                write!(f, "Google Chrome Canary")
+            }
             Browser::ChromeVariant(ChromeVariant::Edge) => write!(f, "Microsoft Edge"),
+            Browser::ChromeVariant(ChromeVariant::EdgeBeta) => write!(f, "Microsoft Edge Beta"),
+            Browser::ChromeVariant(ChromeVariant::EdgeDev) => write!(f, "Microsoft Edge Dev"),
+            Browser::ChromeVariant(ChromeVariant::EdgeCanary) => {
+                write!(f, "Microsoft Edge Canary")
+            }
+            Browser::ChromeVariant(ChromeVariant::Arc) => write!(f, "Arc"),
+            Browser::ChromeVariant(ChromeVariant::Yandex) => write!(f, "Yandex Browser"),
+            Browser::ChromeVariant(ChromeVariant::Whale) => write!(f, "Naver Whale"),
+            Browser::ChromeVariant(ChromeVariant::Falkon) => write!(f, "Falkon"),
+            Browser::ChromeVariant(ChromeVariant::Custom(custom)) => write!(f, "{}", custom.name),
+            Browser::WebKitGtkVariant(WebKitGtkVariant::Epiphany) => write!(f, "GNOME Web"),
+            Browser::InternetExplorerVariant(InternetExplorerVariant::InternetExplorer) => {
+                write!(f, "Internet Explorer")
+            }
+            Browser::InternetExplorerVariant(InternetExplorerVariant::EdgeLegacy) => {
+                write!(f, "Microsoft Edge Legacy")
+            }
         }
     }
 }
 
+impl Browser {
+    /// Firefox, which was a variant of its own before its derivatives were supported.
+    #[deprecated(
+        since = "0.4.0",
+        note = "use `Browser::FirefoxVariant(FirefoxVariant::Firefox)`"
+    )]
+    #[allow(non_upper_case_globals)]
+    pub const Firefox: Browser = Browser::FirefoxVariant(FirefoxVariant::Firefox);
+
+    /// Returns the browsers supported out of the box on the current platform.
+    pub fn builtin() -> Vec<Browser> {
+        let mut browsers = [
+            FirefoxVariant::Firefox,
+            FirefoxVariant::TorBrowser,
+            FirefoxVariant::Waterfox,
+            FirefoxVariant::PaleMoon,
+            FirefoxVariant::SeaMonkey,
+            FirefoxVariant::Thunderbird,
+            FirefoxVariant::Floorp,
+            FirefoxVariant::Zen,
+        ]
+        .map(Browser::FirefoxVariant)
+        .into_iter()
+        .chain(
+            [
+                ChromeVariant::Chromium,
+                ChromeVariant::Chrome,
+                ChromeVariant::ChromeBeta,
+                ChromeVariant::ChromeDev,
+                ChromeVariant::ChromeCanary,
+                ChromeVariant::Edge,
+                ChromeVariant::EdgeBeta,
+                ChromeVariant::EdgeDev,
+                ChromeVariant::EdgeCanary,
+                ChromeVariant::Arc,
+                ChromeVariant::Yandex,
+                ChromeVariant::Whale,
+                ChromeVariant::Falkon,
+            ]
+            .map(Browser::ChromeVariant),
+        )
+        .chain([Browser::WebKitGtkVariant(WebKitGtkVariant::Epiphany)])
+        .collect::<Vec<_>>();
+
+        if cfg!(windows) {
+            browsers.extend(
+                [
+                    InternetExplorerVariant::InternetExplorer,
+                    InternetExplorerVariant::EdgeLegacy,
+                ]
+                .map(Browser::InternetExplorerVariant),
+            );
+        }
+
+        browsers
+    }
+}
+
 impl FromStr for Browser {
     type Err = String;
 
@@ -41,19 +143,70 @@ impl FromStr for Browser {
     ///
     /// Supported browsers are:
     /// - firefox
+    /// - tor-browser
+    /// - waterfox
+    /// - palemoon
+    /// - seamonkey
+    /// - thunderbird
+    /// - floorp
+    /// - zen
     /// - chromium
     /// - chrome
+    /// - chrome-beta
+    /// - chrome-dev
+    /// - chrome-canary
     /// - edge
+    /// - edge-beta
+    /// - edge-dev
+    /// - edge-canary
+    /// - arc
+    /// - yandex
+    /// - whale
+    /// - falkon
+    /// - epiphany
+    /// - ie (Windows only)
+    /// - edge-legacy (Windows only)
     ///
+    /// as well as the names of the browsers registered with [`chrome::register_variant`]
+    /// and [`firefox::register_fork`].
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "firefox" => Ok(Browser::Firefox),
+            "firefox" => Ok(Browser::FirefoxVariant(FirefoxVariant::Firefox)),
+            "tor-browser" => Ok(Browser::FirefoxVariant(FirefoxVariant::TorBrowser)),
+            "waterfox" => Ok(Browser::FirefoxVariant(FirefoxVariant::Waterfox)),
+            "palemoon" => Ok(Browser::FirefoxVariant(FirefoxVariant::PaleMoon)),
+            "seamonkey" => Ok(Browser::FirefoxVariant(FirefoxVariant::SeaMonkey)),
+            "thunderbird" => Ok(Browser::FirefoxVariant(FirefoxVariant::Thunderbird)),
+            "floorp" => Ok(Browser::FirefoxVariant(FirefoxVariant::Floorp)),
+            "zen" => Ok(Browser::FirefoxVariant(FirefoxVariant::Zen)),
             "chromium" => Ok(Browser::ChromeVariant(ChromeVariant::Chromium)),
             "chrome" => Ok(Browser::ChromeVariant(ChromeVariant::Chrome)),
+            "chrome-beta" => Ok(Browser::ChromeVariant(ChromeVariant::ChromeBeta)),
+            "chrome-dev" => Ok(Browser::ChromeVariant(ChromeVariant::ChromeDev)),
+            "chrome-canary" => Ok(Browser::ChromeVariant(ChromeVariant::ChromeCanary)),
             "edge" => Ok(Browser::ChromeVariant(ChromeVariant::Edge)),
-            _ => Err(format!(
-                "'{s}' is not one of the supported browsers (firefox, chromium, chrome, edge)"
+            "edge-beta" => Ok(Browser::ChromeVariant(ChromeVariant::EdgeBeta)),
+            "edge-dev" => Ok(Browser::ChromeVariant(ChromeVariant::EdgeDev)),
+            "edge-canary" => Ok(Browser::ChromeVariant(ChromeVariant::EdgeCanary)),
+            "arc" => Ok(Browser::ChromeVariant(ChromeVariant::Arc)),
+            "yandex" => Ok(Browser::ChromeVariant(ChromeVariant::Yandex)),
+            "whale" => Ok(Browser::ChromeVariant(ChromeVariant::Whale)),
+            "falkon" => Ok(Browser::ChromeVariant(ChromeVariant::Falkon)),
+            "epiphany" => Ok(Browser::WebKitGtkVariant(WebKitGtkVariant::Epiphany)),
+            #[cfg(windows)]
+            "ie" => Ok(Browser::InternetExplorerVariant(
+                InternetExplorerVariant::InternetExplorer,
+            )),
+            #[cfg(windows)]
+            "edge-legacy" => Ok(Browser::InternetExplorerVariant(
+                InternetExplorerVariant::EdgeLegacy,
             )),
+            _ => chrome::custom_variant(s)
+                .map(Browser::ChromeVariant)
+                .or_else(|| firefox::fork_variant(s).map(Browser::FirefoxVariant))
+                .ok_or_else(|| format!(
+                "'{s}' is not one of the supported browsers (firefox, tor-browser, waterfox, palemoon, seamonkey, thunderbird, floorp, zen, chromium, chrome, chrome-beta, chrome-dev, chrome-canary, edge, edge-beta, edge-dev, edge-canary, arc, yandex, whale, falkon, epiphany)"
+                )),
         }
     }
 }
@@ -62,17 +215,91 @@ impl FromStr for Browser {
 pub trait CookiePathProvider {
     /// Returns the path to the cookies database.
     fn cookies_database(&self) -> PathBuf;
+
+    /// Returns the paths of the files used by the browser to lock the profile while running.
+    fn lock_files(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+}
+
+/// Returns whether the file is on a read-only filesystem (e.g. a mounted forensic image),
+/// from the flags of the filesystem.
+///
+/// A file which cannot be written because of its permissions is not on a read-only filesystem,
+/// and `false` is returned if the filesystem cannot be queried (e.g. the file does not exist).
+#[cfg(unix)]
+#[allow(unsafe_code)]
+pub fn is_read_only_filesystem<P: AsRef<Path>>(path: P) -> bool {
+    use std::{ffi::CString, mem::MaybeUninit, os::unix::ffi::OsStrExt};
+
+    let Ok(path) = CString::new(path.as_ref().as_os_str().as_bytes()) else {
+        return false;
+    };
+    let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+
+    // SAFETY: the path is a valid C string, and the structure is only read if it has been filled.
+    unsafe {
+        libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) == 0
+            && stat.assume_init().f_flag & libc::ST_RDONLY != 0
+    }
+}
+
+/// Returns whether the file is on a read-only volume (e.g. a mounted forensic image),
+/// from the flags of the volume.
+///
+/// A file which cannot be written because of its permissions is not on a read-only volume,
+/// and `false` is returned if the volume cannot be queried (e.g. the file does not exist).
+#[cfg(windows)]
+#[allow(unsafe_code)]
+pub fn is_read_only_filesystem<P: AsRef<Path>>(path: P) -> bool {
+    use windows::{
+        core::{HSTRING, PCWSTR},
+        Win32::{
+            Storage::FileSystem::{GetVolumeInformationW, GetVolumePathNameW},
+            System::SystemServices::FILE_READ_ONLY_VOLUME,
+        },
+    };
+
+    /// Maximum length of a path, in characters.
+    const MAX_PATH_LEN: usize = 32767;
+
+    let mut volume = vec![0u16; MAX_PATH_LEN];
+    let mut flags = 0u32;
+
+    // SAFETY: the volume buffer outlives the calls, and is only read once it has been filled
+    // with a null-terminated path.
+    unsafe {
+        GetVolumePathNameW(&HSTRING::from(path.as_ref()), &mut volume).is_ok()
+            && GetVolumeInformationW(
+                PCWSTR(volume.as_ptr()),
+                None,
+                None,
+                None,
+                Some(&mut flags),
+                None,
+            )
+            .is_ok()
+            && flags & FILE_READ_ONLY_VOLUME != 0
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn is_read_only_filesystem<P: AsRef<Path>>(_path: P) -> bool {
+    false
 }
 
 /// Get a connection to the database, while bypassing the file locking if `bypass_lock` is `true`.
 /// Bypassing the lock mechanism can lead to read errors if the browser is still running and writing to the database.
+///
+/// The lock is always bypassed on a read-only filesystem, where SQLite can neither recover
+/// the write-ahead log nor create its shared memory file next to the database.
 fn get_connection<P: AsRef<Path>>(
     db_path: P,
     bypass_lock: bool,
 ) -> Result<Connection, rusqlite::Error> {
     const PREFIX_LEN: usize = "file:".len() + "?immutable=1".len();
 
-    if bypass_lock {
+    if bypass_lock || is_read_only_filesystem(&db_path) {
         let db_path = db_path.as_ref().as_os_str();
         let immutable_path_uri = {
             let mut path = OsString::with_capacity(PREFIX_LEN + db_path.len());
@@ -90,3 +317,45 @@ fn get_connection<P: AsRef<Path>>(
         Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_browser_from_str() {
+        assert_eq!(
+            "waterfox".parse::<Browser>(),
+            Ok(Browser::FirefoxVariant(FirefoxVariant::Waterfox))
+        );
+        assert_eq!(
+            "chrome".parse::<Browser>(),
+            Ok(Browser::ChromeVariant(ChromeVariant::Chrome))
+        );
+        assert!("netscape".parse::<Browser>().is_err());
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_deprecated_firefox() {
+        let browser = "firefox".parse::<Browser>().unwrap();
+
+        assert_eq!(browser, Browser::Firefox);
+        assert!(matches!(browser, Browser::Firefox));
+        assert_eq!(browser.to_string(), "Firefox");
+    }
+
+    #[test]
+    fn test_is_read_only_filesystem() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+
+        assert!(!is_read_only_filesystem(file.path()));
+        let mut permissions = file.as_file().metadata().unwrap().permissions();
+        permissions.set_readonly(true);
+        file.as_file().set_permissions(permissions).unwrap();
+        assert!(!is_read_only_filesystem(file.path()));
+        assert!(!is_read_only_filesystem(
+            file.path().with_extension("missing")
+        ));
+    }
+}
@@ -0,0 +1,216 @@
+//! Detection of the lock files created by browsers to mark a profile as in use.
+//!
+//! Chrome creates a `SingletonLock` symbolic link (`lockfile` on Windows) in its
+//! user data directory, and Firefox creates a `lock` symbolic link (`parent.lock` on Windows)
+//! in its profile directory, as long as they are running.
+//! On Unix platforms, the target of the symbolic link contains the host name and the PID
+//! of the process which owns the profile.
+//! On macOS, Firefox only locks a `.parentlock` file with `fcntl`, which is left after it exits.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::CookiePathProvider;
+
+/// Process owning a profile lock.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockOwner {
+    /// Host name (or IP address) of the machine running the process.
+    pub host: String,
+    /// PID of the process.
+    pub pid: u32,
+}
+
+impl LockOwner {
+    /// Parse the owner from the target of a lock symbolic link,
+    /// which is `host-PID` for Chrome and `host:+PID` for Firefox.
+    fn parse(target: &str) -> Option<Self> {
+        let (host, pid) = target
+            .rsplit_once(':')
+            .or_else(|| target.rsplit_once('-'))?;
+
+        Some(LockOwner {
+            host: host.to_string(),
+            pid: pid.trim_start_matches('+').parse().ok()?,
+        })
+    }
+}
+
+/// Lock of a profile held by a running browser.
+#[derive(Debug, Clone)]
+pub struct ProfileLock {
+    path: PathBuf,
+    owner: Option<LockOwner>,
+}
+
+impl ProfileLock {
+    /// Detect whether the profile provided by `path_provider` is locked by a running browser.
+    pub fn detect<P: CookiePathProvider + ?Sized>(path_provider: &P) -> Option<Self> {
+        path_provider
+            .lock_files()
+            .into_iter()
+            .find_map(|path| Self::from_path(&path))
+    }
+
+    fn from_path(path: &Path) -> Option<Self> {
+        let metadata = fs::symlink_metadata(path).ok()?;
+
+        let owner = if metadata.file_type().is_symlink() {
+            fs::read_link(path)
+                .ok()
+                .and_then(|target| LockOwner::parse(&target.to_string_lossy()))
+        } else {
+            None
+        };
+
+        let lock = ProfileLock {
+            path: path.to_owned(),
+            owner,
+        };
+
+        lock.is_held().then_some(lock)
+    }
+
+    /// Path of the lock file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Process owning the lock, if it is known.
+    pub fn owner(&self) -> Option<&LockOwner> {
+        self.owner.as_ref()
+    }
+
+    /// Returns whether the lock is still held (best effort).
+    ///
+    /// On Linux, the liveness of the owner process is checked when it runs on the same machine.
+    /// On Windows, the lock file is considered as held if it cannot be opened,
+    /// since browsers keep it open exclusively while running.
+    /// On other Unix platforms, a lock file which is not a symbolic link is held
+    /// as long as a process has a `fcntl` lock on it.
+    /// Otherwise, the lock is considered as held as long as the lock file exists.
+    pub fn is_held(&self) -> bool {
+        if fs::symlink_metadata(&self.path).is_err() {
+            return false;
+        }
+
+        if cfg!(windows) {
+            return fs::OpenOptions::new().read(true).open(&self.path).is_err();
+        }
+
+        #[cfg(unix)]
+        if !self.path.is_symlink() {
+            return is_fcntl_locked(&self.path);
+        }
+
+        match &self.owner {
+            Some(owner) if cfg!(target_os = "linux") && is_local_host(&owner.host) => {
+                Path::new("/proc").join(owner.pid.to_string()).exists()
+            }
+            _ => true,
+        }
+    }
+
+    /// Block until the lock is released, checking it every `interval`,
+    /// for at most `timeout`.
+    ///
+    /// Returns whether the lock has been released, since a lock left by a browser
+    /// which crashed, or held by a process on another machine, may never be.
+    pub fn wait(&self, interval: Duration, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+
+        while self.is_held() {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            thread::sleep(interval);
+        }
+
+        true
+    }
+}
+
+/// Returns whether a process holds a `fcntl` lock on the file,
+/// or if it cannot be checked.
+#[cfg(unix)]
+#[allow(unsafe_code)]
+fn is_fcntl_locked(path: &Path) -> bool {
+    use std::{mem::MaybeUninit, os::fd::AsRawFd};
+
+    let Ok(file) = fs::File::open(path) else {
+        return true;
+    };
+    // SAFETY: the structure is plain data, for which zeroes are valid.
+    let mut lock = unsafe { MaybeUninit::<libc::flock>::zeroed().assume_init() };
+    lock.l_type = libc::F_WRLCK as _;
+    lock.l_whence = libc::SEEK_SET as _;
+
+    // SAFETY: the file descriptor is open, and the structure is valid for the duration of the call.
+    let result = unsafe { libc::fcntl(file.as_raw_fd(), libc::F_GETLK, &mut lock) };
+
+    result == -1 || lock.l_type as libc::c_int != libc::F_UNLCK as libc::c_int
+}
+
+/// Returns whether the host of a lock owner designates the current machine.
+fn is_local_host(host: &str) -> bool {
+    let hostname = fs::read_to_string("/proc/sys/kernel/hostname").unwrap_or_default();
+
+    host == hostname.trim() || host.starts_with("127.") || host == "::1"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_lock_owner() {
+        assert_eq!(
+            LockOwner::parse("myhost-12345"),
+            Some(LockOwner {
+                host: "myhost".to_string(),
+                pid: 12345
+            })
+        );
+        assert_eq!(
+            LockOwner::parse("my-host-12345"),
+            Some(LockOwner {
+                host: "my-host".to_string(),
+                pid: 12345
+            })
+        );
+        assert_eq!(
+            LockOwner::parse("127.0.1.1:+4242"),
+            Some(LockOwner {
+                host: "127.0.1.1".to_string(),
+                pid: 4242
+            })
+        );
+        assert_eq!(LockOwner::parse("invalid"), None);
+    }
+
+    #[test]
+    fn test_wait() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(".parentlock");
+        fs::write(&path, "").unwrap();
+
+        // The lock file is left, but no process has it open nor locks it.
+        assert!(ProfileLock::from_path(&path).is_none());
+
+        #[cfg(unix)]
+        {
+            // The liveness of a process on another machine cannot be checked.
+            let path = dir.path().join("lock");
+            std::os::unix::fs::symlink("remote-host:+1", &path).unwrap();
+            let lock = ProfileLock::from_path(&path).unwrap();
+            assert!(!lock.wait(Duration::from_millis(10), Duration::from_millis(50)));
+
+            fs::remove_file(&path).unwrap();
+            assert!(lock.wait(Duration::from_millis(10), Duration::from_millis(50)));
+        }
+    }
+}
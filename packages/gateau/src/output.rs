@@ -0,0 +1,539 @@
+//! Serialization of the cookies for other tools.
+
+use std::io::{self, Write};
+
+use cookie::SameSite;
+use serde::Serialize;
+
+use crate::{chrome, report::BrowserCookie};
+
+/// Cookie as serialized in JSON.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JsonCookie<'a> {
+    pub name: &'a str,
+    pub value: &'a str,
+    pub domain: Option<&'a str>,
+    /// Whether the cookie is only sent to its domain, and not to its subdomains.
+    pub host_only: bool,
+    pub path: Option<&'a str>,
+    /// Expiration time, as a UNIX timestamp, or `None` for session cookies.
+    pub expires: Option<i64>,
+    pub secure: bool,
+    pub http_only: bool,
+    /// SameSite attribute (`Strict`, `Lax` or `None`), if it is set.
+    pub same_site: Option<&'static str>,
+    pub partitioned: bool,
+    /// Top-level site by which the cookie is partitioned, only serialized if it is partitioned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partition_key: Option<&'a str>,
+    /// Source from which the cookie was read, only serialized if the cookies of several sources
+    /// are merged.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<&'a str>,
+    /// Whether the cookie was set by the page of a Chromium extension, only serialized if so.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub extension: bool,
+    /// Category of the cookie (e.g. `analytics`), only serialized if the cookies are classified.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<&'a str>,
+}
+
+impl<'a> From<&'a BrowserCookie> for JsonCookie<'a> {
+    fn from(cookie: &'a BrowserCookie) -> Self {
+        Self {
+            name: cookie.name(),
+            value: cookie.value(),
+            domain: cookie.domain(),
+            host_only: cookie.host_only,
+            path: cookie.path(),
+            expires: cookie
+                .expires()
+                .and_then(|expiration| expiration.datetime())
+                .map(|time| time.unix_timestamp()),
+            secure: cookie.secure().unwrap_or(false),
+            http_only: cookie.http_only().unwrap_or(false),
+            same_site: cookie.same_site().map(|same_site| match same_site {
+                SameSite::Strict => "Strict",
+                SameSite::Lax => "Lax",
+                SameSite::None => "None",
+            }),
+            partitioned: cookie.partitioned().unwrap_or(false),
+            partition_key: cookie.partition_key.as_deref(),
+            source: cookie.source.as_deref(),
+            extension: chrome::is_extension_cookie(cookie),
+            category: None,
+        }
+    }
+}
+
+/// Write the cookies as a JSON array, for `jq` and other tools.
+pub fn json<W: Write>(cookies: &[BrowserCookie], writer: &mut W) -> io::Result<()> {
+    let cookies = cookies.iter().map(JsonCookie::from).collect::<Vec<_>>();
+
+    serde_json::to_writer(&mut *writer, &cookies)?;
+    writeln!(writer)
+}
+
+/// Write the cookies as a MessagePack array, with the same fields as the JSON output.
+///
+/// The cookies are encoded as maps keyed by the field names, so they can be decoded without a schema.
+#[cfg(feature = "msgpack")]
+pub fn msgpack<W: Write>(cookies: &[BrowserCookie], writer: &mut W) -> io::Result<()> {
+    let cookies = cookies.iter().map(JsonCookie::from).collect::<Vec<_>>();
+
+    let encoded = rmp_serde::to_vec_named(&cookies).map_err(io::Error::other)?;
+    writer.write_all(&encoded)
+}
+
+/// Write the cookies as a CBOR array, with the same fields as the JSON output.
+#[cfg(feature = "cbor")]
+pub fn cbor<W: Write>(cookies: &[BrowserCookie], writer: &mut W) -> io::Result<()> {
+    let cookies = cookies.iter().map(JsonCookie::from).collect::<Vec<_>>();
+
+    ciborium::into_writer(&cookies, writer).map_err(|e| match e {
+        ciborium::ser::Error::Io(e) => e,
+        ciborium::ser::Error::Value(message) => io::Error::other(message),
+    })
+}
+
+/// Cookie as serialized in JSON, with its value as stored by Chromium before its decryption.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RawJsonCookie<'a> {
+    #[serde(flatten)]
+    pub cookie: JsonCookie<'a>,
+    /// Content of the `encrypted_value` column in hexadecimal, empty for plain text values.
+    pub encrypted_value: String,
+    /// Way the value is encrypted (`v10`, `v11`, `v20` or `dpapi`), or `None` for plain text.
+    pub encryption_version: Option<&'static str>,
+}
+
+impl<'a> From<&'a BrowserCookie> for RawJsonCookie<'a> {
+    fn from(cookie: &'a BrowserCookie) -> Self {
+        Self {
+            cookie: JsonCookie::from(cookie),
+            encrypted_value: cookie
+                .raw_value
+                .as_ref()
+                .map(chrome::RawValue::encrypted_value_hex)
+                .unwrap_or_default(),
+            encryption_version: cookie
+                .raw_value
+                .as_ref()
+                .and_then(chrome::RawValue::version),
+        }
+    }
+}
+
+/// Write the cookies as a JSON array, with the values stored in the database
+/// alongside the decrypted ones, for the cookies read with their raw values.
+pub fn json_with_raw_values<W: Write>(cookies: &[BrowserCookie], writer: &mut W) -> io::Result<()> {
+    let cookies = cookies.iter().map(RawJsonCookie::from).collect::<Vec<_>>();
+
+    serde_json::to_writer(&mut *writer, &cookies)?;
+    writeln!(writer)
+}
+
+/// Cookie as expected by `page.setCookie` of Puppeteer
+/// (the `CookieParam` type of the Chrome DevTools Protocol).
+///
+/// The host-only cookies are given by the URL of their host instead of their domain,
+/// which would make them domain cookies.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PuppeteerCookie<'a> {
+    pub name: &'a str,
+    pub value: &'a str,
+    /// URL of the host and path of the cookie, over HTTPS if it is secure, only for the host-only cookies.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Domain of the cookie, omitted for the host-only cookies.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub domain: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<&'a str>,
+    pub secure: bool,
+    pub http_only: bool,
+    /// SameSite attribute (`Strict`, `Lax` or `None`), if it is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub same_site: Option<&'static str>,
+    /// Expiration time, in seconds since the UNIX epoch, omitted for session cookies.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires: Option<i64>,
+    /// Top-level site by which the cookie is partitioned, as accepted by Puppeteer and Playwright,
+    /// omitted if it is not partitioned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partition_key: Option<&'a str>,
+}
+
+impl<'a> From<&'a BrowserCookie> for PuppeteerCookie<'a> {
+    fn from(cookie: &'a BrowserCookie) -> Self {
+        let JsonCookie {
+            name,
+            value,
+            domain,
+            path,
+            expires,
+            secure,
+            http_only,
+            same_site,
+            partition_key,
+            ..
+        } = JsonCookie::from(cookie);
+
+        let url = domain.filter(|_| cookie.host_only).map(|host| {
+            let scheme = if secure { "https" } else { "http" };
+            format!("{scheme}://{host}{}", path.unwrap_or("/"))
+        });
+
+        Self {
+            name,
+            value,
+            domain: domain.filter(|_| !cookie.host_only),
+            url,
+            path,
+            secure,
+            http_only,
+            same_site,
+            expires,
+            partition_key,
+        }
+    }
+}
+
+/// Write the cookies as a JSON array of parameters for `page.setCookie` of Puppeteer
+/// (or `Network.setCookies` of the Chrome DevTools Protocol).
+pub fn puppeteer<W: Write>(cookies: &[BrowserCookie], writer: &mut W) -> io::Result<()> {
+    let cookies = cookies
+        .iter()
+        .map(PuppeteerCookie::from)
+        .collect::<Vec<_>>();
+
+    serde_json::to_writer(&mut *writer, &cookies)?;
+    writeln!(writer)
+}
+
+/// Cookie as exported and imported by the EditThisCookie and Cookie-Editor extensions
+/// (the `Cookie` type of the `chrome.cookies` API).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditThisCookie<'a> {
+    /// Domain, with a leading dot unless the cookie is host-only.
+    pub domain: String,
+    /// Expiration time, in seconds since the UNIX epoch, omitted for session cookies.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiration_date: Option<i64>,
+    /// Whether the cookie is only sent to its domain, and not to its subdomains.
+    pub host_only: bool,
+    pub http_only: bool,
+    pub name: &'a str,
+    /// Partition key of the cookie, omitted if it is not partitioned.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub partition_key: Option<PartitionKey<'a>>,
+    pub path: &'a str,
+    /// SameSite attribute (`no_restriction`, `lax`, `strict` or `unspecified`).
+    pub same_site: &'static str,
+    pub secure: bool,
+    pub session: bool,
+    /// Cookie store of the browser, the default store of Chrome (`0`).
+    pub store_id: &'static str,
+    pub value: &'a str,
+    /// Position of the cookie in the export, from 1.
+    pub id: usize,
+}
+
+/// Partition key of a cookie in the `chrome.cookies` API.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PartitionKey<'a> {
+    /// Top-level site by which the cookie is partitioned.
+    pub top_level_site: &'a str,
+}
+
+impl<'a> EditThisCookie<'a> {
+    /// Convert the cookie, at the given position in the export (from 1).
+    pub fn new(cookie: &'a BrowserCookie, id: usize) -> Self {
+        let JsonCookie {
+            name,
+            value,
+            path,
+            expires,
+            secure,
+            http_only,
+            partition_key,
+            ..
+        } = JsonCookie::from(cookie);
+
+        Self {
+            domain: cookie
+                .domain()
+                .map(|_| cookie.stored_domain())
+                .unwrap_or_default(),
+            expiration_date: expires,
+            host_only: cookie.host_only,
+            http_only,
+            name,
+            partition_key: partition_key.map(|top_level_site| PartitionKey { top_level_site }),
+            path: path.unwrap_or("/"),
+            same_site: match cookie.same_site() {
+                Some(SameSite::None) => "no_restriction",
+                Some(SameSite::Lax) => "lax",
+                Some(SameSite::Strict) => "strict",
+                None => "unspecified",
+            },
+            secure,
+            session: expires.is_none(),
+            store_id: "0",
+            value,
+            id,
+        }
+    }
+}
+
+/// Write the cookies as a JSON array which can be imported
+/// with the EditThisCookie and Cookie-Editor extensions.
+pub fn edit_this_cookie<W: Write>(cookies: &[BrowserCookie], writer: &mut W) -> io::Result<()> {
+    let cookies = cookies
+        .iter()
+        .zip(1..)
+        .map(|(cookie, id)| EditThisCookie::new(cookie, id))
+        .collect::<Vec<_>>();
+
+    serde_json::to_writer(&mut *writer, &cookies)?;
+    writeln!(writer)
+}
+
+#[cfg(test)]
+mod tests {
+    use cookie::{time::OffsetDateTime, Cookie};
+
+    use super::*;
+
+    #[test]
+    fn test_json() {
+        let cookies = [
+            Cookie::build(("sid", "abc"))
+                .domain(".example.com")
+                .path("/")
+                .expires(OffsetDateTime::from_unix_timestamp(2000000000).unwrap())
+                .secure(true)
+                .same_site(SameSite::Lax)
+                .into(),
+            BrowserCookie {
+                host_only: true,
+                partition_key: Some("https://example.org".to_string()),
+                source: Some("Firefox".to_string()),
+                ..Cookie::build(("lang", "en"))
+                    .domain("example.com")
+                    .partitioned(true)
+                    .into()
+            },
+        ];
+
+        let mut output = Vec::new();
+        json(&cookies, &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            concat!(
+                r#"[{"name":"sid","value":"abc","domain":"example.com","host_only":false,"path":"/","#,
+                r#""expires":2000000000,"secure":true,"http_only":false,"same_site":"Lax","partitioned":false},"#,
+                r#"{"name":"lang","value":"en","domain":"example.com","host_only":true,"path":null,"expires":null,"#,
+                r#""secure":false,"http_only":false,"same_site":null,"partitioned":true,"#,
+                r#""partition_key":"https://example.org","source":"Firefox"}]"#,
+                "\n"
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(any(feature = "msgpack", feature = "cbor"))]
+    fn test_binary_formats() {
+        let cookies = [
+            Cookie::build(("sid", "abc"))
+                .domain(".example.com")
+                .path("/")
+                .expires(OffsetDateTime::from_unix_timestamp(2000000000).unwrap())
+                .same_site(SameSite::Lax)
+                .into(),
+            Cookie::build(("lang", "en")).domain("example.com").into(),
+        ];
+
+        let mut output = Vec::new();
+        json(&cookies, &mut output).unwrap();
+        let expected = serde_json::from_slice::<serde_json::Value>(&output).unwrap();
+
+        #[cfg(feature = "msgpack")]
+        {
+            let mut output = Vec::new();
+            msgpack(&cookies, &mut output).unwrap();
+            assert_eq!(
+                rmp_serde::from_slice::<serde_json::Value>(&output).unwrap(),
+                expected
+            );
+        }
+
+        #[cfg(feature = "cbor")]
+        {
+            let mut output = Vec::new();
+            cbor(&cookies, &mut output).unwrap();
+            assert_eq!(
+                ciborium::from_reader::<serde_json::Value, _>(&output[..]).unwrap(),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_json_extension_cookie() {
+        let cookie = Cookie::build(("state", "1"))
+            .domain("nmmhkkegccagdldgiimedpiccmgmieda")
+            .into();
+
+        let mut output = Vec::new();
+        json(&[cookie], &mut output).unwrap();
+
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .ends_with("\"partitioned\":false,\"extension\":true}]\n"));
+    }
+
+    #[test]
+    fn test_puppeteer() {
+        let cookies = [
+            Cookie::build(("sid", "abc"))
+                .domain(".example.com")
+                .path("/")
+                .expires(OffsetDateTime::from_unix_timestamp(2000000000).unwrap())
+                .secure(true)
+                .http_only(true)
+                .same_site(SameSite::None)
+                .into(),
+            BrowserCookie {
+                host_only: true,
+                ..Cookie::build(("lang", "en"))
+                    .domain("www.example.com")
+                    .path("/app")
+                    .into()
+            },
+            BrowserCookie {
+                partition_key: Some("https://example.org".to_string()),
+                ..Cookie::build(("embed", "1"))
+                    .domain("example.com")
+                    .secure(true)
+                    .partitioned(true)
+                    .into()
+            },
+        ];
+
+        let mut output = Vec::new();
+        puppeteer(&cookies, &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            concat!(
+                r#"[{"name":"sid","value":"abc","domain":"example.com","path":"/","secure":true,"#,
+                r#""httpOnly":true,"sameSite":"None","expires":2000000000},"#,
+                r#"{"name":"lang","value":"en","url":"http://www.example.com/app","path":"/app","#,
+                r#""secure":false,"httpOnly":false},"#,
+                r#"{"name":"embed","value":"1","domain":"example.com","secure":true,"httpOnly":false,"#,
+                r#""partitionKey":"https://example.org"}]"#,
+                "\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_edit_this_cookie() {
+        let cookies = [
+            Cookie::build(("sid", "abc"))
+                .domain("example.com")
+                .path("/")
+                .expires(OffsetDateTime::from_unix_timestamp(2000000000).unwrap())
+                .http_only(true)
+                .same_site(SameSite::Lax)
+                .into(),
+            BrowserCookie {
+                host_only: true,
+                partition_key: Some("https://example.org".to_string()),
+                ..Cookie::build(("lang", "en"))
+                    .domain("www.example.com")
+                    .path("/")
+                    .partitioned(true)
+                    .into()
+            },
+        ];
+
+        let mut output = Vec::new();
+        edit_this_cookie(&cookies, &mut output).unwrap();
+        let output: serde_json::Value = serde_json::from_slice(&output).unwrap();
+
+        assert_eq!(
+            output,
+            serde_json::json!([
+                {
+                    "domain": ".example.com",
+                    "expirationDate": 2000000000,
+                    "hostOnly": false,
+                    "httpOnly": true,
+                    "name": "sid",
+                    "path": "/",
+                    "sameSite": "lax",
+                    "secure": false,
+                    "session": false,
+                    "storeId": "0",
+                    "value": "abc",
+                    "id": 1
+                },
+                {
+                    "domain": "www.example.com",
+                    "hostOnly": true,
+                    "httpOnly": false,
+                    "name": "lang",
+                    "partitionKey": {"topLevelSite": "https://example.org"},
+                    "path": "/",
+                    "sameSite": "unspecified",
+                    "secure": false,
+                    "session": true,
+                    "storeId": "0",
+                    "value": "en",
+                    "id": 2
+                }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_json_with_raw_values() {
+        let cookies = [
+            BrowserCookie {
+                raw_value: Some(chrome::RawValue {
+                    encrypted_value: b"v10\x01\x02".to_vec(),
+                }),
+                ..Cookie::build(("sid", "abc"))
+                    .domain("example.com")
+                    .path("/")
+                    .into()
+            },
+            BrowserCookie {
+                raw_value: Some(chrome::RawValue {
+                    encrypted_value: Vec::new(),
+                }),
+                ..Cookie::build(("sid", "en"))
+                    .domain("example.com")
+                    .path("/")
+                    .into()
+            },
+        ];
+
+        let mut output = Vec::new();
+        json_with_raw_values(&cookies, &mut output).unwrap();
+        let output: serde_json::Value = serde_json::from_slice(&output).unwrap();
+
+        assert_eq!(output[0]["value"], "abc");
+        assert_eq!(output[0]["encrypted_value"], "7631300102");
+        assert_eq!(output[0]["encryption_version"], "v10");
+        assert_eq!(output[1]["value"], "en");
+        assert_eq!(output[1]["encrypted_value"], "");
+        assert_eq!(output[1]["encryption_version"], serde_json::Value::Null);
+    }
+}
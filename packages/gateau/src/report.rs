@@ -0,0 +1,182 @@
+//! Cookies read from a browser, with the problems met while reading them.
+
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+use cookie::{time::OffsetDateTime, Cookie, CookieBuilder};
+
+use crate::chrome::RawValue;
+
+/// Earliest UNIX timestamp which can be represented (-9999-01-01 00:00:00 UTC).
+const MIN_TIMESTAMP: i64 = -377705116800;
+
+/// Latest UNIX timestamp which can be represented (9999-12-31 23:59:59 UTC).
+const MAX_TIMESTAMP: i64 = 253402300799;
+
+/// Problem met while reading the cookies, which did not prevent reading the others.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CookieWarning {
+    /// A row (or record) could not be read, and was skipped.
+    SkippedRow { reason: String },
+    /// The expiration time of the cookie could not be represented,
+    /// and was clamped to the supported range (years -9999 to 9999).
+    ClampedExpiration {
+        host: String,
+        name: String,
+        /// Expiration time as stored by the browser, in its own unit.
+        stored: i64,
+    },
+    /// The value of the cookie could not be decrypted with the key of its version,
+    /// and was decrypted with another one (e.g. a v10 value with the v11 key).
+    DecryptionFallback {
+        host: String,
+        name: String,
+        version: &'static str,
+    },
+    /// The value of the cookie could not be decrypted nor decoded, and the cookie was skipped.
+    UndecryptableValue {
+        host: String,
+        name: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for CookieWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CookieWarning::SkippedRow { reason } => write!(f, "skipped a cookie ({reason})"),
+            CookieWarning::ClampedExpiration { host, name, stored } => write!(
+                f,
+                "the expiration of {name} for {host} ({stored}) is out of range and was clamped"
+            ),
+            CookieWarning::DecryptionFallback {
+                host,
+                name,
+                version,
+            } => write!(
+                f,
+                "the {version} value of {name} for {host} was decrypted with another key"
+            ),
+            CookieWarning::UndecryptableValue { host, name, reason } => write!(
+                f,
+                "skipped {name} for {host}, whose value could not be decrypted ({reason})"
+            ),
+        }
+    }
+}
+
+/// Domain, path and name of a cookie, which identify it.
+pub type CookieKey = (String, String, String);
+
+/// Returns the domain (as it is stored, so the host-only cookies are distinct from the domain
+/// cookies), path and name identifying the cookie.
+pub fn cookie_key(cookie: &BrowserCookie) -> CookieKey {
+    (
+        cookie.stored_domain(),
+        cookie.path().unwrap_or_default().to_string(),
+        cookie.name().to_string(),
+    )
+}
+
+/// Cookie read from a browser, with what the browser stores about it
+/// besides its attributes.
+///
+/// It dereferences to the cookie itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrowserCookie {
+    pub cookie: Cookie<'static>,
+    /// Whether the cookie is only sent to the host which set it,
+    /// rather than to its domain and subdomains (a cookie set without a Domain attribute).
+    pub host_only: bool,
+    /// Top-level site by which the cookie is partitioned (e.g. `https://example.org`),
+    /// if it is partitioned.
+    pub partition_key: Option<String>,
+    /// Value as stored by Chromium before its decryption, if the raw values are kept.
+    pub raw_value: Option<RawValue>,
+    /// Source from which the cookie was read (e.g. a root path or a browser),
+    /// if the cookies of several sources are merged.
+    pub source: Option<String>,
+    /// Time at which the cookie was last accessed, for the browsers which store it.
+    pub last_access: Option<OffsetDateTime>,
+}
+
+impl BrowserCookie {
+    /// Returns the domain as the browsers store it, with a leading dot
+    /// unless the cookie is host-only.
+    pub fn stored_domain(&self) -> String {
+        let domain = self.cookie.domain().unwrap_or_default();
+
+        if self.host_only {
+            domain.to_string()
+        } else {
+            format!(".{domain}")
+        }
+    }
+}
+
+impl From<Cookie<'static>> for BrowserCookie {
+    fn from(cookie: Cookie<'static>) -> Self {
+        Self {
+            cookie,
+            host_only: false,
+            partition_key: None,
+            raw_value: None,
+            source: None,
+            last_access: None,
+        }
+    }
+}
+
+impl From<CookieBuilder<'static>> for BrowserCookie {
+    fn from(builder: CookieBuilder<'static>) -> Self {
+        Self::from(builder.build())
+    }
+}
+
+impl Deref for BrowserCookie {
+    type Target = Cookie<'static>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.cookie
+    }
+}
+
+impl DerefMut for BrowserCookie {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.cookie
+    }
+}
+
+/// Cookies read from a browser, with the warnings about the ones which were skipped or altered.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CookieSet {
+    pub cookies: Vec<BrowserCookie>,
+    pub warnings: Vec<CookieWarning>,
+}
+
+/// Returns the time of a UNIX timestamp, clamped to the supported range,
+/// and whether it had to be clamped.
+pub(crate) fn clamped_datetime(timestamp: i64) -> (OffsetDateTime, bool) {
+    let clamped = timestamp.clamp(MIN_TIMESTAMP, MAX_TIMESTAMP);
+
+    (
+        OffsetDateTime::from_unix_timestamp(clamped).expect("Invalid timestamp"),
+        clamped != timestamp,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clamped_datetime() {
+        assert!(!clamped_datetime(2000000000).1);
+        assert!(!clamped_datetime(-1).1);
+        let (time, clamped) = clamped_datetime(i64::MAX);
+        assert!(clamped);
+        assert_eq!(time.unix_timestamp(), MAX_TIMESTAMP);
+    }
+}
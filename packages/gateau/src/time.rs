@@ -0,0 +1,96 @@
+//! Conversions between the timestamps of the browsers and UNIX time.
+
+use cookie::time::OffsetDateTime;
+
+// Offset of UNIX epoch (1970-01-01 00:00:00 UTC) from Windows FILETIME epoch
+// (1601-01-01 00:00:00 UTC), in microseconds. This value is derived from the
+// following: ((1970-1601)*365+89)*24*60*60*1000*1000, where 89 is the number
+// of leap year days between 1601 and 1970: (1970-1601)/4 excluding 1700,
+// 1800, and 1900.
+/// Offset of the UNIX epoch from the Windows epoch (1601-01-01 00:00:00 UTC), in microseconds.
+pub const WINDOWS_UNIX_EPOCH_OFFSET_MICROS: i64 = 11644473600000000;
+
+// From Chromium source code:
+// Time is stored internally as microseconds
+// since the Windows epoch (1601-01-01 00:00:00 UTC).
+/// Convert a Chrome timestamp (based on Windows epoch) in microseconds
+/// to a UNIX timestamp (based on UNIX epoch) in nanoseconds.
+///
+/// The whole range of `i64` is converted, without overflowing.
+pub fn chrome_to_unix_timestamp_nanos(chrome_time: i64) -> i128 {
+    const WINDOWS_UNIX_EPOCH_OFFSET_NANOS: i128 = WINDOWS_UNIX_EPOCH_OFFSET_MICROS as i128 * 1000;
+
+    let nanos = chrome_time as i128 * 1000;
+
+    nanos - WINDOWS_UNIX_EPOCH_OFFSET_NANOS
+}
+
+/// Convert a UNIX time to a Chrome timestamp (based on Windows epoch) in microseconds.
+///
+/// The nanoseconds are truncated, towards the past for the times before 1970.
+pub fn unix_to_chrome_timestamp(time: OffsetDateTime) -> i64 {
+    time.unix_timestamp_nanos().div_euclid(1000) as i64 + WINDOWS_UNIX_EPOCH_OFFSET_MICROS
+}
+
+#[cfg(test)]
+mod tests {
+    use cookie::time::{Date, Duration};
+
+    use super::*;
+
+    #[test]
+    fn test_epochs() {
+        assert_eq!(
+            chrome_to_unix_timestamp_nanos(0),
+            -WINDOWS_UNIX_EPOCH_OFFSET_MICROS as i128 * 1000
+        );
+        assert_eq!(
+            chrome_to_unix_timestamp_nanos(WINDOWS_UNIX_EPOCH_OFFSET_MICROS),
+            0
+        );
+        assert_eq!(
+            unix_to_chrome_timestamp(OffsetDateTime::UNIX_EPOCH),
+            11644473600000000
+        );
+        assert_eq!(
+            unix_to_chrome_timestamp(OffsetDateTime::from_unix_timestamp(-11644473600).unwrap()),
+            0
+        );
+    }
+
+    #[test]
+    fn test_extremes() {
+        assert_eq!(
+            chrome_to_unix_timestamp_nanos(i64::MAX),
+            i64::MAX as i128 * 1000 - WINDOWS_UNIX_EPOCH_OFFSET_MICROS as i128 * 1000
+        );
+        assert_eq!(
+            chrome_to_unix_timestamp_nanos(i64::MIN),
+            i64::MIN as i128 * 1000 - WINDOWS_UNIX_EPOCH_OFFSET_MICROS as i128 * 1000
+        );
+
+        let max = Date::MAX
+            .with_hms_micro(23, 59, 59, 999_999)
+            .unwrap()
+            .assume_utc();
+        let chrome_time = unix_to_chrome_timestamp(max);
+        assert_eq!(
+            chrome_to_unix_timestamp_nanos(chrome_time),
+            max.unix_timestamp_nanos()
+        );
+    }
+
+    #[test]
+    fn test_before_1970() {
+        // 1900-01-01 00:00:00 UTC
+        let time =
+            OffsetDateTime::from_unix_timestamp(-2208988800).unwrap() - Duration::nanoseconds(1);
+        let chrome_time = unix_to_chrome_timestamp(time);
+
+        assert!(chrome_time < WINDOWS_UNIX_EPOCH_OFFSET_MICROS);
+        assert_eq!(
+            chrome_to_unix_timestamp_nanos(chrome_time),
+            time.unix_timestamp_nanos() - 999
+        );
+    }
+}
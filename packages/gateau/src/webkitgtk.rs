@@ -0,0 +1,246 @@
+//! WebKitGTK cookie database management.
+//!
+//! WebKitGTK browsers (such as GNOME Web, formerly Epiphany) store their cookies
+//! with libsoup, which uses its own schema for the `moz_cookies` table.
+//! The values are not encrypted.
+//!
+//! ### Scheme (libsoup 2.70+)
+//!
+//! ```sql
+//! CREATE TABLE moz_cookies (
+//!   id INTEGER PRIMARY KEY,
+//!   name TEXT,
+//!   value TEXT,
+//!   host TEXT,
+//!   path TEXT,
+//!   expiry INTEGER,
+//!   lastAccessed INTEGER,
+//!   isSecure INTEGER,
+//!   isHttpOnly INTEGER,
+//!   sameSite INTEGER
+//! );
+//! ```
+
+use cookie::{Cookie, CookieBuilder, Expiration, SameSite};
+
+use rusqlite::functions::FunctionFlags;
+use rusqlite::Connection;
+
+use crate::report::{clamped_datetime, BrowserCookie, CookieSet, CookieWarning};
+use crate::CookiePathProvider;
+
+use super::get_connection;
+
+use super::HostFilterFn;
+
+mod paths;
+pub use paths::PathProvider;
+
+/// Browsers based on WebKitGTK, which store their cookies with libsoup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WebKitGtkVariant {
+    Epiphany,
+}
+
+pub type Result<T, E = WebKitGtkManagerError> = std::result::Result<T, E>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum WebKitGtkManagerError {
+    #[error("Failed to open WebKitGTK cookies database")]
+    SqliteOpen { source: rusqlite::Error },
+
+    #[error("Failed to create function for host filter")]
+    SqliteFunctionCreate { source: rusqlite::Error },
+
+    #[error("Failed to get cookies from WebKitGTK database")]
+    SqliteQuery { source: rusqlite::Error },
+}
+
+/// WebKitGTK cookie database manager.
+pub struct WebKitGtkManager<P: CookiePathProvider> {
+    path_provider: P,
+    conn: Connection,
+}
+
+impl<P: CookiePathProvider> WebKitGtkManager<P> {
+    /// Create a new WebKitGTK manager.
+    pub fn new(
+        path_provider: P,
+        mut filter: Option<Box<HostFilterFn>>,
+        bypass_lock: bool,
+    ) -> Result<Self> {
+        let conn = get_connection(path_provider.cookies_database(), bypass_lock)
+            .map_err(|source| WebKitGtkManagerError::SqliteOpen { source })?;
+        if let Some(mut filter) = filter.take() {
+            conn.create_scalar_function("host_filter", 1, FunctionFlags::default(), move |ctx| {
+                let host = ctx.get::<String>(0)?;
+                Ok(filter(&host) as i64)
+            })
+            .map_err(|source| WebKitGtkManagerError::SqliteFunctionCreate { source })?;
+        }
+
+        Ok(Self {
+            path_provider,
+            conn,
+        })
+    }
+
+    /// Get the path provider.
+    pub fn path_provider(&self) -> &P {
+        &self.path_provider
+    }
+
+    /// Get all cookies from the database.
+    ///
+    /// libsoup stores the `SameSite` attribute with the same values as Firefox
+    /// (`0` for `None`, `1` for `Lax` and `2` for `Strict`),
+    /// but databases created before libsoup 2.70 lack the `sameSite` column.
+    pub fn get_cookies(&self) -> Result<Vec<Cookie<'static>>> {
+        self.get_cookies_with_report().map(|set| {
+            set.cookies
+                .into_iter()
+                .map(|cookie| cookie.cookie)
+                .collect()
+        })
+    }
+
+    /// Get cookies from the database, with the warnings about the rows which could not be read
+    /// and the expiration times which were clamped.
+    pub fn get_cookies_with_report(&self) -> Result<CookieSet> {
+        let has_same_site = self
+            .conn
+            .query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('moz_cookies') WHERE name = 'sameSite'",
+                [],
+                |row| row.get::<_, i64>(0),
+            )
+            .map_err(|source| WebKitGtkManagerError::SqliteQuery { source })?
+            > 0;
+
+        let query = format!(
+            "SELECT name, value, host, path, expiry, isSecure, isHttpOnly, {same_site}
+            FROM moz_cookies
+            WHERE host_filter(host)",
+            same_site = if has_same_site { "sameSite" } else { "0" },
+        );
+
+        let mut stmt = self
+            .conn
+            .prepare(&query)
+            .map_err(|source| WebKitGtkManagerError::SqliteQuery { source })?;
+
+        let rows = stmt
+            .query_map([], |row| {
+                let expiry = row.get::<_, i64>(4)?;
+                let (expires, clamped) = clamped_datetime(expiry);
+                let host = row.get::<_, String>(2)?;
+                // The domain cookies are stored with a leading dot, the host-only ones without.
+                let host_only = !host.starts_with('.');
+                let cookie = CookieBuilder::new(row.get::<_, String>(0)?, row.get::<_, String>(1)?)
+                    .domain(host)
+                    .path(row.get::<_, String>(3)?)
+                    .expires(Expiration::from(expires))
+                    .secure(row.get::<_, isize>(5)? != 0)
+                    .http_only(row.get::<_, isize>(6)? != 0)
+                    .same_site(match row.get(7)? {
+                        0 => SameSite::None,
+                        1 => SameSite::Lax,
+                        _ => SameSite::Strict,
+                    })
+                    .build();
+
+                Ok((
+                    BrowserCookie {
+                        cookie,
+                        host_only,
+                        partition_key: None,
+                        raw_value: None,
+                        source: None,
+                        last_access: None,
+                    },
+                    clamped.then_some(expiry),
+                ))
+            })
+            .map_err(|source| WebKitGtkManagerError::SqliteQuery { source })?;
+
+        let mut set = CookieSet::default();
+        for row in rows {
+            match row {
+                Ok((cookie, clamped)) => {
+                    if let Some(stored) = clamped {
+                        set.warnings.push(CookieWarning::ClampedExpiration {
+                            host: cookie.domain().unwrap_or_default().to_string(),
+                            name: cookie.name().to_string(),
+                            stored,
+                        });
+                    }
+                    set.cookies.push(cookie);
+                }
+                Err(e) => set.warnings.push(CookieWarning::SkippedRow {
+                    reason: e.to_string(),
+                }),
+            }
+        }
+
+        Ok(set)
+    }
+}
+
+impl WebKitGtkManager<PathProvider> {
+    /// Create a new WebKitGTK manager with the default profile of the given variant.
+    pub fn default_profile(
+        variant: WebKitGtkVariant,
+        filter: Option<Box<HostFilterFn>>,
+        bypass_lock: bool,
+    ) -> Result<Self> {
+        let path_provider = PathProvider::default_profile(variant);
+        Self::new(path_provider, filter, bypass_lock)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    struct TestPathProvider(PathBuf);
+
+    impl CookiePathProvider for TestPathProvider {
+        fn cookies_database(&self) -> PathBuf {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn test_get_cookies() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cookies.sqlite");
+
+        let conn = Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE moz_cookies (id INTEGER PRIMARY KEY, name TEXT, value TEXT, host TEXT,
+                path TEXT, expiry INTEGER, lastAccessed INTEGER, isSecure INTEGER,
+                isHttpOnly INTEGER, sameSite INTEGER);
+            INSERT INTO moz_cookies VALUES
+                (1, 'sid', 'abc', '.example.com', '/', 2000000000, 0, 1, 1, 2),
+                (2, 'lang', 'en', 'example.org', '/', 2000000000, 0, 0, 0, 1);",
+        )
+        .unwrap();
+        drop(conn);
+
+        let manager = WebKitGtkManager::new(
+            TestPathProvider(path),
+            Some(Box::new(|host| host.ends_with("example.com"))),
+            false,
+        )
+        .unwrap();
+        let cookies = manager.get_cookies().unwrap();
+
+        assert_eq!(cookies.len(), 1);
+        assert_eq!(cookies[0].name_value(), ("sid", "abc"));
+        assert_eq!(cookies[0].secure(), Some(true));
+        assert_eq!(cookies[0].http_only(), Some(true));
+        assert_eq!(cookies[0].same_site(), Some(SameSite::Strict));
+    }
+}
@@ -0,0 +1,66 @@
+use std::path::{Path, PathBuf};
+
+use crate::{confinement, CookiePathProvider};
+
+use super::WebKitGtkVariant;
+
+/// Path provider for WebKitGTK browsers.
+pub struct PathProvider {
+    profile_dir: PathBuf,
+}
+
+impl PathProvider {
+    /// Create a new path provider for the given profile directory.
+    pub fn from_root<R: AsRef<Path>>(root_dir: R) -> Self {
+        Self {
+            profile_dir: root_dir.as_ref().to_owned(),
+        }
+    }
+
+    /// Returns a path provider for the default profile of the given variant.
+    ///
+    /// If the variant can be installed in several locations (e.g. as a Flatpak),
+    /// the location which contains a cookies database is preferred.
+    pub fn default_profile(variant: WebKitGtkVariant) -> Self {
+        let mut candidates = PathProvider::variant_profile_dirs(variant)
+            .into_iter()
+            .map(Self::from_root)
+            .collect::<Vec<_>>();
+
+        let index = candidates
+            .iter()
+            .position(|candidate| candidate.cookies_database().exists())
+            .unwrap_or(0);
+
+        candidates.swap_remove(index)
+    }
+
+    /// Returns the directories where the variant can store its default profile,
+    /// in order of preference.
+    fn variant_profile_dirs(variant: WebKitGtkVariant) -> Vec<PathBuf> {
+        let (folder, flatpak_id) = match variant {
+            WebKitGtkVariant::Epiphany => ("epiphany", "org.gnome.Epiphany"),
+        };
+
+        let flatpak_dir = confinement::home_dir().map(|home| {
+            home.join(".var/app")
+                .join(flatpak_id)
+                .join("data")
+                .join(folder)
+        });
+
+        [
+            confinement::data_dir().map(|dir| dir.join(folder)),
+            flatpak_dir,
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+impl CookiePathProvider for PathProvider {
+    fn cookies_database(&self) -> PathBuf {
+        self.profile_dir.join("cookies.sqlite")
+    }
+}
@@ -160,15 +160,12 @@ fn dist_subcommand(
 
     let bin_path = loop {
         if let Some(message) = messages.next() {
-            match message? {
-                Message::CompilerArtifact(artifact) => {
-                    if artifact.target.kind.iter().any(|k| k == "bin")
-                        && artifact.target.name == TARGET_BIN_NAME
-                    {
-                        break Ok(artifact.filenames.into_iter().next().unwrap());
-                    }
+            if let Message::CompilerArtifact(artifact) = message? {
+                if artifact.target.kind.iter().any(|k| k == "bin")
+                    && artifact.target.name == TARGET_BIN_NAME
+                {
+                    break Ok(artifact.filenames.into_iter().next().unwrap());
                 }
-                _ => {}
             }
         } else {
             break Err(color_eyre::eyre::eyre!("Could not find binary path"));